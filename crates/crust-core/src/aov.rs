@@ -0,0 +1,430 @@
+//! Arbitrary output variables: the extra per-pixel layers written next to
+//! the beauty image so compositing can rebalance lighting without a
+//! re-render.
+//!
+//! Two families share one mechanism. *Feature* layers (albedo, normal,
+//! position, depth, sample count) describe the first surface a camera ray
+//! hits. *Lighting* layers split the beauty by how light reached the
+//! camera, classified at the primary vertex:
+//!
+//! - `emission` — seen directly: emitters and the background, unscattered.
+//! - `diffuse_direct` / `diffuse_indirect` — the diffuse (and fuzz) share of
+//!   the primary vertex's BSDF, lit by NEE and bounce-hit emission, or by
+//!   everything beyond the first bounce.
+//! - `specular` — the specular and coat share, direct and indirect.
+//! - `transmission` — the refracted share (thick and thin-walled).
+//! - `volume` — everything whose first event was a volume scatter, plus
+//!   volume emission along the camera segment.
+//!
+//! One more layer serves live-action comp: `shadow`, the fraction of light
+//! reaching a shadow catcher (`crust:shadowCatcher`) that the scene lets
+//! through — 1 where nothing is cast, falling toward 0 in full shadow, and
+//! 1 wherever no catcher was seen. Multiplying the plate by it lays the CG
+//! shadows onto the photographed ground.
+//!
+//! The lighting layers *partition* the beauty: their sum reproduces it to
+//! float precision, sample for sample, which is what a comp artist relies on
+//! when adding them back up. The lobe share is evaluated per channel from the
+//! BSDF value at the direction actually taken (the light direction for NEE,
+//! the sampled direction for the bounce) — see [`crate::LobeSplit`] — so each
+//! layer converges to that lobe's own contribution rather than to the lobe
+//! PMF the sampler happened to pick from.
+
+use glam::Vec3A;
+
+/// One output variable. The discriminant is the bit in [`AovSet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Aov {
+    /// Reflectance of the first surface hit (what a denoiser divides by).
+    Albedo,
+    /// World-space shading normal at the first hit, facing the camera.
+    Normal,
+    /// World-space position of the first hit.
+    Position,
+    /// Distance from the camera to the first hit, along the ray. The
+    /// closest sample wins rather than a filtered average, so silhouettes
+    /// keep a depth that belongs to one surface or the other; a pixel whose
+    /// every sample escaped reads `+inf`.
+    Depth,
+    DiffuseDirect,
+    DiffuseIndirect,
+    Specular,
+    Transmission,
+    Emission,
+    Volume,
+    /// Shadow-catcher ratio: light arriving at the catcher with the scene's
+    /// occlusion over light arriving without it, per channel. Formed from
+    /// the filtered sums, not averaged per sample, and faded toward 1 by
+    /// the share of the pixel the catcher covers.
+    Shadow,
+    /// Camera samples the pixel actually took (adaptive sampling stops
+    /// early), summed rather than averaged across guided passes.
+    SampleCount,
+}
+
+impl Aov {
+    /// Every variable, in the order layers are stored and written.
+    pub const ALL: [Aov; 12] = [
+        Aov::Albedo,
+        Aov::Normal,
+        Aov::Position,
+        Aov::Depth,
+        Aov::DiffuseDirect,
+        Aov::DiffuseIndirect,
+        Aov::Specular,
+        Aov::Transmission,
+        Aov::Emission,
+        Aov::Volume,
+        Aov::Shadow,
+        Aov::SampleCount,
+    ];
+
+    /// Short name, as `--aov` and `crust:aovs` spell it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Normal => "N",
+            Aov::Position => "P",
+            Aov::Depth => "Z",
+            Aov::DiffuseDirect => "diffuse_direct",
+            Aov::DiffuseIndirect => "diffuse_indirect",
+            Aov::Specular => "specular",
+            Aov::Transmission => "transmission",
+            Aov::Emission => "emission",
+            Aov::Volume => "volume",
+            Aov::Shadow => "shadow",
+            Aov::SampleCount => "sample_count",
+        }
+    }
+
+    /// Parses a name as [`Aov::name`] spells it. Case-insensitive, and the
+    /// long forms `normal` / `position` / `depth` are accepted too.
+    pub fn from_name(name: &str) -> Option<Aov> {
+        let lower = name.trim().to_ascii_lowercase();
+        match lower.as_str() {
+            "normal" => return Some(Aov::Normal),
+            "position" => return Some(Aov::Position),
+            "depth" => return Some(Aov::Depth),
+            _ => {}
+        }
+        Aov::ALL
+            .into_iter()
+            .find(|a| a.name().eq_ignore_ascii_case(&lower))
+    }
+
+    /// EXR channel names, one per stored component, following the
+    /// conventions Nuke and the other DCCs recognise: `layer.R/G/B` for
+    /// colours, `N.X/Y/Z` and `P.X/Y/Z` for vectors, a bare `Z` for depth.
+    pub fn channels(self) -> &'static [&'static str] {
+        match self {
+            Aov::Albedo => &["albedo.R", "albedo.G", "albedo.B"],
+            Aov::Normal => &["N.X", "N.Y", "N.Z"],
+            Aov::Position => &["P.X", "P.Y", "P.Z"],
+            Aov::Depth => &["Z"],
+            Aov::DiffuseDirect => &["diffuse_direct.R", "diffuse_direct.G", "diffuse_direct.B"],
+            Aov::DiffuseIndirect => &[
+                "diffuse_indirect.R",
+                "diffuse_indirect.G",
+                "diffuse_indirect.B",
+            ],
+            Aov::Specular => &["specular.R", "specular.G", "specular.B"],
+            Aov::Transmission => &["transmission.R", "transmission.G", "transmission.B"],
+            Aov::Emission => &["emission.R", "emission.G", "emission.B"],
+            Aov::Volume => &["volume.R", "volume.G", "volume.B"],
+            Aov::Shadow => &["shadow.R", "shadow.G", "shadow.B"],
+            Aov::SampleCount => &["sample_count"],
+        }
+    }
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// A set of [`Aov`]s. `Copy`, so it can live in [`crate::RenderSettings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AovSet(u32);
+
+impl AovSet {
+    /// No AOVs: the beauty image alone, and no per-sample AOV work at all.
+    pub fn empty() -> Self {
+        AovSet(0)
+    }
+
+    pub fn all() -> Self {
+        Aov::ALL.into_iter().collect()
+    }
+
+    pub fn with(mut self, aov: Aov) -> Self {
+        self.0 |= aov.bit();
+        self
+    }
+
+    pub fn contains(self, aov: Aov) -> bool {
+        self.0 & aov.bit() != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Members in [`Aov::ALL`] order — the order `Buffer` stores them in.
+    pub fn iter(self) -> impl Iterator<Item = Aov> {
+        Aov::ALL.into_iter().filter(move |a| self.contains(*a))
+    }
+
+    /// Parses a comma- or whitespace-separated list of names; `all` selects
+    /// every variable. Returns the first name that did not parse as `Err`.
+    pub fn parse(list: &str) -> Result<AovSet, String> {
+        let mut set = AovSet::empty();
+        for name in list
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+        {
+            if name.eq_ignore_ascii_case("all") {
+                set = AovSet::all();
+                continue;
+            }
+            set = set.with(Aov::from_name(name).ok_or_else(|| name.to_owned())?);
+        }
+        Ok(set)
+    }
+}
+
+impl FromIterator<Aov> for AovSet {
+    fn from_iter<I: IntoIterator<Item = Aov>>(iter: I) -> Self {
+        iter.into_iter().fold(AovSet::empty(), AovSet::with)
+    }
+}
+
+/// What one camera sample contributes to the AOVs. Filled by the
+/// integrator, accumulated per pixel by [`AovAccum`].
+#[derive(Clone, Default)]
+pub(crate) struct AovSample {
+    /// The geometry the camera ray hit first (the Cryptomatte key); `None`
+    /// when it escaped.
+    pub geom_id: Option<u32>,
+    pub albedo: Vec3A,
+    pub normal: Vec3A,
+    pub position: Vec3A,
+    /// `None` when the camera ray escaped.
+    pub depth: Option<f32>,
+    /// Distance to the camera ray's scatter, when its first event was a
+    /// volume scatter rather than a surface (see `deep.rs`).
+    pub scatter_depth: Option<f32>,
+    pub diffuse_direct: Vec3A,
+    pub diffuse_indirect: Vec3A,
+    pub specular: Vec3A,
+    pub transmission: Vec3A,
+    pub emission: Vec3A,
+    pub volume: Vec3A,
+    /// Set when the camera ray met a shadow catcher: the light sampled
+    /// there with the scene's occlusion, and without it.
+    pub catcher: Option<(Vec3A, Vec3A)>,
+    /// One value per light path expression, in `Renderer::lpes` order (see
+    /// `lpe.rs`).
+    pub lpe: Vec<Vec3A>,
+}
+
+impl AovSample {
+    pub fn new(n_lpes: usize) -> Self {
+        Self {
+            lpe: vec![Vec3A::ZERO; n_lpes],
+            ..Default::default()
+        }
+    }
+
+    /// Clears the sample for the next camera ray, keeping the LPE storage.
+    pub fn reset(&mut self) {
+        let mut lpe = std::mem::take(&mut self.lpe);
+        lpe.fill(Vec3A::ZERO);
+        *self = Self {
+            lpe,
+            ..Default::default()
+        };
+    }
+
+    fn get(&self, aov: Aov) -> Vec3A {
+        match aov {
+            Aov::Albedo => self.albedo,
+            Aov::Normal => self.normal,
+            Aov::Position => self.position,
+            Aov::DiffuseDirect => self.diffuse_direct,
+            Aov::DiffuseIndirect => self.diffuse_indirect,
+            Aov::Specular => self.specular,
+            Aov::Transmission => self.transmission,
+            Aov::Emission => self.emission,
+            Aov::Volume => self.volume,
+            // Not filtered — `AovAccum` tracks these itself.
+            Aov::Depth | Aov::Shadow | Aov::SampleCount => Vec3A::ZERO,
+        }
+    }
+}
+
+/// Per-pixel AOV accumulator, filtered with the same FIS weights as the
+/// beauty so a lighting layer sums back to exactly the pixel estimate.
+pub(crate) struct AovAccum {
+    set: AovSet,
+    sums: [Vec3A; Aov::ALL.len()],
+    lpe_sums: Vec<Vec3A>,
+    closest: f32,
+    /// Filter weight of the samples that met a shadow catcher, and their
+    /// weighted occluded / unoccluded light.
+    catcher_weight: f32,
+    catcher_lit: Vec3A,
+    catcher_free: Vec3A,
+}
+
+impl AovAccum {
+    pub fn new(set: AovSet, n_lpes: usize) -> Self {
+        Self {
+            set,
+            sums: [Vec3A::ZERO; Aov::ALL.len()],
+            lpe_sums: vec![Vec3A::ZERO; n_lpes],
+            closest: f32::INFINITY,
+            catcher_weight: 0.0,
+            catcher_lit: Vec3A::ZERO,
+            catcher_free: Vec3A::ZERO,
+        }
+    }
+
+    pub fn add(&mut self, s: &AovSample, weight: f32) {
+        for aov in self.set.iter() {
+            self.sums[aov as usize] += s.get(aov) * weight;
+        }
+        for (sum, value) in self.lpe_sums.iter_mut().zip(&s.lpe) {
+            *sum += *value * weight;
+        }
+        if let Some(d) = s.depth {
+            self.closest = self.closest.min(d);
+        }
+        if let Some((lit, free)) = s.catcher {
+            self.catcher_weight += weight;
+            self.catcher_lit += lit * weight;
+            self.catcher_free += free * weight;
+        }
+    }
+
+    /// The `shadow` value: the catcher's lit-to-unoccluded ratio, blended
+    /// toward 1 (no shadow) by the pixel share the catcher does not cover.
+    fn shadow(&self, norm: f32) -> Vec3A {
+        let ratio = |lit: f32, free: f32| {
+            if free > 0.0 {
+                (lit / free).min(1.0)
+            } else {
+                1.0
+            }
+        };
+        let ratio = Vec3A::new(
+            ratio(self.catcher_lit.x, self.catcher_free.x),
+            ratio(self.catcher_lit.y, self.catcher_free.y),
+            ratio(self.catcher_lit.z, self.catcher_free.z),
+        );
+        let coverage = (self.catcher_weight / norm).clamp(0.0, 1.0);
+        Vec3A::ONE - coverage * (Vec3A::ONE - ratio)
+    }
+
+    /// The pixel's AOV values in [`AovSet::iter`] order and its LPE values,
+    /// normalized the way `render_pixel` normalizes the beauty: by
+    /// `weight_sum`, or by the sample count when the weights cancelled to
+    /// nothing.
+    pub fn finish(&self, weight_sum: f32, taken: u32) -> (Vec<Vec3A>, Vec<Vec3A>) {
+        let norm = if weight_sum > 0.0 {
+            weight_sum
+        } else {
+            taken.max(1) as f32
+        };
+        let aovs = self
+            .set
+            .iter()
+            .map(|aov| match aov {
+                Aov::Depth => Vec3A::splat(self.closest),
+                Aov::Shadow => self.shadow(norm),
+                Aov::SampleCount => Vec3A::splat(taken as f32),
+                _ => self.sums[aov as usize] / norm,
+            })
+            .collect();
+        let lpes = self.lpe_sums.iter().map(|s| *s / norm).collect();
+        (aovs, lpes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for aov in Aov::ALL {
+            assert_eq!(Aov::from_name(aov.name()), Some(aov));
+            assert!(!aov.channels().is_empty() && aov.channels().len() <= 3);
+        }
+        assert_eq!(Aov::from_name("Depth"), Some(Aov::Depth));
+        assert_eq!(Aov::from_name("bogus"), None);
+    }
+
+    #[test]
+    fn set_parses_lists_and_all() {
+        let set = AovSet::parse("albedo, N Z").expect("valid list");
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![Aov::Albedo, Aov::Normal, Aov::Depth]
+        );
+        assert_eq!(AovSet::parse("all").unwrap(), AovSet::all());
+        assert_eq!(AovSet::parse("albedo,nope"), Err("nope".to_owned()));
+        assert!(AovSet::parse("").unwrap().is_empty());
+    }
+
+    /// Depth keeps the nearest surface; colours average like the beauty.
+    #[test]
+    fn accumulator_filters_colours_and_keeps_closest_depth() {
+        let set = AovSet::empty()
+            .with(Aov::Albedo)
+            .with(Aov::Depth)
+            .with(Aov::SampleCount);
+        let mut acc = AovAccum::new(set, 0);
+        acc.add(
+            &AovSample {
+                albedo: Vec3A::ONE,
+                depth: Some(4.0),
+                ..Default::default()
+            },
+            1.0,
+        );
+        acc.add(
+            &AovSample {
+                albedo: Vec3A::ZERO,
+                depth: Some(2.0),
+                ..Default::default()
+            },
+            1.0,
+        );
+        let (out, _) = acc.finish(2.0, 2);
+        assert_eq!(out, vec![Vec3A::splat(0.5), Vec3A::splat(2.0), Vec3A::splat(2.0)]);
+    }
+
+    /// Half the pixel on a catcher at half light reads 0.75; a pixel that
+    /// never saw a catcher reads 1.
+    #[test]
+    fn shadow_is_the_covered_light_ratio() {
+        let set = AovSet::empty().with(Aov::Shadow);
+        let mut acc = AovAccum::new(set, 0);
+        acc.add(
+            &AovSample {
+                catcher: Some((Vec3A::splat(1.0), Vec3A::splat(2.0))),
+                ..Default::default()
+            },
+            1.0,
+        );
+        acc.add(&AovSample::default(), 1.0);
+        assert_eq!(acc.finish(2.0, 2).0, vec![Vec3A::splat(0.75)]);
+
+        let mut empty = AovAccum::new(set, 0);
+        empty.add(&AovSample::default(), 1.0);
+        assert_eq!(empty.finish(1.0, 1).0, vec![Vec3A::ONE]);
+    }
+}
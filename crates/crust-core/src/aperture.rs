@@ -0,0 +1,222 @@
+//! Lens apertures: the shape a camera's lens opening gives defocused
+//! highlights (bokeh).
+//!
+//! A perspective camera with depth of field starts each ray at a point on
+//! its lens, and an out-of-focus point light images as the set of those
+//! points, projected. So the distribution the lens is sampled from *is* the
+//! bokeh: a disk gives round discs, an `n`-gon the hexagons and octagons of
+//! a bladed iris, and an image whatever it depicts — a cat's eye, a star, a
+//! dirty lens's speckle. An anamorphic squeeze narrows any of them into the
+//! tall ovals of anamorphic glass.
+
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+use glam::Vec3A;
+use utils::concentric_disk;
+
+use crate::environment::Distribution1D;
+
+/// A grayscale aperture mask: how much light each point of the lens lets
+/// through. The image covers the square around the lens's unit disk (so
+/// its inscribed circle is a round aperture of the camera's f-stop), row 0
+/// at the top, and the lens is sampled in proportion to it.
+pub struct ApertureImage {
+    width: usize,
+    height: usize,
+    /// Over rows.
+    marginal: Distribution1D,
+    /// One per row, over columns.
+    conditional: Vec<Distribution1D>,
+}
+
+impl std::fmt::Debug for ApertureImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApertureImage")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ApertureImage {
+    /// Builds a mask from row-major transmission values. Returns `None` for
+    /// an empty or mis-sized buffer, or one with nothing open to sample.
+    pub fn new(width: usize, height: usize, values: Vec<f32>) -> Option<Self> {
+        if width == 0 || height == 0 || values.len() != width * height {
+            return None;
+        }
+        let conditional: Vec<Distribution1D> = values
+            .chunks(width)
+            .map(|row| Distribution1D::new(row.iter().map(|v| v.max(0.0)).collect()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|d| d.integral).collect());
+        (marginal.integral > 0.0).then_some(ApertureImage {
+            width,
+            height,
+            marginal,
+            conditional,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// A point in `[-1, 1]²`, drawn in proportion to the mask.
+    fn sample(&self, u: [f32; 2]) -> Vec3A {
+        let (y, _, row) = self.marginal.sample(u[1]);
+        let (x, _, _) = self.conditional[row].sample(u[0]);
+        Vec3A::new(2.0 * x - 1.0, 1.0 - 2.0 * y, 0.0)
+    }
+}
+
+/// The shape of a camera's lens opening. The default is a round, unsqueezed
+/// lens — a disk.
+#[derive(Debug, Clone)]
+pub struct Aperture {
+    /// Iris blades; fewer than 3 is round.
+    blades: u32,
+    /// The blades' rotation, in radians.
+    rotation: f32,
+    /// Anamorphic squeeze: the opening is this many times taller than wide.
+    squeeze: f32,
+    /// Replaces the blades when set.
+    image: Option<Arc<ApertureImage>>,
+}
+
+impl Default for Aperture {
+    fn default() -> Self {
+        Aperture {
+            blades: 0,
+            rotation: 0.0,
+            squeeze: 1.0,
+            image: None,
+        }
+    }
+}
+
+impl Aperture {
+    /// A regular polygon of `blades` sides inscribed in the lens, one
+    /// corner `rotation` degrees counter-clockwise from straight up.
+    /// Fewer than 3 blades is round.
+    pub fn with_blades(mut self, blades: u32, rotation: f32) -> Self {
+        self.blades = blades;
+        self.rotation = rotation.to_radians();
+        self
+    }
+
+    /// Narrows the opening's width by `squeeze` (2 for a classic 2×
+    /// anamorphic); values below a hundredth are clamped.
+    pub fn with_squeeze(mut self, squeeze: f32) -> Self {
+        self.squeeze = squeeze.max(0.01);
+        self
+    }
+
+    /// Samples the lens from `image` instead of the blades.
+    pub fn with_image(mut self, image: Arc<ApertureImage>) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    pub fn blades(&self) -> u32 {
+        self.blades
+    }
+
+    pub fn squeeze(&self) -> f32 {
+        self.squeeze
+    }
+
+    pub fn image(&self) -> Option<&ApertureImage> {
+        self.image.as_deref()
+    }
+
+    /// A point on the lens, in units of the lens radius (`z = 0`), drawn
+    /// uniformly over the opening — or in proportion to its image — from a
+    /// 2D uniform sample.
+    pub(crate) fn sample(&self, u: [f32; 2]) -> Vec3A {
+        let p = match (&self.image, self.blades) {
+            (Some(image), _) => image.sample(u),
+            (None, 0..=2) => concentric_disk(u),
+            (None, n) => {
+                // One of `n` equal triangles fanning out from the centre,
+                // picked by the first coordinate, then a uniform point in
+                // it: `sqrt` of the rescaled coordinate spreads it out to
+                // the far edge in proportion to the triangle's width there.
+                let scaled = u[0] * n as f32;
+                let k = (scaled as u32).min(n - 1);
+                let r = (scaled - k as f32).sqrt();
+                let corner = |i: u32| {
+                    let angle = self.rotation + TAU * i as f32 / n as f32;
+                    Vec3A::new(-angle.sin(), angle.cos(), 0.0)
+                };
+                r * corner(k).lerp(corner(k + 1), u[1])
+            }
+        };
+        Vec3A::new(p.x / self.squeeze, p.y, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(n: usize) -> impl Iterator<Item = [f32; 2]> {
+        (0..n * n).map(move |k| {
+            [
+                ((k % n) as f32 + 0.5) / n as f32,
+                ((k / n) as f32 + 0.5) / n as f32,
+            ]
+        })
+    }
+
+    #[test]
+    fn blades_fill_their_polygon() {
+        let hexagon = Aperture::default().with_blades(6, 0.0);
+        let apothem = (TAU / 12.0).cos();
+        let mut reach_up = 0.0f32;
+        let mut reach_side = 0.0f32;
+        for u in grid(64) {
+            let p = hexagon.sample(u);
+            // Inside every edge: the six edge normals point between the
+            // corners, the first at 30° from straight up.
+            for i in 0..6 {
+                let angle = TAU / 12.0 + TAU * i as f32 / 6.0;
+                let normal = Vec3A::new(-angle.sin(), angle.cos(), 0.0);
+                assert!(p.dot(normal) <= apothem + 1e-5, "{p:?} outside edge {i}");
+            }
+            reach_up = reach_up.max(p.y);
+            reach_side = reach_side.max(p.x);
+        }
+        // A corner points straight up; the sides are flat at the apothem.
+        assert!(reach_up > 0.95 && reach_side <= apothem + 1e-5);
+    }
+
+    #[test]
+    fn squeeze_narrows_the_opening() {
+        let oval = Aperture::default().with_squeeze(2.0);
+        for u in grid(32) {
+            let p = oval.sample(u);
+            assert!((2.0 * p.x).powi(2) + p.y * p.y <= 1.0 + 1e-5);
+        }
+    }
+
+    #[test]
+    fn images_are_sampled_where_they_are_open() {
+        // A 4×4 mask open only in its top-right texel.
+        let mut values = vec![0.0; 16];
+        values[3] = 1.0;
+        let image = Arc::new(ApertureImage::new(4, 4, values).expect("something is open"));
+        let aperture = Aperture::default().with_image(image);
+        for u in grid(16) {
+            let p = aperture.sample(u);
+            assert!(p.x >= 0.5 && p.y >= 0.5, "{p:?}");
+        }
+        assert!(ApertureImage::new(2, 2, vec![0.0; 4]).is_none());
+        assert!(ApertureImage::new(2, 2, vec![1.0; 3]).is_none());
+    }
+}
@@ -1,7 +1,17 @@
+use crate::aov::{Aov, AovSet};
+use crate::cryptomatte::CRYPTO_RANKS;
+use crate::deep::DeepSample;
 use glam::Vec3A;
 
 /// The `Buffer` struct represents a 2D image buffer used to store pixel colors.
 /// It provides methods to set and retrieve pixel values, as well as access RGB data.
+///
+/// Besides the beauty image it may carry one layer per requested [`Aov`],
+/// same resolution and row order, stored in [`AovSet::iter`] order, and one
+/// per light path expression, stored in the order they were declared, and
+/// one per Cryptomatte layer. A deep buffer also keeps every pixel's deep
+/// samples, and a denoised one the beauty as it was rendered.
+#[derive(Clone)]
 pub struct Buffer {
     /// The width of the buffer in pixels.
     width: usize,
@@ -9,6 +19,30 @@ pub struct Buffer {
     height: usize,
     /// A flat vector storing the color data for each pixel.
     data: Vec<Vec3A>,
+    /// Per-pixel alpha; `data` is premultiplied by it.
+    alpha: Vec<f32>,
+    /// Which AOV layers `layers` holds.
+    aovs: AovSet,
+    /// One plane per member of `aovs`. Single-channel variables (depth,
+    /// sample count) use `x` and leave the rest at the same value.
+    layers: Vec<Vec<Vec3A>>,
+    /// Names of the LPE layers, parallel to `lpe_layers`.
+    lpe_names: Vec<String>,
+    lpe_layers: Vec<Vec<Vec3A>>,
+    crypto: Vec<CryptoPlane>,
+    /// Per-pixel deep samples, front to back; `None` for a flat buffer.
+    deep: Option<Vec<Vec<DeepSample>>>,
+    /// The beauty before denoising; `None` unless the buffer was denoised.
+    noisy: Option<Vec<Vec3A>>,
+}
+
+/// One Cryptomatte layer: [`CRYPTO_RANKS`] `(id, coverage)` pairs per
+/// pixel, strongest first, plus the manifest the file header carries.
+#[derive(Clone)]
+struct CryptoPlane {
+    name: String,
+    manifest: String,
+    ranks: Vec<(f32, f32)>,
 }
 
 impl Buffer {
@@ -19,16 +53,221 @@ impl Buffer {
     /// - `height`: The height of the buffer in pixels.
     ///
     /// # Returns
-    /// - A new instance of `Buffer` initialized with black pixels.
+    /// - A new instance of `Buffer` initialized with opaque black pixels.
     pub fn new(width: usize, height: usize) -> Self {
+        Self::with_aovs(width, height, AovSet::empty())
+    }
+
+    /// Creates a black buffer that also carries a layer for each AOV in
+    /// `aovs`.
+    pub fn with_aovs(width: usize, height: usize, aovs: AovSet) -> Self {
         let data = vec![Vec3A::new(0.0, 0.0, 0.0); width * height];
         Buffer {
             width,
             height,
             data,
+            alpha: vec![1.0; width * height],
+            aovs,
+            layers: vec![vec![Vec3A::ZERO; width * height]; aovs.len()],
+            lpe_names: Vec::new(),
+            lpe_layers: Vec::new(),
+            crypto: Vec::new(),
+            deep: None,
+            noisy: None,
+        }
+    }
+
+    /// Adds a black layer per light path expression, named `names`.
+    pub fn with_lpes(mut self, names: Vec<String>) -> Self {
+        self.lpe_layers = vec![vec![Vec3A::ZERO; self.width * self.height]; names.len()];
+        self.lpe_names = names;
+        self
+    }
+
+    /// Names of the LPE layers, in storage order.
+    pub fn lpe_names(&self) -> &[String] {
+        &self.lpe_names
+    }
+
+    /// Sets one pixel of the `index`-th LPE layer; ignored out of range.
+    pub fn set_lpe(&mut self, x: usize, y: usize, index: usize, value: Vec3A) {
+        if x < self.width
+            && y < self.height
+            && let Some(layer) = self.lpe_layers.get_mut(index)
+        {
+            layer[y * self.width + x] = value;
+        }
+    }
+
+    /// One pixel of the `index`-th LPE layer; black out of range.
+    pub fn get_lpe(&self, x: usize, y: usize, index: usize) -> Vec3A {
+        match self.lpe_layers.get(index) {
+            Some(layer) if x < self.width && y < self.height => layer[y * self.width + x],
+            _ => Vec3A::ZERO,
+        }
+    }
+
+    /// As [`Buffer::get_lpe`], in image-file row order.
+    pub fn get_lpe_flipped(&self, x: usize, y: usize, index: usize) -> Vec3A {
+        self.get_lpe(x, self.height - 1 - y, index)
+    }
+
+    /// Adds an empty Cryptomatte layer per `(name, manifest)` pair.
+    pub fn with_cryptomatte(mut self, layers: Vec<(String, String)>) -> Self {
+        let len = self.width * self.height * CRYPTO_RANKS;
+        self.crypto = layers
+            .into_iter()
+            .map(|(name, manifest)| CryptoPlane {
+                name,
+                manifest,
+                ranks: vec![(0.0, 0.0); len],
+            })
+            .collect();
+        self
+    }
+
+    /// `(name, manifest)` of each Cryptomatte layer, in storage order.
+    pub fn cryptomatte_layers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.crypto
+            .iter()
+            .map(|c| (c.name.as_str(), c.manifest.as_str()))
+    }
+
+    /// Sets one pixel of the `index`-th Cryptomatte layer to `ranks`
+    /// (`(id, coverage)`, strongest first); ranks past the end are cleared,
+    /// ranks past [`CRYPTO_RANKS`] dropped. Ignored out of range.
+    pub fn set_crypto(&mut self, x: usize, y: usize, index: usize, ranks: &[(f32, f32)]) {
+        if x < self.width
+            && y < self.height
+            && let Some(plane) = self.crypto.get_mut(index)
+        {
+            let base = (y * self.width + x) * CRYPTO_RANKS;
+            for (k, slot) in plane.ranks[base..base + CRYPTO_RANKS].iter_mut().enumerate() {
+                *slot = ranks.get(k).copied().unwrap_or((0.0, 0.0));
+            }
+        }
+    }
+
+    /// The `(id, coverage)` pairs of one pixel of the `index`-th Cryptomatte
+    /// layer; empty out of range.
+    pub fn get_crypto(&self, x: usize, y: usize, index: usize) -> &[(f32, f32)] {
+        match self.crypto.get(index) {
+            Some(plane) if x < self.width && y < self.height => {
+                let base = (y * self.width + x) * CRYPTO_RANKS;
+                &plane.ranks[base..base + CRYPTO_RANKS]
+            }
+            _ => &[],
+        }
+    }
+
+    /// As [`Buffer::get_crypto`], in image-file row order.
+    pub fn get_crypto_flipped(&self, x: usize, y: usize, index: usize) -> &[(f32, f32)] {
+        self.get_crypto(x, self.height - 1 - y, index)
+    }
+
+    /// Makes the buffer deep, every pixel starting with no samples.
+    pub fn with_deep(mut self) -> Self {
+        self.deep = Some(vec![Vec::new(); self.width * self.height]);
+        self
+    }
+
+    pub fn is_deep(&self) -> bool {
+        self.deep.is_some()
+    }
+
+    /// Sets one pixel's deep samples, front to back. Ignored on a flat
+    /// buffer or out of bounds.
+    pub fn set_deep(&mut self, x: usize, y: usize, samples: Vec<DeepSample>) {
+        if x < self.width
+            && y < self.height
+            && let Some(deep) = &mut self.deep
+        {
+            deep[y * self.width + x] = samples;
+        }
+    }
+
+    /// One pixel's deep samples, front to back; empty on a flat buffer or
+    /// out of bounds.
+    pub fn get_deep(&self, x: usize, y: usize) -> &[DeepSample] {
+        match &self.deep {
+            Some(deep) if x < self.width && y < self.height => &deep[y * self.width + x],
+            _ => &[],
         }
     }
 
+    /// As [`Buffer::get_deep`], in image-file row order.
+    pub fn get_deep_flipped(&self, x: usize, y: usize) -> &[DeepSample] {
+        self.get_deep(x, self.height - 1 - y)
+    }
+
+    /// Replaces the beauty with `denoised` (same size and row order),
+    /// keeping the rendered one as the noisy layer.
+    pub(crate) fn set_denoised(&mut self, denoised: Vec<Vec3A>) {
+        debug_assert_eq!(denoised.len(), self.data.len());
+        self.noisy = Some(std::mem::replace(&mut self.data, denoised));
+    }
+
+    pub fn is_denoised(&self) -> bool {
+        self.noisy.is_some()
+    }
+
+    /// One pixel of the beauty as rendered, before denoising; black if the
+    /// buffer was not denoised, or out of bounds.
+    pub fn get_noisy(&self, x: usize, y: usize) -> Vec3A {
+        match &self.noisy {
+            Some(noisy) if x < self.width && y < self.height => noisy[y * self.width + x],
+            _ => Vec3A::ZERO,
+        }
+    }
+
+    /// As [`Buffer::get_noisy`], in image-file row order.
+    pub fn get_noisy_flipped(&self, x: usize, y: usize) -> Vec3A {
+        self.get_noisy(x, self.height - 1 - y)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The AOV layers this buffer carries.
+    pub fn aovs(&self) -> AovSet {
+        self.aovs
+    }
+
+    fn layer_index(&self, aov: Aov) -> Option<usize> {
+        self.aovs.iter().position(|a| a == aov)
+    }
+
+    /// Sets one pixel of an AOV layer. Ignored for a layer the buffer does
+    /// not carry, or coordinates out of bounds.
+    pub fn set_aov(&mut self, x: usize, y: usize, aov: Aov, value: Vec3A) {
+        if x < self.width
+            && y < self.height
+            && let Some(l) = self.layer_index(aov)
+        {
+            self.layers[l][y * self.width + x] = value;
+        }
+    }
+
+    /// One pixel of an AOV layer; black for a layer the buffer does not
+    /// carry, or coordinates out of bounds.
+    pub fn get_aov(&self, x: usize, y: usize, aov: Aov) -> Vec3A {
+        match self.layer_index(aov) {
+            Some(l) if x < self.width && y < self.height => self.layers[l][y * self.width + x],
+            _ => Vec3A::ZERO,
+        }
+    }
+
+    /// As [`Buffer::get_aov`], with the y flip of [`Buffer::get_rgb`] — the
+    /// image-file row order.
+    pub fn get_aov_flipped(&self, x: usize, y: usize, aov: Aov) -> Vec3A {
+        self.get_aov(x, self.height - 1 - y, aov)
+    }
+
     /// Sets the color of a specific pixel in the buffer.
     ///
     /// # Parameters
@@ -60,6 +299,27 @@ impl Buffer {
         }
     }
 
+    /// Sets the alpha of a pixel; ignored out of bounds.
+    pub fn set_alpha(&mut self, x: usize, y: usize, alpha: f32) {
+        if x < self.width && y < self.height {
+            self.alpha[y * self.width + x] = alpha;
+        }
+    }
+
+    /// The alpha of a pixel; zero out of bounds.
+    pub fn get_alpha(&self, x: usize, y: usize) -> f32 {
+        if x < self.width && y < self.height {
+            self.alpha[y * self.width + x]
+        } else {
+            0.0
+        }
+    }
+
+    /// As [`Buffer::get_alpha`], with the y flip of [`Buffer::get_rgb`].
+    pub fn get_alpha_flipped(&self, x: usize, y: usize) -> f32 {
+        self.get_alpha(x, self.height - 1 - y)
+    }
+
     /// Retrieves the RGB values of a specific pixel in the buffer.
     ///
     /// # Parameters
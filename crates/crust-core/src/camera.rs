@@ -1,10 +1,126 @@
+use crate::aperture::Aperture;
+use crate::lens::LensSystem;
 use crate::ray::Ray;
-use glam::Vec3A;
-use utils::concentric_disk;
+use glam::{Affine3A, Quat, Vec2, Vec3, Vec3A};
+use std::f32::consts::{PI, TAU};
+use std::sync::Arc;
+
+/// How a camera maps the viewport to rays.
+///
+/// The panoramic projections (spherical, fisheye, cubemap) cast every ray
+/// from the camera's position and have no depth of field. Their directions
+/// are given in the camera's frame: `x` to the right, `y` up, looking down
+/// `-z`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Projection {
+    /// Every ray leaves the lens and passes through its point on the
+    /// viewport: distant objects shrink.
+    #[default]
+    Perspective,
+    /// Every ray leaves its point on the viewport along the view direction:
+    /// size does not change with distance, and parallel lines stay parallel.
+    Orthographic,
+    /// Lat-long over the whole sphere: longitude across, latitude up, the
+    /// view direction at the centre. The layout [`crate::EnvironmentMap`]
+    /// reads, so an image rendered by a camera with no rotation can light a
+    /// dome directly. Wants a 2:1 resolution.
+    Spherical,
+    /// A fisheye whose image circle, inscribed in the frame's shorter side,
+    /// covers `fov` degrees; the distance from the centre grows linearly
+    /// with the angle off the view direction. Pixels outside the circle see
+    /// nothing.
+    FisheyeEquidistant { fov: f32 },
+    /// As [`Projection::FisheyeEquidistant`], but equal solid angles cover
+    /// equal image areas: the distance from the centre grows as the sine of
+    /// half the angle.
+    FisheyeEquisolid { fov: f32 },
+    /// Six square 90° faces side by side in a 6:1 strip, in the order +X,
+    /// −X, +Y, −Y, +Z, −Z. Each face is what a square 90° camera looking
+    /// down that axis sees, unmirrored: `y` up for the four side faces, and
+    /// `+z` and `−z` up when looking up and down. The −Z face is the view
+    /// straight ahead.
+    Cubemap,
+    /// Rays traced from the film out through a [`LensSystem`]'s glass:
+    /// its field of view, focus, distortion and vignetting are the lens's.
+    Realistic,
+}
+
+impl Projection {
+    pub fn is_panoramic(&self) -> bool {
+        !matches!(
+            self,
+            Projection::Perspective | Projection::Orthographic | Projection::Realistic
+        )
+    }
+}
+
+/// How the shutter exposes the frame over the interval it is open for,
+/// which ray times `0..1` span from its opening to its closing.
+///
+/// A real shutter takes a while to open and close: its efficiency ramps up
+/// from nothing to fully open at `opening.0`, stays there until
+/// `opening.1`, and ramps back down by the end (a trapezoid; the default
+/// `(0, 1)` is the ideal instant shutter). A rolling shutter exposes the
+/// image row by row: the top row first, the bottom row `rolling` of the
+/// interval later, each for the remaining `1 - rolling` of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shutter {
+    opening: (f32, f32),
+    rolling: f32,
+}
+
+impl Default for Shutter {
+    fn default() -> Self {
+        Shutter {
+            opening: (0.0, 1.0),
+            rolling: 0.0,
+        }
+    }
+}
+
+impl Shutter {
+    /// A shutter fully open between `opening.0` and `opening.1`, fractions
+    /// of its interval (clamped to `0 <= opening.0 <= opening.1 <= 1`),
+    /// with a rolling offset of `rolling` (clamped to `0..1`).
+    pub fn new(opening: (f32, f32), rolling: f32) -> Self {
+        let open = opening.0.clamp(0.0, 1.0);
+        Shutter {
+            opening: (open, opening.1.clamp(open, 1.0)),
+            rolling: rolling.clamp(0.0, 0.99),
+        }
+    }
+
+    pub fn opening(&self) -> (f32, f32) {
+        self.opening
+    }
+
+    pub fn rolling(&self) -> f32 {
+        self.rolling
+    }
+
+    /// The shutter time of a ray through viewport row `t` (`0` at the
+    /// bottom), drawn from uniform sample `u` in proportion to the
+    /// shutter's efficiency.
+    pub fn time(&self, u: f32, t: f32) -> f32 {
+        // Invert the trapezoid's CDF: a quadratic ramp up, a linear
+        // plateau, a quadratic ramp down.
+        let (rise, fall) = (self.opening.0, 1.0 - self.opening.1);
+        let area = 1.0 - 0.5 * (rise + fall);
+        let target = u * area;
+        let x = if target < 0.5 * rise {
+            (2.0 * target * rise).sqrt()
+        } else if target < area - 0.5 * fall {
+            target + 0.5 * rise
+        } else {
+            1.0 - (2.0 * (area - target).max(0.0) * fall).sqrt()
+        };
+        self.rolling * (1.0 - t.clamp(0.0, 1.0)) + (1.0 - self.rolling) * x
+    }
+}
 
 /// The `Camera` struct represents a virtual camera in the ray tracing system.
 /// It is responsible for generating rays that simulate the perspective view of a scene.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Camera {
     /// The origin of the camera (position in 3D space).
     origin: Vec3A,
@@ -18,8 +134,26 @@ pub struct Camera {
     u: Vec3A,
     /// The camera's local vertical axis.
     v: Vec3A,
+    /// The camera's local backward axis (it looks down `-w`).
+    w: Vec3A,
     /// The radius of the camera's lens (used for depth of field).
     lens_radius: f32,
+    /// The lens opening's shape, within `lens_radius`.
+    aperture: Aperture,
+    projection: Projection,
+    shutter: Shutter,
+    /// Where the camera has moved to at evenly spaced shutter times, as
+    /// scale, rotation and translation relative to the pose it was built
+    /// at. Empty for a camera that stays put.
+    motion: Vec<(Vec3, Quat, Vec3)>,
+    /// A realistic camera's lens, and the scene units in a millimetre.
+    lens: Option<(Arc<LensSystem>, f32)>,
+    /// The film back's shift, in fractions of the frame's width and height.
+    film_offset: (f32, f32),
+    /// Near and far clipping distances along the view direction.
+    clipping: Option<(f32, f32)>,
+    /// Scales the light reaching the film.
+    exposure: f32,
 }
 
 impl Camera {
@@ -55,31 +189,517 @@ impl Camera {
             vertical,
             u,
             v,
+            w,
             lens_radius,
+            projection: Projection::Perspective,
+            aperture: Aperture::default(),
+            shutter: Shutter::default(),
+            motion: Vec::new(),
+            lens: None,
+            film_offset: (0.0, 0.0),
+            clipping: None,
+            exposure: 1.0,
         }
     }
 
-    /// Generates a ray originating from the camera through the viewport.
+    /// Creates an orthographic camera whose viewport, centred on `lookfrom`
+    /// and facing `lookat`, spans `width × height` scene units. It has no
+    /// depth of field: with no lens there is nothing to defocus.
+    pub fn orthographic(
+        lookfrom: Vec3A,
+        lookat: Vec3A,
+        vup: Vec3A,
+        width: f32,
+        height: f32,
+    ) -> Camera {
+        let w = (lookfrom - lookat).normalize();
+        let u = vup.cross(w).normalize();
+        let v = w.cross(u);
+        let horizontal = width * u;
+        let vertical = height * v;
+        Camera {
+            origin: lookfrom,
+            lower_left_corner: lookfrom - horizontal / 2.0 - vertical / 2.0,
+            horizontal,
+            vertical,
+            u,
+            v,
+            w,
+            lens_radius: 0.0,
+            projection: Projection::Orthographic,
+            aperture: Aperture::default(),
+            shutter: Shutter::default(),
+            motion: Vec::new(),
+            lens: None,
+            film_offset: (0.0, 0.0),
+            clipping: None,
+            exposure: 1.0,
+        }
+    }
+
+    /// Creates a camera at `lookfrom`, facing `lookat`, with one of the
+    /// panoramic projections. `aspect_ratio` is the image's width over its
+    /// height, which places a fisheye's image circle.
+    pub fn panoramic(
+        lookfrom: Vec3A,
+        lookat: Vec3A,
+        vup: Vec3A,
+        projection: Projection,
+        aspect_ratio: f32,
+    ) -> Camera {
+        assert!(projection.is_panoramic(), "{projection:?} is not panoramic");
+        let w = (lookfrom - lookat).normalize();
+        let u = vup.cross(w).normalize();
+        let v = w.cross(u);
+        Camera {
+            origin: lookfrom,
+            lower_left_corner: lookfrom,
+            horizontal: aspect_ratio * u,
+            vertical: v,
+            u,
+            v,
+            w,
+            lens_radius: 0.0,
+            projection,
+            aperture: Aperture::default(),
+            shutter: Shutter::default(),
+            motion: Vec::new(),
+            lens: None,
+            film_offset: (0.0, 0.0),
+            clipping: None,
+            exposure: 1.0,
+        }
+    }
+
+    /// Creates a camera at `lookfrom`, facing `lookat`, that sees through
+    /// `lens`. `lookfrom` is the film's centre, and the lens's millimetres
+    /// are `mm` scene units each. Depth of field comes from the lens's own
+    /// stop, so the camera takes no aperture.
+    pub fn realistic(
+        lookfrom: Vec3A,
+        lookat: Vec3A,
+        vup: Vec3A,
+        lens: Arc<LensSystem>,
+        mm: f32,
+    ) -> Camera {
+        let w = (lookfrom - lookat).normalize();
+        let u = vup.cross(w).normalize();
+        let v = w.cross(u);
+        let film = lens.film();
+        Camera {
+            origin: lookfrom,
+            lower_left_corner: lookfrom,
+            horizontal: film.x * mm * u,
+            vertical: film.y * mm * v,
+            u,
+            v,
+            w,
+            lens_radius: 0.0,
+            projection: Projection::Realistic,
+            aperture: Aperture::default(),
+            shutter: Shutter::default(),
+            motion: Vec::new(),
+            lens: Some((lens, mm)),
+            film_offset: (0.0, 0.0),
+            clipping: None,
+            exposure: 1.0,
+        }
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// This camera with its lens opening shaped as `aperture`. Only a
+    /// perspective camera with a nonzero aperture has a lens to shape.
+    pub fn with_aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    pub fn aperture(&self) -> &Aperture {
+        &self.aperture
+    }
+
+    /// A realistic camera's lens.
+    pub fn lens(&self) -> Option<&LensSystem> {
+        self.lens.as_ref().map(|(lens, _)| &**lens)
+    }
+
+    /// This camera with its film back shifted `x` of the frame's width to
+    /// the right and `y` of its height up, so the frame sees off-centre
+    /// without turning — a tilt-shift lens's shift, or a plate's
+    /// reframing. The panoramic projections have no film to shift.
+    pub fn with_film_offset(mut self, x: f32, y: f32) -> Self {
+        self.film_offset = (x, y);
+        self
+    }
+
+    pub fn film_offset(&self) -> (f32, f32) {
+        self.film_offset
+    }
+
+    /// This camera seeing geometry only between `near` and `far`, distances
+    /// along its view direction (along each ray, for the panoramic
+    /// projections, which look every way).
+    pub fn with_clipping(mut self, near: f32, far: f32) -> Self {
+        self.clipping = Some((near.max(0.0), far.max(near)));
+        self
+    }
+
+    pub fn clipping(&self) -> Option<(f32, f32)> {
+        self.clipping
+    }
+
+    /// This camera with the light reaching its film scaled by `exposure`.
+    pub fn with_exposure(mut self, exposure: f32) -> Self {
+        self.exposure = exposure.max(0.0);
+        self
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    /// This camera with `shutter`'s exposure.
+    pub fn with_shutter(mut self, shutter: Shutter) -> Self {
+        self.shutter = shutter;
+        self
+    }
+
+    pub fn shutter(&self) -> Shutter {
+        self.shutter
+    }
+
+    /// This camera moving over the shutter: `keys` are where it is at
+    /// evenly spaced times from the shutter's opening to its closing (two
+    /// or more), each relative to the pose it was built at. Rays follow
+    /// the keys' interpolation — scale and translation linearly, rotation
+    /// along the shorter arc — at their shutter time. Fewer than two keys
+    /// leave it still.
+    pub fn with_motion(mut self, keys: &[Affine3A]) -> Self {
+        self.motion = if keys.len() < 2 {
+            Vec::new()
+        } else {
+            keys.iter()
+                .map(|k| k.to_scale_rotation_translation())
+                .collect()
+        };
+        self
+    }
+
+    /// Whether the camera moves over the shutter, so its rays depend on
+    /// their time.
+    pub fn has_motion(&self) -> bool {
+        !self.motion.is_empty()
+    }
+
+    /// The camera's placement at shutter time `time`, relative to its
+    /// built pose; `None` when it does not move.
+    fn motion_at(&self, time: f32) -> Option<Affine3A> {
+        let last = self.motion.len().checked_sub(1).filter(|&l| l > 0)?;
+        let x = time.clamp(0.0, 1.0) * last as f32;
+        let k = (x as usize).min(last - 1);
+        let f = x - k as f32;
+        let ((s0, r0, t0), (s1, r1, t1)) = (self.motion[k], self.motion[k + 1]);
+        Some(Affine3A::from_scale_rotation_translation(
+            s0.lerp(s1, f),
+            r0.slerp(r1, f),
+            t0.lerp(t1, f),
+        ))
+    }
+
+    /// Generates a ray originating from the camera through the viewport, or
+    /// `None` where the projection has no ray (outside a fisheye's image
+    /// circle, past a cubemap's strip).
     ///
     /// # Parameters
     /// - `s`, `t`: Normalized viewport coordinates in `[0, 1]`.
-    /// - `lens_uv`: A 2D uniform sample used to sample the lens for depth of
-    ///   field. Ignored when the camera has zero aperture. Callers pass a QMC
+    /// - `lens_uv`: A 2D uniform sample used to sample the lens, shaped by
+    ///   the camera's [`Aperture`], for depth of field. Ignored when the
+    ///   camera has zero aperture. Callers pass a QMC
     ///   sample so this dimension is decorrelated from the pixel jitter.
-    /// - `time`: Shutter time in `[0, 1)`, carried on the ray for motion
-    ///   blur (moving instances interpolate their transform at this time).
-    pub fn get_ray(&self, s: f32, t: f32, lens_uv: [f32; 2], time: f32) -> Ray {
-        let offset = if self.lens_radius > 0.0 {
-            let rd = self.lens_radius * concentric_disk(lens_uv);
-            self.u * rd.x + self.v * rd.y
+    /// - `time`: A uniform sample in `[0, 1)` that the camera's [`Shutter`]
+    ///   turns into the ray's shutter time, carried on the ray for motion
+    ///   blur (moving instances interpolate their transform at this time)
+    ///   and placing the ray where a moving camera is then.
+    pub fn get_ray(&self, s: f32, t: f32, lens_uv: [f32; 2], time: f32) -> Option<Ray> {
+        self.sample_ray(s, t, lens_uv, time).map(|(ray, _)| ray)
+    }
+
+    /// As [`Camera::get_ray`], with the ray's weight: the camera's exposure,
+    /// times the share of light the lens lets through to that point of the
+    /// film for a realistic camera (its cos⁴ falloff and vignetting). A
+    /// realistic camera also returns `None` where the lens's glass or
+    /// barrel blocks the ray.
+    pub fn sample_ray(&self, s: f32, t: f32, lens_uv: [f32; 2], time: f32) -> Option<(Ray, f32)> {
+        let time = self.shutter.time(time, t);
+        let (s, t) = if self.projection.is_panoramic() {
+            (s, t)
         } else {
-            Vec3A::ZERO
+            (s + self.film_offset.0, t + self.film_offset.1)
         };
-        Ray::new(
-            self.origin + offset,
-            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
+        let mut weight = self.exposure;
+        let (origin, direction) = match self.projection {
+            Projection::Perspective => {
+                let offset = if self.lens_radius > 0.0 {
+                    let rd = self.lens_radius * self.aperture.sample(lens_uv);
+                    self.u * rd.x + self.v * rd.y
+                } else {
+                    Vec3A::ZERO
+                };
+                let target = self.lower_left_corner + s * self.horizontal + t * self.vertical;
+                (self.origin + offset, target - self.origin - offset)
+            }
+            Projection::Orthographic => (
+                self.lower_left_corner + s * self.horizontal + t * self.vertical,
+                -self.w,
+            ),
+            Projection::Realistic => {
+                let (lens, mm) = self.lens.as_ref()?;
+                let (o, d, w) = lens.sample_ray(Vec2::new(s, t), lens_uv)?;
+                weight *= w;
+                let to_world = |p: Vec3A| p.x * self.u + p.y * self.v + p.z * self.w;
+                (self.origin + *mm * to_world(o), to_world(d))
+            }
+            _ => {
+                let d = self.panoramic_direction(s, t)?;
+                (self.origin, d.x * self.u + d.y * self.v + d.z * self.w)
+            }
+        };
+        let clip = self.clipping.map(|(near, far)| {
+            if self.projection.is_panoramic() {
+                let len = direction.length();
+                (near / len, far / len)
+            } else {
+                // Depths from the camera's position, where the lens or
+                // glass ahead of it starts the ray.
+                let ahead = (origin - self.origin).dot(-self.w);
+                let speed = direction.dot(-self.w);
+                ((near - ahead) / speed, (far - ahead) / speed)
+            }
+        });
+        let (origin, direction) = match self.motion_at(time) {
+            Some(m) => (m.transform_point3a(origin), m.transform_vector3a(direction)),
+            None => (origin, direction),
+        };
+        let ray = Ray::new(origin, direction)
+            .with_time(time)
+            .with_mask(crate::ray::MASK_CAMERA);
+        let ray = match clip {
+            Some((t_min, t_max)) => ray.with_clip(t_min, t_max),
+            None => ray,
+        };
+        Some((ray, weight))
+    }
+
+    /// A panoramic projection's direction for viewport point `(s, t)`, in
+    /// the camera's frame.
+    fn panoramic_direction(&self, s: f32, t: f32) -> Option<Vec3A> {
+        match self.projection {
+            Projection::Spherical => {
+                // The inverse of `EnvironmentMap`'s mapping: `t` runs from
+                // the -Y pole at the bottom to +Y at the top.
+                let phi = (s - 0.5) * TAU;
+                let theta = (1.0 - t) * PI;
+                Some(Vec3A::new(
+                    phi.sin() * theta.sin(),
+                    theta.cos(),
+                    -phi.cos() * theta.sin(),
+                ))
+            }
+            Projection::FisheyeEquidistant { fov } | Projection::FisheyeEquisolid { fov } => {
+                // Scale the longer side so the unit circle touches the
+                // shorter side's edges.
+                let aspect = self.horizontal.length();
+                let (x, y) = if aspect >= 1.0 {
+                    ((2.0 * s - 1.0) * aspect, 2.0 * t - 1.0)
+                } else {
+                    (2.0 * s - 1.0, (2.0 * t - 1.0) / aspect)
+                };
+                let r = (x * x + y * y).sqrt();
+                if r > 1.0 {
+                    return None;
+                }
+                let half = 0.5 * fov.clamp(1.0, 360.0).to_radians();
+                let theta = match self.projection {
+                    Projection::FisheyeEquidistant { .. } => r * half,
+                    _ => 2.0 * (r * (0.5 * half).sin()).asin(),
+                };
+                let psi = y.atan2(x);
+                Some(Vec3A::new(
+                    theta.sin() * psi.cos(),
+                    theta.sin() * psi.sin(),
+                    -theta.cos(),
+                ))
+            }
+            Projection::Cubemap => {
+                if !(0.0..1.0).contains(&s) || !(0.0..=1.0).contains(&t) {
+                    return None;
+                }
+                let face = (s * 6.0) as usize;
+                // Face coordinates in [-1, 1]: `a` to the face's right, `b`
+                // to its top.
+                let a = 2.0 * (s * 6.0 - face as f32) - 1.0;
+                let b = 2.0 * t - 1.0;
+                let d = match face {
+                    0 => Vec3A::new(1.0, b, a),
+                    1 => Vec3A::new(-1.0, b, -a),
+                    2 => Vec3A::new(a, 1.0, b),
+                    3 => Vec3A::new(a, -1.0, -b),
+                    4 => Vec3A::new(-a, b, 1.0),
+                    _ => Vec3A::new(a, b, -1.0),
+                };
+                Some(d.normalize())
+            }
+            Projection::Perspective | Projection::Orthographic | Projection::Realistic => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(projection: Projection, aspect_ratio: f32) -> Camera {
+        Camera::panoramic(
+            Vec3A::ZERO,
+            Vec3A::NEG_Z,
+            Vec3A::Y,
+            projection,
+            aspect_ratio,
         )
-        .with_time(time)
-        .with_mask(crate::ray::MASK_CAMERA)
+    }
+
+    fn direction(camera: &Camera, s: f32, t: f32) -> Option<Vec3A> {
+        let ray = camera.get_ray(s, t, [0.5, 0.5], 0.0)?;
+        Some(ray.direction().normalize())
+    }
+
+    fn close(a: Vec3A, b: Vec3A) -> bool {
+        (a - b).length() < 1e-5
+    }
+
+    #[test]
+    fn spherical_matches_the_environment_layout() {
+        let cam = camera(Projection::Spherical, 2.0);
+        assert!(close(direction(&cam, 0.5, 0.5).unwrap(), Vec3A::NEG_Z));
+        assert!(close(direction(&cam, 0.75, 0.5).unwrap(), Vec3A::X));
+        assert!(close(direction(&cam, 0.5, 1.0).unwrap(), Vec3A::Y));
+        // `EnvironmentMap` puts +X at u = 0.75 and +Y at v = 0 (the top).
+        let d = direction(&cam, 0.3, 0.8).unwrap();
+        let u = 0.5 + d.x.atan2(-d.z) / TAU;
+        let v = d.y.acos() / PI;
+        assert!((u - 0.3).abs() < 1e-5 && (v - 0.2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn fisheyes_cover_their_field_of_view_inside_the_circle() {
+        for projection in [
+            Projection::FisheyeEquidistant { fov: 180.0 },
+            Projection::FisheyeEquisolid { fov: 180.0 },
+        ] {
+            let cam = camera(projection, 2.0);
+            assert!(close(direction(&cam, 0.5, 0.5).unwrap(), Vec3A::NEG_Z));
+            // The circle's rim, at the top and at the side, is 90° off axis.
+            assert!(close(direction(&cam, 0.5, 1.0).unwrap(), Vec3A::Y));
+            assert!(close(direction(&cam, 0.75, 0.5).unwrap(), Vec3A::X));
+            assert!(direction(&cam, 0.9, 0.5).is_none());
+        }
+        // Halfway out: 45° for equidistant, 2·asin(sin 45° / 2) ≈ 41.4° for
+        // equisolid.
+        let angle = |projection| {
+            let d = direction(&camera(projection, 1.0), 0.75, 0.5).unwrap();
+            d.dot(Vec3A::NEG_Z).acos().to_degrees()
+        };
+        assert!((angle(Projection::FisheyeEquidistant { fov: 180.0 }) - 45.0).abs() < 1e-3);
+        assert!((angle(Projection::FisheyeEquisolid { fov: 180.0 }) - 41.41).abs() < 1e-2);
+    }
+
+    #[test]
+    fn cubemap_faces_look_down_their_axes() {
+        let cam = camera(Projection::Cubemap, 6.0);
+        let axes = [
+            Vec3A::X,
+            Vec3A::NEG_X,
+            Vec3A::Y,
+            Vec3A::NEG_Y,
+            Vec3A::Z,
+            Vec3A::NEG_Z,
+        ];
+        for (face, axis) in axes.into_iter().enumerate() {
+            let s = (face as f32 + 0.5) / 6.0;
+            assert!(close(direction(&cam, s, 0.5).unwrap(), axis), "face {face}");
+        }
+        // Unmirrored: the −Z face's right edge leans to +X, and looking up
+        // from it the +Y face's bottom edge leans forward.
+        let right = direction(&cam, 5.99 / 6.0, 0.5).unwrap();
+        assert!(right.x > 0.0 && right.z < 0.0);
+        let up_bottom = direction(&cam, 2.5 / 6.0, 0.01).unwrap();
+        assert!(up_bottom.y > 0.0 && up_bottom.z < 0.0);
+        assert!(direction(&cam, 1.2, 0.5).is_none());
+    }
+
+    #[test]
+    fn shutter_times_follow_the_efficiency_and_roll_down_the_frame() {
+        let ideal = Shutter::default();
+        for u in [0.0, 0.25, 0.7] {
+            assert_eq!(ideal.time(u, 0.3), u);
+        }
+        // Half the interval spent opening and closing: the trapezoid's area
+        // is 3/4, a quarter of which lies under each ramp.
+        let slow = Shutter::new((0.25, 0.75), 0.0);
+        assert!((slow.time(1.0 / 6.0, 0.5) - 0.25).abs() < 1e-5);
+        assert!((slow.time(0.5, 0.5) - 0.5).abs() < 1e-5);
+        assert!((slow.time(5.0 / 6.0, 0.5) - 0.75).abs() < 1e-5);
+        assert!((slow.time(1.0, 0.5) - 1.0).abs() < 1e-5);
+        // The top row is exposed over the first half, the bottom row over
+        // the second.
+        let rolling = Shutter::new((0.0, 1.0), 0.5);
+        assert_eq!(rolling.time(0.0, 1.0), 0.0);
+        assert_eq!(rolling.time(1.0, 1.0), 0.5);
+        assert_eq!(rolling.time(0.0, 0.0), 0.5);
+        assert_eq!(rolling.time(1.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn a_moving_camera_places_rays_at_their_time() {
+        let still = Camera::new(Vec3A::ZERO, Vec3A::NEG_Z, Vec3A::Y, 60.0, 1.0, 0.0, 1.0);
+        // Two units to the right and a quarter turn left over the shutter.
+        let turned = Affine3A::from_rotation_translation(
+            Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            Vec3::new(2.0, 0.0, 0.0),
+        );
+        let moving = still.clone().with_motion(&[Affine3A::IDENTITY, turned]);
+        assert!(!still.has_motion() && moving.has_motion());
+
+        let ray = |time| moving.get_ray(0.5, 0.5, [0.5, 0.5], time).unwrap();
+        assert!(close(ray(0.0).origin(), Vec3A::ZERO));
+        assert!(close(ray(0.0).direction().normalize(), Vec3A::NEG_Z));
+        assert!(close(ray(1.0).origin(), Vec3A::new(2.0, 0.0, 0.0)));
+        assert!(close(ray(1.0).direction().normalize(), Vec3A::NEG_X));
+        let half = ray(0.5);
+        assert!(close(half.origin(), Vec3A::new(1.0, 0.0, 0.0)));
+        let diagonal = Vec3A::new(-1.0, 0.0, -1.0).normalize();
+        assert!(close(half.direction().normalize(), diagonal));
+        assert_eq!(half.time(), 0.5);
+    }
+
+    #[test]
+    fn film_offset_shifts_the_frame_and_clipping_bounds_rays() {
+        let centred = Camera::new(Vec3A::ZERO, Vec3A::NEG_Z, Vec3A::Y, 60.0, 1.5, 0.0, 1.0);
+        let shifted = centred.clone().with_film_offset(0.5, -0.25);
+        let d = |camera: &Camera, s, t| camera.get_ray(s, t, [0.5, 0.5], 0.0).unwrap().direction();
+        assert!(close(d(&shifted, 0.5, 0.5), d(&centred, 1.0, 0.25)));
+
+        // Two units ahead to five, along the view axis: further along an
+        // oblique ray.
+        let clipped = centred.with_clipping(2.0, 5.0);
+        for (s, t) in [(0.5, 0.5), (0.9, 0.2)] {
+            let ray = clipped.get_ray(s, t, [0.5, 0.5], 0.0).unwrap();
+            let (near, far) = ray.clip().expect("a clipped camera clips its rays");
+            assert!((ray.at(near).z + 2.0).abs() < 1e-5);
+            assert!((ray.at(far).z + 5.0).abs() < 1e-5);
+        }
     }
 }
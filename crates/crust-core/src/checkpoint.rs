@@ -0,0 +1,554 @@
+//! Checkpoints: a long render's running per-pixel estimate, written to disk
+//! every so often so that a crash or a preempted job resumes where it
+//! stopped instead of starting over.
+//!
+//! Resuming is exact, not merely unbiased. Sample `k` of a pixel is a pure
+//! function of the pixel, the frame seed and `k` — the Sobol sequence is
+//! indexed, not streamed — so a checkpoint only has to remember how far each
+//! pixel got and the sums its samples produced. Continuing from there draws
+//! the very samples the uninterrupted render would have drawn. What it stores
+//! is what `render_pixel` already carries for adaptive sampling: the
+//! filter-weighted colour and alpha sums, the weight sum, the sample count
+//! and the luminance moments, plus whether adaptive sampling stopped the
+//! pixel.
+//!
+//! A path-guided render adds its trained [`GuidingField`], whether the final
+//! pass runs guided, and the training passes the final one is blended with.
+//! Checkpoints begin once training is over; a crash during training retrains.
+//!
+//! Only the beauty and alpha are covered. The extra layers (AOVs, LPEs,
+//! Cryptomatte, deep) keep per-pixel accumulators of their own, so a render
+//! that asks for them runs without checkpoints.
+//!
+//! The file is a little-endian dump: a magic, a format version, and a
+//! fingerprint of the scene and the settings that shape the image, so a
+//! checkpoint cannot be resumed into a render it does not belong to.
+
+use std::io::Write;
+use std::path::Path;
+
+use glam::Vec3A;
+
+use crate::error::Error;
+use crate::guiding::GuidingField;
+use crate::tracer::RenderSettings;
+
+const MAGIC: &[u8; 8] = b"CRUSTCKP";
+const VERSION: u32 = 1;
+/// Encoded sizes of a [`PixelAccum`] and of a prior pass's pixel.
+pub(crate) const ACCUM_BYTES: usize = 41;
+const PRIOR_PIXEL_BYTES: usize = 16;
+
+/// One pixel's running estimate — the state `render_pixel` resumes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct PixelAccum {
+    /// Σ wᵢ·Lᵢ over the samples taken.
+    pub sum: Vec3A,
+    /// Σ wᵢ, the filter-weight normalization.
+    pub weight_sum: f32,
+    /// Σ wᵢ·αᵢ.
+    pub alpha_sum: f32,
+    /// Σ and Σ² of the weighted sample luminance (adaptive sampling's
+    /// moments).
+    pub lum_sum: f64,
+    pub lum_sq: f64,
+    /// Samples taken so far; the next one drawn is sample `taken`.
+    pub taken: u32,
+    /// Adaptive sampling stopped this pixel — it takes no more samples.
+    pub converged: bool,
+}
+
+impl PixelAccum {
+    /// Relative standard error of the pixel's mean luminance — what
+    /// adaptive sampling stops a pixel on. Infinite below two samples.
+    pub fn relative_error(&self) -> f64 {
+        if self.taken < 2 {
+            return f64::INFINITY;
+        }
+        let n = self.taken as f64;
+        let var_of_mean =
+            ((self.lum_sq - self.lum_sum * self.lum_sum / n) / (n - 1.0) / n).max(0.0);
+        var_of_mean.sqrt() / (self.lum_sum / n).max(1e-4)
+    }
+
+    /// The pixel's colour and alpha: the filter-weighted averages
+    /// `render_pixel` reports, black before the first sample.
+    pub fn estimate(&self) -> (Vec3A, f32) {
+        // As in `render_pixel`: the plain mean when negative filter lobes
+        // zeroed the weight sum.
+        let norm = if self.weight_sum > 0.0 {
+            self.weight_sum
+        } else {
+            self.taken as f32
+        };
+        if norm == 0.0 {
+            return (Vec3A::ZERO, 0.0);
+        }
+        (self.sum / norm, self.alpha_sum / norm)
+    }
+}
+
+/// A finished training pass a guided render blends its final pass with.
+#[derive(Debug, Clone)]
+pub(crate) struct PriorPass {
+    /// Beauty and alpha, row-major.
+    pub pixels: Vec<(Vec3A, f32)>,
+    /// The pass's variance estimate, which sets its blending weight.
+    pub variance: f64,
+}
+
+/// What a path-guided render needs besides the final pass's pixels.
+#[derive(Debug, Clone)]
+pub(crate) struct GuidedState {
+    pub field: GuidingField,
+    pub guide_final: bool,
+    pub passes: Vec<PriorPass>,
+}
+
+/// A render's saved state — see the module docs.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub(crate) fingerprint: u64,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    /// Final-pass accumulators, row-major.
+    pub(crate) pixels: Vec<PixelAccum>,
+    pub(crate) guided: Option<GuidedState>,
+}
+
+impl Checkpoint {
+    /// Reads a checkpoint written by [`crate::Renderer::with_checkpoints`].
+    pub fn load(path: &Path) -> Result<Checkpoint, Error> {
+        let invalid = |message: &str| Error::Checkpoint {
+            path: path.to_path_buf(),
+            message: message.to_owned(),
+        };
+        let bytes = std::fs::read(path).map_err(|e| invalid(&e.to_string()))?;
+        let rest = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .ok_or_else(|| invalid("not a crust checkpoint"))?;
+        let mut r = ByteReader::new(rest);
+        if r.u32() != Some(VERSION) {
+            return Err(invalid("written by an incompatible version"));
+        }
+        Self::decode(&mut r).ok_or_else(|| invalid("truncated or corrupt"))
+    }
+
+    fn decode(r: &mut ByteReader) -> Option<Checkpoint> {
+        let fingerprint = r.u64()?;
+        let width = r.u32()? as usize;
+        let height = r.u32()? as usize;
+        // The size comes from the file: bound it by the data actually there
+        // before allocating for it.
+        let count = width.checked_mul(height)?;
+        r.holds(count, ACCUM_BYTES)?;
+        let mut pixels = Vec::with_capacity(count);
+        for _ in 0..count {
+            pixels.push(r.accum()?);
+        }
+        let guided = match r.u8()? {
+            0 => None,
+            _ => {
+                let guide_final = r.u8()? != 0;
+                let field = GuidingField::read(r)?;
+                let count = r.u32()?;
+                let mut passes = Vec::new();
+                for _ in 0..count {
+                    let variance = r.f64()?;
+                    r.holds(count, PRIOR_PIXEL_BYTES)?;
+                    let mut pass = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        pass.push((r.vec3()?, r.f32()?));
+                    }
+                    passes.push(PriorPass {
+                        pixels: pass,
+                        variance,
+                    });
+                }
+                Some(GuidedState {
+                    field,
+                    guide_final,
+                    passes,
+                })
+            }
+        };
+        r.is_empty().then_some(Checkpoint {
+            fingerprint,
+            width,
+            height,
+            pixels,
+            guided,
+        })
+    }
+
+    /// Writes the checkpoint next to `path` and renames it into place, so a
+    /// crash mid-write leaves the previous checkpoint intact.
+    pub(crate) fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut w = ByteWriter::default();
+        w.bytes(MAGIC);
+        w.u32(VERSION);
+        w.u64(self.fingerprint);
+        w.u32(self.width as u32);
+        w.u32(self.height as u32);
+        for p in &self.pixels {
+            w.accum(p);
+        }
+        match &self.guided {
+            None => w.u8(0),
+            Some(g) => {
+                w.u8(1);
+                w.u8(g.guide_final as u8);
+                g.field.write(&mut w);
+                w.u32(g.passes.len() as u32);
+                for pass in &g.passes {
+                    w.f64(pass.variance);
+                    for &(c, a) in &pass.pixels {
+                        w.vec3(c);
+                        w.f32(a);
+                    }
+                }
+            }
+        }
+        let tmp = path.with_extension("tmp");
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(&w.0)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    }
+
+    /// Samples taken across the image — for reporting how far a resumed
+    /// render already got.
+    pub fn samples_taken(&self) -> u64 {
+        self.pixels.iter().map(|p| p.taken as u64).sum()
+    }
+}
+
+/// Identifies the render a checkpoint belongs to: the scene's identity (see
+/// [`crate::Scene::identity`]) and size, and every setting except the
+/// sample budget (raising it and resuming is how a finished render gets
+/// more samples). Hashes the settings' `Debug` form, which names every
+/// field.
+pub(crate) fn fingerprint(settings: &RenderSettings, scene: u64, geometries: usize) -> u64 {
+    let text = format!(
+        "{:?} {scene} {geometries}",
+        settings.with_samples_per_pixel(1)
+    );
+    fnv1a(text.as_bytes())
+}
+
+/// 64-bit FNV-1a: stable across builds and platforms, unlike `std`'s
+/// hashers, which is what a hash written to disk needs.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |h, &b| {
+        (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Little-endian encoder for checkpoint (and partial render) data.
+#[derive(Default)]
+pub(crate) struct ByteWriter(pub Vec<u8>);
+
+impl ByteWriter {
+    pub fn bytes(&mut self, v: &[u8]) {
+        self.0.extend_from_slice(v);
+    }
+
+    pub fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    pub fn u32(&mut self, v: u32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn i32(&mut self, v: i32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, v: u64) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn f32(&mut self, v: f32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn f64(&mut self, v: f64) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn vec3(&mut self, v: Vec3A) {
+        for c in v.to_array() {
+            self.f32(c);
+        }
+    }
+
+    pub fn accum(&mut self, p: &PixelAccum) {
+        self.vec3(p.sum);
+        self.f32(p.weight_sum);
+        self.f32(p.alpha_sum);
+        self.f64(p.lum_sum);
+        self.f64(p.lum_sq);
+        self.u32(p.taken);
+        self.u8(p.converged as u8);
+    }
+}
+
+/// The matching decoder. Every read is `None` past the end of the data.
+pub(crate) struct ByteReader<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { rest: bytes }
+    }
+
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, rest) = self.rest.split_first_chunk::<N>()?;
+        self.rest = rest;
+        Some(*head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rest.is_empty()
+    }
+
+    /// `Some` when `count` items of `size` bytes each are left to read —
+    /// the check a count read from the data needs before it sizes an
+    /// allocation.
+    pub fn holds(&self, count: usize, size: usize) -> Option<()> {
+        (count.checked_mul(size)? <= self.rest.len()).then_some(())
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|b| b[0])
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    pub fn i32(&mut self) -> Option<i32> {
+        self.take().map(i32::from_le_bytes)
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        self.take().map(f32::from_le_bytes)
+    }
+
+    pub fn f64(&mut self) -> Option<f64> {
+        self.take().map(f64::from_le_bytes)
+    }
+
+    pub fn vec3(&mut self) -> Option<Vec3A> {
+        Some(Vec3A::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    pub fn accum(&mut self) -> Option<PixelAccum> {
+        Some(PixelAccum {
+            sum: self.vec3()?,
+            weight_sum: self.f32()?,
+            alpha_sum: self.f32()?,
+            lum_sum: self.f64()?,
+            lum_sq: self.f64()?,
+            taken: self.u32()?,
+            converged: self.u8()? != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aabb::AABB;
+    use crate::guiding::{GuidingConfig, SampleData};
+    use crate::tracer::{CancelToken, Renderer};
+    use crate::world::ball_on_floor;
+    use std::time::Duration;
+
+    #[test]
+    fn checkpoints_round_trip() {
+        let mut field =
+            GuidingField::new(AABB::new(Vec3A::ZERO, Vec3A::ONE), GuidingConfig::default());
+        let samples: Vec<SampleData> = (0..5000)
+            .map(|i| SampleData {
+                pos: Vec3A::splat((i % 10) as f32 / 10.0),
+                dir: Vec3A::new(0.3, 0.2, 1.0).normalize(),
+                radiance: 1.0 + (i % 3) as f32,
+            })
+            .collect();
+        field.update(&samples, 1);
+
+        let pixel = PixelAccum {
+            sum: Vec3A::new(1.0, 2.0, 3.0),
+            weight_sum: 4.0,
+            alpha_sum: 3.5,
+            lum_sum: 8.25,
+            lum_sq: 20.5,
+            taken: 4,
+            converged: true,
+        };
+        let ckpt = Checkpoint {
+            fingerprint: 42,
+            width: 2,
+            height: 1,
+            pixels: vec![pixel, PixelAccum::default()],
+            guided: Some(GuidedState {
+                field: field.clone(),
+                guide_final: true,
+                passes: vec![PriorPass {
+                    pixels: vec![(Vec3A::ONE, 1.0), (Vec3A::ZERO, 0.0)],
+                    variance: 0.5,
+                }],
+            }),
+        };
+        let path = std::env::temp_dir().join("crust_checkpoint_round_trip.ckpt");
+        ckpt.save(&path).expect("write checkpoint");
+        let back = Checkpoint::load(&path).expect("read checkpoint");
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(back.fingerprint, 42);
+        assert_eq!(back.pixels, ckpt.pixels);
+        assert_eq!(back.samples_taken(), 4);
+        let guided = back.guided.expect("guided state kept");
+        assert!(guided.guide_final);
+        assert_eq!(
+            guided.passes[0].pixels,
+            vec![(Vec3A::ONE, 1.0), (Vec3A::ZERO, 0.0)]
+        );
+        // The field answers exactly as the one that was saved.
+        let (p, d) = (Vec3A::splat(0.5), Vec3A::new(0.3, 0.2, 1.0).normalize());
+        assert_eq!(guided.field.pdf(p, d), field.pdf(p, d));
+        assert_eq!(
+            guided.field.sample(p, [0.3, 0.7]),
+            field.sample(p, [0.3, 0.7])
+        );
+    }
+
+    /// A header claiming a frame far larger than the file is an error,
+    /// not an overflow or an allocation the process cannot survive.
+    #[test]
+    fn oversized_headers_are_rejected() {
+        let mut w = ByteWriter::default();
+        w.bytes(MAGIC);
+        w.u32(VERSION);
+        w.u64(42);
+        w.u32(u32::MAX);
+        w.u32(u32::MAX);
+        let header = w.0.len();
+        w.accum(&PixelAccum::default());
+        assert_eq!(w.0.len() - header, ACCUM_BYTES);
+        let path = std::env::temp_dir().join("crust_checkpoint_oversized.ckpt");
+        std::fs::write(&path, &w.0).expect("write file");
+        let loaded = Checkpoint::load(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(loaded, Err(Error::Checkpoint { .. })));
+    }
+
+    #[test]
+    fn fingerprints_tell_scenes_apart() {
+        let settings = RenderSettings::new(64, 32, 16, 16, 8, 0.05, 0);
+        let base = fingerprint(&settings, 7, 3);
+        assert_eq!(
+            base,
+            fingerprint(&settings.with_samples_per_pixel(256), 7, 3)
+        );
+        assert_ne!(base, fingerprint(&settings, 8, 3));
+        assert_ne!(base, fingerprint(&settings, 7, 4));
+        assert_ne!(base, fingerprint(&settings.with_frame(1), 7, 3));
+    }
+
+    fn renderer(spp: u32, guiding: bool) -> Renderer {
+        let (camera, world, lights, settings) = ball_on_floor();
+        let settings = settings
+            .with_samples_per_pixel(spp)
+            .with_guiding(guiding, 1, 0.5);
+        Renderer::new(camera, world, lights, settings)
+    }
+
+    /// A render checkpointed at 8 spp and resumed to 40 is the very image a
+    /// straight 40 spp render produces — unguided, and guided (one training
+    /// iteration, so the guided/unguided verdict cannot depend on timing).
+    #[test]
+    fn resumed_render_matches_an_uninterrupted_one() {
+        for guiding in [false, true] {
+            let path = std::env::temp_dir().join(format!("crust_checkpoint_resume_{guiding}.ckpt"));
+            renderer(8, guiding)
+                .with_checkpoints(path.clone(), Duration::from_secs(3600))
+                .render();
+            let checkpoint = Checkpoint::load(&path);
+            let _ = std::fs::remove_file(&path);
+            let checkpoint = checkpoint.expect("the finished render saved a checkpoint");
+            assert_eq!(checkpoint.samples_taken(), 12 * 10 * 8);
+
+            let resumed = renderer(40, guiding).with_resume(checkpoint).render();
+            let straight = renderer(40, guiding).render();
+            for y in 0..10 {
+                for x in 0..12 {
+                    assert_eq!(
+                        resumed.get_pixel(x, y),
+                        straight.get_pixel(x, y),
+                        "guiding {guiding}, pixel ({x}, {y})"
+                    );
+                    assert_eq!(resumed.get_alpha(x, y), straight.get_alpha(x, y));
+                }
+            }
+        }
+    }
+
+    /// A render cancelled half way down its first slice saves the rows it
+    /// finished and two samples of every other pixel, and resuming from
+    /// there still lands on the uninterrupted image.
+    #[test]
+    fn cancelled_render_resumes_mid_pass() {
+        let path = std::env::temp_dir().join("crust_checkpoint_cancelled.ckpt");
+        let cancel = CancelToken::new();
+        let token = cancel.clone();
+        renderer(40, false)
+            .with_checkpoints(path.clone(), Duration::from_secs(3600))
+            .with_cancel(cancel)
+            .render_with_progress(false, &move |done, _| {
+                if done == 5 {
+                    token.cancel();
+                }
+            });
+        let checkpoint = Checkpoint::load(&path);
+        let _ = std::fs::remove_file(&path);
+        let checkpoint = checkpoint.expect("the cancelled render saved a checkpoint");
+        let taken: Vec<u32> = checkpoint.pixels.iter().map(|p| p.taken).collect();
+        assert!(
+            taken.contains(&2),
+            "rows after the cancel stop at two samples"
+        );
+        assert!(taken.iter().any(|&t| t > 2), "rows before it keep theirs");
+
+        let resumed = renderer(40, false).with_resume(checkpoint).render();
+        let straight = renderer(40, false).render();
+        for y in 0..10 {
+            for x in 0..12 {
+                assert_eq!(
+                    resumed.get_pixel(x, y),
+                    straight.get_pixel(x, y),
+                    "pixel ({x}, {y})"
+                );
+                assert_eq!(resumed.get_alpha(x, y), straight.get_alpha(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn foreign_files_are_rejected() {
+        let path = std::env::temp_dir().join("crust_checkpoint_foreign.ckpt");
+        std::fs::write(&path, b"not a checkpoint").expect("write file");
+        assert!(matches!(
+            Checkpoint::load(&path),
+            Err(Error::Checkpoint { .. })
+        ));
+        let _ = std::fs::remove_file(&path);
+    }
+}
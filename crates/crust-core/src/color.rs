@@ -0,0 +1,207 @@
+//! The render working colour space: the RGB primaries every colour the
+//! integrator touches is expressed in.
+//!
+//! Scenes are authored in linear Rec.709 (USD's default for `color3f`), and
+//! that stays the default working space. Selecting ACEScg or Rec.2020
+//! (`crust:colorSpace`) converts at the door — imported colours, Ptex texels
+//! and environment maps — so the integrator itself stays
+//! primaries-agnostic, except where a scalar stands in for a colour:
+//! luminance for importance sampling and adaptive sampling, and the
+//! per-channel wavelengths of the spectral material effects (dispersion,
+//! thin-film), which must follow the primaries they represent.
+
+use glam::Vec3A;
+
+/// A linear working space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// Linear Rec.709 / sRGB primaries, D65.
+    #[default]
+    LinRec709,
+    /// ACES AP1 primaries, D60 — the ACES rendering space.
+    AcesCg,
+    /// Linear Rec.2020 primaries, D65.
+    LinRec2020,
+}
+
+/// Row-major 3×3: `out[i] = m[i] · c`.
+type Mat3 = [[f32; 3]; 3];
+
+fn mul(m: &Mat3, c: Vec3A) -> Vec3A {
+    Vec3A::new(
+        Vec3A::from(m[0]).dot(c),
+        Vec3A::from(m[1]).dot(c),
+        Vec3A::from(m[2]).dot(c),
+    )
+}
+
+// Linear Rec.709 → AP1, with a Bradford D65 → D60 adaptation, and back.
+const REC709_TO_ACESCG: Mat3 = [
+    [0.613_097_4, 0.339_523_1, 0.047_379_3],
+    [0.070_194_2, 0.916_355_6, 0.013_452_6],
+    [0.020_615_6, 0.109_569_8, 0.869_815_1],
+];
+const ACESCG_TO_REC709: Mat3 = [
+    [1.705_051, -0.621_792_1, -0.083_259],
+    [-0.130_256_4, 1.140_804_6, -0.010_548_3],
+    [-0.024_003_4, -0.128_969, 1.152_972_3],
+];
+// Linear Rec.709 → Rec.2020 (same white), and back.
+const REC709_TO_REC2020: Mat3 = [
+    [0.627_404, 0.329_282, 0.043_313_6],
+    [0.069_097, 0.919_54, 0.011_361_2],
+    [0.016_391_6, 0.088_013_2, 0.895_595],
+];
+const REC2020_TO_REC709: Mat3 = [
+    [1.660_491, -0.587_641_1, -0.072_849_9],
+    [-0.124_550_5, 1.132_899_9, -0.008_349_4],
+    [-0.018_150_8, -0.100_578_9, 1.118_729_7],
+];
+
+/// CIE xy of a space's primaries and white point, as the OpenEXR
+/// `chromaticities` attribute records them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chromaticities {
+    pub red: [f32; 2],
+    pub green: [f32; 2],
+    pub blue: [f32; 2],
+    pub white: [f32; 2],
+}
+
+impl ColorSpace {
+    pub const ALL: [ColorSpace; 3] = [
+        ColorSpace::LinRec709,
+        ColorSpace::AcesCg,
+        ColorSpace::LinRec2020,
+    ];
+
+    /// The name `crust:colorSpace` uses, after the OCIO / USD convention.
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorSpace::LinRec709 => "lin_rec709",
+            ColorSpace::AcesCg => "acescg",
+            ColorSpace::LinRec2020 => "lin_rec2020",
+        }
+    }
+
+    /// Parses [`ColorSpace::name`], case-insensitively, plus the common
+    /// aliases `lin_srgb`, `srgb_linear`, `rec709`, `aces_cg`, `rec2020`.
+    pub fn from_name(name: &str) -> Option<ColorSpace> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "lin_rec709" | "lin_srgb" | "srgb_linear" | "rec709" => Some(ColorSpace::LinRec709),
+            "acescg" | "aces_cg" => Some(ColorSpace::AcesCg),
+            "lin_rec2020" | "rec2020" => Some(ColorSpace::LinRec2020),
+            _ => None,
+        }
+    }
+
+    /// Converts a linear Rec.709 colour into this space. Exact identity
+    /// for Rec.709 itself, so the default renders bit for bit as before.
+    pub fn convert_rec709(self, c: Vec3A) -> Vec3A {
+        match self {
+            ColorSpace::LinRec709 => c,
+            ColorSpace::AcesCg => mul(&REC709_TO_ACESCG, c),
+            ColorSpace::LinRec2020 => mul(&REC709_TO_REC2020, c),
+        }
+    }
+
+    /// Converts a colour in this space to linear Rec.709 — what a display
+    /// pipeline built for sRGB expects.
+    pub fn to_rec709(self, c: Vec3A) -> Vec3A {
+        match self {
+            ColorSpace::LinRec709 => c,
+            ColorSpace::AcesCg => mul(&ACESCG_TO_REC709, c),
+            ColorSpace::LinRec2020 => mul(&REC2020_TO_REC709, c),
+        }
+    }
+
+    /// The Y row of the space's RGB → XYZ matrix: luminance weights.
+    pub fn luminance_weights(self) -> Vec3A {
+        match self {
+            ColorSpace::LinRec709 => Vec3A::new(0.2126, 0.7152, 0.0722),
+            ColorSpace::AcesCg => Vec3A::new(0.272_228_7, 0.674_081_8, 0.053_689_5),
+            ColorSpace::LinRec2020 => Vec3A::new(0.2627, 0.6780, 0.0593),
+        }
+    }
+
+    /// Relative luminance of a colour in this space. Importance sampling
+    /// only needs a scalar that tracks brightness; with the wrong weights
+    /// it tracks the wrong brightness, and the variance it was meant to
+    /// remove comes back.
+    pub fn luminance(self, c: Vec3A) -> f32 {
+        self.luminance_weights().dot(c)
+    }
+
+    pub fn chromaticities(self) -> Chromaticities {
+        const D65: [f32; 2] = [0.3127, 0.3290];
+        match self {
+            ColorSpace::LinRec709 => Chromaticities {
+                red: [0.64, 0.33],
+                green: [0.30, 0.60],
+                blue: [0.15, 0.06],
+                white: D65,
+            },
+            ColorSpace::AcesCg => Chromaticities {
+                red: [0.713, 0.293],
+                green: [0.165, 0.830],
+                blue: [0.128, 0.044],
+                white: [0.32168, 0.33767],
+            },
+            ColorSpace::LinRec2020 => Chromaticities {
+                red: [0.708, 0.292],
+                green: [0.170, 0.797],
+                blue: [0.131, 0.046],
+                white: D65,
+            },
+        }
+    }
+
+    /// Representative wavelengths (nm) of the R, G, B primaries — the
+    /// dominant wavelengths of each — for the per-channel spectral effects.
+    pub fn wavelengths(self) -> [f32; 3] {
+        match self {
+            ColorSpace::LinRec709 => [615.0, 545.0, 465.0],
+            ColorSpace::AcesCg => [632.0, 530.0, 467.0],
+            // Rec.2020's primaries are monochromatic.
+            ColorSpace::LinRec2020 => [630.0, 532.0, 467.0],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for space in ColorSpace::ALL {
+            assert_eq!(ColorSpace::from_name(space.name()), Some(space));
+        }
+        assert_eq!(ColorSpace::from_name("ACEScg"), Some(ColorSpace::AcesCg));
+        assert_eq!(
+            ColorSpace::from_name("lin_srgb"),
+            Some(ColorSpace::LinRec709)
+        );
+        assert_eq!(ColorSpace::from_name("xyz"), None);
+    }
+
+    /// White stays white, the two directions invert each other, and the
+    /// luminance of a colour survives the trip into another space.
+    #[test]
+    fn conversions_are_consistent() {
+        let c = Vec3A::new(0.8, 0.3, 0.1);
+        for space in ColorSpace::ALL {
+            let white = space.convert_rec709(Vec3A::ONE);
+            assert!((white - Vec3A::ONE).abs().max_element() < 1e-4, "{space:?}");
+            let back = space.to_rec709(space.convert_rec709(c));
+            assert!((back - c).abs().max_element() < 1e-4, "{space:?}");
+            let weights = space.luminance_weights();
+            assert!((weights.element_sum() - 1.0).abs() < 1e-4, "{space:?}");
+        }
+        // Rec.709 and Rec.2020 share a white, so luminance is preserved
+        // exactly up to rounding.
+        let y709 = ColorSpace::LinRec709.luminance(c);
+        let y2020 = ColorSpace::LinRec2020.luminance(ColorSpace::LinRec2020.convert_rec709(c));
+        assert!((y709 - y2020).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,388 @@
+//! Cryptomatte ID mattes (Friedman & Jones, "Fully Automatic ID Mattes
+//! with Support for Motion Blur and Transparency", SIGGRAPH 2015).
+//!
+//! Every geometry carries a name — its USD prim path for the object layer,
+//! its bound material's path for the material layer. A name is hashed with
+//! MurmurHash3 (x86, 32-bit, seed 0) and the hash is reinterpreted as a
+//! float (exponent clamped so it is never a denormal, infinity or NaN): that
+//! float is the ID stored in the image. Per pixel, the filtered coverage of
+//! each ID is accumulated with the beauty's FIS weights, and the strongest
+//! [`CRYPTO_RANKS`] are written as `(id, coverage)` pairs, two per RGBA
+//! channel group:
+//!
+//! ```text
+//! CryptoObject00.R = id₀   .G = coverage₀   .B = id₁   .A = coverage₁
+//! CryptoObject01.R = id₂   …
+//! ```
+//!
+//! The header carries a manifest mapping each name to its ID (as the hex of
+//! the float's bits) so a keyer can list mattes by name. Keys follow the
+//! specification: `cryptomatte/<first 7 hex digits of the layer name's
+//! hash>/{name,hash,conversion,manifest}`.
+
+use crate::rt_world::World;
+use std::collections::HashMap;
+
+/// `(id, coverage)` pairs kept per pixel and layer. Six is the depth the
+/// reference implementations default to, written as three RGBA groups.
+pub const CRYPTO_RANKS: usize = 6;
+
+/// One ID matte layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CryptoLayer {
+    /// Keyed on the prim path of the geometry hit.
+    Object,
+    /// Keyed on the path of the material bound to it.
+    Material,
+}
+
+impl CryptoLayer {
+    pub const ALL: [CryptoLayer; 2] = [CryptoLayer::Object, CryptoLayer::Material];
+
+    /// Layer name as written to the EXR — the channel prefix and the
+    /// `cryptomatte/<key>/name` value.
+    pub fn name(self) -> &'static str {
+        match self {
+            CryptoLayer::Object => "CryptoObject",
+            CryptoLayer::Material => "CryptoMaterial",
+        }
+    }
+
+    /// Parses `object` / `material` (case-insensitive), as `--cryptomatte`
+    /// and `crust:cryptomatte` spell them.
+    pub fn from_name(name: &str) -> Option<CryptoLayer> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "object" => Some(CryptoLayer::Object),
+            "material" => Some(CryptoLayer::Material),
+            _ => None,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A set of [`CryptoLayer`]s. `Copy`, so it can live in
+/// [`crate::RenderSettings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CryptoSet(u8);
+
+impl CryptoSet {
+    pub fn empty() -> Self {
+        CryptoSet(0)
+    }
+
+    pub fn all() -> Self {
+        CryptoLayer::ALL.into_iter().collect()
+    }
+
+    pub fn with(mut self, layer: CryptoLayer) -> Self {
+        self.0 |= layer.bit();
+        self
+    }
+
+    pub fn contains(self, layer: CryptoLayer) -> bool {
+        self.0 & layer.bit() != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Members in [`CryptoLayer::ALL`] order — the order `Buffer` stores
+    /// them in.
+    pub fn iter(self) -> impl Iterator<Item = CryptoLayer> {
+        CryptoLayer::ALL.into_iter().filter(move |l| self.contains(*l))
+    }
+
+    /// Parses a comma- or whitespace-separated list of layer names; `all`
+    /// selects both. Returns the first name that did not parse as `Err`.
+    pub fn parse(list: &str) -> Result<CryptoSet, String> {
+        let mut set = CryptoSet::empty();
+        for name in list
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+        {
+            if name.eq_ignore_ascii_case("all") {
+                set = CryptoSet::all();
+                continue;
+            }
+            set = set.with(CryptoLayer::from_name(name).ok_or_else(|| name.to_owned())?);
+        }
+        Ok(set)
+    }
+}
+
+impl FromIterator<CryptoLayer> for CryptoSet {
+    fn from_iter<I: IntoIterator<Item = CryptoLayer>>(iter: I) -> Self {
+        iter.into_iter().fold(CryptoSet::empty(), CryptoSet::with)
+    }
+}
+
+/// MurmurHash3, x86 32-bit variant.
+pub(crate) fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let mix = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+    let mut h = seed;
+    let blocks = data.chunks_exact(4);
+    let tail = blocks.remainder();
+    for block in blocks {
+        h ^= mix(u32::from_le_bytes([block[0], block[1], block[2], block[3]]));
+        h = h.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .enumerate()
+            .fold(0u32, |k, (i, b)| k | ((*b as u32) << (8 * i)));
+        h ^= mix(k);
+    }
+    h ^= data.len() as u32;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+/// The ID of `name`, as the bits of the float written to the image: the
+/// hash with its exponent clamped to `1..=254`, which keeps every ID a
+/// normal, finite float that survives any float pipeline untouched. Never
+/// zero, so zero can mean "no ID".
+pub(crate) fn name_id(name: &str) -> u32 {
+    let hash = murmur3_32(name.as_bytes(), 0);
+    let exponent = ((hash >> 23) & 0xff).clamp(1, 254);
+    (hash & !(0xff << 23)) | (exponent << 23)
+}
+
+/// The per-layer metadata key: the first seven hex digits of the layer
+/// name's hash.
+fn layer_key(name: &str) -> String {
+    format!("{:08x}", murmur3_32(name.as_bytes(), 0))[..7].to_owned()
+}
+
+/// The EXR header attributes describing one layer, as `(key, value)` text
+/// pairs: its name, hash and conversion method, and the name → ID manifest
+/// (JSON, IDs as 8-digit hex).
+pub fn cryptomatte_header(layer: &str, manifest: &str) -> Vec<(String, String)> {
+    let prefix = format!("cryptomatte/{}", layer_key(layer));
+    vec![
+        (format!("{prefix}/name"), layer.to_owned()),
+        (format!("{prefix}/hash"), "MurmurHash3_32".to_owned()),
+        (format!("{prefix}/conversion"), "uint32_to_float32".to_owned()),
+        (format!("{prefix}/manifest"), manifest.to_owned()),
+    ]
+}
+
+/// Appends `s` as a JSON string literal. Anything outside printable ASCII
+/// is `\u`-escaped, since EXR text attributes are not UTF-8.
+fn json_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            ' '..='~' => out.push(c),
+            c => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    out.push_str(&format!("\\u{unit:04x}"));
+                }
+            }
+        }
+    }
+    out.push('"');
+}
+
+/// Every geometry's IDs, hashed once per distinct name when a render first
+/// needs them, plus the manifests they produce.
+pub(crate) struct CryptoTable {
+    /// Indexed by `geom_id`, one ID per [`CryptoLayer::ALL`] entry; zero
+    /// for a geometry with no name in that layer.
+    ids: Vec<[u32; 2]>,
+    /// Per layer, the JSON manifest over every name in the world.
+    manifests: [String; 2],
+}
+
+impl CryptoTable {
+    pub fn new(world: &World) -> Self {
+        let mut ids = vec![[0u32; 2]; world.count()];
+        let mut manifests = [String::new(), String::new()];
+        for layer in CryptoLayer::ALL {
+            // Usually a handful of materials across thousands of
+            // geometries: hash each distinct name once.
+            let mut seen: HashMap<&str, u32> = HashMap::new();
+            for (geom_id, slot) in ids.iter_mut().enumerate() {
+                let name = match layer {
+                    CryptoLayer::Object => world.object_name(geom_id as u32),
+                    CryptoLayer::Material => world.material_name(geom_id as u32),
+                };
+                if let Some(name) = name {
+                    slot[layer as usize] =
+                        *seen.entry(name).or_insert_with(|| name_id(name));
+                }
+            }
+            let mut entries: Vec<_> = seen.into_iter().collect();
+            entries.sort_unstable();
+            let json = &mut manifests[layer as usize];
+            json.push('{');
+            for (k, (name, id)) in entries.iter().enumerate() {
+                if k > 0 {
+                    json.push(',');
+                }
+                json_string(name, json);
+                json.push_str(&format!(":\"{id:08x}\""));
+            }
+            json.push('}');
+        }
+        Self { ids, manifests }
+    }
+
+    pub fn manifest(&self, layer: CryptoLayer) -> &str {
+        &self.manifests[layer as usize]
+    }
+
+    fn id(&self, geom_id: u32, layer: CryptoLayer) -> u32 {
+        self.ids
+            .get(geom_id as usize)
+            .map_or(0, |ids| ids[layer as usize])
+    }
+}
+
+/// Per-pixel coverage accumulator: filter weight summed per geometry hit
+/// by a camera ray, resolved to ranked IDs once the pixel is done.
+pub(crate) struct CryptoAccum {
+    /// `(geom_id, weight)`; a pixel rarely sees more than a few geometries,
+    /// so a linear scan beats hashing.
+    weights: Vec<(u32, f32)>,
+}
+
+impl CryptoAccum {
+    pub fn new() -> Self {
+        Self {
+            weights: Vec::new(),
+        }
+    }
+
+    /// Adds one camera sample. Samples that hit nothing add nothing: their
+    /// coverage belongs to no ID.
+    pub fn add(&mut self, geom_id: Option<u32>, weight: f32) {
+        let Some(geom_id) = geom_id else { return };
+        match self.weights.iter_mut().find(|(g, _)| *g == geom_id) {
+            Some((_, w)) => *w += weight,
+            None => self.weights.push((geom_id, weight)),
+        }
+    }
+
+    /// The strongest [`CRYPTO_RANKS`] `(id, coverage)` pairs of each layer
+    /// in `set`, coverage descending and normalized like the beauty (`norm`
+    /// is the FIS weight sum). Geometries sharing a name merge.
+    pub fn finish(&self, table: &CryptoTable, set: CryptoSet, norm: f32) -> Vec<Vec<(f32, f32)>> {
+        set.iter()
+            .map(|layer| {
+                let mut ranks: Vec<(u32, f32)> = Vec::new();
+                for &(geom_id, w) in &self.weights {
+                    let id = table.id(geom_id, layer);
+                    if id == 0 {
+                        continue;
+                    }
+                    match ranks.iter_mut().find(|(i, _)| *i == id) {
+                        Some((_, c)) => *c += w / norm,
+                        None => ranks.push((id, w / norm)),
+                    }
+                }
+                ranks.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+                ranks
+                    .into_iter()
+                    .take(CRYPTO_RANKS)
+                    .map(|(id, c)| (f32::from_bits(id), c))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference vectors of the x86 32-bit variant.
+    #[test]
+    fn murmur3_matches_reference() {
+        assert_eq!(murmur3_32(b"", 0), 0);
+        assert_eq!(murmur3_32(b"", 1), 0x514e_28b7);
+        assert_eq!(murmur3_32(b"hello", 0), 0x248b_fa47);
+        assert_eq!(
+            murmur3_32(b"The quick brown fox jumps over the lazy dog", 0),
+            0x2e4f_f723
+        );
+    }
+
+    /// An ID must read back as an ordinary float: no NaN, infinity or
+    /// denormal a compositor could flush or canonicalize.
+    #[test]
+    fn ids_are_normal_floats() {
+        for name in ["/World/bunny", "/World/Looks/red", "", "x"] {
+            let f = f32::from_bits(name_id(name));
+            assert!(f.is_normal(), "{name:?} -> {f}");
+        }
+    }
+
+    #[test]
+    fn set_parses_layer_names() {
+        assert_eq!(
+            CryptoSet::parse("object").unwrap().iter().collect::<Vec<_>>(),
+            vec![CryptoLayer::Object]
+        );
+        assert_eq!(CryptoSet::parse("Material, object").unwrap(), CryptoSet::all());
+        assert_eq!(CryptoSet::parse("all").unwrap(), CryptoSet::all());
+        assert_eq!(CryptoSet::parse("asset"), Err("asset".to_owned()));
+    }
+
+    #[test]
+    fn header_keys_follow_the_spec() {
+        let header = cryptomatte_header("CryptoObject", "{}");
+        let key = &header[0].0;
+        assert!(key.starts_with("cryptomatte/") && key.ends_with("/name"));
+        assert_eq!(key.len(), "cryptomatte/".len() + 7 + "/name".len());
+        assert!(header.iter().all(|(k, _)| k.starts_with(&key[..19])));
+    }
+
+    /// Two geometries bound to one material are one material matte, and
+    /// ranks come out strongest first with coverage normalized.
+    #[test]
+    fn coverage_merges_by_name_and_ranks() {
+        let (a, b, shared) = (name_id("/a"), name_id("/b"), name_id("/Looks/m"));
+        let table = CryptoTable {
+            ids: vec![[a, shared], [b, shared]],
+            manifests: Default::default(),
+        };
+        let mut acc = CryptoAccum::new();
+        acc.add(Some(0), 1.0);
+        acc.add(Some(1), 1.0);
+        acc.add(Some(1), 1.0);
+        acc.add(None, 1.0);
+        let out = acc.finish(&table, CryptoSet::all(), 4.0);
+        assert_eq!(
+            out[0],
+            vec![(f32::from_bits(b), 0.5), (f32::from_bits(a), 0.25)]
+        );
+        assert_eq!(out[1], vec![(f32::from_bits(shared), 0.75)]);
+    }
+
+    #[test]
+    fn json_escapes_to_ascii() {
+        let mut s = String::new();
+        json_string("a\"b\\c", &mut s);
+        assert_eq!(s, r#""a\"b\\c""#);
+        s.clear();
+        json_string("é\n", &mut s);
+        assert_eq!(s, r#""\u00e9\u000a""#);
+    }
+}
@@ -0,0 +1,172 @@
+//! Deep output: per pixel, a depth-sorted list of samples, each with its own
+//! colour, alpha and depth range, instead of one flattened value — what a
+//! deep compositor needs to merge renders (and volumes) by depth rather
+//! than by a fixed layer order.
+//!
+//! Camera samples are merged before they reach the image, or a pixel would
+//! carry hundreds of entries. Samples that first hit the same geometry
+//! merge into one surface sample spanning their depths; samples whose first
+//! event was a volume scatter merge per depth slab, [`SLABS_PER_OCTAVE`]
+//! slabs to each doubling of distance, so a smoke element keeps its depth
+//! extent without one entry per scatter; samples that escaped (or met a
+//! holdout) merge into a single zero-alpha sample at infinity, which holds
+//! the background's radiance.
+//!
+//! Each merged sample then converts from *coverage* (the filtered share of
+//! the pixel's camera samples that ended there, which is what rendering
+//! produces) to *over* alpha (which is what a deep file stores): a sample
+//! behind others is divided by the coverage they leave. Flattening the
+//! samples front to back with `over` therefore gives back the flat beauty
+//! and alpha exactly.
+
+use glam::Vec3A;
+
+use crate::aov::AovSample;
+
+/// Volume slabs per doubling of camera distance — slabs about 9% as deep as
+/// they are far away.
+pub const SLABS_PER_OCTAVE: f32 = 8.0;
+
+/// One deep sample: premultiplied colour and `over` alpha between the
+/// camera distances `z` and `z_back`. A surface sample whose hits all lay
+/// at one depth has `z == z_back`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeepSample {
+    pub z: f32,
+    pub z_back: f32,
+    pub color: Vec3A,
+    pub alpha: f32,
+}
+
+/// What a camera sample's first event merges by.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DeepKey {
+    Surface(u32),
+    Volume(i32),
+    Background,
+}
+
+struct DeepBin {
+    key: DeepKey,
+    z: f32,
+    z_back: f32,
+    color: Vec3A,
+    coverage: f32,
+}
+
+/// Per-pixel deep accumulator, filtered with the beauty's weights.
+pub(crate) struct DeepAccum {
+    bins: Vec<DeepBin>,
+}
+
+impl DeepAccum {
+    pub fn new() -> Self {
+        Self { bins: Vec::new() }
+    }
+
+    /// Adds one camera sample: its radiance and alpha, keyed by the first
+    /// event `s` recorded.
+    pub fn add(&mut self, s: &AovSample, color: Vec3A, alpha: f32, weight: f32) {
+        let (key, z) = match (s.scatter_depth, s.geom_id, s.depth) {
+            (Some(d), _, _) => (
+                DeepKey::Volume((d.max(1e-6).log2() * SLABS_PER_OCTAVE).floor() as i32),
+                d,
+            ),
+            (None, Some(g), Some(d)) => (DeepKey::Surface(g), d),
+            _ => (DeepKey::Background, f32::INFINITY),
+        };
+        let bin = match self.bins.iter_mut().position(|b| b.key == key) {
+            Some(i) => &mut self.bins[i],
+            None => {
+                self.bins.push(DeepBin {
+                    key,
+                    z,
+                    z_back: z,
+                    color: Vec3A::ZERO,
+                    coverage: 0.0,
+                });
+                self.bins.last_mut().expect("just pushed")
+            }
+        };
+        bin.z = bin.z.min(z);
+        bin.z_back = bin.z_back.max(z);
+        bin.color += color * weight;
+        bin.coverage += alpha * weight;
+    }
+
+    /// The pixel's deep samples, front to back, normalized by `norm` like
+    /// the beauty. Samples fully hidden behind the ones in front are
+    /// dropped.
+    pub fn finish(mut self, norm: f32) -> Vec<DeepSample> {
+        self.bins.sort_by(|a, b| a.z.total_cmp(&b.z));
+        let mut out = Vec::with_capacity(self.bins.len());
+        // The share of the pixel not yet covered by the samples in front.
+        let mut open = 1.0f32;
+        for bin in self.bins {
+            if open <= 1e-6 {
+                break;
+            }
+            let coverage = (bin.coverage / norm).clamp(0.0, open);
+            out.push(DeepSample {
+                z: bin.z,
+                z_back: bin.z_back,
+                color: bin.color / norm / open,
+                alpha: coverage / open,
+            });
+            open -= coverage;
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(geom: u32, depth: f32) -> AovSample {
+        AovSample {
+            geom_id: Some(geom),
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    /// Flattening with `over` must give back the flat pixel.
+    #[test]
+    fn samples_flatten_to_the_beauty() {
+        let mut acc = DeepAccum::new();
+        acc.add(&hit(1, 5.0), Vec3A::splat(1.0), 1.0, 1.0);
+        acc.add(&hit(0, 2.0), Vec3A::splat(0.5), 1.0, 1.0);
+        acc.add(&hit(1, 5.5), Vec3A::splat(1.0), 1.0, 1.0);
+        acc.add(&AovSample::default(), Vec3A::splat(0.2), 0.0, 1.0);
+        let samples = acc.finish(4.0);
+
+        assert_eq!(samples.len(), 3);
+        assert_eq!((samples[0].z, samples[0].z_back), (2.0, 2.0));
+        assert_eq!((samples[1].z, samples[1].z_back), (5.0, 5.5));
+        assert!(samples[2].z.is_infinite() && samples[2].alpha == 0.0);
+
+        let (mut color, mut alpha) = (Vec3A::ZERO, 0.0);
+        for s in &samples {
+            color += s.color * (1.0 - alpha);
+            alpha += s.alpha * (1.0 - alpha);
+        }
+        assert!((color - Vec3A::splat((0.5 + 1.0 + 1.0 + 0.2) / 4.0)).abs().max_element() < 1e-6);
+        assert!((alpha - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn volume_scatters_merge_per_slab() {
+        let mut acc = DeepAccum::new();
+        for d in [4.0, 4.1, 8.0] {
+            let s = AovSample {
+                scatter_depth: Some(d),
+                ..Default::default()
+            };
+            acc.add(&s, Vec3A::ONE, 1.0, 1.0);
+        }
+        let samples = acc.finish(3.0);
+        assert_eq!(samples.len(), 2);
+        assert_eq!((samples[0].z, samples[0].z_back), (4.0, 4.1));
+    }
+}
@@ -0,0 +1,267 @@
+//! The built-in denoiser: a feature-guided non-local-means filter.
+//!
+//! Every pixel becomes a weighted average of the pixels in a window around
+//! it. Two things decide a neighbour's weight, and the stricter one wins
+//! (Rousselle et al., *Robust Denoising using Feature and Color
+//! Information*, 2013):
+//!
+//! - **Colour**: how far its 3×3 patch is from the pixel's own, measured in
+//!   units of the noise both carry. `render_pixel` already estimates the
+//!   variance of every pixel's mean, so a difference the noise explains costs
+//!   nothing and one it does not shuts the neighbour out — flat regions
+//!   average widely, real detail survives.
+//! - **Features**: the albedo, normal and depth of the first hit, which are
+//!   nearly noise-free at any spp. A texture edge, a crease or a silhouette
+//!   shows up there even where the beauty is too noisy to tell, and keeps
+//!   the filter from blurring across it.
+//!
+//! The beauty is all it changes: alpha and every other layer are written as
+//! rendered, and the noisy beauty is kept alongside for comparison.
+
+use glam::Vec3A;
+use rayon::prelude::*;
+
+use crate::aov::Aov;
+use crate::buffer::Buffer;
+
+/// Neighbours considered on each side of a pixel: a 15×15 window.
+const WINDOW_RADIUS: i32 = 7;
+/// Half-size of the patches compared for the colour weight: 3×3.
+const PATCH_RADIUS: usize = 1;
+/// How many standard deviations of noise a colour difference may span and
+/// still count as the same signal.
+const K_COLOR: f32 = 0.45;
+/// Guards the colour distance of noise-free pixels.
+const EPSILON: f32 = 1e-10;
+/// Feature bandwidths: albedo and normal differences are absolute, depth
+/// differences relative to the pixel's own depth.
+const SIGMA_ALBEDO: f32 = 0.1;
+const SIGMA_NORMAL: f32 = 0.3;
+const SIGMA_DEPTH: f32 = 0.05;
+
+/// The features of every pixel, row-major like the beauty.
+struct Features {
+    albedo: Vec<Vec3A>,
+    normal: Vec<Vec3A>,
+    depth: Vec<f32>,
+}
+
+/// Denoises `buffer`'s beauty, given the per-pixel variance of each pixel's
+/// mean luminance (row-major), and returns the filtered pixels. The buffer
+/// must carry the albedo, normal and depth AOVs.
+pub(crate) fn denoise(buffer: &Buffer, variance: &[f64]) -> Vec<Vec3A> {
+    let (width, height) = (buffer.width(), buffer.height());
+    let n = width * height;
+    let pixels = |f: &dyn Fn(usize, usize) -> Vec3A| {
+        (0..n).map(|k| f(k % width, k / width)).collect::<Vec<_>>()
+    };
+    let color = pixels(&|x, y| buffer.get_pixel(x, y));
+    let features = Features {
+        albedo: pixels(&|x, y| buffer.get_aov(x, y, Aov::Albedo)),
+        normal: pixels(&|x, y| buffer.get_aov(x, y, Aov::Normal)),
+        depth: (0..n)
+            .map(|k| buffer.get_aov(k % width, k / width, Aov::Depth).x)
+            .collect(),
+    };
+    // A pixel with fewer than two samples has no variance estimate; it is
+    // matched on colour as if it were noise-free, so only its features
+    // let neighbours in.
+    let variance: Vec<f32> = variance
+        .iter()
+        .map(|&v| if v.is_finite() { v as f32 } else { 0.0 })
+        .collect();
+
+    let mut weight_sum = vec![0.0f32; n];
+    let mut color_sum = vec![Vec3A::ZERO; n];
+    let mut distance = vec![0.0f32; n];
+    let mut scratch = vec![0.0f32; n];
+    for dy in -WINDOW_RADIUS..=WINDOW_RADIUS {
+        for dx in -WINDOW_RADIUS..=WINDOW_RADIUS {
+            let neighbour = |x: usize, y: usize| {
+                let (qx, qy) = (x as i32 + dx, y as i32 + dy);
+                (qx >= 0 && qy >= 0 && (qx as usize) < width && (qy as usize) < height)
+                    .then(|| qy as usize * width + qx as usize)
+            };
+            // Per-pixel colour distance to the neighbour at this offset...
+            distance
+                .par_chunks_mut(width)
+                .enumerate()
+                .for_each(|(y, row)| {
+                    for (x, d) in row.iter_mut().enumerate() {
+                        let p = y * width + x;
+                        *d = neighbour(x, y).map_or(0.0, |q| {
+                            color_distance(color[p], color[q], variance[p], variance[q])
+                        });
+                    }
+                });
+            // ...averaged over the patch, as two separable box passes...
+            box_filter(&distance, &mut scratch, width, 1, 0);
+            box_filter(&scratch, &mut distance, width, 0, 1);
+            // ...and turned into this neighbour's weight.
+            weight_sum
+                .par_chunks_mut(width)
+                .zip(color_sum.par_chunks_mut(width))
+                .enumerate()
+                .for_each(|(y, (weights, colors))| {
+                    for (x, (weight, sum)) in weights.iter_mut().zip(colors).enumerate() {
+                        let p = y * width + x;
+                        let Some(q) = neighbour(x, y) else { continue };
+                        if !color[q].is_finite() {
+                            continue;
+                        }
+                        let d = distance[p].max(features.distance(p, q));
+                        let w = (-d).exp();
+                        *weight += w;
+                        *sum += w * color[q];
+                    }
+                });
+        }
+    }
+    (0..n)
+        .map(|p| {
+            if weight_sum[p] > 0.0 {
+                color_sum[p] / weight_sum[p]
+            } else {
+                color[p]
+            }
+        })
+        .collect()
+}
+
+/// Rousselle's variance-cancelled colour distance, averaged over channels:
+/// zero (or below) when the difference is what the noise would produce.
+fn color_distance(p: Vec3A, q: Vec3A, var_p: f32, var_q: f32) -> f32 {
+    if !(p.is_finite() && q.is_finite()) {
+        return f32::INFINITY;
+    }
+    let diff = (p - q) * (p - q) - Vec3A::splat(var_p + var_p.min(var_q));
+    let scale = EPSILON + K_COLOR * K_COLOR * (var_p + var_q);
+    diff.element_sum() / (3.0 * scale)
+}
+
+/// Averages `src` over a `2·PATCH_RADIUS + 1` window along x (`sx = 1`) or
+/// y (`sy = 1`) into `dst`, over the samples inside the image.
+fn box_filter(src: &[f32], dst: &mut [f32], width: usize, sx: usize, sy: usize) {
+    let height = src.len() / width;
+    dst.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+        for (x, out) in row.iter_mut().enumerate() {
+            let (mut sum, mut count) = (0.0, 0.0);
+            for k in 0..=2 * PATCH_RADIUS {
+                let (Some(qx), Some(qy)) = (
+                    (x + k * sx).checked_sub(PATCH_RADIUS * sx),
+                    (y + k * sy).checked_sub(PATCH_RADIUS * sy),
+                ) else {
+                    continue;
+                };
+                if qx < width && qy < height {
+                    sum += src[qy * width + qx];
+                    count += 1.0;
+                }
+            }
+            *out = sum / count;
+        }
+    });
+}
+
+impl Features {
+    /// The largest of the three feature distances between pixels `p` and
+    /// `q`. Background pixels (infinite depth) match each other and nothing
+    /// else.
+    fn distance(&self, p: usize, q: usize) -> f32 {
+        let albedo =
+            (self.albedo[p] - self.albedo[q]).length_squared() / (SIGMA_ALBEDO * SIGMA_ALBEDO);
+        let normal =
+            (self.normal[p] - self.normal[q]).length_squared() / (SIGMA_NORMAL * SIGMA_NORMAL);
+        let (zp, zq) = (self.depth[p], self.depth[q]);
+        let depth = match (zp.is_finite(), zq.is_finite()) {
+            (true, true) => {
+                let rel = (zp - zq) / zp.max(zq).max(f32::MIN_POSITIVE);
+                rel * rel / (SIGMA_DEPTH * SIGMA_DEPTH)
+            }
+            (false, false) => 0.0,
+            _ => f32::INFINITY,
+        };
+        albedo.max(normal).max(depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aov::AovSet;
+    use crate::tracer::Renderer;
+    use crate::world::ball_on_floor;
+
+    /// Two flat, noisy halves with different albedos: the denoiser
+    /// flattens the noise inside each half and keeps the edge between them.
+    #[test]
+    fn noise_is_removed_and_feature_edges_kept() {
+        let (w, h) = (24, 16);
+        let aovs = AovSet::empty()
+            .with(Aov::Albedo)
+            .with(Aov::Normal)
+            .with(Aov::Depth);
+        let mut buffer = Buffer::with_aovs(w, h, aovs);
+        let mut variance = Vec::new();
+        let mut state = 1u32;
+        for y in 0..h {
+            for x in 0..w {
+                // xorshift: cheap deterministic noise, ±0.1 around the mean.
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let noise = (state as f32 / u32::MAX as f32 - 0.5) * 0.2;
+                let left = x < w / 2;
+                let base = if left { 0.2 } else { 0.8 };
+                buffer.set_pixel(x, y, Vec3A::splat(base + noise));
+                buffer.set_aov(x, y, Aov::Albedo, Vec3A::splat(base));
+                buffer.set_aov(x, y, Aov::Normal, Vec3A::Z);
+                buffer.set_aov(x, y, Aov::Depth, Vec3A::splat(5.0));
+                variance.push(0.01 / 3.0);
+            }
+        }
+        let denoised = denoise(&buffer, &variance);
+
+        let spread = |values: &[Vec3A], range: std::ops::Range<usize>| {
+            let (lo, hi) = range
+                .flat_map(|x| (0..h).map(move |y| y * w + x))
+                .map(|p| values[p].x)
+                .fold((f32::MAX, f32::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+            hi - lo
+        };
+        let noisy: Vec<Vec3A> = (0..w * h).map(|p| buffer.get_pixel(p % w, p / w)).collect();
+        assert!(spread(&denoised, 0..w / 2) < 0.5 * spread(&noisy, 0..w / 2));
+        for y in 0..h {
+            assert!(
+                (denoised[y * w + w / 2 - 1].x - 0.2).abs() < 0.05,
+                "row {y}"
+            );
+            assert!((denoised[y * w + w / 2].x - 0.8).abs() < 0.05, "row {y}");
+        }
+    }
+
+    /// A denoised render keeps the render it filtered as its noisy layer, and
+    /// the filtered beauty stays finite everywhere.
+    #[test]
+    fn denoised_render_keeps_the_noisy_beauty() {
+        let render = |enabled: bool| {
+            let (camera, world, lights, settings) = ball_on_floor();
+            Renderer::new(camera, world, lights, settings.with_denoise(enabled)).render()
+        };
+
+        let plain = render(false);
+        let denoised = render(true);
+        assert!(!plain.is_denoised());
+        assert!(denoised.is_denoised());
+        for y in 0..10 {
+            for x in 0..12 {
+                assert_eq!(
+                    denoised.get_noisy(x, y),
+                    plain.get_pixel(x, y),
+                    "pixel ({x}, {y})"
+                );
+                assert!(denoised.get_pixel(x, y).is_finite());
+            }
+        }
+    }
+}
@@ -17,27 +17,23 @@
 
 use glam::Vec3A;
 
-/// Perceptual weight used to decide where the light is. Importance
-/// sampling only needs a scalar that tracks brightness; the sampled
-/// radiance is always the full colour.
-fn luminance(c: Vec3A) -> f32 {
-    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
-}
+use crate::color::ColorSpace;
 
 /// A piecewise-constant 1D distribution over `[0, 1)`, sampled by inverting
-/// its CDF. The building block of the 2D environment distribution: one of
-/// these over rows, and one per row over columns.
-struct Distribution1D {
+/// its CDF. The building block of the 2D environment distribution (and of
+/// [`crate::ApertureImage`]'s): one of these over rows, and one per row over
+/// columns.
+pub(crate) struct Distribution1D {
     /// Unnormalized per-bin weights.
     func: Vec<f32>,
     /// `cdf[i]` is the summed weight below bin `i`; `len = func.len() + 1`.
     cdf: Vec<f32>,
     /// Integral of `func` over `[0, 1)` — the mean of `func`.
-    integral: f32,
+    pub(crate) integral: f32,
 }
 
 impl Distribution1D {
-    fn new(func: Vec<f32>) -> Self {
+    pub(crate) fn new(func: Vec<f32>) -> Self {
         let n = func.len();
         let mut cdf = Vec::with_capacity(n + 1);
         cdf.push(0.0);
@@ -67,7 +63,7 @@ impl Distribution1D {
 
     /// Inverts the CDF at `u`, returning `(x in [0,1), pdf, bin)`. The pdf
     /// is with respect to `x`, so it integrates to 1 over `[0, 1)`.
-    fn sample(&self, u: f32) -> (f32, f32, usize) {
+    pub(crate) fn sample(&self, u: f32) -> (f32, f32, usize) {
         // First index whose cdf exceeds `u`, minus one.
         let bin = match self
             .cdf
@@ -99,7 +95,9 @@ impl Distribution1D {
 /// A lat-long environment map with a 2D sampling distribution built over
 /// it.
 ///
-/// The distribution weights each texel by luminance **times `sin θ`**: a
+/// The distribution weights each texel by luminance (in the map's working
+/// space — it only decides where the light is; the sampled radiance is
+/// always the full colour) **times `sin θ`**: a
 /// lat-long image devotes as many pixels to a degree near the pole as near
 /// the equator, but those polar texels cover far less solid angle, and
 /// omitting the Jacobian would over-sample the poles and bias the estimate.
@@ -121,6 +119,24 @@ impl EnvironmentMap {
         if width == 0 || height == 0 || pixels.len() != width * height {
             return None;
         }
+        Some(Self::build(width, height, pixels, ColorSpace::LinRec709))
+    }
+
+    /// Converts the map's (linear Rec.709) pixels into `space` and rebuilds
+    /// the sampling distribution with that space's luminance weights.
+    pub fn in_color_space(self, space: ColorSpace) -> Self {
+        if space == ColorSpace::LinRec709 {
+            return self;
+        }
+        let pixels = self
+            .pixels
+            .into_iter()
+            .map(|c| space.convert_rec709(c))
+            .collect();
+        Self::build(self.width, self.height, pixels, space)
+    }
+
+    fn build(width: usize, height: usize, pixels: Vec<Vec3A>, space: ColorSpace) -> Self {
         let mut conditional = Vec::with_capacity(height);
         let mut row_weights = Vec::with_capacity(height);
         for y in 0..height {
@@ -128,19 +144,19 @@ impl EnvironmentMap {
             let theta = (y as f32 + 0.5) / height as f32 * std::f32::consts::PI;
             let sin_theta = theta.sin();
             let row: Vec<f32> = (0..width)
-                .map(|x| luminance(pixels[y * width + x]).max(0.0) * sin_theta)
+                .map(|x| space.luminance(pixels[y * width + x]).max(0.0) * sin_theta)
                 .collect();
             let d = Distribution1D::new(row);
             row_weights.push(d.integral);
             conditional.push(d);
         }
-        Some(Self {
+        Self {
             width,
             height,
             pixels,
             marginal: Distribution1D::new(row_weights),
             conditional,
-        })
+        }
     }
 
     pub fn width(&self) -> usize {
@@ -9,6 +9,18 @@ pub enum Error {
     NonUtf8Path(PathBuf),
     /// Opening or parsing the USD stage failed.
     UsdOpen { path: PathBuf, message: String },
+    /// A render checkpoint could not be read, or belongs to another render.
+    Checkpoint { path: PathBuf, message: String },
+    /// A partial render (see `Renderer::render_partial`) could not be read.
+    Partial { path: PathBuf, message: String },
+    /// A render that cannot be split into parts — see
+    /// `Renderer::render_partial`.
+    Split(String),
+    /// Partial renders do not add up to one frame.
+    Merge(String),
+    /// The camera or render settings asked for (see
+    /// [`crate::ImportOptions`]) are not in the stage.
+    Selection(String),
 }
 
 impl fmt::Display for Error {
@@ -20,6 +32,15 @@ impl fmt::Display for Error {
             Error::UsdOpen { path, message } => {
                 write!(f, "failed to open USD stage {}: {}", path.display(), message)
             }
+            Error::Checkpoint { path, message } => {
+                write!(f, "cannot resume from checkpoint {}: {}", path.display(), message)
+            }
+            Error::Partial { path, message } => {
+                write!(f, "cannot read partial render {}: {}", path.display(), message)
+            }
+            Error::Split(message) => write!(f, "cannot render a partial frame: {message}"),
+            Error::Merge(message) => write!(f, "cannot merge partial renders: {message}"),
+            Error::Selection(message) => write!(f, "cannot select the render's prims: {message}"),
         }
     }
 }
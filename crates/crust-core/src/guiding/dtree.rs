@@ -8,6 +8,7 @@
 //! level; sampling proportional to flux and pdf evaluation both descend the
 //! same path, which keeps them exactly consistent.
 
+use crate::checkpoint::{ByteReader, ByteWriter};
 use glam::Vec3A;
 use std::f32::consts::{PI, TAU};
 
@@ -289,6 +290,38 @@ impl DTree {
         }
         rec(&self.nodes, 0)
     }
+
+    pub fn write(&self, w: &mut ByteWriter) {
+        w.u32(self.nodes.len() as u32);
+        for n in &self.nodes {
+            for s in n.sums {
+                w.f32(s);
+            }
+            for c in n.children {
+                w.u32(c);
+            }
+        }
+    }
+
+    /// Reads what [`DTree::write`] wrote; `None` if it is truncated or a
+    /// child index does not point further down the node list (children are
+    /// always stored after their parent, so this also rules out cycles).
+    pub fn read(r: &mut ByteReader) -> Option<Self> {
+        let count = r.u32()?;
+        let mut nodes = Vec::new();
+        for i in 0..count {
+            let sums = [r.f32()?, r.f32()?, r.f32()?, r.f32()?];
+            let children = [r.u32()?, r.u32()?, r.u32()?, r.u32()?];
+            if children
+                .iter()
+                .any(|&c| c != NO_CHILD && (c <= i || c >= count))
+            {
+                return None;
+            }
+            nodes.push(DNode { sums, children });
+        }
+        (!nodes.is_empty()).then_some(DTree { nodes })
+    }
 }
 
 #[cfg(test)]
@@ -6,6 +6,7 @@
 use super::dtree::{canonical_to_dir, dir_to_canonical};
 use super::sdtree::SDTree;
 use crate::aabb::AABB;
+use crate::checkpoint::{ByteReader, ByteWriter};
 use glam::Vec3A;
 
 /// Tuning knobs for the guiding field. Defaults follow the PPG paper, with a
@@ -110,6 +111,33 @@ impl GuidingField {
             self.cfg.dtree_max_depth,
         );
     }
+
+    /// Serializes the trained field into a render checkpoint.
+    pub(crate) fn write(&self, w: &mut ByteWriter) {
+        w.u32(self.cfg.train_iterations);
+        w.f32(self.cfg.guide_prob);
+        w.f32(self.cfg.spatial_c);
+        w.f32(self.cfg.dtree_rho);
+        w.u32(self.cfg.dtree_max_depth);
+        w.u32(self.cfg.spatial_max_depth);
+        self.tree.write(w);
+    }
+
+    /// The inverse of [`GuidingField::write`]; `None` on malformed data.
+    pub(crate) fn read(r: &mut ByteReader) -> Option<Self> {
+        let cfg = GuidingConfig {
+            train_iterations: r.u32()?,
+            guide_prob: r.f32()?,
+            spatial_c: r.f32()?,
+            dtree_rho: r.f32()?,
+            dtree_max_depth: r.u32()?,
+            spatial_max_depth: r.u32()?,
+        };
+        Some(GuidingField {
+            tree: SDTree::read(r)?,
+            cfg,
+        })
+    }
 }
 
 #[cfg(test)]
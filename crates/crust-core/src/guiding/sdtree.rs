@@ -6,6 +6,7 @@
 
 use super::dtree::DTree;
 use crate::aabb::AABB;
+use crate::checkpoint::{ByteReader, ByteWriter};
 use glam::Vec3A;
 
 #[derive(Clone, Debug)]
@@ -167,6 +168,79 @@ impl SDTree {
         }
     }
 
+    /// Serializes the tree, flux and structure, for a render checkpoint.
+    pub fn write(&self, w: &mut ByteWriter) {
+        w.vec3(self.bounds.minimum);
+        w.vec3(self.bounds.maximum);
+        w.u32(self.nodes.len() as u32);
+        for node in &self.nodes {
+            match node {
+                SNode::Inner {
+                    axis,
+                    split,
+                    children,
+                } => {
+                    w.u8(0);
+                    w.u32(*axis as u32);
+                    w.f32(*split);
+                    w.u32(children[0]);
+                    w.u32(children[1]);
+                }
+                SNode::Leaf {
+                    dtree,
+                    sample_count,
+                } => {
+                    w.u8(1);
+                    w.u32(*dtree);
+                    w.u64(*sample_count);
+                }
+            }
+        }
+        w.u32(self.dtrees.len() as u32);
+        for dtree in &self.dtrees {
+            dtree.write(w);
+        }
+    }
+
+    /// Reads what [`SDTree::write`] wrote, checking every index so a corrupt
+    /// file cannot make a lookup go out of bounds or loop.
+    pub fn read(r: &mut ByteReader) -> Option<Self> {
+        let bounds = AABB::new(r.vec3()?, r.vec3()?);
+        let node_count = r.u32()?;
+        let mut nodes = Vec::new();
+        for _ in 0..node_count {
+            nodes.push(match r.u8()? {
+                0 => SNode::Inner {
+                    axis: r.u32()? as usize,
+                    split: r.f32()?,
+                    children: [r.u32()?, r.u32()?],
+                },
+                _ => SNode::Leaf {
+                    dtree: r.u32()?,
+                    sample_count: r.u64()?,
+                },
+            });
+        }
+        let dtree_count = r.u32()?;
+        let mut dtrees = Vec::new();
+        for _ in 0..dtree_count {
+            dtrees.push(DTree::read(r)?);
+        }
+        // Children are stored after their parent, which rules out cycles.
+        let valid = !nodes.is_empty()
+            && nodes.iter().zip(0..).all(|(n, i)| match n {
+                SNode::Inner { axis, children, .. } => {
+                    *axis < 3 && children.iter().all(|&c| c > i && c < node_count)
+                }
+                SNode::Leaf { dtree, .. } => *dtree < dtree_count,
+            });
+        valid.then_some(SDTree {
+            nodes,
+            dtrees,
+            bounds,
+        })
+    }
+
     /// Number of spatial leaves (test/diagnostics helper).
     #[allow(dead_code)]
     pub fn leaf_count(&self) -> usize {
@@ -0,0 +1,446 @@
+//! Realistic lens systems: camera rays traced through a tabulated stack of
+//! spherical glass elements, after pbrt's `RealisticCamera` (Pharr, Jakob &
+//! Humphreys, *Physically Based Rendering*, 3rd ed., §6.4).
+//!
+//! A prescription lists the lens's surfaces from the front (scene side) to
+//! the rear (film side), one per line, as four numbers: the surface's
+//! radius of curvature (`0` for the aperture stop, a flat disk), the axial
+//! thickness from it to the next surface (the last one's is the distance
+//! to the film), the index of refraction behind it (`0` or `1` for air), and
+//! its aperture diameter. Lengths are millimetres, the unit patents and
+//! pbrt's `.dat` files use; `#` starts a comment. That is all a double
+//! Gauss or a vintage Petzval needs, and what the lens makes of it —
+//! barrel distortion, the cat's-eye vignetting of its barrel, the focus
+//! breathing as its rear group moves — comes out of the tracing rather
+//! than being modelled.
+//!
+//! # Conventions
+//!
+//! Lens space is the camera's frame in millimetres: the film sits at
+//! `z = 0`, the elements at negative `z`, and the scene beyond them, so it
+//! looks down `-z` like every camera in crust.
+
+use glam::{Vec2, Vec3A};
+use rayon::prelude::*;
+
+/// The film is split into this many rings, each with its own exit-pupil
+/// bound.
+const PUPIL_INTERVALS: usize = 64;
+/// Rays traced to find each ring's exit pupil.
+const PUPIL_SAMPLES: u32 = 1 << 14;
+
+/// One surface of a lens prescription, in millimetres.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Element {
+    /// Radius of curvature: positive when the centre lies behind the
+    /// surface (towards the film), `0` for the flat aperture stop.
+    radius: f32,
+    thickness: f32,
+    /// Index of refraction between this surface and the next; `1` is air.
+    eta: f32,
+    aperture_radius: f32,
+}
+
+/// An axis-aligned rectangle on the rear element's plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bounds {
+    min: Vec2,
+    max: Vec2,
+}
+
+impl Bounds {
+    const EMPTY: Bounds = Bounds {
+        min: Vec2::splat(f32::INFINITY),
+        max: Vec2::splat(f32::NEG_INFINITY),
+    };
+
+    fn area(&self) -> f32 {
+        let d = (self.max - self.min).max(Vec2::ZERO);
+        d.x * d.y
+    }
+}
+
+/// A lens prescription focused for a film, with the exit pupil bounded for
+/// every distance from the film's centre. See the module docs.
+#[derive(Debug, Clone)]
+pub struct LensSystem {
+    elements: Vec<Element>,
+    /// The film's width and height, in millimetres.
+    film: Vec2,
+    /// Per film ring, where rays from it can leave the rear element.
+    pupil: Vec<Bounds>,
+    /// Scales ray weights so the film's centre sees the scene at its own
+    /// brightness: its pupil bound is a rectangle around a round stop.
+    exposure: f32,
+}
+
+impl LensSystem {
+    /// Reads a prescription (see the module docs) into the rows
+    /// [`LensSystem::new`] takes. Fails with a line-level message on
+    /// anything malformed.
+    pub fn parse(text: &str) -> Result<Vec<[f32; 4]>, String> {
+        let mut rows = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let values: Vec<f32> = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("line {}: {e}", n + 1))?;
+            let row: [f32; 4] = values
+                .try_into()
+                .map_err(|v: Vec<f32>| format!("line {}: {} values, expected 4", n + 1, v.len()))?;
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Err("no lens surfaces".to_owned());
+        }
+        Ok(rows)
+    }
+
+    /// Builds the lens from parsed prescription `rows` for a `film` (width,
+    /// height, in millimetres), focused at `focus_distance` millimetres from
+    /// the film by moving the rear group, as most lenses focus. With
+    /// `f_stop` (when positive) the stop closes to the effective focal
+    /// length over it — it never opens past the prescription's own stop.
+    ///
+    /// Fails if no ray gets through the lens or it cannot focus that close.
+    pub fn new(
+        rows: &[[f32; 4]],
+        film: Vec2,
+        focus_distance: f32,
+        f_stop: f32,
+    ) -> Result<LensSystem, String> {
+        let elements = rows
+            .iter()
+            .map(|&[radius, thickness, eta, diameter]| Element {
+                radius,
+                thickness,
+                eta: if eta == 0.0 { 1.0 } else { eta },
+                aperture_radius: 0.5 * diameter,
+            })
+            .collect();
+        let mut lens = LensSystem {
+            elements,
+            film,
+            pupil: Vec::new(),
+            exposure: 1.0,
+        };
+        let (principal, focal) = lens.thick_lens()?;
+        let focal_length = focal[0] - principal[0];
+        if f_stop > 0.0
+            && let Some(stop) = lens.elements.iter_mut().find(|e| e.radius == 0.0)
+        {
+            stop.aperture_radius = stop.aperture_radius.min(0.5 * focal_length.abs() / f_stop);
+        }
+        let rear = lens.elements.len() - 1;
+        lens.elements[rear].thickness = lens.focus_thickness(focus_distance)?;
+        let ring = 0.5 * lens.film.length() / PUPIL_INTERVALS as f32;
+        lens.pupil = (0..PUPIL_INTERVALS)
+            .into_par_iter()
+            .map(|i| lens.bound_exit_pupil(ring * i as f32, ring * (i + 1) as f32))
+            .collect();
+        if lens.pupil[0].area() <= 0.0 {
+            return Err("no ray from the film's centre gets through the lens".to_owned());
+        }
+        let centre: f32 = (0..1024)
+            .filter_map(|i| {
+                let u = [radical_inverse(2, i), radical_inverse(3, i)];
+                lens.sample_ray(Vec2::splat(0.5), u)
+            })
+            .map(|(_, _, weight)| weight)
+            .sum();
+        lens.exposure = 1024.0 / centre;
+        Ok(lens)
+    }
+
+    /// The film's size, in millimetres.
+    pub fn film(&self) -> Vec2 {
+        self.film
+    }
+
+    /// The lens's effective focal length, in millimetres.
+    pub fn focal_length(&self) -> f32 {
+        self.thick_lens()
+            .map(|(p, f)| f[0] - p[0])
+            .unwrap_or(f32::NAN)
+    }
+
+    fn rear_z(&self) -> f32 {
+        -self.elements.last().map_or(0.0, |e| e.thickness)
+    }
+
+    fn front_z(&self) -> f32 {
+        -self.elements.iter().map(|e| e.thickness).sum::<f32>()
+    }
+
+    /// A ray from film point `film` (in `[0, 1]²`, `(0, 0)` the bottom-left
+    /// of the *image* — the film itself sees it upside down) through the
+    /// exit pupil, by 2D sample `u`: its origin and direction in lens space
+    /// as it leaves the front element, and its weight — the cos⁴ falloff
+    /// and the exit pupil's size relative to the centre's, averaging 1 at
+    /// the centre of the film. `None` where the lens's elements block it:
+    /// vignetting.
+    pub(crate) fn sample_ray(&self, film: Vec2, u: [f32; 2]) -> Option<(Vec3A, Vec3A, f32)> {
+        let p = (Vec2::splat(0.5) - film) * self.film;
+        let r = p.length();
+        let ring = ((r / (0.5 * self.film.length())) * PUPIL_INTERVALS as f32) as usize;
+        let bounds = self.pupil[ring.min(PUPIL_INTERVALS - 1)];
+        // The bound is for points on the +x axis; rotate it around to `p`.
+        let q = bounds.min + (bounds.max - bounds.min) * Vec2::new(u[0], u[1]);
+        let (sin, cos) = if r > 0.0 {
+            (p.y / r, p.x / r)
+        } else {
+            (0.0, 1.0)
+        };
+        let rear = Vec3A::new(cos * q.x - sin * q.y, sin * q.x + cos * q.y, self.rear_z());
+        let origin = Vec3A::new(p.x, p.y, 0.0);
+        let direction = rear - origin;
+        let cos_theta = direction.normalize().z.abs();
+        let (o, d) = self.trace_from_film(origin, direction)?;
+        let weight = self.exposure * cos_theta.powi(4) * bounds.area() / self.pupil[0].area();
+        Some((o, d, weight))
+    }
+
+    /// Traces a ray from the film side out through every element, or
+    /// `None` if one blocks it.
+    fn trace_from_film(&self, mut o: Vec3A, mut d: Vec3A) -> Option<(Vec3A, Vec3A)> {
+        let mut z = 0.0;
+        for (i, element) in self.elements.iter().enumerate().rev() {
+            z -= element.thickness;
+            let (o2, normal) = intersect(element, z, o, d)?;
+            o = o2;
+            if let Some(n) = normal {
+                let eta_t = if i > 0 { self.elements[i - 1].eta } else { 1.0 };
+                d = refract(-d.normalize(), n, element.eta / eta_t)?;
+            }
+        }
+        Some((o, d))
+    }
+
+    /// Traces a ray from the scene side in through every element.
+    fn trace_from_scene(&self, mut o: Vec3A, mut d: Vec3A) -> Option<(Vec3A, Vec3A)> {
+        let mut z = self.front_z();
+        for (i, element) in self.elements.iter().enumerate() {
+            let (o2, normal) = intersect(element, z, o, d)?;
+            o = o2;
+            if let Some(n) = normal {
+                let eta_i = if i > 0 { self.elements[i - 1].eta } else { 1.0 };
+                d = refract(-d.normalize(), n, eta_i / element.eta)?;
+            }
+            z += element.thickness;
+        }
+        Some((o, d))
+    }
+
+    /// The thick-lens approximation's principal planes and focal points,
+    /// `[film side, scene side]`, found with a ray parallel to the axis from
+    /// each side.
+    fn thick_lens(&self) -> Result<([f32; 2], [f32; 2]), String> {
+        let x = 0.001 * self.film.length();
+        let failed = || "a paraxial ray does not get through the lens".to_owned();
+        let scene_in = (Vec3A::new(x, 0.0, self.front_z() - 1.0), Vec3A::Z);
+        let film_out = self
+            .trace_from_scene(scene_in.0, scene_in.1)
+            .ok_or_else(failed)?;
+        let (p0, f0) = cardinal_points(scene_in, film_out);
+        let film_in = (Vec3A::new(x, 0.0, self.rear_z() + 1.0), Vec3A::NEG_Z);
+        let scene_out = self
+            .trace_from_film(film_in.0, film_in.1)
+            .ok_or_else(failed)?;
+        let (p1, f1) = cardinal_points(film_in, scene_out);
+        Ok(([p0, p1], [f0, f1]))
+    }
+
+    /// The rear element's distance from the film that focuses the lens at
+    /// `distance` millimetres from the film.
+    fn focus_thickness(&self, distance: f32) -> Result<f32, String> {
+        let (p, f) = self.thick_lens()?;
+        let focal_length = f[0] - p[0];
+        let z = -distance;
+        let c = (p[1] - z - p[0]) * (p[1] - z - 4.0 * focal_length - p[0]);
+        if c <= 0.0 {
+            return Err(format!(
+                "cannot focus at {distance} mm: closer than the lens allows"
+            ));
+        }
+        let delta = 0.5 * (p[1] - z + p[0] - c.sqrt());
+        Ok(self.elements.last().map_or(0.0, |e| e.thickness) + delta)
+    }
+
+    /// Bounds where rays from film points between `r0` and `r1` millimetres
+    /// out along +x leave the rear element and make it through the lens.
+    fn bound_exit_pupil(&self, r0: f32, r1: f32) -> Bounds {
+        let rear = self.elements.last().map_or(0.0, |e| e.aperture_radius);
+        let search = 1.5 * rear;
+        let mut bounds = Bounds::EMPTY;
+        for i in 0..PUPIL_SAMPLES {
+            let x = r0 + (r1 - r0) * (i as f32 + 0.5) / PUPIL_SAMPLES as f32;
+            let u = Vec2::new(radical_inverse(2, i), radical_inverse(3, i));
+            let q = Vec2::splat(-search) + 2.0 * search * u;
+            let inside = q.cmpge(bounds.min).all() && q.cmple(bounds.max).all();
+            if inside {
+                continue;
+            }
+            let origin = Vec3A::new(x, 0.0, 0.0);
+            let rear_point = Vec3A::new(q.x, q.y, self.rear_z());
+            if self.trace_from_film(origin, rear_point - origin).is_some() {
+                bounds.min = bounds.min.min(q);
+                bounds.max = bounds.max.max(q);
+            }
+        }
+        if bounds.area() <= 0.0 {
+            return bounds;
+        }
+        // Pad by a couple of sample spacings: the samples only approach
+        // the edge.
+        let pad = 4.0 * search * std::f32::consts::SQRT_2 / (PUPIL_SAMPLES as f32).sqrt();
+        Bounds {
+            min: bounds.min - pad,
+            max: bounds.max + pad,
+        }
+    }
+}
+
+/// Where a ray meets a surface at axial position `z`, and the surface's
+/// normal there facing back along the ray (`None` for the flat stop, which
+/// does not bend it). `None` if it misses, or lands outside the aperture.
+fn intersect(element: &Element, z: f32, o: Vec3A, d: Vec3A) -> Option<(Vec3A, Option<Vec3A>)> {
+    let (t, normal) = if element.radius == 0.0 {
+        ((z - o.z) / d.z, None)
+    } else {
+        let centre = Vec3A::new(0.0, 0.0, z + element.radius);
+        let oc = o - centre;
+        let a = d.length_squared();
+        let b = 2.0 * d.dot(oc);
+        let c = oc.length_squared() - element.radius * element.radius;
+        let disc = b * b - 4.0 * a * c;
+        if disc < 0.0 {
+            return None;
+        }
+        let root = disc.sqrt();
+        let (t0, t1) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+        // Of the sphere's two crossings, the surface is the one on the
+        // lens's side of the centre.
+        let closer = (d.z > 0.0) ^ (element.radius < 0.0);
+        let t = if closer { t0.min(t1) } else { t0.max(t1) };
+        let n = (oc + t * d).normalize();
+        (t, Some(if n.dot(d) > 0.0 { -n } else { n }))
+    };
+    // A ray parallel to the stop never meets it: NaN.
+    if t.is_nan() || t <= 0.0 {
+        return None;
+    }
+    let hit = o + t * d;
+    let r = element.aperture_radius;
+    (hit.x * hit.x + hit.y * hit.y <= r * r).then_some((hit, normal))
+}
+
+/// Snell's law: the direction `wi` (pointing away from the surface, on
+/// the normal's side) refracts into, for `eta` = incident over
+/// transmitted index; `None` on total internal reflection.
+fn refract(wi: Vec3A, n: Vec3A, eta: f32) -> Option<Vec3A> {
+    let cos_i = n.dot(wi);
+    let sin2_t = eta * eta * (1.0 - cos_i * cos_i).max(0.0);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(eta * -wi + (eta * cos_i - cos_t) * n)
+}
+
+/// The principal plane and focal point along the axis of a lens that bends
+/// axis-parallel ray `into` into `out`.
+fn cardinal_points(into: (Vec3A, Vec3A), out: (Vec3A, Vec3A)) -> (f32, f32) {
+    let (o, d) = out;
+    let focal = o.z + d.z * (-o.x / d.x);
+    let principal = o.z + d.z * ((into.0.x - o.x) / d.x);
+    (principal, focal)
+}
+
+/// `i`'s digits in `base`, mirrored about the radix point.
+fn radical_inverse(base: u32, mut i: u32) -> f32 {
+    let inv = 1.0 / base as f32;
+    let (mut result, mut scale) = (0.0, inv);
+    while i > 0 {
+        result += (i % base) as f32 * scale;
+        i /= base;
+        scale *= inv;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// pbrt's `dgauss.50mm.dat`: a 50 mm double Gauss at f/2.
+    const DOUBLE_GAUSS: &str = "
+        # radius  thickness  ior    aperture
+        29.475    3.76       1.67   25.2
+        84.83     0.12       1      25.2
+        19.275    4.025      1.67   23
+        40.77     3.275      1.699  23
+        12.75     5.705      1      18
+        0         4.5        1      17.1
+        -14.495   1.18       1.603  17
+        40.77     6.065      1.658  20
+        -20.385   0.19       1      20
+        437.065   3.22       1.717  20
+        -39.73    40         1      20
+    ";
+
+    fn lens(focus: f32) -> LensSystem {
+        let rows = LensSystem::parse(DOUBLE_GAUSS).expect("a valid prescription");
+        LensSystem::new(&rows, Vec2::new(36.0, 24.0), focus, 0.0).expect("the lens focuses")
+    }
+
+    #[test]
+    fn prescriptions_parse_and_reject_garbage() {
+        assert_eq!(LensSystem::parse(DOUBLE_GAUSS).unwrap().len(), 11);
+        assert!(LensSystem::parse("1 2 3").unwrap_err().contains("line 1"));
+        assert!(LensSystem::parse("# nothing").is_err());
+        assert!(LensSystem::parse("1 2 x 4").is_err());
+    }
+
+    #[test]
+    fn a_double_gauss_is_fifty_millimetres_and_focuses() {
+        let far = lens(100_000.0);
+        assert!(
+            (far.focal_length() - 50.0).abs() < 1.5,
+            "{}",
+            far.focal_length()
+        );
+        // Rays from the centre of the film converge back onto the axis at
+        // the focus distance; focusing closer moves the rear group out.
+        let near = lens(1_000.0);
+        assert!(near.rear_z() < far.rear_z());
+        for u in [[0.3, 0.5], [0.7, 0.6], [0.5, 0.2]] {
+            let (o, d, _) = near
+                .sample_ray(Vec2::splat(0.5), u)
+                .expect("the centre is clear");
+            let t = (-1_000.0 - o.z) / d.z;
+            let p = o + t * d;
+            assert!(p.x.hypot(p.y) < 1.0, "{p:?} misses focus");
+        }
+    }
+
+    #[test]
+    fn the_corners_vignette() {
+        let lens = lens(10_000.0);
+        let weight = |film: Vec2| {
+            let mut sum = 0.0;
+            for i in 0..256u32 {
+                let u = [radical_inverse(2, i), radical_inverse(3, i)];
+                sum += lens.sample_ray(film, u).map_or(0.0, |(_, _, w)| w);
+            }
+            sum / 256.0
+        };
+        let (centre, corner) = (weight(Vec2::splat(0.5)), weight(Vec2::new(0.98, 0.98)));
+        assert!((centre - 1.0).abs() < 0.05, "{centre}");
+        assert!(corner < 0.5 * centre, "{corner}");
+    }
+}
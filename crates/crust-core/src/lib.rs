@@ -1,14 +1,25 @@
 mod aabb;
+mod aov;
+mod aperture;
 mod buffer;
 mod camera;
+mod checkpoint;
+mod color;
+mod cryptomatte;
+mod deep;
+mod denoise;
 mod error;
 mod environment;
 mod filter;
 mod guiding;
 mod hittable;
+mod lens;
 mod light;
+mod light_tree;
+mod lpe;
 mod material;
 mod medium;
+mod partial;
 mod ray;
 mod rt_world;
 mod scene;
@@ -16,6 +27,7 @@ mod stats;
 mod texture;
 mod tracer;
 mod volume;
+mod window;
 mod world;
 
 /// The path tracer's QMC sampler: OpenQMC's Owen-scrambled Sobol, consumed
@@ -28,29 +40,43 @@ pub type PathSampler = openqmc::SobolSampler;
 pub use crust_rt as rt;
 
 pub use aabb::AABB;
+pub use aov::{Aov, AovSet};
+pub use aperture::{Aperture, ApertureImage};
 pub use buffer::Buffer;
-pub use camera::Camera;
+pub use camera::{Camera, Projection, Shutter};
+pub use checkpoint::Checkpoint;
+pub use color::{Chromaticities, ColorSpace};
+pub use cryptomatte::{CRYPTO_RANKS, CryptoLayer, CryptoSet, cryptomatte_header};
+pub use deep::{DeepSample, SLABS_PER_OCTAVE};
 pub use error::Error;
 pub use filter::{FilterSampler, PixelFilter};
 pub use glam::{Mat4, Vec3A};
 pub use guiding::{GuidingConfig, GuidingField, SampleData};
 pub use hittable::HitRecord;
+pub use lens::LensSystem;
 pub use environment::EnvironmentMap;
-pub use scene::{AssetLoader, NoAssets};
+pub use lpe::LightPathExpr;
+pub use scene::{AssetLoader, ImportOptions, NoAssets};
 pub use light::{
-    AreaLight, DistantLight, DomeLight, Light, LightList, LightSample, LightShape, RectShape,
-    SphereShape,
+    AreaLight, CylinderShape, DiskShape, DistantLight, DomeLight, Light, LightList, LightSample,
+    LightShape, MeshLight, RectShape, SphereShape,
 };
+pub use light_tree::LightBounds;
 pub use material::*;
 pub use medium::Medium;
+pub use partial::{PartialRender, TileSplit};
 pub use ray::{MASK_ALL, MASK_CAMERA, MASK_INDIRECT, MASK_SHADOW, Ray};
 pub use rt_world::{FaceMap, FanSlice, World, WorldBuilder, WorldHit};
-pub use scene::Scene;
+pub use scene::{Scene, SceneSequence};
 pub use stats::{
     ImageCounters, MemorySample, Phase, PrimitiveCounts, RayStats, RenderStats, SceneCounters,
     peak_memory_bytes,
 };
 pub use texture::{PtexRef, PtexTexture};
-pub use tracer::{ProgressCallback, RenderSettings, Renderer, SamplingStrategy, ray_color};
+pub use tracer::{
+    CancelToken, PassCallback, ProgressCallback, RenderSettings, Renderer, SamplingStrategy,
+    ray_color,
+};
 pub use volume::{DensityField, PhaseMix, VolumeEvent, VolumeRegion, Volumes};
+pub use window::PixelWindow;
 pub use world::{get_settings, simple_scene};
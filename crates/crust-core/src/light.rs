@@ -1,11 +1,14 @@
+use crate::aabb::AABB;
 use crate::environment::EnvironmentMap;
+use crate::light_tree::{LightBounds, LightTree, angle_between, safe_sqrt};
 use crate::material::{Emissive, Material};
 use glam::{Mat3A, Vec3A};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 
 /// The emitting surface of an area light, decoupled from any material: pure
 /// geometry that knows how to sample itself uniformly by area. One shape
-/// implementation per supported UsdLux schema (sphere, rect, …).
+/// implementation per supported UsdLux schema (sphere, rect, disk, cylinder).
 pub trait LightShape: Send + Sync {
     /// A point on the surface, uniform by area, from two unit random numbers.
     fn sample_point(&self, u: f32, v: f32) -> Vec3A;
@@ -15,6 +18,13 @@ pub trait LightShape: Send + Sync {
 
     /// Total surface area.
     fn area(&self) -> f32;
+
+    /// Axis-aligned bounds of the surface.
+    fn bounds(&self) -> AABB;
+
+    /// The cone holding every outward normal of the surface, as `(axis,
+    /// cos half-angle)`; a cosine of `-1` covers every direction.
+    fn normal_cone(&self) -> (Vec3A, f32);
 }
 
 /// Spherical light surface (UsdLux `SphereLight`).
@@ -42,6 +52,17 @@ impl LightShape for SphereShape {
     fn area(&self) -> f32 {
         4.0 * std::f32::consts::PI * self.radius * self.radius
     }
+
+    fn bounds(&self) -> AABB {
+        AABB::new(
+            self.center - Vec3A::splat(self.radius),
+            self.center + Vec3A::splat(self.radius),
+        )
+    }
+
+    fn normal_cone(&self) -> (Vec3A, f32) {
+        (Vec3A::Z, -1.0)
+    }
 }
 
 /// Rectangular light surface (UsdLux `RectLight`): the parallelogram
@@ -78,6 +99,136 @@ impl LightShape for RectShape {
     fn area(&self) -> f32 {
         self.edge_u.cross(self.edge_v).length()
     }
+
+    fn bounds(&self) -> AABB {
+        let far = self.origin + self.edge_u + self.edge_v;
+        AABB::new(
+            self.origin
+                .min(far)
+                .min(self.origin + self.edge_u)
+                .min(self.origin + self.edge_v),
+            self.origin
+                .max(far)
+                .max(self.origin + self.edge_u)
+                .max(self.origin + self.edge_v),
+        )
+    }
+
+    fn normal_cone(&self) -> (Vec3A, f32) {
+        (self.normal, 1.0)
+    }
+}
+
+/// Disk light surface (UsdLux `DiskLight`): the ellipse
+/// `center + r·cos φ·axis_u + r·sin φ·axis_v` for `r ≤ 1`, emitting from
+/// the side its `normal` faces. The axes carry the radius (and whatever
+/// scale the prim's transform adds); like [`RectShape`], the importer
+/// orients the normal along the light's local -Z.
+pub struct DiskShape {
+    pub center: Vec3A,
+    pub axis_u: Vec3A,
+    pub axis_v: Vec3A,
+    pub normal: Vec3A,
+}
+
+impl DiskShape {
+    pub fn new(center: Vec3A, axis_u: Vec3A, axis_v: Vec3A, normal: Vec3A) -> Self {
+        Self {
+            center,
+            axis_u,
+            axis_v,
+            normal: normal.normalize(),
+        }
+    }
+}
+
+impl LightShape for DiskShape {
+    fn sample_point(&self, u: f32, v: f32) -> Vec3A {
+        // sqrt keeps the unit disk uniform by area; the linear map onto
+        // the axes scales every patch by the same factor, so it stays so.
+        let r = u.sqrt();
+        let phi = std::f32::consts::TAU * v;
+        self.center + r * (phi.cos() * self.axis_u + phi.sin() * self.axis_v)
+    }
+
+    fn normal_at(&self, _p: Vec3A) -> Vec3A {
+        self.normal
+    }
+
+    fn area(&self) -> f32 {
+        std::f32::consts::PI * self.axis_u.cross(self.axis_v).length()
+    }
+
+    fn bounds(&self) -> AABB {
+        // Per axis, the ellipse reaches `√(u_i² + v_i²)` from its center.
+        let r2 = self.axis_u * self.axis_u + self.axis_v * self.axis_v;
+        let reach = Vec3A::new(r2.x.sqrt(), r2.y.sqrt(), r2.z.sqrt());
+        AABB::new(self.center - reach, self.center + reach)
+    }
+
+    fn normal_cone(&self) -> (Vec3A, f32) {
+        (self.normal, 1.0)
+    }
+}
+
+/// Cylinder light surface (UsdLux `CylinderLight`): the side of a right
+/// circular cylinder of `radius` around `axis`, reaching `half_length` to
+/// either side of `center`. Only the side emits — UsdLux leaves the end
+/// caps dark.
+pub struct CylinderShape {
+    pub center: Vec3A,
+    /// Unit direction of the cylinder's axis.
+    pub axis: Vec3A,
+    pub half_length: f32,
+    pub radius: f32,
+}
+
+impl CylinderShape {
+    pub fn new(center: Vec3A, axis: Vec3A, half_length: f32, radius: f32) -> Self {
+        Self {
+            center,
+            axis: axis.normalize(),
+            half_length,
+            radius,
+        }
+    }
+}
+
+impl LightShape for CylinderShape {
+    fn sample_point(&self, u: f32, v: f32) -> Vec3A {
+        let phi = std::f32::consts::TAU * v;
+        let local = Vec3A::new(
+            self.radius * phi.cos(),
+            self.radius * phi.sin(),
+            (2.0 * u - 1.0) * self.half_length,
+        );
+        self.center + utils::align_to_normal(local, self.axis)
+    }
+
+    fn normal_at(&self, p: Vec3A) -> Vec3A {
+        let offset = p - self.center;
+        (offset - offset.dot(self.axis) * self.axis).normalize()
+    }
+
+    fn area(&self) -> f32 {
+        std::f32::consts::TAU * self.radius * 2.0 * self.half_length
+    }
+
+    fn bounds(&self) -> AABB {
+        // The end circles reach `r·√(1 − a_i²)` past the axis on each
+        // world axis.
+        let s2 = (Vec3A::ONE - self.axis * self.axis).max(Vec3A::ZERO);
+        let reach = self.radius * Vec3A::new(s2.x.sqrt(), s2.y.sqrt(), s2.z.sqrt());
+        let (a, b) = (
+            self.center - self.half_length * self.axis,
+            self.center + self.half_length * self.axis,
+        );
+        AABB::new(a.min(b) - reach, a.max(b) + reach)
+    }
+
+    fn normal_cone(&self) -> (Vec3A, f32) {
+        (self.axis, -1.0)
+    }
 }
 
 /// One sampled connection from a shading point to a light: where to aim
@@ -114,7 +265,7 @@ pub struct LightSample {
 /// ways of being found: NEE samples it directly, and a bounce ray may
 /// arrive at it by chance. Both sides must evaluate the *same* density or
 /// emission is double-counted. For lights with geometry that second path
-/// is a bounce hit, weighted with [`Light::pdf_at_point`]; for lights at
+/// is a bounce hit, weighted with [`Light::pdf_at_hit`]; for lights at
 /// infinity it is a ray escaping the scene, weighted with
 /// [`Light::escaped`]. A light implements whichever applies.
 pub trait Light: Send + Sync {
@@ -133,6 +284,13 @@ pub trait Light: Send + Sync {
         0.0
     }
 
+    /// [`Light::pdf_at_point`] for a bounce hit that also reports which
+    /// primitive of the light's geometry it struck, so a light made of many
+    /// (a [`MeshLight`]) need not search for it. Defaults to ignoring it.
+    fn pdf_at_hit(&self, from: Vec3A, light_point: Vec3A, _prim_id: u32) -> f32 {
+        self.pdf_at_point(from, light_point)
+    }
+
     /// For a ray that escaped the scene along `direction`: the radiance it
     /// picks up and the solid-angle pdf NEE would have used for that
     /// direction, as `(radiance, pdf)`. This is the bounce side of MIS for
@@ -148,6 +306,13 @@ pub trait Light: Send + Sync {
     fn geom_id(&self) -> Option<u32> {
         None
     }
+
+    /// Where the light is, how much it emits and in which directions, for
+    /// [`LightList`] to judge how much it can matter at a shading point.
+    /// `None` for lights at infinity, which are chosen uniformly instead.
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
 }
 
 /// A geometric area light: any [`LightShape`] paired with the [`Emissive`]
@@ -210,6 +375,290 @@ impl Light for AreaLight {
     fn geom_id(&self) -> Option<u32> {
         Some(self.geom_id)
     }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let (direction, cos_theta_o) = self.shape.normal_cone();
+        Some(LightBounds {
+            bounds: self.shape.bounds(),
+            power: self.material.emitted().max_element() * self.shape.area() * std::f32::consts::PI,
+            direction,
+            cos_theta_o,
+            // Every point emits over its whole hemisphere.
+            cos_theta_e: 0.0,
+            // The emissive surface glows from both faces when a bounce ray
+            // hits it; only MIS against the vanishing `pdf_at_point` of
+            // the back face keeps a planar light effectively one-sided. If
+            // the tree ruled the back face out, that MIS weight would go
+            // to one and the back would light the scene.
+            two_sided: true,
+        })
+    }
+}
+
+/// An emissive triangle mesh sampled as a light — geometry whose material
+/// emits (an OpenPBR `emission_luminance`, a UsdPreviewSurface
+/// `emissiveColor`) rather than a UsdLux prim. Neon tubes and screens are
+/// modelled this way, and left to bounce rays alone they are nothing but
+/// fireflies.
+///
+/// A triangle is chosen in proportion to its power, which for a mesh of one
+/// material is its area. Within it, a triangle that subtends a usable solid
+/// angle from the shading point is sampled uniformly over that solid angle
+/// (Arvo 1995, as pbrt-v4's `SampleSphericalTriangle`): by area, a large
+/// triangle close by is sampled most densely where it is foreshortened and
+/// contributes least. A very small or very wide one falls back to area
+/// sampling, where the spherical construction loses precision.
+///
+/// Emission is two-sided, as it is when a bounce ray hits the mesh.
+///
+/// The triangles are fixed in world space, so a mesh that moves over the
+/// shutter is not one: its emission is found by bounce rays alone, and the
+/// importer warns that it will be noisy.
+pub struct MeshLight {
+    /// World-space triangles, indexed like the kernel's `prim_id`s for the
+    /// geometry — how a bounce hit finds the triangle it landed on.
+    triangles: Vec<[Vec3A; 3]>,
+    /// Running sum of triangle areas; the last entry is the total.
+    cdf: Vec<f32>,
+    material: Arc<dyn Material>,
+    geom_id: u32,
+}
+
+/// Solid angles, in steradians, between which a triangle is sampled over
+/// its spherical projection rather than by area (pbrt-v4's bounds).
+const MIN_SPHERICAL_SOLID_ANGLE: f32 = 3e-4;
+const MAX_SPHERICAL_SOLID_ANGLE: f32 = 6.22;
+
+impl MeshLight {
+    /// A light over `triangles`, emitting as `material` does. `None` when the
+    /// triangles have no area between them.
+    pub fn new(
+        triangles: Vec<[Vec3A; 3]>,
+        material: Arc<dyn Material>,
+        geom_id: u32,
+    ) -> Option<Self> {
+        let cdf: Vec<f32> = triangles
+            .iter()
+            .scan(0.0, |sum, t| {
+                *sum += triangle_area(t);
+                Some(*sum)
+            })
+            .collect();
+        if cdf.last().is_none_or(|&area| area <= 0.0) {
+            return None;
+        }
+        Some(Self {
+            triangles,
+            cdf,
+            material,
+            geom_id,
+        })
+    }
+
+    fn area(&self) -> f32 {
+        self.cdf[self.cdf.len() - 1]
+    }
+
+    /// Probability of choosing triangle `i`.
+    fn triangle_pmf(&self, i: usize) -> f32 {
+        triangle_area(&self.triangles[i]) / self.area()
+    }
+
+    /// Solid-angle pdf of sampling `point` on triangle `i` from `from`,
+    /// under whichever strategy [`Light::sample_li`] takes for that pair.
+    fn triangle_pdf(&self, from: Vec3A, point: Vec3A, i: usize) -> f32 {
+        let tri = &self.triangles[i];
+        let pmf = self.triangle_pmf(i);
+        let omega = solid_angle(tri, from);
+        if (MIN_SPHERICAL_SOLID_ANGLE..=MAX_SPHERICAL_SOLID_ANGLE).contains(&omega) {
+            return pmf / omega;
+        }
+        let to_point = point - from;
+        let distance_squared = to_point.length_squared();
+        let cosine = triangle_normal(tri)
+            .dot(to_point / distance_squared.sqrt())
+            .abs();
+        if !cosine.is_finite() || cosine <= 0.0 {
+            return 0.0;
+        }
+        pmf * distance_squared / (cosine * triangle_area(tri))
+    }
+
+    /// The triangle `point` lies on: the nearest to its plane among those
+    /// whose edges enclose it.
+    fn triangle_at(&self, point: Vec3A) -> Option<usize> {
+        let mut best = None;
+        let mut best_distance = f32::INFINITY;
+        for (i, [a, b, c]) in self.triangles.iter().enumerate() {
+            let n = (*b - *a).cross(*c - *a);
+            let n2 = n.length_squared();
+            if n2 <= 0.0 {
+                continue;
+            }
+            let d = point - *a;
+            let w_b = d.cross(*c - *a).dot(n) / n2;
+            let w_c = (*b - *a).cross(d).dot(n) / n2;
+            let tol = 1e-4;
+            if w_b < -tol || w_c < -tol || w_b + w_c > 1.0 + tol {
+                continue;
+            }
+            let distance = d.dot(n).abs() / n2.sqrt();
+            if distance < best_distance {
+                best_distance = distance;
+                best = Some(i);
+            }
+        }
+        best
+    }
+}
+
+fn triangle_area([a, b, c]: &[Vec3A; 3]) -> f32 {
+    0.5 * (*b - *a).cross(*c - *a).length()
+}
+
+fn triangle_normal([a, b, c]: &[Vec3A; 3]) -> Vec3A {
+    (*b - *a).cross(*c - *a).normalize_or_zero()
+}
+
+/// Solid angle `tri` subtends from `p` (Van Oosterom & Strackee 1983). NaN
+/// when `p` is one of its corners.
+fn solid_angle(tri: &[Vec3A; 3], p: Vec3A) -> f32 {
+    let [a, b, c] = tri.map(|v| (v - p).normalize());
+    (2.0 * f32::atan2(a.dot(b.cross(c)), 1.0 + a.dot(b) + a.dot(c) + b.dot(c))).abs()
+}
+
+/// A direction uniform over the solid angle `tri` subtends from `p`, from
+/// two unit random numbers. `None` for a triangle seen edge-on.
+fn sample_spherical_triangle(tri: &[Vec3A; 3], p: Vec3A, u: f32, v: f32) -> Option<Vec3A> {
+    let [a, b, c] = tri.map(|t| (t - p).normalize());
+    // Normals of the great circles through each edge; the angle at a
+    // corner is the angle between the two that meet there.
+    let n_ab = a.cross(b).try_normalize()?;
+    let n_bc = b.cross(c).try_normalize()?;
+    let n_ca = c.cross(a).try_normalize()?;
+    let alpha = angle_between(n_ab, -n_ca);
+    let beta = angle_between(n_bc, -n_ab);
+    let gamma = angle_between(n_ca, -n_bc);
+
+    // Pick the sub-triangle `a b c'` holding a fraction `u` of the area,
+    // which fixes where `c'` lies on the arc from `a` to `c`.
+    let area_pi = alpha + beta + gamma;
+    let sub_area_pi = std::f32::consts::PI + u * (area_pi - std::f32::consts::PI);
+    let (sin_alpha, cos_alpha) = alpha.sin_cos();
+    let (sin_sub, cos_sub) = sub_area_pi.sin_cos();
+    let sin_phi = sin_sub * cos_alpha - cos_sub * sin_alpha;
+    let cos_phi = cos_sub * cos_alpha + sin_sub * sin_alpha;
+    let k1 = cos_phi + cos_alpha;
+    let k2 = sin_phi - sin_alpha * a.dot(b);
+    let cos_b = ((k2 + (k2 * cos_phi - k1 * sin_phi) * cos_alpha)
+        / ((k2 * sin_phi + k1 * cos_phi) * sin_alpha))
+        .clamp(-1.0, 1.0);
+    let sin_b = safe_sqrt(1.0 - cos_b * cos_b);
+    let c_sub = cos_b * a + sin_b * (c - c.dot(a) * a).try_normalize()?;
+
+    // Then a point along the arc from `b` to `c'`.
+    let cos_theta = 1.0 - v * (1.0 - c_sub.dot(b));
+    let sin_theta = safe_sqrt(1.0 - cos_theta * cos_theta);
+    let w = cos_theta * b + sin_theta * (c_sub - c_sub.dot(b) * b).try_normalize()?;
+    w.try_normalize()
+}
+
+impl Light for MeshLight {
+    fn sample_li(&self, from: Vec3A, u: f32, v: f32) -> Option<LightSample> {
+        // Choose a triangle with `u`, then stretch the part of `u` inside
+        // its CDF bin back over `[0, 1)` to place the point.
+        let area = self.area();
+        let target = u * area;
+        let i = self
+            .cdf
+            .partition_point(|&c| c <= target)
+            .min(self.cdf.len() - 1);
+        let start = if i == 0 { 0.0 } else { self.cdf[i - 1] };
+        let u = ((target - start) / (self.cdf[i] - start)).clamp(0.0, 1.0 - f32::EPSILON);
+        let tri = &self.triangles[i];
+        let normal = triangle_normal(tri);
+
+        let omega = solid_angle(tri, from);
+        let (direction, distance) =
+            if (MIN_SPHERICAL_SOLID_ANGLE..=MAX_SPHERICAL_SOLID_ANGLE).contains(&omega) {
+                let direction = sample_spherical_triangle(tri, from, u, v)?;
+                // Where the direction meets the triangle's plane.
+                let distance = (tri[0] - from).dot(normal) / direction.dot(normal);
+                (direction, distance)
+            } else {
+                let su = u.sqrt();
+                let point = tri[0] * (1.0 - su) + tri[1] * (su * (1.0 - v)) + tri[2] * (su * v);
+                let to_light = point - from;
+                let distance = to_light.length();
+                (to_light / distance, distance)
+            };
+        if !distance.is_finite() || distance <= 1e-6 {
+            return None;
+        }
+        let pdf = self.triangle_pdf(from, from + direction * distance, i);
+        if !pdf.is_finite() || pdf <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self
+                .material
+                .emitted_directional(normal.dot(direction).abs()),
+            pdf,
+        })
+    }
+
+    fn pdf_at_point(&self, from: Vec3A, light_point: Vec3A) -> f32 {
+        self.triangle_at(light_point)
+            .map_or(0.0, |i| self.triangle_pdf(from, light_point, i))
+    }
+
+    fn pdf_at_hit(&self, from: Vec3A, light_point: Vec3A, prim_id: u32) -> f32 {
+        if (prim_id as usize) < self.triangles.len() {
+            self.triangle_pdf(from, light_point, prim_id as usize)
+        } else {
+            self.pdf_at_point(from, light_point)
+        }
+    }
+
+    fn geom_id(&self) -> Option<u32> {
+        Some(self.geom_id)
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let mut lo = Vec3A::splat(f32::INFINITY);
+        let mut hi = Vec3A::splat(f32::NEG_INFINITY);
+        let mut axis = Vec3A::ZERO;
+        for tri in &self.triangles {
+            for v in tri {
+                lo = lo.min(*v);
+                hi = hi.max(*v);
+            }
+            axis += triangle_area(tri) * triangle_normal(tri);
+        }
+        // The area-weighted mean normal, widened to hold every triangle's;
+        // a closed or folded mesh cancels out to every direction.
+        let (direction, cos_theta_o) = match axis.try_normalize() {
+            Some(axis) => (
+                axis,
+                self.triangles
+                    .iter()
+                    .filter(|t| triangle_area(t) > 0.0)
+                    .map(|t| axis.dot(triangle_normal(t)))
+                    .fold(1.0, f32::min),
+            ),
+            None => (Vec3A::Z, -1.0),
+        };
+        Some(LightBounds {
+            bounds: AABB::new(lo, hi),
+            power: self.material.emitted().max_element() * self.area() * std::f32::consts::PI,
+            direction,
+            cos_theta_o,
+            cos_theta_e: 0.0,
+            two_sided: true,
+        })
+    }
 }
 
 /// A `UsdLuxDistantLight`: parallel light from infinitely far away, as the
@@ -389,9 +838,30 @@ impl Light for DomeLight {
 }
 
 /// The `LightList` struct manages a collection of light sources in the scene.
+///
+/// Lights are chosen for NEE through a [`LightTree`] over the list, so a
+/// shading point spends its shadow rays on the lights that can matter
+/// there rather than uniformly on all of them. The tree, and the lookups
+/// from geometry and from light to index, are built from `lights` the first
+/// time they are needed; [`LightList::add`] discards them again.
 pub struct LightList {
     /// A vector of light sources stored as `Arc<dyn Light>` for shared ownership.
     pub lights: Vec<Arc<dyn Light>>,
+    index: OnceLock<LightIndex>,
+}
+
+/// What [`LightList`] derives from its lights for sampling them.
+struct LightIndex {
+    tree: LightTree,
+    /// Light index by the `geom_id` of its scene geometry (the first light,
+    /// should two share one).
+    by_geom: HashMap<u32, usize>,
+    /// Light index by the address of its allocation.
+    by_address: HashMap<usize, usize>,
+}
+
+fn light_address(light: &Arc<dyn Light>) -> usize {
+    Arc::as_ptr(light) as *const () as usize
 }
 
 impl Default for LightList {
@@ -404,29 +874,60 @@ impl Default for LightList {
 impl LightList {
     /// Creates a new, empty `LightList`.
     pub fn new() -> Self {
-        Self { lights: Vec::new() }
+        Self {
+            lights: Vec::new(),
+            index: OnceLock::new(),
+        }
     }
 
     /// Adds a light source to the `LightList`.
     pub fn add(&mut self, light: Arc<dyn Light>) {
         self.lights.push(light);
+        self.index = OnceLock::new();
+    }
+
+    fn index(&self) -> &LightIndex {
+        self.index.get_or_init(|| LightIndex {
+            tree: LightTree::build(&self.lights),
+            by_geom: self
+                .lights
+                .iter()
+                .enumerate()
+                .rev()
+                .filter_map(|(i, l)| Some((l.geom_id()?, i)))
+                .collect(),
+            by_address: self
+                .lights
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(i, l)| (light_address(l), i))
+                .collect(),
+        })
     }
 
-    /// Uniformly picks a light source from the `LightList` from a single
-    /// `[0, 1)` sample `u`.
+    /// Chooses a light to sample from a shading point at `p`, from a single
+    /// `[0, 1)` sample `u`: lights likely to contribute at `p` are chosen
+    /// more often. Returns the light with the probability of having chosen
+    /// it, which scales the light's own sampling pdf.
     ///
     /// # Returns
-    /// - `Some(&Arc<dyn Light>)` if the list is not empty.
-    /// - `None` if the list is empty.
-    pub fn pick(&self, u: f32) -> Option<&Arc<dyn Light>> {
-        if self.lights.is_empty() {
-            None
-        } else {
-            let i = (u * self.lights.len() as f32) as usize;
-            // Guard against `u == 1.0 - epsilon` rounding to len.
-            let i = i.min(self.lights.len() - 1);
-            self.lights.get(i)
-        }
+    /// - `Some((light, pmf))` when some light can reach `p`.
+    /// - `None` if the list is empty or nothing can light `p`.
+    pub fn sample(&self, p: Vec3A, u: f32) -> Option<(&Arc<dyn Light>, f32)> {
+        let (i, pmf) = self.index().tree.sample(p, u)?;
+        Some((&self.lights[i], pmf))
+    }
+
+    /// The probability that [`LightList::sample`] chooses `light` from `p` —
+    /// what the bounce side of MIS needs for a light it found by chance.
+    /// Zero for lights not in this list.
+    pub fn pmf(&self, p: Vec3A, light: &Arc<dyn Light>) -> f32 {
+        let index = self.index();
+        index
+            .by_address
+            .get(&light_address(light))
+            .map_or(0.0, |&i| index.tree.pmf(p, i))
     }
 
     /// Finds the light whose scene geometry has world id `geom_id`. Used
@@ -434,9 +935,17 @@ impl LightList {
     /// light for MIS; emissive geometry with no light-list entry returns
     /// `None`.
     pub fn find_by_geom(&self, geom_id: u32) -> Option<&Arc<dyn Light>> {
-        self.lights
+        let i = *self.index().by_geom.get(&geom_id)?;
+        Some(&self.lights[i])
+    }
+
+    /// The lights at infinity — the ones a ray escaping the scene can find.
+    pub fn infinite(&self) -> impl Iterator<Item = &Arc<dyn Light>> {
+        self.index()
+            .tree
+            .infinite()
             .iter()
-            .find(|l| l.geom_id() == Some(geom_id))
+            .map(|&i| &self.lights[i])
     }
 
     /// Returns the number of lights in the `LightList`.
@@ -478,6 +987,182 @@ mod tests {
         assert_eq!(shape.normal_at(p), Vec3A::new(0.0, -1.0, 0.0));
     }
 
+    #[test]
+    fn disk_shape_samples_lie_in_disk() {
+        let shape = DiskShape::new(
+            Vec3A::new(0.0, 3.0, 0.0),
+            Vec3A::new(2.0, 0.0, 0.0),
+            Vec3A::new(0.0, 0.0, 2.0),
+            Vec3A::new(0.0, -1.0, 0.0),
+        );
+        assert!((shape.area() - 4.0 * std::f32::consts::PI).abs() < 1e-4);
+        for (u, v) in [(0.0, 0.0), (0.25, 0.75), (0.99, 0.5), (1.0, 0.01)] {
+            let p = shape.sample_point(u, v);
+            assert!((p.y - 3.0).abs() < 1e-5, "sample off plane: {p}");
+            assert!((p - shape.center).length() <= 2.0 + 1e-5);
+        }
+        // Uniform by area: half the samples fall inside radius 2/√2.
+        let n = 1000;
+        let inner = (0..n)
+            .filter(|i| {
+                let p = shape.sample_point((*i as f32 + 0.5) / n as f32, 0.3);
+                (p - shape.center).length() < std::f32::consts::SQRT_2
+            })
+            .count();
+        assert_eq!(inner, n / 2);
+    }
+
+    #[test]
+    fn cylinder_shape_samples_lie_on_side() {
+        let shape = CylinderShape::new(Vec3A::new(1.0, 0.0, 0.0), Vec3A::X, 2.0, 0.25);
+        assert!((shape.area() - std::f32::consts::TAU * 0.25 * 4.0).abs() < 1e-5);
+        for (u, v) in [(0.0, 0.0), (0.25, 0.75), (0.99, 0.5), (0.5, 0.01)] {
+            let p = shape.sample_point(u, v);
+            let radial = Vec3A::new(0.0, p.y, p.z);
+            assert!(
+                (radial.length() - 0.25).abs() < 1e-5,
+                "sample off side: {p}"
+            );
+            assert!((p.x - 1.0).abs() <= 2.0 + 1e-5);
+            let n = shape.normal_at(p);
+            assert!((n - radial / 0.25).length() < 1e-4);
+        }
+    }
+
+    /// The light tree trusts a shape's bounds: every surface point lies
+    /// inside them, and every normal inside its cone.
+    #[test]
+    fn shape_bounds_hold_their_samples() {
+        let axis = Vec3A::new(1.0, 2.0, -0.5).normalize();
+        let shapes: [Box<dyn LightShape>; 4] = [
+            Box::new(SphereShape {
+                center: Vec3A::new(1.0, -2.0, 0.5),
+                radius: 0.7,
+            }),
+            Box::new(RectShape::new(
+                Vec3A::new(-1.0, 5.0, -2.0),
+                Vec3A::new(2.0, 0.5, 0.0),
+                Vec3A::new(0.0, -1.0, 4.0),
+                Vec3A::new(0.0, -1.0, 0.0),
+            )),
+            Box::new(DiskShape::new(
+                Vec3A::ZERO,
+                Vec3A::new(0.6, 0.0, 0.8),
+                Vec3A::new(0.0, 0.5, 0.0),
+                Vec3A::new(-0.8, 0.0, 0.6),
+            )),
+            Box::new(CylinderShape::new(Vec3A::ONE, axis, 1.5, 0.3)),
+        ];
+        for shape in shapes {
+            let b = shape.bounds();
+            let (cone, cos) = shape.normal_cone();
+            for i in 0..64 {
+                let (u, v) = ((i % 8) as f32 / 7.0, (i / 8) as f32 / 7.0);
+                let p = shape.sample_point(u, v);
+                assert!(
+                    p.cmpge(b.minimum - 1e-4).all() && p.cmple(b.maximum + 1e-4).all(),
+                    "{p} outside {b:?}"
+                );
+                assert!(shape.normal_at(p).dot(cone) >= cos - 1e-4);
+            }
+        }
+    }
+
+    /// Both MIS sides agree for the new shapes too: the density NEE reports
+    /// for a sample is the one a bounce ray landing on that point gets.
+    #[test]
+    fn disk_and_cylinder_lights_agree_with_pdf_at_point() {
+        let shapes: [Box<dyn LightShape>; 2] = [
+            Box::new(DiskShape::new(
+                Vec3A::new(0.0, 4.0, 0.0),
+                Vec3A::new(0.5, 0.0, 0.0),
+                Vec3A::new(0.0, 0.0, 0.5),
+                Vec3A::NEG_Y,
+            )),
+            Box::new(CylinderShape::new(
+                Vec3A::new(0.0, 4.0, 0.0),
+                Vec3A::X,
+                1.0,
+                0.5,
+            )),
+        ];
+        for shape in shapes {
+            let light = AreaLight::new(shape, Arc::new(Emissive::new(Vec3A::ONE)), 0);
+            for (u, v) in [(0.3, 0.7), (0.5, 0.75), (0.9, 0.8)] {
+                let s = light
+                    .sample_li(Vec3A::ZERO, u, v)
+                    .expect("the light is in reach");
+                let point = s.direction * s.distance;
+                let from_point = light.pdf_at_point(Vec3A::ZERO, point);
+                assert!(s.pdf.is_finite() && s.pdf > 0.0);
+                assert!((s.pdf - from_point).abs() <= 1e-3 * s.pdf.max(from_point));
+            }
+        }
+    }
+
+    /// A 2×2 square of two triangles in the plane `y = 1`.
+    fn square_mesh_light() -> MeshLight {
+        let [a, b, c, d] = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+            .map(|(x, z)| Vec3A::new(x, 1.0, z));
+        MeshLight::new(
+            vec![[a, b, c], [a, c, d]],
+            Arc::new(Emissive::new(Vec3A::ONE)),
+            0,
+        )
+        .expect("the square has area")
+    }
+
+    /// Close by, the triangles are sampled by solid angle; from far away,
+    /// by area. Either way the point lands on the square and `sample_li`
+    /// and `pdf_at_point` agree on its density.
+    #[test]
+    fn mesh_light_agrees_with_pdf_at_point() {
+        let light = square_mesh_light();
+        for from in [Vec3A::new(0.2, 0.0, 0.3), Vec3A::new(0.0, -300.0, 0.0)] {
+            for (u, v) in [(0.1, 0.7), (0.4, 0.25), (0.9, 0.8)] {
+                let s = light.sample_li(from, u, v).expect("the square is in reach");
+                let point = from + s.direction * s.distance;
+                assert!(
+                    (point.y - 1.0).abs() < 1e-3 * s.distance,
+                    "off the plane: {point}"
+                );
+                assert!(point.x.abs() <= 1.0 + 1e-3 && point.z.abs() <= 1.0 + 1e-3);
+                let from_point = light.pdf_at_point(from, point);
+                assert!(s.pdf.is_finite() && s.pdf > 0.0);
+                assert!(
+                    (s.pdf - from_point).abs() <= 1e-3 * s.pdf.max(from_point),
+                    "MIS sides disagree: sample_li {} vs pdf_at_point {}",
+                    s.pdf,
+                    from_point
+                );
+            }
+        }
+    }
+
+    /// `1/pdf` averages to the solid angle the square subtends — which holds
+    /// only if the sampled directions really have the density reported.
+    #[test]
+    fn mesh_light_pdf_integrates_to_solid_angle() {
+        let light = square_mesh_light();
+        for from in [Vec3A::new(0.3, 0.0, -0.2), Vec3A::new(0.0, -300.0, 0.0)] {
+            let expected: f32 = light.triangles.iter().map(|t| solid_angle(t, from)).sum();
+            let mut rng = openqmc::pcg::Rng::new(11);
+            let n = 20_000;
+            let mut sum = 0.0f64;
+            for _ in 0..n {
+                let s = light
+                    .sample_li(from, rng.next_f32(), rng.next_f32())
+                    .expect("the square is in reach");
+                sum += 1.0 / s.pdf as f64;
+            }
+            let estimate = (sum / n as f64) as f32;
+            assert!(
+                (estimate - expected).abs() < 0.02 * expected,
+                "from {from}: E[1/pdf] = {estimate}, solid angle {expected}"
+            );
+        }
+    }
+
     #[test]
     fn area_light_pdf_is_positive_facing_side() {
         let light = AreaLight::new(
@@ -0,0 +1,527 @@
+//! Light BVH for many-light importance sampling, after Conty Estevez & Kulla
+//! 2018, "Importance Sampling of Many Lights with Adaptive Tree Splitting",
+//! in the form pbrt-v4 gives it.
+//!
+//! Every node bounds its lights' positions, total power and emission
+//! directions ([`LightBounds`]). From a shading point, each node gets a
+//! conservative *importance* — power over squared distance, scaled by the
+//! best cosine any of its lights could present — and a light is chosen by
+//! descending from the root, taking each child with probability
+//! proportional to its importance. The probability of a choice is the
+//! product of those branch probabilities; [`LightTree::pmf`] walks the same
+//! branches back up from the light's leaf, which keeps sampling and pmf
+//! exactly consistent for MIS.
+//!
+//! Lights at infinity have no position to bound. They are kept out of the
+//! tree and chosen uniformly, the tree as a whole counting as one more
+//! candidate beside them.
+
+use crate::aabb::AABB;
+use crate::light::Light;
+use glam::Vec3A;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+const ONE_MINUS_EPS: f32 = 1.0 - f32::EPSILON;
+
+/// Buckets per axis when binning lights for a split.
+const BUCKETS: usize = 12;
+
+/// What the light tree knows about a light with a position: where it is,
+/// how much it emits, and in which directions.
+///
+/// Emission directions are a cone of surface normals (`direction`,
+/// `cos_theta_o`) widened by `cos_theta_e`, how far from its normal any
+/// point still emits — `0` (a hemisphere) for a diffuse emitter.
+#[derive(Clone, Copy, Debug)]
+pub struct LightBounds {
+    pub bounds: AABB,
+    /// Emitted power, in any unit as long as every light uses the same.
+    pub power: f32,
+    /// Axis of the normal cone.
+    pub direction: Vec3A,
+    /// Cosine of the normal cone's half-angle; `-1` for every direction.
+    pub cos_theta_o: f32,
+    /// Cosine of the emission spread around each normal.
+    pub cos_theta_e: f32,
+    /// Whether the surface emits from both faces.
+    pub two_sided: bool,
+}
+
+impl LightBounds {
+    fn centroid(&self) -> Vec3A {
+        0.5 * (self.bounds.minimum + self.bounds.maximum)
+    }
+
+    /// How much the lights inside these bounds could contribute at `p`, up
+    /// to a common scale. Zero only where no light can face `p` at all.
+    fn importance(&self, p: Vec3A) -> f32 {
+        let center = self.centroid();
+        let diagonal = self.bounds.maximum - self.bounds.minimum;
+        let distance_2 = p.distance_squared(center);
+        let d2 = distance_2.max(0.5 * diagonal.length());
+
+        let wi = (p - center).normalize_or_zero();
+        let mut cos_w = self.direction.dot(wi);
+        if self.two_sided {
+            cos_w = cos_w.abs();
+        }
+        let sin_w = safe_sqrt(1.0 - cos_w * cos_w);
+
+        // The bounds subtend a cone of directions from `p`: the normal best
+        // aligned with `p` may be off by that much as well.
+        let radius_2 = 0.25 * diagonal.length_squared();
+        let cos_b = if distance_2 < radius_2 {
+            -1.0
+        } else {
+            safe_sqrt(1.0 - radius_2 / distance_2)
+        };
+        let sin_b = safe_sqrt(1.0 - cos_b * cos_b);
+
+        let sin_o = safe_sqrt(1.0 - self.cos_theta_o * self.cos_theta_o);
+        let (sin_x, cos_x) = sub_clamped(sin_w, cos_w, sin_o, self.cos_theta_o);
+        let (_, cos_p) = sub_clamped(sin_x, cos_x, sin_b, cos_b);
+        if cos_p <= self.cos_theta_e {
+            return 0.0;
+        }
+        self.power * cos_p / d2
+    }
+
+    /// Bounds covering both `self` and `other`.
+    fn union(&self, other: &LightBounds) -> LightBounds {
+        if self.power == 0.0 {
+            return *other;
+        }
+        if other.power == 0.0 {
+            return *self;
+        }
+        let (direction, cos_theta_o) = cone_union(
+            (self.direction, self.cos_theta_o),
+            (other.direction, other.cos_theta_o),
+        );
+        LightBounds {
+            bounds: AABB::surrounding_box(self.bounds, other.bounds),
+            power: self.power + other.power,
+            direction,
+            cos_theta_o,
+            cos_theta_e: self.cos_theta_e.min(other.cos_theta_e),
+            two_sided: self.two_sided || other.two_sided,
+        }
+    }
+
+    /// The surface-area-orientation heuristic's cost of a node with these
+    /// bounds, inside a parent spanning `parent` and split along `axis`.
+    fn cost(&self, parent: &AABB, axis: usize) -> f32 {
+        let theta_o = self.cos_theta_o.clamp(-1.0, 1.0).acos();
+        let theta_e = self.cos_theta_e.clamp(-1.0, 1.0).acos();
+        let theta_w = (theta_o + theta_e).min(PI);
+        let sin_o = safe_sqrt(1.0 - self.cos_theta_o * self.cos_theta_o);
+        let m_omega = 2.0 * PI * (1.0 - self.cos_theta_o)
+            + 0.5
+                * PI
+                * (2.0 * theta_w * sin_o - (theta_o - 2.0 * theta_w).cos() - 2.0 * theta_o * sin_o
+                    + self.cos_theta_o);
+        let extent = parent.maximum - parent.minimum;
+        let regularity = extent.max_element() / extent[axis];
+        self.power * m_omega * regularity * surface_area(&self.bounds)
+    }
+}
+
+pub(crate) fn safe_sqrt(x: f32) -> f32 {
+    x.max(0.0).sqrt()
+}
+
+/// `θa − θb` as `(sin, cos)`, clamped at zero.
+fn sub_clamped(sin_a: f32, cos_a: f32, sin_b: f32, cos_b: f32) -> (f32, f32) {
+    if cos_a > cos_b {
+        (0.0, 1.0)
+    } else {
+        (sin_a * cos_b - cos_a * sin_b, cos_a * cos_b + sin_a * sin_b)
+    }
+}
+
+/// Angle between two unit vectors, accurate near 0 and π.
+pub(crate) fn angle_between(a: Vec3A, b: Vec3A) -> f32 {
+    if a.dot(b) < 0.0 {
+        PI - 2.0 * ((a + b).length() / 2.0).min(1.0).asin()
+    } else {
+        2.0 * ((b - a).length() / 2.0).min(1.0).asin()
+    }
+}
+
+/// The smallest cone holding two cones, each `(axis, cos half-angle)`.
+fn cone_union(a: (Vec3A, f32), b: (Vec3A, f32)) -> (Vec3A, f32) {
+    let theta_a = a.1.clamp(-1.0, 1.0).acos();
+    let theta_b = b.1.clamp(-1.0, 1.0).acos();
+    let theta_d = angle_between(a.0, b.0);
+    if (theta_d + theta_b).min(PI) <= theta_a {
+        return a;
+    }
+    if (theta_d + theta_a).min(PI) <= theta_b {
+        return b;
+    }
+    let theta_o = 0.5 * (theta_a + theta_d + theta_b);
+    let axis = a.0.cross(b.0);
+    if theta_o >= PI || axis.length_squared() == 0.0 {
+        return (a.0, -1.0);
+    }
+    // Rotate a's axis toward b's, about their common normal.
+    let theta_r = theta_o - theta_a;
+    let k = axis.normalize();
+    let direction = a.0 * theta_r.cos() + k.cross(a.0) * theta_r.sin();
+    (direction.normalize(), theta_o.cos())
+}
+
+fn surface_area(b: &AABB) -> f32 {
+    let d = b.maximum - b.minimum;
+    2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+}
+
+struct Node {
+    bounds: LightBounds,
+    /// A leaf's light index, or an interior node's second child (its first
+    /// child is the node right after it).
+    index: u32,
+    leaf: bool,
+}
+
+/// The tree over a light list, with the bookkeeping to answer for any
+/// light how likely [`LightTree::sample`] was to choose it.
+pub(crate) struct LightTree {
+    nodes: Vec<Node>,
+    /// Each node's parent; the root's is `u32::MAX`.
+    parents: Vec<u32>,
+    /// Per light: its leaf, or `None` when it is not in the tree — lights
+    /// at infinity, and lights with no power, which are never chosen.
+    leaves: Vec<Option<u32>>,
+    /// Lights at infinity, chosen uniformly beside the tree.
+    infinite: Vec<usize>,
+}
+
+impl LightTree {
+    pub(crate) fn build(lights: &[Arc<dyn Light>]) -> Self {
+        let mut infinite = Vec::new();
+        let mut bounded = Vec::new();
+        for (i, light) in lights.iter().enumerate() {
+            match light.bounds() {
+                Some(b) if b.power > 0.0 => bounded.push((i, b)),
+                Some(_) => {}
+                None => infinite.push(i),
+            }
+        }
+        let mut tree = LightTree {
+            nodes: Vec::with_capacity(2 * bounded.len()),
+            parents: Vec::with_capacity(2 * bounded.len()),
+            leaves: vec![None; lights.len()],
+            infinite,
+        };
+        if !bounded.is_empty() {
+            tree.build_node(&mut bounded, u32::MAX);
+        }
+        tree
+    }
+
+    /// Builds the subtree over `lights` and returns its bounds.
+    fn build_node(&mut self, lights: &mut [(usize, LightBounds)], parent: u32) -> LightBounds {
+        let at = self.nodes.len() as u32;
+        self.parents.push(parent);
+        if let [(light, bounds)] = *lights {
+            self.nodes.push(Node {
+                bounds,
+                index: light as u32,
+                leaf: true,
+            });
+            self.leaves[light] = Some(at);
+            return bounds;
+        }
+
+        let mid = split(lights);
+        // Reserved now, filled in once both children are built.
+        self.nodes.push(Node {
+            bounds: lights[0].1,
+            index: 0,
+            leaf: false,
+        });
+        let (first, second) = lights.split_at_mut(mid);
+        let a = self.build_node(first, at);
+        let second_child = self.nodes.len() as u32;
+        let b = self.build_node(second, at);
+        let bounds = a.union(&b);
+        self.nodes[at as usize] = Node {
+            bounds,
+            index: second_child,
+            leaf: false,
+        };
+        bounds
+    }
+
+    /// The probability of choosing the tree rather than one of the lights
+    /// at infinity.
+    fn tree_prob(&self) -> f32 {
+        if self.nodes.is_empty() {
+            0.0
+        } else {
+            1.0 / (self.infinite.len() + 1) as f32
+        }
+    }
+
+    /// The two children of interior node `n` and the probability of
+    /// descending into the first, from `p`. `None` when neither can light
+    /// `p`.
+    fn branch(&self, n: usize, p: Vec3A) -> Option<(usize, usize, f32)> {
+        let (first, second) = (n + 1, self.nodes[n].index as usize);
+        let a = self.nodes[first].bounds.importance(p);
+        let b = self.nodes[second].bounds.importance(p);
+        (a + b > 0.0).then(|| (first, second, a / (a + b)))
+    }
+
+    /// Chooses a light for a shading point at `p` from one `[0, 1)` sample
+    /// `u`: its index and the probability of having chosen it.
+    pub(crate) fn sample(&self, p: Vec3A, u: f32) -> Option<(usize, f32)> {
+        let tree = self.tree_prob();
+        if u >= tree {
+            // One of the lights at infinity, uniformly.
+            let n = self.infinite.len();
+            if n == 0 {
+                return None;
+            }
+            let i = ((u - tree) / (1.0 - tree) * n as f32) as usize;
+            return Some((self.infinite[i.min(n - 1)], (1.0 - tree) / n as f32));
+        }
+        let mut u = (u / tree).min(ONE_MINUS_EPS);
+        let mut pmf = tree;
+        let mut n = 0;
+        if self.nodes[0].bounds.importance(p) == 0.0 {
+            return None;
+        }
+        while !self.nodes[n].leaf {
+            let (first, second, p_first) = self.branch(n, p)?;
+            if u < p_first {
+                u = (u / p_first).min(ONE_MINUS_EPS);
+                pmf *= p_first;
+                n = first;
+            } else {
+                u = ((u - p_first) / (1.0 - p_first)).min(ONE_MINUS_EPS);
+                pmf *= 1.0 - p_first;
+                n = second;
+            }
+        }
+        Some((self.nodes[n].index as usize, pmf))
+    }
+
+    /// The probability that [`LightTree::sample`] chooses light `light`
+    /// from `p`.
+    pub(crate) fn pmf(&self, p: Vec3A, light: usize) -> f32 {
+        if self.infinite.contains(&light) {
+            return (1.0 - self.tree_prob()) / self.infinite.len() as f32;
+        }
+        let Some(Some(leaf)) = self.leaves.get(light) else {
+            return 0.0;
+        };
+        if self.nodes[0].bounds.importance(p) == 0.0 {
+            return 0.0;
+        }
+        let mut pmf = self.tree_prob();
+        let mut n = *leaf as usize;
+        while self.parents[n] != u32::MAX {
+            let parent = self.parents[n] as usize;
+            let Some((first, _, p_first)) = self.branch(parent, p) else {
+                return 0.0;
+            };
+            pmf *= if n == first { p_first } else { 1.0 - p_first };
+            n = parent;
+        }
+        pmf
+    }
+
+    /// Indices of the lights at infinity.
+    pub(crate) fn infinite(&self) -> &[usize] {
+        &self.infinite
+    }
+}
+
+/// Reorders `lights` into two runs for a node's children and returns where
+/// the second begins: the binned split with the lowest orientation-aware
+/// cost, or the middle when no axis separates the lights.
+fn split(lights: &mut [(usize, LightBounds)]) -> usize {
+    let mut bounds = lights[0].1.bounds;
+    let (mut lo, mut hi) = (lights[0].1.centroid(), lights[0].1.centroid());
+    for (_, b) in lights.iter() {
+        bounds = AABB::surrounding_box(bounds, b.bounds);
+        lo = lo.min(b.centroid());
+        hi = hi.max(b.centroid());
+    }
+    let bucket = |b: &LightBounds, axis: usize| {
+        let t = (b.centroid()[axis] - lo[axis]) / (hi[axis] - lo[axis]);
+        ((BUCKETS as f32 * t) as usize).min(BUCKETS - 1)
+    };
+
+    let mut best: Option<(f32, usize, usize)> = None;
+    for axis in 0..3 {
+        if hi[axis] <= lo[axis] {
+            continue;
+        }
+        let mut buckets: [Option<LightBounds>; BUCKETS] = [None; BUCKETS];
+        for (_, b) in lights.iter() {
+            let slot = &mut buckets[bucket(b, axis)];
+            *slot = Some(slot.map_or(*b, |s| s.union(b)));
+        }
+        let cost_of = |run: &[Option<LightBounds>]| {
+            run.iter()
+                .flatten()
+                .copied()
+                .reduce(|a, b| a.union(&b))
+                .map_or(0.0, |b| b.cost(&bounds, axis))
+        };
+        for i in 0..BUCKETS - 1 {
+            let cost = cost_of(&buckets[..=i]) + cost_of(&buckets[i + 1..]);
+            if best.is_none_or(|(c, _, _)| cost < c) {
+                best = Some((cost, axis, i));
+            }
+        }
+    }
+
+    let mid = match best {
+        Some((_, axis, last)) => partition(lights, |b| bucket(b, axis) <= last),
+        None => 0,
+    };
+    if mid == 0 || mid == lights.len() {
+        lights.len() / 2
+    } else {
+        mid
+    }
+}
+
+/// Moves the lights satisfying `left` to the front; returns how many.
+fn partition(lights: &mut [(usize, LightBounds)], left: impl Fn(&LightBounds) -> bool) -> usize {
+    let mut mid = 0;
+    for i in 0..lights.len() {
+        if left(&lights[i].1) {
+            lights.swap(i, mid);
+            mid += 1;
+        }
+    }
+    mid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::{AreaLight, DomeLight, RectShape, SphereShape};
+    use crate::material::Emissive;
+    use glam::Mat3A;
+
+    fn sphere(center: Vec3A, radius: f32, power: f32) -> Arc<dyn Light> {
+        Arc::new(AreaLight::new(
+            Box::new(SphereShape { center, radius }),
+            Arc::new(Emissive::new(Vec3A::splat(power))),
+            0,
+        ))
+    }
+
+    /// A few hundred small lights scattered over a plane, a dome, and a
+    /// panel facing away from the origin.
+    fn many_lights() -> Vec<Arc<dyn Light>> {
+        let mut lights: Vec<Arc<dyn Light>> = (0..300)
+            .map(|i| {
+                let (x, z) = ((i % 20) as f32, (i / 20) as f32);
+                sphere(
+                    Vec3A::new(x - 10.0, 3.0, z - 7.0),
+                    0.05,
+                    1.0 + (i % 7) as f32,
+                )
+            })
+            .collect();
+        lights.push(Arc::new(DomeLight::new(Vec3A::ONE, None, Mat3A::IDENTITY)));
+        lights.push(Arc::new(AreaLight::new(
+            Box::new(RectShape::new(
+                Vec3A::new(-1.0, 1.0, 5.0),
+                Vec3A::new(2.0, 0.0, 0.0),
+                Vec3A::new(0.0, 2.0, 0.0),
+                Vec3A::Z,
+            )),
+            Arc::new(Emissive::new(Vec3A::splat(4.0))),
+            1,
+        )));
+        lights
+    }
+
+    /// The pmf is a distribution over the lights, and sampling reports the
+    /// very probability `pmf` gives the light it chose — the two MIS sides.
+    #[test]
+    fn sampling_and_pmf_agree() {
+        let lights = many_lights();
+        let tree = LightTree::build(&lights);
+        for p in [
+            Vec3A::ZERO,
+            Vec3A::new(4.0, 3.0, -2.0),
+            Vec3A::new(0.0, 1.0, 9.0),
+        ] {
+            let total: f32 = (0..lights.len()).map(|i| tree.pmf(p, i)).sum();
+            assert!((total - 1.0).abs() < 1e-3, "pmf sums to {total} at {p}");
+
+            let n = 4096;
+            let mut counts = vec![0u32; lights.len()];
+            for k in 0..n {
+                let u = (k as f32 + 0.5) / n as f32;
+                let (light, pmf) = tree.sample(p, u).expect("something lights p");
+                let expected = tree.pmf(p, light);
+                assert!(
+                    (pmf - expected).abs() <= 1e-4 * expected,
+                    "{pmf} vs {expected}"
+                );
+                counts[light] += 1;
+            }
+            // Stratified draws land on each light in proportion to its pmf.
+            for (i, &count) in counts.iter().enumerate() {
+                let expected = tree.pmf(p, i) * n as f32;
+                assert!(
+                    (count as f32 - expected).abs() <= 2.0,
+                    "light {i}: {count} vs {expected}"
+                );
+            }
+        }
+    }
+
+    /// The tree steers samples to lights that matter: a bright light close
+    /// by over dim lights far away.
+    #[test]
+    fn near_bright_lights_are_preferred() {
+        let lights = many_lights();
+        let tree = LightTree::build(&lights);
+        let p = Vec3A::new(-10.0, 2.9, -7.0);
+        let near = tree.pmf(p, 0);
+        let far = tree.pmf(p, 299);
+        assert!(near > 50.0 * far, "near {near} vs far {far}");
+        // Uniform choice would give each light 1 / 302.
+        assert!(near > 10.0 / lights.len() as f32, "near light pmf {near}");
+        // The dome keeps its share, whatever the point.
+        assert!((tree.pmf(p, 300) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cone_union_holds_both_cones() {
+        let a = (Vec3A::Z, 0.9);
+        let b = (Vec3A::X, 0.95);
+        let (axis, cos) = cone_union(a, b);
+        for (w, c) in [a, b] {
+            let spread = angle_between(axis, w) + c.acos();
+            assert!(spread <= cos.acos() + 1e-4);
+        }
+        // Opposite axes leave nothing narrower than the whole sphere.
+        assert_eq!(cone_union((Vec3A::Z, 0.5), (Vec3A::NEG_Z, 0.5)).1, -1.0);
+    }
+
+    #[test]
+    fn infinite_lights_share_with_the_tree() {
+        let lights: Vec<Arc<dyn Light>> = vec![
+            Arc::new(DomeLight::new(Vec3A::ONE, None, Mat3A::IDENTITY)),
+            Arc::new(DomeLight::new(Vec3A::ONE, None, Mat3A::IDENTITY)),
+        ];
+        let tree = LightTree::build(&lights);
+        assert_eq!(tree.infinite(), &[0, 1]);
+        assert_eq!(tree.sample(Vec3A::ZERO, 0.2), Some((0, 0.5)));
+        assert_eq!(tree.sample(Vec3A::ZERO, 0.7), Some((1, 0.5)));
+        assert!(LightTree::build(&[]).sample(Vec3A::ZERO, 0.5).is_none());
+    }
+}
@@ -0,0 +1,593 @@
+//! Light path expressions: output layers declared as regular expressions
+//! over the events of a light path, in the OSL notation (`C<RD>L` for direct
+//! diffuse, `C.*<TS>.*L` for caustics, `C[DG]+<L.>` …).
+//!
+//! A path is read from the camera: `C`, then one event per vertex, then the
+//! emitter that ends it. An event is a *type* and a *scattering* label.
+//! Types: `C` camera, `R` reflection, `T` transmission, `V` volume scatter,
+//! `L` a light (UsdLux, or a mesh sampled as one), `O` any other emissive
+//! surface or volume emission, `B` the built-in background sky. Scattering:
+//! `D` diffuse (and fuzz), `G` glossy (microfacet and coat), `S` singular
+//! (delta lobes).
+//!
+//! `C`, `V`, `L`, `O` and `B` carry no scattering label, so they only match
+//! `.` in that slot. Custom labels (OSL's third tuple slot and `'quoted'`
+//! names) are not supported.
+//!
+//! Syntax: `<ts>` is one event (each slot a letter, `.`, or a `[…]` set);
+//! a bare letter is shorthand for the tuple with that letter in its slot
+//! (`D` ≡ `<.D>`, `R` ≡ `<R.>`); `.` is any event; `[…]` / `[^…]` are sets
+//! of shorthands; `|`, `*`, `+`, `?` and parentheses mean what they do in
+//! any regex. Whitespace is ignored.
+//!
+//! An expression compiles to a DFA over the 28 possible events, so
+//! matching a vertex costs one table lookup. The integrator feeds it from
+//! the records of its forward walk — see `tracer.rs::gather_lpes` for how a
+//! vertex whose BSDF mixes lobes is split between the events it stands for.
+
+use crate::material::LobeSplit;
+use glam::Vec3A;
+use std::collections::HashMap;
+
+/// Event types, in alphabet order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EventType {
+    Camera,
+    Reflect,
+    Transmit,
+    Volume,
+    Light,
+    Object,
+    Background,
+}
+
+/// Scattering labels, in alphabet order. `None` is the label of every
+/// event that is not a surface scatter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Scatter {
+    Diffuse,
+    Glossy,
+    Singular,
+    None,
+}
+
+const N_TYPES: usize = 7;
+const N_SCATTER: usize = 4;
+const N_SYMBOLS: usize = N_TYPES * N_SCATTER;
+
+/// One symbol of the alphabet: a (type, scattering) pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Event(u8);
+
+impl Event {
+    pub const fn new(ty: EventType, scatter: Scatter) -> Self {
+        Event(ty as u8 * N_SCATTER as u8 + scatter as u8)
+    }
+
+    pub const CAMERA: Event = Event::new(EventType::Camera, Scatter::None);
+    pub const LIGHT: Event = Event::new(EventType::Light, Scatter::None);
+    pub const OBJECT: Event = Event::new(EventType::Object, Scatter::None);
+    pub const BACKGROUND: Event = Event::new(EventType::Background, Scatter::None);
+    pub const VOLUME: Event = Event::new(EventType::Volume, Scatter::None);
+}
+
+/// A set of symbols, one bit each.
+type SymbolSet = u32;
+
+const ALL_SYMBOLS: SymbolSet = (1 << N_SYMBOLS) - 1;
+
+/// Symbols whose type slot is `ty`.
+fn type_set(ty: usize) -> SymbolSet {
+    ((1 << N_SCATTER) - 1) << (ty * N_SCATTER)
+}
+
+/// Symbols whose scattering slot is `scatter`.
+fn scatter_set(scatter: usize) -> SymbolSet {
+    (0..N_TYPES).fold(0, |s, ty| s | 1 << (ty * N_SCATTER + scatter))
+}
+
+fn type_index(c: char) -> Option<usize> {
+    Some(match c {
+        'C' => EventType::Camera,
+        'R' => EventType::Reflect,
+        'T' => EventType::Transmit,
+        'V' => EventType::Volume,
+        'L' => EventType::Light,
+        'O' => EventType::Object,
+        'B' => EventType::Background,
+        _ => return None,
+    } as usize)
+}
+
+fn scatter_index(c: char) -> Option<usize> {
+    Some(match c {
+        'D' => Scatter::Diffuse,
+        'G' => Scatter::Glossy,
+        'S' => Scatter::Singular,
+        _ => return None,
+    } as usize)
+}
+
+/// A bare letter outside a tuple: whichever slot it names.
+fn shorthand_set(c: char) -> Option<SymbolSet> {
+    type_index(c)
+        .map(type_set)
+        .or_else(|| scatter_index(c).map(scatter_set))
+}
+
+/// Thompson NFA under construction. The parser hands fragments around as
+/// `(entry, exit)` state pairs and wires them together with epsilon edges.
+#[derive(Default)]
+struct Nfa {
+    edges: Vec<Vec<(SymbolSet, usize)>>,
+    eps: Vec<Vec<usize>>,
+}
+
+impl Nfa {
+    fn state(&mut self) -> usize {
+        self.edges.push(Vec::new());
+        self.eps.push(Vec::new());
+        self.edges.len() - 1
+    }
+
+    fn symbol(&mut self, set: SymbolSet) -> (usize, usize) {
+        let (a, b) = (self.state(), self.state());
+        self.edges[a].push((set, b));
+        (a, b)
+    }
+
+    fn empty(&mut self) -> (usize, usize) {
+        let (a, b) = (self.state(), self.state());
+        self.eps[a].push(b);
+        (a, b)
+    }
+
+    /// Epsilon closure of `states`, sorted so it can key the DFA map.
+    fn closure(&self, states: impl IntoIterator<Item = usize>) -> Vec<usize> {
+        let mut seen = vec![false; self.eps.len()];
+        let mut stack: Vec<usize> = states.into_iter().collect();
+        let mut out = Vec::new();
+        while let Some(s) = stack.pop() {
+            if std::mem::replace(&mut seen[s], true) {
+                continue;
+            }
+            out.push(s);
+            stack.extend(&self.eps[s]);
+        }
+        out.sort_unstable();
+        out
+    }
+}
+
+/// Recursive-descent parser straight into the NFA.
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    nfa: Nfa,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.peek();
+        self.chars.next()
+    }
+
+    fn expect(&mut self, want: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == want => Ok(()),
+            Some(c) => Err(format!("expected '{want}', found '{c}'")),
+            None => Err(format!("expected '{want}', found end of expression")),
+        }
+    }
+
+    fn alternation(&mut self) -> Result<(usize, usize), String> {
+        let mut frag = self.concatenation()?;
+        while self.peek() == Some('|') {
+            self.next();
+            let rhs = self.concatenation()?;
+            let (a, b) = (self.nfa.state(), self.nfa.state());
+            self.nfa.eps[a].extend([frag.0, rhs.0]);
+            self.nfa.eps[frag.1].push(b);
+            self.nfa.eps[rhs.1].push(b);
+            frag = (a, b);
+        }
+        Ok(frag)
+    }
+
+    fn concatenation(&mut self) -> Result<(usize, usize), String> {
+        let mut frag: Option<(usize, usize)> = None;
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let next = self.repetition()?;
+            frag = Some(match frag {
+                Some((a, b)) => {
+                    self.nfa.eps[b].push(next.0);
+                    (a, next.1)
+                }
+                None => next,
+            });
+        }
+        Ok(match frag {
+            Some(f) => f,
+            None => self.nfa.empty(),
+        })
+    }
+
+    fn repetition(&mut self) -> Result<(usize, usize), String> {
+        let mut frag = self.atom()?;
+        while let Some(op @ ('*' | '+' | '?')) = self.peek() {
+            self.next();
+            let (a, b) = (self.nfa.state(), self.nfa.state());
+            self.nfa.eps[a].push(frag.0);
+            self.nfa.eps[frag.1].push(b);
+            if op != '+' {
+                self.nfa.eps[a].push(b);
+            }
+            if op != '?' {
+                self.nfa.eps[frag.1].push(frag.0);
+            }
+            frag = (a, b);
+        }
+        Ok(frag)
+    }
+
+    fn atom(&mut self) -> Result<(usize, usize), String> {
+        match self.next() {
+            Some('(') => {
+                let frag = self.alternation()?;
+                self.expect(')')?;
+                Ok(frag)
+            }
+            Some('<') => {
+                let set = self.tuple()?;
+                Ok(self.nfa.symbol(set))
+            }
+            Some('[') => {
+                let set = self.set(shorthand_set)?;
+                Ok(self.nfa.symbol(set))
+            }
+            Some('.') => Ok(self.nfa.symbol(ALL_SYMBOLS)),
+            Some(c) => match shorthand_set(c) {
+                Some(set) => Ok(self.nfa.symbol(set)),
+                None => Err(format!("unknown event '{c}'")),
+            },
+            None => Err("unexpected end of expression".to_owned()),
+        }
+    }
+
+    /// `<ts>` after the `<`: a type slot and a scattering slot, each a
+    /// letter, `.` or a set.
+    fn tuple(&mut self) -> Result<SymbolSet, String> {
+        let types = self.slot(|c| type_index(c).map(type_set))?;
+        let scatters = match self.peek() {
+            Some('>') => ALL_SYMBOLS,
+            _ => self.slot(|c| scatter_index(c).map(scatter_set))?,
+        };
+        match self.next() {
+            Some('>') => Ok(types & scatters),
+            Some(c) => Err(format!(
+                "expected '>' after two event slots, found '{c}' (custom labels are not supported)"
+            )),
+            None => Err("unterminated '<'".to_owned()),
+        }
+    }
+
+    fn slot(&mut self, letter: fn(char) -> Option<SymbolSet>) -> Result<SymbolSet, String> {
+        match self.next() {
+            Some('.') => Ok(ALL_SYMBOLS),
+            Some('[') => self.set(letter),
+            Some(c) => letter(c).ok_or_else(|| format!("'{c}' is not valid in this event slot")),
+            None => Err("unterminated '<'".to_owned()),
+        }
+    }
+
+    /// `[…]` after the `[`: a union of letters, complemented with a
+    /// leading `^`.
+    fn set(&mut self, letter: fn(char) -> Option<SymbolSet>) -> Result<SymbolSet, String> {
+        let negate = self.peek() == Some('^');
+        if negate {
+            self.next();
+        }
+        let mut set = 0;
+        loop {
+            match self.next() {
+                Some(']') => break,
+                Some(c) => {
+                    set |= letter(c).ok_or_else(|| format!("'{c}' is not valid in this set"))?
+                }
+                None => return Err("unterminated '['".to_owned()),
+            }
+        }
+        Ok(if negate { ALL_SYMBOLS & !set } else { set })
+    }
+}
+
+/// The compiled automaton. State 0 is the dead state (the empty NFA set);
+/// state 1 is the start.
+#[derive(Debug, Clone)]
+struct Dfa {
+    next: Vec<[u16; N_SYMBOLS]>,
+    accept: Vec<bool>,
+    /// Whether an accepting state is still reachable — a path in a dead
+    /// state can be dropped from the gather early.
+    live: Vec<bool>,
+}
+
+/// DFA states an expression may compile to. Real LPEs need a handful; this
+/// only guards against pathological input.
+const MAX_STATES: usize = 4096;
+
+impl Dfa {
+    fn build(nfa: &Nfa, start: usize, end: usize) -> Result<Dfa, String> {
+        let mut ids: HashMap<Vec<usize>, u16> = HashMap::new();
+        let mut sets: Vec<Vec<usize>> = vec![Vec::new()];
+        ids.insert(Vec::new(), 0);
+        let first = nfa.closure([start]);
+        ids.insert(first.clone(), 1);
+        sets.push(first);
+
+        let mut next = vec![[0u16; N_SYMBOLS]];
+        let mut i = 1;
+        while i < sets.len() {
+            let mut row = [0u16; N_SYMBOLS];
+            for (sym, slot) in row.iter_mut().enumerate() {
+                let targets = sets[i].iter().flat_map(|&s| {
+                    nfa.edges[s]
+                        .iter()
+                        .filter(move |(set, _)| set & (1 << sym) != 0)
+                        .map(|(_, t)| *t)
+                });
+                let set = nfa.closure(targets);
+                *slot = match ids.get(&set) {
+                    Some(&id) => id,
+                    None => {
+                        if sets.len() >= MAX_STATES {
+                            return Err("expression is too complex".to_owned());
+                        }
+                        let id = sets.len() as u16;
+                        ids.insert(set.clone(), id);
+                        sets.push(set);
+                        id
+                    }
+                };
+            }
+            next.push(row);
+            i += 1;
+        }
+
+        let accept: Vec<bool> = sets.iter().map(|s| s.binary_search(&end).is_ok()).collect();
+        // Backward reachability from the accepting states.
+        let mut live = accept.clone();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for s in 0..next.len() {
+                if !live[s] && next[s].iter().any(|&t| live[t as usize]) {
+                    live[s] = true;
+                    changed = true;
+                }
+            }
+        }
+        Ok(Dfa { next, accept, live })
+    }
+}
+
+/// A named light path expression, compiled. Outputs land in the EXR as
+/// `<name>.R/G/B`.
+#[derive(Debug, Clone)]
+pub struct LightPathExpr {
+    name: String,
+    expression: String,
+    dfa: Dfa,
+}
+
+impl LightPathExpr {
+    /// Compiles `expression` as the output layer `name`. `Err` carries a
+    /// message saying what did not parse.
+    pub fn new(name: impl Into<String>, expression: &str) -> Result<Self, String> {
+        let name = name.into();
+        if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '.') {
+            return Err(format!("\"{name}\" is not a valid layer name"));
+        }
+        let mut parser = Parser {
+            chars: expression.chars().peekable(),
+            nfa: Nfa::default(),
+        };
+        let (start, end) = parser.alternation()?;
+        if let Some(c) = parser.next() {
+            return Err(format!("unexpected '{c}'"));
+        }
+        let dfa = Dfa::build(&parser.nfa, start, end)?;
+        Ok(LightPathExpr {
+            name,
+            expression: expression.to_owned(),
+            dfa,
+        })
+    }
+
+    /// Parses `name=expression`, the form `--lpe` and `crust:lpes` use.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (name, expression) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("\"{spec}\" is not of the form name=expression"))?;
+        LightPathExpr::new(name.trim(), expression)
+            .map_err(|e| format!("{}: {e}", name.trim()))
+    }
+
+    /// Parses a `;`-separated list of `name=expression` entries.
+    pub fn parse_list(list: &str) -> Result<Vec<Self>, String> {
+        list.split(';')
+            .filter(|s| !s.trim().is_empty())
+            .map(LightPathExpr::parse)
+            .collect()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// The state after the camera event every path starts with.
+    pub(crate) fn camera_state(&self) -> u16 {
+        self.step(1, Event::CAMERA)
+    }
+
+    #[inline]
+    pub(crate) fn step(&self, state: u16, event: Event) -> u16 {
+        self.dfa.next[state as usize][event.0 as usize]
+    }
+
+    #[inline]
+    pub(crate) fn accepts(&self, state: u16) -> bool {
+        self.dfa.accept[state as usize]
+    }
+
+    #[inline]
+    pub(crate) fn is_live(&self, state: u16) -> bool {
+        self.dfa.live[state as usize]
+    }
+}
+
+/// The scattering events one vertex stands for, with the per-channel share
+/// of the vertex's contribution each receives. Shares sum to one, so
+/// splitting a vertex this way never adds or loses energy.
+#[derive(Clone, Copy)]
+pub(crate) struct LobeEvents {
+    events: [(Event, Vec3A); 3],
+    len: usize,
+}
+
+impl LobeEvents {
+    /// No scattering at all (an absorbed vertex's bounce, or an NEE sample
+    /// that found nothing).
+    pub const NONE: LobeEvents = LobeEvents {
+        events: [(Event::VOLUME, Vec3A::ZERO); 3],
+        len: 0,
+    };
+
+    /// A single event taking the whole contribution.
+    pub fn single(event: Event) -> Self {
+        let mut out = LobeEvents::NONE;
+        out.events[0] = (event, Vec3A::ONE);
+        out.len = 1;
+        out
+    }
+
+    /// A continuous surface sample: diffuse is reflected diffuse, specular
+    /// is reflected glossy, transmission is transmitted glossy. `shares`
+    /// must already be normalized (see `tracer.rs::lobe_shares`).
+    pub fn surface(shares: LobeSplit) -> Self {
+        LobeEvents {
+            events: [
+                (Event::new(EventType::Reflect, Scatter::Diffuse), shares.diffuse),
+                (Event::new(EventType::Reflect, Scatter::Glossy), shares.specular),
+                (Event::new(EventType::Transmit, Scatter::Glossy), shares.transmission),
+            ],
+            len: 3,
+        }
+    }
+
+    /// A delta sample, reflected or transmitted.
+    pub fn singular(reflected: bool) -> Self {
+        let ty = if reflected {
+            EventType::Reflect
+        } else {
+            EventType::Transmit
+        };
+        LobeEvents::single(Event::new(ty, Scatter::Singular))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Event, Vec3A)> + '_ {
+        self.events[..self.len]
+            .iter()
+            .copied()
+            .filter(|(_, share)| *share != Vec3A::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ev(ty: EventType, s: Scatter) -> Event {
+        Event::new(ty, s)
+    }
+
+    /// Runs a full event string (camera included) through the automaton.
+    fn matches(lpe: &LightPathExpr, events: &[Event]) -> bool {
+        let mut s = 1;
+        for e in events {
+            s = lpe.step(s, *e);
+        }
+        lpe.accepts(s)
+    }
+
+    const RD: Event = Event::new(EventType::Reflect, Scatter::Diffuse);
+    const RG: Event = Event::new(EventType::Reflect, Scatter::Glossy);
+    const TS: Event = Event::new(EventType::Transmit, Scatter::Singular);
+
+    #[test]
+    fn direct_diffuse_matches_one_diffuse_bounce_only() {
+        let lpe = LightPathExpr::new("dd", "C<RD>L").unwrap();
+        assert!(matches(&lpe, &[Event::CAMERA, RD, Event::LIGHT]));
+        assert!(!matches(&lpe, &[Event::CAMERA, RG, Event::LIGHT]));
+        assert!(!matches(&lpe, &[Event::CAMERA, RD, RD, Event::LIGHT]));
+        assert!(!matches(&lpe, &[Event::CAMERA, RD, Event::OBJECT]));
+    }
+
+    #[test]
+    fn caustics_need_a_singular_transmission_somewhere() {
+        let lpe = LightPathExpr::new("caustic", "C.*<TS>.*L").unwrap();
+        assert!(matches(&lpe, &[Event::CAMERA, RD, TS, TS, Event::LIGHT]));
+        assert!(matches(&lpe, &[Event::CAMERA, TS, Event::LIGHT]));
+        assert!(!matches(&lpe, &[Event::CAMERA, RD, RG, Event::LIGHT]));
+    }
+
+    #[test]
+    fn shorthands_sets_and_alternation() {
+        let lpe = LightPathExpr::new("x", "C[DG]+(L|O)").unwrap();
+        assert!(matches(&lpe, &[Event::CAMERA, RD, RG, Event::OBJECT]));
+        assert!(!matches(&lpe, &[Event::CAMERA, TS, Event::LIGHT]));
+        assert!(!matches(&lpe, &[Event::CAMERA, Event::LIGHT]));
+
+        let not_diffuse = LightPathExpr::new("y", "C<.[^D]>B").unwrap();
+        assert!(matches(&not_diffuse, &[Event::CAMERA, RG, Event::BACKGROUND]));
+        assert!(!matches(&not_diffuse, &[Event::CAMERA, RD, Event::BACKGROUND]));
+        assert!(matches(
+            &not_diffuse,
+            &[Event::CAMERA, ev(EventType::Volume, Scatter::None), Event::BACKGROUND]
+        ));
+    }
+
+    /// A state from which nothing can match any more is reported dead, so
+    /// the gather can stop following it.
+    #[test]
+    fn dead_states_are_detected() {
+        let lpe = LightPathExpr::new("dd", "C<RD>L").unwrap();
+        let s = lpe.step(lpe.camera_state(), RG);
+        assert!(!lpe.is_live(s));
+        assert!(lpe.is_live(lpe.step(lpe.camera_state(), RD)));
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        for bad in ["C<RD", "C(RD", "CX", "C<R D 'lbl'>L", "C[RD"] {
+            assert!(LightPathExpr::new("x", bad).is_err(), "{bad} parsed");
+        }
+        assert!(LightPathExpr::parse("no_equals_sign").is_err());
+        let list = LightPathExpr::parse_list("a=C<RD>L; b = C.*L").unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[1].name(), "b");
+    }
+}
@@ -296,13 +296,15 @@ pub fn coat_darkening_factor(base_color: Vec3A, coat_ior: f32, darkening: f32) -
 //
 // Three-layer Airy-summation reflectance for a single dielectric film of
 // thickness `d` (nm) and index `η_film` sandwiched between an outer medium
-// of index `η_1` and a base of index `η_2`. Evaluated at the CIE sRGB
-// primaries (R = 615 nm, G = 545 nm, B = 465 nm) — a 3-wavelength
-// approximation that captures the characteristic soap-bubble / oil-slick
-// look without full spectral rendering.
+// of index `η_1` and a base of index `η_2`. Evaluated at one wavelength per
+// channel — the working space's primaries, R = 615 nm, G = 545 nm,
+// B = 465 nm for sRGB — a 3-wavelength approximation that captures the
+// characteristic soap-bubble / oil-slick look without full spectral
+// rendering.
 /// Representative wavelengths (nm) of the sRGB primaries, shared by the
 /// thin-film interference and dispersion models so per-channel spectral
-/// effects stay consistent.
+/// effects stay consistent. Other working spaces supply their own through
+/// `OpenPBR::channel_wavelengths`.
 pub const LAMBDA_RGB: [f32; 3] = [615.0, 545.0, 465.0];
 
 // -------- Physical dispersion (Cauchy / Abbe) --------
@@ -378,9 +380,10 @@ pub fn thin_film_fresnel(
     eta_film: f32,
     eta_2: f32,
     thickness_nm: f32,
+    lambdas: [f32; 3],
 ) -> Vec3A {
     let mut out = [0.0f32; 3];
-    for (i, lambda) in LAMBDA_RGB.into_iter().enumerate() {
+    for (i, lambda) in lambdas.into_iter().enumerate() {
         out[i] =
             thin_film_reflectance_lambda(cos_theta_1, eta_1, eta_film, eta_2, thickness_nm, lambda);
     }
@@ -400,9 +403,10 @@ pub fn thin_film_fresnel_metal(
     eta_film: f32,
     f0: Vec3A,
     thickness_nm: f32,
+    lambdas: [f32; 3],
 ) -> Vec3A {
     let mut out = [0.0f32; 3];
-    for (i, lambda) in LAMBDA_RGB.into_iter().enumerate() {
+    for (i, lambda) in lambdas.into_iter().enumerate() {
         let f0_c = f0[i].clamp(0.0, 0.9999);
         let sqrt_f0 = f0_c.sqrt();
         let eta_2 = (1.0 + sqrt_f0) / (1.0 - sqrt_f0);
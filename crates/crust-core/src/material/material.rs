@@ -21,6 +21,26 @@ pub struct ScatterSample {
     pub delta: bool,
 }
 
+/// A continuous BSDF value split across the lobe families the lighting AOVs
+/// report (see `aov.rs`), in the same `brdf * cos(theta_i)` convention as
+/// [`Material::eval`]. Only the *ratios* between the fields are used: they
+/// say what share of a sample's throughput belongs to which layer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LobeSplit {
+    /// Diffuse and fuzz (sheen) lobes.
+    pub diffuse: Vec3A,
+    /// Specular and coat reflection.
+    pub specular: Vec3A,
+    /// Refraction into or through the surface.
+    pub transmission: Vec3A,
+}
+
+impl LobeSplit {
+    pub fn total(&self) -> Vec3A {
+        self.diffuse + self.specular + self.transmission
+    }
+}
+
 /// The `Material` trait defines the behavior of materials in the ray tracing system.
 /// Materials determine how rays interact with surfaces, including scattering and emission.
 pub trait Material: Send + Sync {
@@ -72,6 +92,25 @@ pub trait Material: Send + Sync {
         None
     }
 
+    /// [`Material::eval`] split by lobe family, for the lighting AOVs.
+    /// Same contract on `None`. The default files the whole value under
+    /// diffuse, which is right for any single-lobe material.
+    fn eval_lobes(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3A) -> Option<LobeSplit> {
+        self.eval(r_in, rec, wi).map(|(value, _)| LobeSplit {
+            diffuse: value,
+            ..Default::default()
+        })
+    }
+
+    /// Surface reflectance at the hit, for the albedo AOV: roughly the
+    /// colour the surface would have under uniform white light. No physical
+    /// meaning is attached beyond that — it is a denoising and compositing
+    /// feature, not an input to the estimator. Black by default.
+    fn albedo(&self, rec: &HitRecord) -> Vec3A {
+        let _ = rec;
+        Vec3A::ZERO
+    }
+
     /// Builds the continuation ray for an externally chosen direction `wi`
     /// (e.g. drawn from the guiding field). Materials that tag rays with an
     /// interior medium on transmission must do the same here, so a guided
@@ -1,5 +1,5 @@
 mod material;
-pub use material::{Material, ScatterSample};
+pub use material::{LobeSplit, Material, ScatterSample};
 mod emissive;
 pub use emissive::Emissive;
 mod brdf;
@@ -36,7 +36,7 @@
 //!   normals/tangents.
 
 use crate::hittable::HitRecord;
-use crate::material::{Material, ScatterSample};
+use crate::material::{LobeSplit, Material, ScatterSample};
 use crate::material::brdf::*;
 use crate::medium::Medium;
 use crate::ray::Ray;
@@ -62,7 +62,7 @@ fn subsurface_radius_scale_default() -> Vec3A {
 fn base_default() -> Vec3A {
     Vec3A::new(0.8, 0.8, 0.8)
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct OpenPBR {
     // --- base -----------------------------------------------------------
     pub base_weight: f32,
@@ -125,6 +125,12 @@ pub struct OpenPBR {
     /// fallback for hits with no face identity (and for hosts that decode no
     /// Ptex), so an unresolved texture degrades to a flat plausible colour.
     pub base_color_ptex: Option<crate::PtexRef>,
+
+    // --- working space --------------------------------------------------
+    /// Wavelength (nm) each RGB channel stands for in the spectral effects —
+    /// thin-film interference and dispersion. [`LAMBDA_RGB`] for sRGB
+    /// primaries; [`OpenPBR::in_color_space`] sets the working space's.
+    pub channel_wavelengths: [f32; 3],
 }
 
 impl Default for OpenPBR {
@@ -168,11 +174,37 @@ impl Default for OpenPBR {
             geometry_opacity: 1.0,
             geometry_thin_walled: false,
             base_color_ptex: None,
+            channel_wavelengths: LAMBDA_RGB,
         }
     }
 }
 
 impl OpenPBR {
+    /// Re-expresses the material in `space`: every colour parameter (authored
+    /// in linear Rec.709) is converted, the Ptex base colour is converted as
+    /// it is sampled, and the spectral effects move to the space's primary
+    /// wavelengths. Scalar and per-channel-distance parameters
+    /// (`subsurface_radius_scale`) are left alone.
+    pub fn in_color_space(self, space: crate::ColorSpace) -> Self {
+        if space == crate::ColorSpace::LinRec709 {
+            return self;
+        }
+        let c = |v: Vec3A| space.convert_rec709(v);
+        Self {
+            base_color: c(self.base_color),
+            specular_color: c(self.specular_color),
+            transmission_color: c(self.transmission_color),
+            transmission_scatter: c(self.transmission_scatter),
+            subsurface_color: c(self.subsurface_color),
+            fuzz_color: c(self.fuzz_color),
+            coat_color: c(self.coat_color),
+            emission_color: c(self.emission_color),
+            base_color_ptex: self.base_color_ptex.map(|t| t.in_color_space(space)),
+            channel_wavelengths: space.wavelengths(),
+            ..self
+        }
+    }
+
     /// Pure diffuse surface (the old `Lambertian` preset).
     pub fn diffuse(base_color: Vec3A) -> Self {
         OpenPBR {
@@ -497,6 +529,7 @@ fn eval_specular(
                 m.thin_film_ior,
                 m.specular_ior,
                 tf_thickness_nm,
+                m.channel_wavelengths,
             );
             f_normal * (1.0 - m.thin_film_weight) + f_iri * m.thin_film_weight
         } else {
@@ -536,6 +569,7 @@ fn eval_specular(
                 m.thin_film_ior,
                 metal_f0,
                 tf_thickness_nm,
+                m.channel_wavelengths,
             );
             f_metal_base * (1.0 - m.thin_film_weight) + f_iri * m.thin_film_weight
         } else {
@@ -682,6 +716,51 @@ fn eval_all(m: &OpenPBR, v_local: Vec3A, l_local: Vec3A, entering: bool) -> Vec3
     fuzz + base_atten * (coat + coat_atten * (diffuse + specular))
 }
 
+/// [`eval_all`] with its terms kept apart by lobe family, for the lighting
+/// AOVs. The layering factors are distributed onto each term — fuzz sits on
+/// top untouched, coat and base specular share the specular family, the
+/// attenuated base diffuse joins fuzz — so the fields sum to `eval_all` up
+/// to float rounding. Not used by the estimator itself, which keeps the
+/// bit-exact composition above.
+fn eval_split(m: &OpenPBR, v_local: Vec3A, l_local: Vec3A, entering: bool) -> LobeSplit {
+    if v_local.z <= 0.0 {
+        return LobeSplit::default();
+    }
+    if l_local.z <= 0.0 {
+        if !transmission_is_continuous(m) {
+            return LobeSplit::default();
+        }
+        return LobeSplit {
+            transmission: eval_transmission(m, v_local, l_local, entering).0,
+            ..Default::default()
+        };
+    }
+    let h_local = (v_local + l_local).normalize();
+    let (ax, ay) = roughness_to_alpha_aniso(m.specular_roughness, m.specular_roughness_anisotropy);
+    let f_avg_diel = f0_from_ior(m.specular_ior);
+    let diffuse = eval_diffuse(m, v_local, l_local, f_avg_diel);
+    let specular = eval_specular(m, v_local, l_local, h_local, ax, ay);
+    let coat = if m.coat_weight > 0.0 {
+        let (ax_coat, ay_coat) =
+            roughness_to_alpha_aniso(m.coat_roughness, m.coat_roughness_anisotropy);
+        eval_coat(m, v_local, l_local, h_local, ax_coat, ay_coat)
+    } else {
+        Vec3A::ZERO
+    };
+    let fuzz = if m.fuzz_weight > 0.0 {
+        eval_fuzz(m, v_local, l_local, h_local)
+    } else {
+        Vec3A::ZERO
+    };
+    let coat_atten = coat_attenuation(m, v_local.z, l_local.z);
+    let base_atten = (1.0 - m.fuzz_weight).clamp(0.0, 1.0);
+    LobeSplit {
+        diffuse: fuzz + base_atten * coat_atten * diffuse,
+        specular: base_atten * (coat + coat_atten * specular),
+        transmission: Vec3A::ZERO,
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Mixture PDF: p(l) = Σ p_lobe · pdf_lobe(l)
 // ---------------------------------------------------------------------------
@@ -726,14 +805,14 @@ fn pdf_all(m: &OpenPBR, pmf: &LobePmf, v_local: Vec3A, l_local: Vec3A, entering:
 // ---------------------------------------------------------------------------
 
 /// Per-channel IOR for a dispersive dielectric, `(η_R, η_G, η_B)` at the
-/// sRGB primary wavelengths, via the Cauchy/Abbe fit (`cauchy_ior`).
+/// channel wavelengths `lambdas`, via the Cauchy/Abbe fit (`cauchy_ior`).
 /// `transmission_dispersion_scale` divides the authored Abbe number — the
 /// effective Abbe is `abbe / scale`, so scale 1 is the physical dispersion
 /// of a glass with that Abbe number, larger scales exaggerate it linearly,
 /// and 0 collapses to `(η_D, η_D, η_D)`. An IOR below 1 (interior less
 /// dense than the exterior) disperses via its reciprocal, keeping the
 /// model symmetric across the interface.
-fn dispersive_ior(n_d: f32, abbe: f32, dispersion_scale: f32) -> Vec3A {
+fn dispersive_ior(n_d: f32, abbe: f32, dispersion_scale: f32, lambdas: [f32; 3]) -> Vec3A {
     if dispersion_scale <= 0.0 || n_d == 1.0 {
         return Vec3A::splat(n_d);
     }
@@ -743,7 +822,7 @@ fn dispersive_ior(n_d: f32, abbe: f32, dispersion_scale: f32) -> Vec3A {
     // (tiny Abbe, huge scale) from producing a runaway Cauchy B term.
     let v_d = (abbe.max(1.0) / dispersion_scale).max(1.0);
     let mut out = [0.0f32; 3];
-    for (c, lambda) in LAMBDA_RGB.into_iter().enumerate() {
+    for (c, lambda) in lambdas.into_iter().enumerate() {
         let n = cauchy_ior(n_above_one, v_d, lambda);
         out[c] = if inverted { 1.0 / n } else { n };
     }
@@ -835,6 +914,7 @@ fn transmission_iors(m: &OpenPBR) -> Vec3A {
         m.specular_ior,
         m.transmission_dispersion_abbe_number,
         m.transmission_dispersion_scale,
+        m.channel_wavelengths,
     )
 }
 
@@ -1157,6 +1237,33 @@ impl OpenPBR {
         ))
     }
 
+    fn eval_lobes_resolved(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3A) -> Option<LobeSplit> {
+        let frame = Frame::new(rec.normal);
+        let v_local = frame.to_local(-r_in.direction().normalize());
+        if v_local.z <= 0.0 {
+            return None;
+        }
+        let l_local = frame.to_local(wi.normalize());
+        let split = eval_split(self, v_local, l_local, rec.front_face);
+        let cos = l_local.z.abs();
+        Some(LobeSplit {
+            diffuse: split.diffuse * cos,
+            specular: split.specular * cos,
+            transmission: split.transmission * cos,
+        })
+    }
+
+    /// The albedo AOV: the base colour as each substrate weights it —
+    /// diffuse base and transmission tint for the dielectric, the tinted
+    /// reflectance for the metal — blended by metalness. A feature layer,
+    /// so a plausible proxy beats an integrated directional albedo.
+    fn albedo_resolved(&self) -> Vec3A {
+        let dielectric = self.base_weight * self.base_color * (1.0 - self.transmission_weight)
+            + self.transmission_weight * self.transmission_color;
+        let metal = self.base_weight * self.base_color * self.specular_weight;
+        dielectric.lerp(metal, self.base_metalness.clamp(0.0, 1.0))
+    }
+
 }
 
 impl Material for OpenPBR {
@@ -1179,6 +1286,20 @@ impl Material for OpenPBR {
         }
     }
 
+    fn eval_lobes(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3A) -> Option<LobeSplit> {
+        match self.shaded(rec) {
+            Some(m) => m.eval_lobes_resolved(r_in, rec, wi),
+            None => self.eval_lobes_resolved(r_in, rec, wi),
+        }
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Vec3A {
+        match self.shaded(rec) {
+            Some(m) => m.albedo_resolved(),
+            None => self.albedo_resolved(),
+        }
+    }
+
     fn face_texture(&self) -> Option<&dyn crate::PtexTexture> {
         self.base_color_ptex.as_ref().map(|t| &*t.0)
     }
@@ -1563,7 +1684,7 @@ mod tests {
 
     #[test]
     fn thin_film_at_normal_incidence_is_bounded() {
-        let r = thin_film_fresnel(1.0, 1.0, 1.4, 1.5, 500.0);
+        let r = thin_film_fresnel(1.0, 1.0, 1.4, 1.5, 500.0, LAMBDA_RGB);
         assert!(r.x >= 0.0 && r.x <= 1.0);
         assert!(r.y >= 0.0 && r.y <= 1.0);
         assert!(r.z >= 0.0 && r.z <= 1.0);
@@ -1599,7 +1720,7 @@ mod tests {
 
     #[test]
     fn dispersive_ior_no_scale_is_flat() {
-        let v = dispersive_ior(1.5, 30.0, 0.0);
+        let v = dispersive_ior(1.5, 30.0, 0.0, LAMBDA_RGB);
         assert_eq!(v.x, 1.5);
         assert_eq!(v.y, 1.5);
         assert_eq!(v.z, 1.5);
@@ -1607,7 +1728,7 @@ mod tests {
 
     #[test]
     fn dispersive_ior_blue_bends_more() {
-        let v = dispersive_ior(1.5, 30.0, 1.0);
+        let v = dispersive_ior(1.5, 30.0, 1.0, LAMBDA_RGB);
         assert!(v.z > v.y, "blue IOR {} not > green {}", v.z, v.y);
         assert!(v.y > v.x, "green IOR {} not > red {}", v.y, v.x);
     }
@@ -1633,8 +1754,8 @@ mod tests {
         // The effective Abbe is abbe/scale, and the Cauchy B term (hence the
         // per-channel spread) is proportional to 1/V_d — so doubling the
         // scale doubles the R↔B spread.
-        let full = dispersive_ior(1.5, 40.0, 1.0);
-        let half = dispersive_ior(1.5, 40.0, 0.5);
+        let full = dispersive_ior(1.5, 40.0, 1.0, LAMBDA_RGB);
+        let half = dispersive_ior(1.5, 40.0, 0.5, LAMBDA_RGB);
         let ratio = (full.z - full.x) / (half.z - half.x);
         assert!((ratio - 2.0).abs() < 1e-3, "spread ratio {ratio}");
         // And scale 1 gives the physical glass: green stays near n_d.
@@ -1645,8 +1766,8 @@ mod tests {
     fn dispersive_ior_below_one_uses_reciprocal() {
         // η < 1 disperses via its reciprocal: dispersive(1/n) = 1/dispersive(n)
         // per channel, so the model is symmetric across the interface.
-        let n = dispersive_ior(1.5, 30.0, 1.0);
-        let inv = dispersive_ior(1.0 / 1.5, 30.0, 1.0);
+        let n = dispersive_ior(1.5, 30.0, 1.0, LAMBDA_RGB);
+        let inv = dispersive_ior(1.0 / 1.5, 30.0, 1.0, LAMBDA_RGB);
         for c in 0..3 {
             assert!(
                 (inv[c] - 1.0 / n[c]).abs() < 1e-5,
@@ -1971,7 +2092,7 @@ mod tests {
     #[test]
     fn thin_film_on_metal_is_bounded_and_active() {
         let f0 = Vec3A::new(0.9, 0.7, 0.4);
-        let r = thin_film_fresnel_metal(0.8, 1.0, 1.4, f0, 500.0);
+        let r = thin_film_fresnel_metal(0.8, 1.0, 1.4, f0, 500.0, LAMBDA_RGB);
         for c in [r.x, r.y, r.z] {
             assert!((0.0..=1.0).contains(&c), "out of range: {r}");
         }
@@ -2243,4 +2364,37 @@ mod tests {
             }
         }
     }
+
+    /// The AOV split must account for the whole BSDF: splitting a sample's
+    /// throughput by the ratios must not create or lose energy, or the
+    /// lighting layers stop summing to the beauty.
+    #[test]
+    fn lobe_split_sums_to_eval() {
+        use crate::hittable::HitRecord;
+        let m = OpenPBR {
+            base_color: Vec3A::new(0.7, 0.3, 0.2),
+            base_metalness: 0.3,
+            coat_weight: 0.5,
+            fuzz_weight: 0.2,
+            ..OpenPBR::default()
+        };
+        let mut rec = HitRecord::new();
+        rec.normal = Vec3A::Y;
+        rec.front_face = true;
+        let ray = Ray::new(Vec3A::new(0.0, 1.0, 1.0), Vec3A::new(0.0, -1.0, -1.0).normalize());
+        for wi in [
+            Vec3A::new(0.0, 1.0, 0.0),
+            Vec3A::new(0.3, 0.8, -0.5).normalize(),
+            Vec3A::new(-0.9, 0.1, 0.2).normalize(),
+        ] {
+            let (value, _) = m.eval(&ray, &rec, wi).expect("opaque OpenPBR evaluates");
+            let split = m.eval_lobes(&ray, &rec, wi).expect("and splits");
+            assert!(
+                (split.total() - value).abs().max_element() <= 1e-4 * value.max_element().max(1.0),
+                "split {split:?} does not sum to eval {value:?}"
+            );
+            assert!(split.diffuse.max_element() > 0.0 && split.specular.max_element() > 0.0);
+            assert_eq!(split.transmission, Vec3A::ZERO);
+        }
+    }
 }
@@ -0,0 +1,444 @@
+//! Distributed rendering: one frame split across processes or machines,
+//! then merged.
+//!
+//! A [`TileSplit`] `k/n` picks every `n`-th bucket of the frame's 16×16
+//! tiling, starting at the `k`-th, so each part gets an even spread of easy
+//! and hard regions. A part renders its buckets and writes their pixels'
+//! running sums — the same [`PixelAccum`] state a checkpoint keeps — rather
+//! than finished colours, and [`PartialRender::merge`] resolves them into
+//! the image. Nothing about a pixel depends on which process rendered it:
+//! its samples are indexed by pixel and sample number, and filter importance
+//! sampling keeps each sample inside the pixel that drew it, so no pixel
+//! needs a neighbouring bucket's samples. The merged image is the one a
+//! single process would have rendered.
+//!
+//! Like checkpoints, parts cover the beauty and alpha only, and render
+//! unguided: a guiding field trained on one part's buckets would differ
+//! from its neighbours', and show as seams.
+
+use std::io::Write;
+use std::path::Path;
+
+use crate::buffer::Buffer;
+use crate::checkpoint::{ACCUM_BYTES, ByteReader, ByteWriter, PixelAccum};
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::tracer::{TILE_SIZE, Tile};
+use crate::window::PixelWindow;
+
+const MAGIC: &[u8; 8] = b"CRUSTPRT";
+const VERSION: u32 = 1;
+
+/// Part `index` of `count` (1-based, as `--tiles 3/16` spells it).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileSplit {
+    index: u32,
+    count: u32,
+}
+
+impl TileSplit {
+    /// `None` unless `1 <= index <= count`.
+    pub fn new(index: u32, count: u32) -> Option<Self> {
+        (index >= 1 && index <= count).then_some(TileSplit { index, count })
+    }
+
+    /// Parses `k/n`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (index, count) = text.split_once('/')?;
+        Self::new(index.trim().parse().ok()?, count.trim().parse().ok()?)
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Whether the `tile`-th bucket of the frame's tiling is this part's.
+    pub(crate) fn owns(&self, tile: usize) -> bool {
+        tile % self.count as usize == (self.index - 1) as usize
+    }
+
+    /// This part's buckets of a `width × height` image, in tiling order —
+    /// the ones [`crate::tracer::generate_tiles`] lists at its positions,
+    /// found without listing the rest.
+    fn tiles(&self, width: usize, height: usize) -> impl ExactSizeIterator<Item = Tile> {
+        let columns = width.div_ceil(TILE_SIZE);
+        let total = columns * height.div_ceil(TILE_SIZE);
+        (self.index as usize - 1..total)
+            .step_by(self.count as usize)
+            .map(move |t| {
+                let (x, y) = (t % columns * TILE_SIZE, t / columns * TILE_SIZE);
+                Tile {
+                    x,
+                    y,
+                    width: TILE_SIZE.min(width - x),
+                    height: TILE_SIZE.min(height - y),
+                }
+            })
+    }
+}
+
+/// One part's share of a frame — see the module docs.
+#[derive(Debug, Clone)]
+pub struct PartialRender {
+    pub(crate) fingerprint: u64,
+    pub(crate) split: TileSplit,
+    /// The full frame's resolution.
+    pub(crate) display: (usize, usize),
+    /// The pixels the whole render traces; the tiling covers this.
+    pub(crate) window: PixelWindow,
+    pub(crate) color_space: ColorSpace,
+    /// The part's pixels: bucket by bucket in tiling order, row-major
+    /// (bottom-up, like [`Buffer`]) within a bucket.
+    pub(crate) pixels: Vec<PixelAccum>,
+}
+
+impl PartialRender {
+    /// Collects the part's pixels out of a whole-image accumulator array.
+    pub(crate) fn gather(
+        fingerprint: u64,
+        split: TileSplit,
+        display: (usize, usize),
+        window: PixelWindow,
+        color_space: ColorSpace,
+        accums: &[PixelAccum],
+    ) -> Self {
+        let width = window.width();
+        let pixels = split
+            .tiles(width, window.height())
+            .flat_map(|t| tile_pixels(&t).map(move |(x, y)| accums[y * width + x]))
+            .collect();
+        PartialRender {
+            fingerprint,
+            split,
+            display,
+            window,
+            color_space,
+            pixels,
+        }
+    }
+
+    pub fn split(&self) -> TileSplit {
+        self.split
+    }
+
+    /// The full frame's resolution — the EXR's display window.
+    pub fn display_size(&self) -> (usize, usize) {
+        self.display
+    }
+
+    /// The traced rectangle of the frame — the EXR's data window.
+    pub fn window(&self) -> PixelWindow {
+        self.window
+    }
+
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Assembles the frame from every one of its parts, each exactly once,
+    /// in any order.
+    pub fn merge(parts: &[PartialRender]) -> Result<Buffer, Error> {
+        let first = parts
+            .first()
+            .ok_or_else(|| Error::Merge("no parts given".to_owned()))?;
+        let count = first.split.count;
+        let mut seen = vec![false; count as usize];
+        for part in parts {
+            if part.fingerprint != first.fingerprint
+                || part.split.count != count
+                || part.display != first.display
+                || part.window != first.window
+                || part.color_space != first.color_space
+            {
+                return Err(Error::Merge(format!(
+                    "part {}/{} belongs to another render than part {}/{}",
+                    part.split.index, part.split.count, first.split.index, count
+                )));
+            }
+            let slot = &mut seen[(part.split.index - 1) as usize];
+            if std::mem::replace(slot, true) {
+                return Err(Error::Merge(format!(
+                    "part {}/{count} is given twice",
+                    part.split.index
+                )));
+            }
+        }
+        if let Some(missing) = seen.iter().position(|s| !s) {
+            return Err(Error::Merge(format!(
+                "part {}/{count} is missing",
+                missing + 1
+            )));
+        }
+
+        let (width, height) = (first.window.width(), first.window.height());
+        let mut buffer = Buffer::new(width, height);
+        for part in parts {
+            let mut pixels = part.pixels.iter();
+            for tile in part.split.tiles(width, height) {
+                for (x, y) in tile_pixels(&tile) {
+                    let (color, alpha) = pixels
+                        .next()
+                        .ok_or_else(|| Error::Merge("a part is truncated".to_owned()))?
+                        .estimate();
+                    buffer.set_pixel(x, y, color);
+                    buffer.set_alpha(x, y, alpha);
+                }
+            }
+        }
+        Ok(buffer)
+    }
+
+    /// Reads a part written by [`PartialRender::save`].
+    pub fn load(path: &Path) -> Result<PartialRender, Error> {
+        let invalid = |message: &str| Error::Partial {
+            path: path.to_path_buf(),
+            message: message.to_owned(),
+        };
+        let bytes = std::fs::read(path).map_err(|e| invalid(&e.to_string()))?;
+        let rest = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .ok_or_else(|| invalid("not a crust partial render"))?;
+        let mut r = ByteReader::new(rest);
+        if r.u32() != Some(VERSION) {
+            return Err(invalid("written by an incompatible version"));
+        }
+        Self::decode(&mut r).ok_or_else(|| invalid("truncated or corrupt"))
+    }
+
+    fn decode(r: &mut ByteReader) -> Option<PartialRender> {
+        let fingerprint = r.u64()?;
+        let split = TileSplit::new(r.u32()?, r.u32()?)?;
+        // Sizes from the file: a frame and a window no render could have,
+        // or more pixels than the data holds, are corrupt — checked before
+        // anything is allocated for them.
+        let extent = |v: u32| (1..=i32::MAX as u32).contains(&v).then_some(v as usize);
+        let display = (extent(r.u32()?)?, extent(r.u32()?)?);
+        let (x_min, y_min, x_max, y_max) = (r.i32()?, r.i32()?, r.i32()?, r.i32()?);
+        if x_max.checked_sub(x_min)? <= 0 || y_max.checked_sub(y_min)? <= 0 {
+            return None;
+        }
+        let window = PixelWindow::new(x_min, y_min, x_max, y_max);
+        let color_space = *ColorSpace::ALL.get(r.u8()? as usize)?;
+        let (width, height) = (window.width(), window.height());
+        // Every bucket has a pixel, so the bucket count is bounded first.
+        r.holds(split.tiles(width, height).len(), ACCUM_BYTES)?;
+        let len = split.tiles(width, height).map(|t| t.width * t.height).sum();
+        r.holds(len, ACCUM_BYTES)?;
+        let mut pixels = Vec::with_capacity(len);
+        for _ in 0..len {
+            pixels.push(r.accum()?);
+        }
+        r.is_empty().then_some(PartialRender {
+            fingerprint,
+            split,
+            display,
+            window,
+            color_space,
+            pixels,
+        })
+    }
+
+    /// Writes the part to `path`.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut w = ByteWriter::default();
+        w.bytes(MAGIC);
+        w.u32(VERSION);
+        w.u64(self.fingerprint);
+        w.u32(self.split.index);
+        w.u32(self.split.count);
+        w.u32(self.display.0 as u32);
+        w.u32(self.display.1 as u32);
+        for v in [
+            self.window.x_min,
+            self.window.y_min,
+            self.window.x_max,
+            self.window.y_max,
+        ] {
+            w.i32(v);
+        }
+        let space = ColorSpace::ALL.iter().position(|s| *s == self.color_space);
+        w.u8(space.expect("every space is listed") as u8);
+        for p in &self.pixels {
+            w.accum(p);
+        }
+        std::fs::File::create(path)?.write_all(&w.0)
+    }
+}
+
+/// A bucket's pixels, row by row.
+fn tile_pixels(tile: &Tile) -> impl Iterator<Item = (usize, usize)> + use<> {
+    let (x0, y0, w, h) = (tile.x, tile.y, tile.width, tile.height);
+    (y0..y0 + h).flat_map(move |y| (x0..x0 + w).map(move |x| (x, y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracer::{RenderSettings, Renderer};
+    use crate::world::ball_on_floor;
+
+    #[test]
+    fn splits_parse_and_partition_the_tiles() {
+        assert_eq!(TileSplit::parse("3/16"), TileSplit::new(3, 16));
+        assert!(TileSplit::parse("0/4").is_none());
+        assert!(TileSplit::parse("5/4").is_none());
+        assert!(TileSplit::parse("2").is_none());
+        let parts: Vec<_> = (1..=3).map(|k| TileSplit::new(k, 3).unwrap()).collect();
+        for tile in 0..20 {
+            assert_eq!(parts.iter().filter(|p| p.owns(tile)).count(), 1);
+        }
+        // A part's buckets are the tiling's, at the positions it owns.
+        let corners = |t: &Tile| (t.x, t.y, t.width, t.height);
+        let tiling: Vec<_> = crate::tracer::generate_tiles(40, 35, TILE_SIZE)
+            .iter()
+            .map(corners)
+            .collect();
+        for part in &parts {
+            let owned: Vec<_> = tiling
+                .iter()
+                .enumerate()
+                .filter(|(t, _)| part.owns(*t))
+                .map(|(_, t)| *t)
+                .collect();
+            let tiles: Vec<_> = part.tiles(40, 35).map(|t| corners(&t)).collect();
+            assert_eq!(tiles, owned);
+        }
+    }
+
+    /// Sizes a hostile or damaged file claims are refused before anything
+    /// is allocated for them.
+    #[test]
+    fn impossible_sizes_are_rejected() {
+        let header = |window: [i32; 4]| {
+            let mut w = ByteWriter::default();
+            w.bytes(MAGIC);
+            w.u32(VERSION);
+            w.u64(7);
+            w.u32(1);
+            w.u32(1);
+            w.u32(64);
+            w.u32(64);
+            for v in window {
+                w.i32(v);
+            }
+            w.u8(0);
+            w.accum(&PixelAccum::default());
+            w.0
+        };
+        let path = std::env::temp_dir().join("crust_partial_hostile.part");
+        for window in [
+            [i32::MIN, 0, i32::MAX, 64],
+            [0, 0, i32::MAX, i32::MAX],
+            [10, 0, 0, 64],
+        ] {
+            std::fs::write(&path, header(window)).expect("write part");
+            assert!(
+                matches!(PartialRender::load(&path), Err(Error::Partial { .. })),
+                "{window:?}"
+            );
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn parts_round_trip_and_must_all_be_there() {
+        let window = PixelWindow::full(40, 20);
+        let accums: Vec<PixelAccum> = (0..40 * 20)
+            .map(|k| PixelAccum {
+                sum: glam::Vec3A::splat(k as f32),
+                weight_sum: 2.0,
+                alpha_sum: 2.0,
+                taken: 2,
+                ..Default::default()
+            })
+            .collect();
+        let part = |k| {
+            let split = TileSplit::new(k, 2).unwrap();
+            PartialRender::gather(7, split, (40, 20), window, ColorSpace::AcesCg, &accums)
+        };
+        let path = std::env::temp_dir().join("crust_partial_round_trip.part");
+        part(2).save(&path).expect("write part");
+        let back = PartialRender::load(&path).expect("read part");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(back.color_space(), ColorSpace::AcesCg);
+        assert_eq!(back.pixels, part(2).pixels);
+
+        let merged = PartialRender::merge(&[back, part(1)]).expect("both parts");
+        assert_eq!(
+            merged.get_pixel(37, 19),
+            glam::Vec3A::splat((19 * 40 + 37) as f32 / 2.0)
+        );
+        assert_eq!(merged.get_alpha(0, 0), 1.0);
+        assert!(matches!(
+            PartialRender::merge(&[part(1)]),
+            Err(Error::Merge(_))
+        ));
+        assert!(matches!(
+            PartialRender::merge(&[part(1), part(1)]),
+            Err(Error::Merge(_))
+        ));
+        let rec709 = PartialRender {
+            color_space: ColorSpace::LinRec709,
+            ..part(2)
+        };
+        assert!(matches!(
+            PartialRender::merge(&[part(1), rec709]),
+            Err(Error::Merge(_))
+        ));
+    }
+
+    fn renderer(settings: impl FnOnce(RenderSettings) -> RenderSettings) -> Renderer {
+        let (camera, world, lights, base) = ball_on_floor();
+        Renderer::new(camera, world, lights, settings(base))
+    }
+
+    /// A frame split three ways and merged is the frame one process renders:
+    /// every bucket lands in place, with the samples it would have had.
+    #[test]
+    fn split_render_parts_merge_into_the_full_frame() {
+        // Overscan grows the 12x10 frame to 32x30: four buckets to share out.
+        let overscan = |s: RenderSettings| s.with_overscan(10);
+        let full = renderer(overscan).render_with_tiles();
+        let mut parts: Vec<PartialRender> = (1..=3)
+            .map(|k| {
+                let split = TileSplit::new(k, 3).unwrap();
+                renderer(overscan)
+                    .render_partial(split, None)
+                    .expect("a beauty-only render splits")
+                    .0
+            })
+            .collect();
+        parts.reverse();
+        let merged = PartialRender::merge(&parts).expect("all three parts");
+        assert_eq!(parts[0].display_size(), (12, 10));
+        for y in 0..30 {
+            for x in 0..32 {
+                let (a, b) = (merged.get_pixel(x, y), full.get_pixel(x, y));
+                assert!((a - b).abs().max_element() < 1e-5, "pixel ({x}, {y})");
+                assert!((merged.get_alpha(x, y) - full.get_alpha(x, y)).abs() < 1e-5);
+            }
+        }
+        assert!(PartialRender::merge(&parts[1..]).is_err());
+    }
+
+    /// Guiding trains on the whole frame and parts carry no extra layers, so
+    /// a render needing either is refused rather than rendered without it.
+    #[test]
+    fn split_renders_refuse_what_a_part_cannot_carry() {
+        let split = TileSplit::new(1, 2).unwrap();
+        for settings in [
+            |s: RenderSettings| s.with_guiding(true, 1, 0.5),
+            |s: RenderSettings| s.with_denoise(true),
+            |s: RenderSettings| s.with_deep(true),
+        ] {
+            assert!(matches!(
+                renderer(settings).render_partial(split, None),
+                Err(Error::Split(_))
+            ));
+        }
+    }
+}
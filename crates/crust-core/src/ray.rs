@@ -9,11 +9,13 @@ pub use crust_rt::{MASK_ALL, MASK_CAMERA, MASK_INDIRECT, MASK_SHADOW};
 /// the kernel deliberately does not know about: the participating
 /// `medium` the ray is currently travelling through, used by transmissive
 /// OpenPBR materials so the tracer can apply Beer-Lambert attenuation
-/// between surface hits.
+/// between surface hits, and a camera ray's clipping range.
 #[derive(Default, Clone)]
 pub struct Ray {
     rt: crust_rt::Ray,
     medium: Option<Arc<Medium>>,
+    /// The span of `t` geometry is seen in; `None` is everything ahead.
+    clip: Option<(f32, f32)>,
 }
 
 impl Ray {
@@ -23,6 +25,7 @@ impl Ray {
         Ray {
             rt: crust_rt::Ray::new(origin, direction),
             medium: None,
+            clip: None,
         }
     }
 
@@ -32,6 +35,7 @@ impl Ray {
         Ray {
             rt: crust_rt::Ray::new(origin, direction),
             medium: Some(medium),
+            clip: None,
         }
     }
 
@@ -47,6 +51,14 @@ impl Ray {
         self
     }
 
+    /// Same ray seeing geometry only between `t_min` and `t_max` — a
+    /// camera's near and far clipping planes. Only the segment this ray
+    /// starts is clipped; rays it spawns see everything.
+    pub fn with_clip(mut self, t_min: f32, t_max: f32) -> Ray {
+        self.clip = Some((t_min, t_max));
+        self
+    }
+
     /// The kernel view of this ray — what `crust_rt` queries take.
     pub fn rt(&self) -> &crust_rt::Ray {
         &self.rt
@@ -72,6 +84,10 @@ impl Ray {
         self.rt.mask
     }
 
+    pub fn clip(&self) -> Option<(f32, f32)> {
+        self.clip
+    }
+
     pub fn at(&self, t: f32) -> Vec3A {
         self.rt.at(t)
     }
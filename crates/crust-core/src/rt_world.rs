@@ -93,6 +93,27 @@ pub struct WorldBuilder {
     /// samples a per-face texture carry a table, so a scene with no Ptex pays
     /// one `None` per geometry and nothing more.
     faces: Vec<Option<FaceRef>>,
+    /// Indexed by `geom_id`: the scene names ID mattes are keyed on.
+    names: Vec<GeomNames>,
+    /// Indexed by `geom_id`: the geometry's compositing role, if any.
+    flags: Vec<GeomFlags>,
+}
+
+/// Where a geometry came from, for Cryptomatte: the prim that produced it
+/// and the path of its bound material. Shared `Arc<str>`s, since one prim
+/// can yield many geometries and one material is bound to many prims.
+#[derive(Clone, Default)]
+struct GeomNames {
+    object: Option<Arc<str>>,
+    material: Option<Arc<str>>,
+}
+
+/// How a geometry takes part in compositing: as a holdout matte, or as a
+/// shadow catcher. Both concern camera rays only.
+#[derive(Clone, Copy, Default)]
+struct GeomFlags {
+    holdout: bool,
+    shadow_catcher: bool,
 }
 
 impl WorldBuilder {
@@ -117,6 +138,8 @@ impl WorldBuilder {
         let id = self.rt.attach_masked(geometry, mask);
         self.materials.push(material);
         self.faces.push(None);
+        self.names.push(GeomNames::default());
+        self.flags.push(GeomFlags::default());
         debug_assert_eq!(id as usize + 1, self.materials.len());
         id
     }
@@ -138,6 +161,47 @@ impl WorldBuilder {
         self.materials.len()
     }
 
+    /// Names geometry `id` after the scene object it came from — the key of
+    /// the object ID matte.
+    ///
+    /// # Panics
+    /// If `id` was never attached or reserved.
+    pub fn set_object_name(&mut self, id: u32, name: Arc<str>) {
+        self.names[id as usize].object = Some(name);
+    }
+
+    /// Makes geometry `id` a holdout: camera rays that hit it return no
+    /// light and no alpha, every other ray sees it as usual.
+    ///
+    /// # Panics
+    /// If `id` was never attached or reserved.
+    pub fn set_holdout(&mut self, id: u32) {
+        self.flags[id as usize].holdout = true;
+    }
+
+    /// Makes geometry `id` a shadow catcher: camera rays that hit it see
+    /// through it, and record how much of the light reaching it the rest of
+    /// the scene blocks (the `shadow` AOV). Every other ray sees it as
+    /// usual.
+    ///
+    /// # Panics
+    /// If `id` was never attached or reserved.
+    pub fn set_shadow_catcher(&mut self, id: u32) {
+        self.flags[id as usize].shadow_catcher = true;
+    }
+
+    /// Names every geometry's material, the key of the material ID matte:
+    /// `name_of` is asked once per geometry, with the material it was
+    /// attached with. Materials it declines stay unnamed.
+    pub fn set_material_names(
+        &mut self,
+        name_of: impl Fn(&Arc<dyn Material>) -> Option<Arc<str>>,
+    ) {
+        for (names, material) in self.names.iter_mut().zip(&self.materials) {
+            names.material = name_of(material);
+        }
+    }
+
     /// Claims a `geom_id` and binds its material now, leaving the geometry to
     /// be supplied later by [`WorldBuilder::set_geometry`].
     ///
@@ -173,6 +237,8 @@ impl WorldBuilder {
         self.rt.reserve(additional);
         self.materials.reserve(additional);
         self.faces.reserve(additional);
+        self.names.reserve(additional);
+        self.flags.reserve(additional);
     }
 
     /// Builds the acceleration structure (parallel, deterministic).
@@ -181,6 +247,8 @@ impl WorldBuilder {
             scene: self.rt.commit(),
             materials: self.materials,
             faces: self.faces,
+            names: self.names,
+            flags: self.flags,
         }
     }
 }
@@ -200,6 +268,8 @@ pub struct World {
     scene: crust_rt::Scene,
     materials: Vec<Arc<dyn Material>>,
     faces: Vec<Option<FaceRef>>,
+    names: Vec<GeomNames>,
+    flags: Vec<GeomFlags>,
 }
 
 impl World {
@@ -291,6 +361,34 @@ impl World {
     pub fn material(&self, geom_id: u32) -> &dyn Material {
         self.materials[geom_id as usize].as_ref()
     }
+
+    /// Is the geometry a holdout (see [`WorldBuilder::set_holdout`])?
+    pub fn is_holdout(&self, geom_id: u32) -> bool {
+        self.flags.get(geom_id as usize).is_some_and(|f| f.holdout)
+    }
+
+    /// Is the geometry a shadow catcher (see
+    /// [`WorldBuilder::set_shadow_catcher`])?
+    pub fn is_shadow_catcher(&self, geom_id: u32) -> bool {
+        self.flags.get(geom_id as usize).is_some_and(|f| f.shadow_catcher)
+    }
+
+    /// Does any geometry catch shadows? The importer adds the `shadow` AOV
+    /// when one does.
+    pub fn has_shadow_catchers(&self) -> bool {
+        self.flags.iter().any(|f| f.shadow_catcher)
+    }
+
+    /// The scene object a geometry came from (its USD prim path), if the
+    /// builder recorded one.
+    pub fn object_name(&self, geom_id: u32) -> Option<&str> {
+        self.names.get(geom_id as usize)?.object.as_deref()
+    }
+
+    /// The path of a geometry's bound material, if the builder recorded one.
+    pub fn material_name(&self, geom_id: u32) -> Option<&str> {
+        self.names.get(geom_id as usize)?.material.as_deref()
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,8 @@
+use crate::aperture::ApertureImage;
 use crate::camera::Camera;
 use crate::environment::EnvironmentMap;
 use crate::light::LightList;
+use crate::lpe::LightPathExpr;
 use crate::rt_world::World;
 use crate::stats::RenderStats;
 use crate::tracer::RenderSettings;
@@ -19,10 +21,20 @@ pub struct Scene {
     /// Participating-media regions (smoke, fog, …), kept outside `world`
     /// so their bounds never act as occluding geometry.
     pub volumes: Vec<VolumeRegion>,
+    /// Light path expressions declared by the stage (`crust:lpes`), for
+    /// [`crate::Renderer::with_lpes`].
+    pub lpes: Vec<LightPathExpr>,
     /// Import phase timings and scene counts. Populated by
     /// [`Scene::from_usd`]; empty for a hand-assembled scene. The host adds
     /// its own render and output phases before reporting.
     pub stats: RenderStats,
+    /// Identifies the stage the scene was read from — a hash of its root
+    /// layer's file name, size and modification time — for
+    /// [`crate::Renderer::with_scene_identity`], so a checkpoint or a split
+    /// part is not used with another scene or an edited one. Edits confined
+    /// to sublayers, references or payloads go unnoticed. Zero for a
+    /// hand-assembled scene.
+    pub identity: u64,
 }
 
 impl Scene {
@@ -38,7 +50,9 @@ impl Scene {
             lights,
             settings,
             volumes: Vec::new(),
+            lpes: Vec::new(),
             stats: RenderStats::new(),
+            identity: 0,
         }
     }
 
@@ -50,13 +64,49 @@ impl Scene {
 
 mod usd_import;
 
+/// Which of a stage's prims a render uses, for stages with several: a
+/// layout with a camera per shot, or a settings prim per pass. Unset, the
+/// stage's `renderSettingsPrimPath` (or `/Render/settings`) names the
+/// render settings, their `camera` relationship the camera, and failing
+/// that the first camera found renders.
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    camera: Option<String>,
+    settings: Option<String>,
+}
+
+impl ImportOptions {
+    /// Renders through the camera at prim path `path`, over the one the
+    /// render settings name.
+    pub fn with_camera(mut self, path: impl Into<String>) -> Self {
+        self.camera = Some(path.into());
+        self
+    }
+
+    /// Reads the render settings from the prim at `path`, over the one the
+    /// stage's metadata names.
+    pub fn with_settings(mut self, path: impl Into<String>) -> Self {
+        self.settings = Some(path.into());
+        self
+    }
+
+    pub fn camera(&self) -> Option<&str> {
+        self.camera.as_deref()
+    }
+
+    pub fn settings(&self) -> Option<&str> {
+        self.settings.as_deref()
+    }
+}
+
 impl Scene {
     /// Load a full runtime scene (camera, geometry, lights, render settings)
     /// from a USD stage — `.usda`, `.usdc`, or `.usdz`.
     ///
     /// * `UsdGeomCamera` → `Camera` (world transform + focal length +
-    ///   aperture-derived vfov). Falls back to `world::get_settings`'s
-    ///   camera when the stage authors none.
+    ///   aperture-derived vfov): the one the render settings' `camera`
+    ///   relationship names, else the first found. Falls back to
+    ///   `world::get_settings`'s camera when the stage authors none.
     /// * `UsdGeomMesh` → triangulated BVH with world-baked vertices. Bound
     ///   material resolved via `MaterialBindingAPI`.
     /// * `UsdGeomSphere` → analytic `crust::Sphere`.
@@ -80,7 +130,57 @@ impl Scene {
         path: &std::path::Path,
         assets: &dyn AssetLoader,
     ) -> Result<Scene, crate::Error> {
-        usd_import::load_scene(path, assets)
+        Scene::from_usd_with_options(path, assets, &ImportOptions::default())
+    }
+
+    /// [`Scene::from_usd_with_assets`], rendering the camera and settings
+    /// `options` pick. Fails if either names a prim the stage lacks.
+    pub fn from_usd_with_options(
+        path: &std::path::Path,
+        assets: &dyn AssetLoader,
+        options: &ImportOptions,
+    ) -> Result<Scene, crate::Error> {
+        usd_import::load_scene(path, assets, options)
+    }
+}
+
+impl Scene {
+    /// Opens a USD stage for rendering a sequence of its frames, e.g. a
+    /// shot's `1001-1100`. Each [`SceneSequence::frame`] is the scene at that
+    /// time code: transforms, cameras, lights and material inputs follow
+    /// their `timeSamples`, and the frame number seeds the sampler.
+    ///
+    /// Cheaper than a [`Scene::from_usd_with_assets`] per frame: the stage is
+    /// composed once, and meshes, prototypes, unchanged materials and
+    /// environment maps are built once and shared by every frame. Mesh
+    /// points, instancer arrays and the render settings are read at the
+    /// default time, so they do not animate.
+    pub fn open_sequence<'a>(
+        path: &'a std::path::Path,
+        assets: &'a dyn AssetLoader,
+    ) -> Result<SceneSequence<'a>, crate::Error> {
+        Scene::open_sequence_with_options(path, assets, &ImportOptions::default())
+    }
+
+    /// [`Scene::open_sequence`], rendering the camera and settings
+    /// `options` pick, as [`Scene::from_usd_with_options`] does.
+    pub fn open_sequence_with_options<'a>(
+        path: &'a std::path::Path,
+        assets: &'a dyn AssetLoader,
+        options: &ImportOptions,
+    ) -> Result<SceneSequence<'a>, crate::Error> {
+        usd_import::open_sequence(path, assets, options).map(SceneSequence)
+    }
+}
+
+/// A USD stage held open across frames — see [`Scene::open_sequence`].
+pub struct SceneSequence<'a>(usd_import::SequenceImport<'a>);
+
+impl SceneSequence<'_> {
+    /// The scene at time code `frame`. Its stats cover this frame's walk
+    /// and top-level build; the stage's opening is not billed to any frame.
+    pub fn frame(&mut self, frame: isize) -> Scene {
+        self.0.frame(frame)
     }
 }
 
@@ -117,6 +217,19 @@ pub trait AssetLoader: Send + Sync {
         );
         None
     }
+
+    /// Decodes a grayscale lens-aperture mask, the shape a camera's
+    /// `crust:aperture:texture` gives its bokeh. `path` has already been
+    /// resolved against the USD layer's directory; `None` leaves the lens
+    /// to its blades. Defaulted like [`Self::load_ptex`].
+    fn load_aperture(&self, path: &std::path::Path) -> Option<ApertureImage> {
+        tracing::warn!(
+            "Asset loader does not decode aperture images: {} ignored — the \
+             lens keeps its blades.",
+            path.display()
+        );
+        None
+    }
 }
 
 /// The default host: decodes nothing. `Scene::from_usd` uses it, so a
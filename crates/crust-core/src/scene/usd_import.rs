@@ -1,7 +1,7 @@
 //! USD scene import: opens a stage and produces a runtime `Scene`
 //! (camera, world, lights, render settings). See `Scene::from_usd`.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::path::Path;
 use std::sync::Arc;
@@ -10,15 +10,22 @@ use std::time::{Duration, Instant};
 use glam::Mat4 as GMat4;
 use tracing::{debug, info, warn};
 
-use crate::camera::Camera;
+use crate::aov::{Aov, AovSet};
+use crate::cryptomatte::CryptoSet;
+use crate::lpe::LightPathExpr;
+use crate::aperture::{Aperture, ApertureImage};
+use crate::camera::{Camera, Projection, Shutter};
+use crate::checkpoint::fnv1a;
+use crate::lens::LensSystem;
+use crate::color::ColorSpace;
 use crate::light::{
-    AreaLight, DistantLight as CoreDistantLight, DomeLight as CoreDomeLight, LightList, RectShape,
-    SphereShape,
+    AreaLight, CylinderShape, DiskShape, DistantLight as CoreDistantLight,
+    DomeLight as CoreDomeLight, LightList, MeshLight, RectShape, SphereShape,
 };
-use crate::scene::AssetLoader;
+use crate::scene::{AssetLoader, ImportOptions};
 use crate::material::{Emissive, Material, OpenPBR};
 use crate::ray::{MASK_ALL, MASK_CAMERA, MASK_INDIRECT, MASK_SHADOW};
-use crate::rt_world::{FaceMap, FanSlice, WorldBuilder};
+use crate::rt_world::{FaceMap, FanSlice, World, WorldBuilder};
 use crate::scene::Scene;
 use crate::stats::{ImageCounters, MemorySample, RenderStats, SceneCounters};
 use crust_rt::{
@@ -27,7 +34,8 @@ use crust_rt::{
 use crate::filter::PixelFilter;
 use crate::tracer::{RenderSettings, SamplingStrategy};
 use crate::volume::{DensityField, VolumeRegion};
-use glam::{Affine3A, Mat3A, Vec3, Vec3A};
+use crate::window::PixelWindow;
+use glam::{Affine3A, Mat3A, Vec2, Vec3, Vec3A};
 
 use openusd::gf::{Matrix4d, Vec3f};
 use openusd::schemas::geom::{
@@ -122,6 +130,8 @@ struct ImportCtx<'a> {
     lights: LightList,
     volumes: Vec<VolumeRegion>,
     camera: Option<Camera>,
+    /// The camera to import; `None` takes the first the walk reaches.
+    camera_path: Option<sdf::Path>,
     caches: ImportCaches<'a>,
     /// Direct mesh placements whose representation is not yet decided, in
     /// traversal order. Drained by [`flush_meshes`] after the last chunk —
@@ -129,18 +139,41 @@ struct ImportCtx<'a> {
     /// made while walking. Holds ~88 bytes per mesh prim, not per triangle.
     pending_meshes: Vec<MeshPlacement>,
     settings: RenderSettings,
-    /// The stage file, for resolving asset paths against its directory.
-    stage_path: &'a Path,
-    assets: &'a dyn AssetLoader,
+}
+
+/// Applies `prim`'s per-object properties to every geometry attached
+/// since `first`: its path, the key of the object ID matte (see
+/// `cryptomatte.rs`), and `crust:holdout`. Whatever a prim expands into
+/// (its triangles, its instances, a light's emitter) acts as that one prim.
+fn tag_new_geometry(world: &mut WorldBuilder, first: usize, prim: &Prim) {
+    if world.count() == first {
+        return;
+    }
+    let name: Arc<str> = Arc::from(prim.path().as_str());
+    let holdout = prim_holdout(prim);
+    let catcher = prim_shadow_catcher(prim);
+    for id in first..world.count() {
+        world.set_object_name(id as u32, name.clone());
+        if holdout {
+            world.set_holdout(id as u32);
+        }
+        if catcher {
+            world.set_shadow_catcher(id as u32);
+        }
+    }
 }
 
 /// Walks `root` and its subtree, emitting geometry, lights and volumes
 /// into `ctx`. Takes the stage by reference and keeps nothing borrowed
 /// from it, so the caller may drop the stage afterwards and walk another.
 fn traverse_into(stage: &Stage, root: Prim, root_xf: GMat4, ctx: &mut ImportCtx) {
-    let mut stack: Vec<(Prim, GMat4)> = vec![(root, root_xf)];
+    // Over an open shutter each prim also carries its transforms at the
+    // shutter's opening and closing: what its geometry blurs between.
+    let shutter = ctx.caches.shutter_times(ctx.settings.frame());
+    let root_span = shutter.map(|_| (root_xf, root_xf));
+    let mut stack: Vec<(Prim, GMat4, Option<(GMat4, GMat4)>)> = vec![(root, root_xf, root_span)];
 
-    while let Some((prim, parent_world)) = stack.pop() {
+    while let Some((prim, parent_world, parent_span)) = stack.pop() {
         // `class` prims (and their descendants) describe geometry that
         // exists only to be referenced or instanced — they are never
         // rendered in their own right. Prototypes reach the same prims
@@ -158,9 +191,19 @@ fn traverse_into(stage: &Stage, root: Prim, root_xf: GMat4, ctx: &mut ImportCtx)
             continue;
         }
 
-        let local = local_matrix_at(stage, &prim);
+        let local = local_matrix_at(stage, &prim, ctx.caches.time);
         let resets = resets_xform_stack_at(stage, &prim);
         let this_world = if resets { local } else { parent_world * local };
+        let span = shutter
+            .zip(parent_span)
+            .map(|((open, close), (parent_open, parent_close))| {
+                let at = |time, parent: GMat4| {
+                    let local = local_matrix_at(stage, &prim, Some(time));
+                    if resets { local } else { parent * local }
+                };
+                (at(open, parent_open), at(close, parent_close))
+            });
+        let first_geom = ctx.world.count();
 
         // Native instancing: an `instanceable` prim with a composition arc
         // shares one prototype with every other instance of it. Take the
@@ -178,6 +221,7 @@ fn traverse_into(stage: &Stage, root: Prim, root_xf: GMat4, ctx: &mut ImportCtx)
                         this_world,
                         &mut ctx.caches,
                     );
+                    tag_new_geometry(&mut ctx.world, first_geom, &prim);
                     continue;
                 }
                 _ => warn!(
@@ -202,25 +246,30 @@ fn traverse_into(stage: &Stage, root: Prim, root_xf: GMat4, ctx: &mut ImportCtx)
                 this_world,
                 &mut ctx.caches,
             );
+            tag_new_geometry(&mut ctx.world, first_geom, &prim);
             // Prototypes are conventionally authored beneath the
             // instancer; they are drawn through it, never on their own.
             continue;
         } else if custom_token(&prim, "crust:volume:type").is_some() {
-            emit_volume(&prim, this_world, &mut ctx.volumes);
+            emit_volume(&prim, this_world, ctx.caches.color_space, &mut ctx.volumes);
         } else if let Ok(Some(mesh)) = UsdMesh::get(stage, prim.path().clone()) {
             let mat = resolve_material(stage, &prim, &mut ctx.caches);
+            let (start, end) = prim_motion(&prim, this_world, span);
             emit_mesh(
                 &mut ctx.world,
+                &mut ctx.lights,
                 &prim,
                 &mesh,
-                this_world,
+                start,
+                end,
                 mat,
                 &mut ctx.caches.meshes,
                 &mut ctx.pending_meshes,
             );
         } else if let Ok(Some(sphere)) = UsdSphere::get(stage, prim.path().clone()) {
             let mat = resolve_material(stage, &prim, &mut ctx.caches);
-            emit_sphere(&mut ctx.world, &prim, &sphere, this_world, mat);
+            let (start, end) = prim_motion(&prim, this_world, span);
+            emit_sphere(&mut ctx.world, &prim, &sphere, start, end, mat);
         } else if let Ok(Some(curves)) = UsdBasisCurves::get(stage, prim.path().clone()) {
             let mat = resolve_material(stage, &prim, &mut ctx.caches);
             emit_curves(&mut ctx.world, &prim, &curves, this_world, mat);
@@ -229,8 +278,9 @@ fn traverse_into(stage: &Stage, root: Prim, root_xf: GMat4, ctx: &mut ImportCtx)
             .flatten()
             .is_some()
         {
-            if ctx.camera.is_none() {
-                match build_camera(stage, &prim, &ctx.settings) {
+            let chosen = ctx.camera_path.as_ref().is_none_or(|p| p == prim.path());
+            if ctx.camera.is_none() && chosen {
+                match build_camera(stage, &prim, &ctx.settings, &mut ctx.caches) {
                     Some(c) => {
                         info!("Imported USD camera at {}", prim.path());
                         ctx.camera = Some(c);
@@ -239,35 +289,87 @@ fn traverse_into(stage: &Stage, root: Prim, root_xf: GMat4, ctx: &mut ImportCtx)
                 }
             }
         } else if let Ok(Some(light)) = SphereLight::get(stage, prim.path().clone()) {
-            emit_sphere_light(&mut ctx.world, &mut ctx.lights, &prim, &light, this_world);
+            emit_sphere_light(
+                &mut ctx.world,
+                &mut ctx.lights,
+                &prim,
+                &light,
+                this_world,
+                ctx.caches.color_space,
+                ctx.caches.time,
+            );
         } else if let Ok(Some(light)) = RectLight::get(stage, prim.path().clone()) {
-            emit_rect_light(&mut ctx.world, &mut ctx.lights, &prim, &light, this_world);
-        } else if let Ok(Some(light)) = UsdDistantLight::get(stage, prim.path().clone()) {
-            emit_distant_light(&mut ctx.lights, &light, this_world);
-        } else if let Ok(Some(light)) = DomeLight::get(stage, prim.path().clone()) {
-            emit_dome_light(
+            emit_rect_light(
+                &mut ctx.world,
                 &mut ctx.lights,
                 &prim,
                 &light,
                 this_world,
-                ctx.stage_path,
-                ctx.assets,
-                &mut ctx.caches.asset_time,
+                ctx.caches.color_space,
+                ctx.caches.time,
             );
-        } else {
-            warn_unsupported_light(stage, &prim);
+        } else if let Ok(Some(light)) = DiskLight::get(stage, prim.path().clone()) {
+            emit_disk_light(
+                &mut ctx.world,
+                &mut ctx.lights,
+                &prim,
+                &light,
+                this_world,
+                ctx.caches.color_space,
+                ctx.caches.time,
+            );
+        } else if let Ok(Some(light)) = CylinderLight::get(stage, prim.path().clone()) {
+            emit_cylinder_light(
+                &mut ctx.world,
+                &mut ctx.lights,
+                &prim,
+                &light,
+                this_world,
+                ctx.caches.color_space,
+                ctx.caches.time,
+            );
+        } else if let Ok(Some(light)) = UsdDistantLight::get(stage, prim.path().clone()) {
+            emit_distant_light(
+                &mut ctx.lights,
+                &light,
+                this_world,
+                ctx.caches.color_space,
+                ctx.caches.time,
+            );
+        } else if let Ok(Some(light)) = DomeLight::get(stage, prim.path().clone()) {
+            emit_dome_light(&mut ctx.lights, &prim, &light, this_world, &mut ctx.caches);
         }
+        tag_new_geometry(&mut ctx.world, first_geom, &prim);
 
         // Recurse. We push children onto the stack unconditionally; the
         // per-prim dispatch above will pick up any typed schemas encountered.
         if let Ok(children) = prim.children() {
             for child in children {
-                stack.push((child, this_world));
+                stack.push((child, this_world, span));
             }
         }
     }
 }
 
+/// [`Scene::identity`] of the stage at `path`: its root layer's file name,
+/// size and modification time. The name rather than the whole path, so
+/// that parts rendered on machines mounting the scene at different places
+/// still merge.
+fn stage_identity(path: &Path) -> u64 {
+    let meta = std::fs::metadata(path).ok();
+    let modified = meta
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok());
+    let text = format!(
+        "{:?} {:?} {:?}",
+        path.file_name(),
+        meta.map(|m| m.len()),
+        modified
+    );
+    fnv1a(text.as_bytes())
+}
+
 /// Opens the stage with payloads loaded, optionally masked to one subtree.
 fn open_stage(
     path: &Path,
@@ -284,7 +386,11 @@ fn open_stage(
     })
 }
 
-pub(crate) fn load_scene(path: &Path, assets: &dyn AssetLoader) -> Result<Scene, crate::Error> {
+pub(crate) fn load_scene(
+    path: &Path,
+    assets: &dyn AssetLoader,
+    options: &ImportOptions,
+) -> Result<Scene, crate::Error> {
     let import_start = Instant::now();
     let mut stats = RenderStats::new();
 
@@ -305,7 +411,10 @@ pub(crate) fn load_scene(path: &Path, assets: &dyn AssetLoader) -> Result<Scene,
             message: e.to_string(),
         })?;
     // Render settings come first — the camera importer needs the aspect ratio.
-    let settings = import_render_settings(&index);
+    let selection = select_prims(&index, options)?;
+    let settings = import_render_settings(&index, selection.settings.as_ref());
+    let lpes = import_lpes(&index, selection.settings.as_ref());
+    let shutter = import_shutter(&index, selection.camera.as_ref(), None);
     let chunks = stream_roots(&index);
     drop(index);
     let open_elapsed = open_start.elapsed();
@@ -316,16 +425,16 @@ pub(crate) fn load_scene(path: &Path, assets: &dyn AssetLoader) -> Result<Scene,
         lights: LightList::new(),
         volumes: Vec::new(),
         camera: None,
+        camera_path: selection.camera.clone(),
         // Prims binding the same material path share one Arc, and prims
         // with identical local geometry + material share one copy of that
         // geometry — placed by an instance when it is placed more than once,
         // baked flat into the parent BVH when it is placed exactly once.
-        caches: ImportCaches::new(assets, path),
+        caches: ImportCaches::new(assets, path, settings.color_space()),
         pending_meshes: Vec::new(),
         settings,
-        stage_path: path,
-        assets,
     };
+    ctx.caches.shutter = shutter;
 
     let traverse_start = Instant::now();
     if chunks.is_empty() {
@@ -368,20 +477,14 @@ pub(crate) fn load_scene(path: &Path, assets: &dyn AssetLoader) -> Result<Scene,
     let asset_time = ctx.caches.asset_time;
     let traverse_elapsed = traverse_start.elapsed().saturating_sub(asset_time);
     let traverse_mem = MemorySample::now();
-
-    let camera = ctx.camera.unwrap_or_else(|| {
-        warn!("USD stage has no UsdGeomCamera — falling back to world::get_settings camera");
-        crate::world::get_settings().0
-    });
+    if ctx.camera.is_none() {
+        selection.missing_camera()?;
+    }
 
     // Every chunk has been walked, so each mesh's placement count is final
-    // and the deferred instance-vs-bake decisions can be made. Must happen
-    // before `commit`, which is what consumes the geometry table.
-    let pending = std::mem::take(&mut ctx.pending_meshes);
-    flush_meshes(&mut ctx.world, &mut ctx.caches.meshes, pending);
-
+    // and the deferred instance-vs-bake decisions can be made.
     let commit_start = Instant::now();
-    let committed = ctx.world.commit();
+    let committed = commit_world(&mut ctx);
     let commit_elapsed = commit_start.elapsed();
     let commit_mem = MemorySample::now();
 
@@ -401,14 +504,59 @@ pub(crate) fn load_scene(path: &Path, assets: &dyn AssetLoader) -> Result<Scene,
         commit_mem,
     );
 
+    let mut scene = assemble_scene(&mut ctx, committed, lpes, stats);
+    scene.identity = stage_identity(path);
+    Ok(scene)
+}
+
+/// Makes the walk's deferred mesh decisions (see [`flush_meshes`]) and
+/// commits the top-level BVH over everything it attached. Leaves
+/// `ctx.world` empty for another walk.
+fn commit_world(ctx: &mut ImportCtx) -> World {
+    // Must happen before `commit`, which is what consumes the geometry
+    // table.
+    let pending = std::mem::take(&mut ctx.pending_meshes);
+    flush_meshes(
+        &mut ctx.world,
+        &mut ctx.lights,
+        &mut ctx.caches.meshes,
+        pending,
+    );
+
+    let material_names = ctx.caches.materials.names();
+    ctx.world
+        .set_material_names(|m| material_names.get(&Arc::as_ptr(m).cast::<()>()).cloned());
+    std::mem::replace(&mut ctx.world, WorldBuilder::new()).commit()
+}
+
+/// The scene a walk produced: `world`, with the camera, lights and volumes
+/// taken out of `ctx`. Fills in `stats`' scene and image counters.
+fn assemble_scene(
+    ctx: &mut ImportCtx,
+    world: World,
+    lpes: Vec<LightPathExpr>,
+    mut stats: RenderStats,
+) -> Scene {
+    let camera = ctx.camera.take().unwrap_or_else(|| {
+        warn!("USD stage has no UsdGeomCamera — falling back to world::get_settings camera");
+        crate::world::get_settings().0
+    });
     stats.scene = SceneCounters {
-        geometries: committed.count(),
-        top_level: committed.primitive_breakdown().into(),
-        unique: committed.unique_primitive_breakdown().into(),
-        footprint: committed.memory_footprint(),
+        geometries: world.count(),
+        top_level: world.primitive_breakdown().into(),
+        unique: world.unique_primitive_breakdown().into(),
+        footprint: world.memory_footprint(),
         lights: ctx.lights.count(),
         volumes: ctx.volumes.len(),
     };
+    // A catcher is only useful through its shadow layer, so render one
+    // whether or not `crust:aovs` asked for it.
+    let settings = ctx.settings;
+    let settings = if world.has_shadow_catchers() && !settings.aovs().contains(Aov::Shadow) {
+        settings.with_aovs(settings.aovs().with(Aov::Shadow))
+    } else {
+        settings
+    };
     let (w, h) = settings.get_dimensions();
     stats.image = ImageCounters {
         width: w,
@@ -417,10 +565,108 @@ pub(crate) fn load_scene(path: &Path, assets: &dyn AssetLoader) -> Result<Scene,
         max_depth: settings.max_depth(),
     };
 
-    let mut scene =
-        Scene::new(camera, committed, ctx.lights, settings).with_volumes(ctx.volumes);
+    let lights = std::mem::replace(&mut ctx.lights, LightList::new());
+    let volumes = std::mem::take(&mut ctx.volumes);
+    let mut scene = Scene::new(camera, world, lights, settings).with_volumes(volumes);
+    scene.lpes = lpes;
     scene.stats = stats;
-    Ok(scene)
+    scene
+}
+
+/// A stage held open across the frames of a sequence — see
+/// [`crate::SceneSequence`].
+///
+/// Each frame walks the whole stage again at its time code, into caches
+/// that outlive the frame. Meshes and prototypes are read at the default
+/// time, so their kernel scenes are built on the first frame and only
+/// re-placed after it; a material is rebuilt only in the frames its inputs
+/// change; a dome light's image is decoded once. What each frame does
+/// rebuild is the top-level BVH over the placements. The stage is never
+/// streamed: it stays open, whole, for the sequence's life.
+pub(crate) struct SequenceImport<'a> {
+    stage: Stage,
+    ctx: ImportCtx<'a>,
+    /// The stage's settings, before a frame's seed is applied.
+    settings: RenderSettings,
+    lpes: Vec<LightPathExpr>,
+    identity: u64,
+}
+
+pub(crate) fn open_sequence<'a>(
+    path: &'a Path,
+    assets: &'a dyn AssetLoader,
+    options: &ImportOptions,
+) -> Result<SequenceImport<'a>, crate::Error> {
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| crate::Error::NonUtf8Path(path.to_path_buf()))?;
+    let stage = open_stage(path, path_str, None)?;
+    let selection = select_prims(&stage, options)?;
+    // The whole stage is open, so the camera can be checked before the
+    // first frame rather than after every one.
+    if let Some(camera) = &selection.camera
+        && UsdCamera::get(&stage, camera.clone())
+            .ok()
+            .flatten()
+            .is_none()
+    {
+        selection.missing_camera()?;
+    }
+    let settings = import_render_settings(&stage, selection.settings.as_ref());
+    let lpes = import_lpes(&stage, selection.settings.as_ref());
+    let mut caches = ImportCaches::new(assets, path, settings.color_space());
+    caches.meshes.resident = true;
+    let ctx = ImportCtx {
+        world: WorldBuilder::new(),
+        lights: LightList::new(),
+        volumes: Vec::new(),
+        camera: None,
+        camera_path: selection.camera,
+        caches,
+        pending_meshes: Vec::new(),
+        settings,
+    };
+    Ok(SequenceImport {
+        stage,
+        ctx,
+        settings,
+        lpes,
+        identity: stage_identity(path),
+    })
+}
+
+impl SequenceImport<'_> {
+    /// The scene at time code `frame`, which also seeds its sampler.
+    pub(crate) fn frame(&mut self, frame: isize) -> Scene {
+        let mut stats = RenderStats::new();
+        let ctx = &mut self.ctx;
+        ctx.settings = self.settings.with_frame(frame);
+        ctx.caches.time = Some(frame as f64);
+        ctx.caches.shutter = import_shutter(&self.stage, ctx.camera_path.as_ref(), ctx.caches.time);
+        ctx.caches.materials.next_frame();
+        ctx.caches.meshes.next_frame();
+        ctx.caches.asset_time = Duration::ZERO;
+
+        let traverse_start = Instant::now();
+        let root = self.stage.prim(sdf::Path::abs_root());
+        traverse_into(&self.stage, root, GMat4::IDENTITY, ctx);
+        let asset_time = ctx.caches.asset_time;
+        stats.record(
+            "Traverse prims",
+            0,
+            traverse_start.elapsed().saturating_sub(asset_time),
+        );
+        if !asset_time.is_zero() {
+            stats.record("Load assets", 0, asset_time);
+        }
+        let commit_start = Instant::now();
+        let world = commit_world(ctx);
+        stats.record("Commit acceleration structure", 0, commit_start.elapsed());
+
+        let mut scene = assemble_scene(ctx, world, self.lpes.clone(), stats);
+        scene.identity = self.identity;
+        scene
+    }
 }
 
 // -----------------------------------------------------------------------
@@ -432,7 +678,7 @@ pub(crate) fn load_scene(path: &Path, assets: &dyn AssetLoader) -> Result<Scene,
 /// `Cube`'s convention; USD's default cube size is 2) and the unit cube
 /// `[-0.5, 0.5]^3` otherwise; placement, orientation and scale come from
 /// the composed prim transform.
-fn emit_volume(prim: &Prim, world_xf: GMat4, volumes: &mut Vec<VolumeRegion>) {
+fn emit_volume(prim: &Prim, world_xf: GMat4, space: ColorSpace, volumes: &mut Vec<VolumeRegion>) {
     let ty = custom_token(prim, "crust:volume:type").expect("checked by dispatch");
 
     let field = match ty.as_str() {
@@ -482,6 +728,13 @@ fn emit_volume(prim: &Prim, world_xf: GMat4, volumes: &mut Vec<VolumeRegion>) {
     let sigma_s = custom_color3(prim, "crust:volume:sigmaS").unwrap_or(Vec3A::splat(0.5));
     let sigma_a = custom_color3(prim, "crust:volume:sigmaA").unwrap_or(Vec3A::ZERO);
     let emission = custom_color3(prim, "crust:volume:emission").unwrap_or(Vec3A::ZERO);
+    // Per-channel coefficients, converted like any other colour so a grey
+    // medium stays grey and a tinted one keeps its hue in the working space.
+    let (sigma_s, sigma_a, emission) = (
+        space.convert_rec709(sigma_s),
+        space.convert_rec709(sigma_a),
+        space.convert_rec709(emission),
+    );
     let g = custom_f32(prim, "crust:volume:anisotropy").unwrap_or(0.0);
     let density_scale = custom_f32(prim, "crust:volume:densityScale").unwrap_or(1.0);
     let half = custom_f32(prim, "size").map_or(0.5, |s| s * 0.5);
@@ -535,8 +788,9 @@ fn usd_mat_to_glam(m: Matrix4d) -> GMat4 {
     ])
 }
 
-/// Local-to-parent transform of `prim`, composed from its authored
-/// `xformOp:*` attributes by [`compose_xform_ops`].
+/// Local-to-parent transform of `prim` at `time` (see [`value_at`]),
+/// composed from its authored `xformOp:*` attributes by
+/// [`compose_xform_ops`].
 ///
 /// This deliberately does NOT use openusd's `local_to_parent_transform`:
 /// openusd 0.5.0 composes multi-op `xformOpOrder` stacks in the wrong
@@ -546,8 +800,8 @@ fn usd_mat_to_glam(m: Matrix4d) -> GMat4 {
 /// floating objects against sky. Stacks with an op we cannot decode fall
 /// back to openusd's composition with a warning, so unusual scenes behave
 /// no worse than before.
-fn local_matrix_at(stage: &Stage, prim: &Prim) -> GMat4 {
-    match compose_xform_ops(prim) {
+fn local_matrix_at(stage: &Stage, prim: &Prim, time: Option<f64>) -> GMat4 {
+    match compose_xform_ops(prim, time) {
         Some(m) => m,
         None => {
             warn!(
@@ -555,7 +809,7 @@ fn local_matrix_at(stage: &Stage, prim: &Prim) -> GMat4 {
                  composition (known to be wrong for multi-op stacks)",
                 prim.path()
             );
-            local_matrix_via_openusd(stage, prim)
+            local_matrix_via_openusd(stage, prim, time)
         }
     }
 }
@@ -570,8 +824,11 @@ fn local_matrix_at(stage: &Stage, prim: &Prim) -> GMat4 {
 /// and translates last: the composed translation equals the authored
 /// translate.)
 ///
+/// Each op's value is evaluated at `time` on its own, as USD does, so a
+/// rotation interpolates as an angle rather than as a matrix.
+///
 /// Returns `None` if any op token or value cannot be decoded.
-fn compose_xform_ops(prim: &Prim) -> Option<GMat4> {
+fn compose_xform_ops(prim: &Prim, time: Option<f64>) -> Option<GMat4> {
     let order = match prim.attribute("xformOpOrder").get::<sdf::Value>() {
         Ok(Some(sdf::Value::TokenVec(order))) => order,
         Ok(Some(_)) => return None,
@@ -590,7 +847,7 @@ fn compose_xform_ops(prim: &Prim) -> Option<GMat4> {
             Some(rest) => (rest, true),
             None => (token.as_str(), false),
         };
-        let mut m = xform_op_matrix(prim, name)?;
+        let mut m = xform_op_matrix(prim, name, time)?;
         if inverted {
             m = m.inverse();
         }
@@ -601,12 +858,12 @@ fn compose_xform_ops(prim: &Prim) -> Option<GMat4> {
 
 /// Matrix of a single `xformOp:<kind>[:<suffix>]` attribute on `prim`, or
 /// `None` for op kinds/value types we do not support.
-fn xform_op_matrix(prim: &Prim, name: &str) -> Option<GMat4> {
+fn xform_op_matrix(prim: &Prim, name: &str, time: Option<f64>) -> Option<GMat4> {
     let kind = name.strip_prefix("xformOp:")?;
     // Suffixes name op instances (`xformOp:translate:pivot`); the kind is
     // the first segment.
     let kind = kind.split(':').next().unwrap_or(kind);
-    let value = prim.attribute(name).get::<sdf::Value>().ok().flatten()?;
+    let value = value_at(&prim.attribute(name), time)?;
 
     match kind {
         "translate" => Some(GMat4::from_translation(value_as_vec3(&value)?)),
@@ -669,34 +926,45 @@ fn value_as_f32(value: &sdf::Value) -> Option<f32> {
 /// openusd's own composition, kept as the fallback for op stacks
 /// `compose_xform_ops` cannot decode. Known to compose multi-op stacks in
 /// the wrong order (see `local_matrix_at`).
-fn local_matrix_via_openusd(stage: &Stage, prim: &Prim) -> GMat4 {
+fn local_matrix_via_openusd(stage: &Stage, prim: &Prim, time: Option<f64>) -> GMat4 {
+    let time = time.unwrap_or(0.0);
     if let Ok(Some(x)) = Xform::get(stage, prim.path().clone()) {
-        if let Ok(m) = x.local_to_parent_transform(0.0) {
+        if let Ok(m) = x.local_to_parent_transform(time) {
             return usd_mat_to_glam(m);
         }
     }
     if let Ok(Some(m)) = UsdMesh::get(stage, prim.path().clone()) {
-        if let Ok(mat) = m.local_to_parent_transform(0.0) {
+        if let Ok(mat) = m.local_to_parent_transform(time) {
             return usd_mat_to_glam(mat);
         }
     }
     if let Ok(Some(s)) = UsdSphere::get(stage, prim.path().clone()) {
-        if let Ok(mat) = s.local_to_parent_transform(0.0) {
+        if let Ok(mat) = s.local_to_parent_transform(time) {
             return usd_mat_to_glam(mat);
         }
     }
     if let Ok(Some(c)) = UsdCamera::get(stage, prim.path().clone()) {
-        if let Ok(mat) = c.local_to_parent_transform(0.0) {
+        if let Ok(mat) = c.local_to_parent_transform(time) {
             return usd_mat_to_glam(mat);
         }
     }
     if let Ok(Some(l)) = SphereLight::get(stage, prim.path().clone()) {
-        if let Ok(mat) = l.local_to_parent_transform(0.0) {
+        if let Ok(mat) = l.local_to_parent_transform(time) {
             return usd_mat_to_glam(mat);
         }
     }
     if let Ok(Some(l)) = RectLight::get(stage, prim.path().clone()) {
-        if let Ok(mat) = l.local_to_parent_transform(0.0) {
+        if let Ok(mat) = l.local_to_parent_transform(time) {
+            return usd_mat_to_glam(mat);
+        }
+    }
+    if let Ok(Some(l)) = DiskLight::get(stage, prim.path().clone()) {
+        if let Ok(mat) = l.local_to_parent_transform(time) {
+            return usd_mat_to_glam(mat);
+        }
+    }
+    if let Ok(Some(l)) = CylinderLight::get(stage, prim.path().clone()) {
+        if let Ok(mat) = l.local_to_parent_transform(time) {
             return usd_mat_to_glam(mat);
         }
     }
@@ -722,11 +990,17 @@ fn resets_xform_stack_at(stage: &Stage, prim: &Prim) -> bool {
     if let Ok(Some(l)) = RectLight::get(stage, prim.path().clone()) {
         return l.resets_xform_stack().unwrap_or(false);
     }
+    if let Ok(Some(l)) = DiskLight::get(stage, prim.path().clone()) {
+        return l.resets_xform_stack().unwrap_or(false);
+    }
+    if let Ok(Some(l)) = CylinderLight::get(stage, prim.path().clone()) {
+        return l.resets_xform_stack().unwrap_or(false);
+    }
     false
 }
 
 // -----------------------------------------------------------------------
-// Per-prim geometry attributes (visibility mask, motion)
+// Per-prim geometry attributes (visibility mask, holdout, motion)
 // -----------------------------------------------------------------------
 
 /// `crust:rayMask` — which ray categories see this geometry (bit 0 camera,
@@ -738,6 +1012,26 @@ fn prim_ray_mask(prim: &Prim) -> u32 {
         .unwrap_or(MASK_ALL)
 }
 
+/// `crust:holdout` — the prim is a matte: camera rays that reach it see
+/// nothing and leave alpha at zero, while shadow and indirect rays still
+/// see it as authored, so it keeps casting shadows and reflecting into the
+/// rest of the scene. Composited over a plate, the plate shows through the
+/// hole. Applies to everything the prim expands into; on an instance or a
+/// `PointInstancer`, author it on the instancing prim itself.
+fn prim_holdout(prim: &Prim) -> bool {
+    custom_bool(prim, "crust:holdout").unwrap_or(false)
+}
+
+/// `crust:shadowCatcher` — the prim stands in for a surface in the plate
+/// (typically the ground): camera rays see through it like a holdout, and
+/// the `shadow` AOV records how much of the light reaching it the rest of
+/// the scene blocks, for the comp to darken the plate by. Secondary rays
+/// see it as authored, so CG objects still pick up its bounce. Same scope
+/// rules as `crust:holdout`.
+fn prim_shadow_catcher(prim: &Prim) -> bool {
+    custom_bool(prim, "crust:shadowCatcher").unwrap_or(false)
+}
+
 /// Ray mask for a light's *source geometry*. Industry default (Arnold,
 /// RenderMan, Karma): the surface is invisible to camera rays — lights sit
 /// in frame without showing up — while shadow and indirect rays still see
@@ -758,6 +1052,28 @@ fn prim_motion_translate(prim: &Prim) -> Option<Vec3> {
     custom_color3(prim, "crust:motion:translate").map(|v| Vec3::new(v.x, v.y, v.z))
 }
 
+/// Where a prim's geometry starts over the shutter, and where it ends if it
+/// moves. `span` is its transform at the shutter's opening and closing, when
+/// the camera's shutter is open for a while; without one the prim sits at
+/// `world_xf`. `crust:motion:translate` moves the end further.
+fn prim_motion(
+    prim: &Prim,
+    world_xf: GMat4,
+    span: Option<(GMat4, GMat4)>,
+) -> (GMat4, Option<GMat4>) {
+    let (start, end) = match span {
+        Some((open, close)) => (open, (open != close).then_some(close)),
+        None => (world_xf, None),
+    };
+    match prim_motion_translate(prim) {
+        Some(v) => (
+            start,
+            Some(GMat4::from_translation(v) * end.unwrap_or(start)),
+        ),
+        None => (start, end),
+    }
+}
+
 // -----------------------------------------------------------------------
 // Mesh
 // -----------------------------------------------------------------------
@@ -824,6 +1140,16 @@ struct MeshSlot {
     /// placements (a non-invertible transform) are not counted: they never
     /// reference the slot again.
     n_place: u32,
+    /// Set when the mesh's material emits. Every static placement is then
+    /// also a [`MeshLight`], built in [`flush_meshes`] — after baking or
+    /// committing has taken `local`, hence a copy of its own.
+    emitter: Option<MeshEmitter>,
+}
+
+/// The local triangles and material of an emissive mesh.
+struct MeshEmitter {
+    geom: MeshGeom,
+    material: Arc<dyn Material>,
 }
 
 /// Distinct meshes seen so far, and the index of each by content.
@@ -837,6 +1163,11 @@ struct MeshArena {
     /// `HashMap`'s order is not stable and the build must be deterministic.
     slots: Vec<MeshSlot>,
     by_key: HashMap<MeshKey, u32>,
+    /// Keep every mesh as its own kernel scene, never baked flat: set for a
+    /// sequence, whose every frame places the same meshes again. A baked
+    /// mesh would have to be re-baked, and its triangles rebuilt into the
+    /// top-level BVH, each frame; a resident one only gets a new instance.
+    resident: bool,
 }
 
 /// A direct mesh prim whose geometry is recorded but not yet attached.
@@ -846,7 +1177,8 @@ struct MeshPlacement {
     /// Index into [`MeshArena::slots`].
     slot: u32,
     l2w: Affine3A,
-    motion: Option<Vec3>,
+    /// Where the placement has moved to by the shutter's close.
+    l2w_end: Option<Affine3A>,
 }
 
 impl MeshArena {
@@ -874,17 +1206,33 @@ impl MeshArena {
                 None
             })?;
         check_face_count(prim, counts, material.as_ref());
+        let emitter = emits(material.as_ref()).then(|| MeshEmitter {
+            geom: MeshGeom {
+                verts: verts.clone(),
+                tris: tris.clone(),
+            },
+            material: Arc::clone(material),
+        });
         let slot = self.slots.len() as u32;
         self.slots.push(MeshSlot {
             local: Some(MeshGeom { verts, tris }),
             faces: faces.map(Arc::new),
             committed: None,
             n_place: 0,
+            emitter,
         });
         self.by_key.insert(key, slot);
         Some(slot)
     }
 
+    /// Unindexes the meshes interned with `material`, which a sequence has
+    /// replaced, so that a material later allocated at its address cannot
+    /// find them. The slots stay, so that the others keep their indices.
+    fn forget_material(&mut self, material: &Arc<dyn Material>) {
+        let address = Arc::as_ptr(material) as *const u8 as usize;
+        self.by_key.retain(|key, _| key.material != address);
+    }
+
     /// The slot's geometry as a committed local-space kernel scene, built on
     /// first demand and shared thereafter. Once this is called the slot can
     /// no longer be baked — see [`MeshSlot::local`].
@@ -907,13 +1255,25 @@ impl MeshArena {
         s.committed = Some(Arc::clone(&scene));
         scene
     }
+
+    /// Starts a sequence's next frame, whose placements are counted afresh.
+    fn next_frame(&mut self) {
+        for slot in &mut self.slots {
+            slot.n_place = 0;
+        }
+    }
 }
 
+/// Places `mesh` at `world_xf`, moving to `world_xf_end` over the shutter
+/// if it moves (see [`prim_motion`]).
+#[allow(clippy::too_many_arguments)]
 fn emit_mesh(
     world: &mut WorldBuilder,
+    lights: &mut LightList,
     prim: &Prim,
     mesh: &UsdMesh,
     world_xf: GMat4,
+    world_xf_end: Option<GMat4>,
     material: Arc<dyn Material>,
     meshes: &mut MeshArena,
     pending: &mut Vec<MeshPlacement>,
@@ -927,7 +1287,6 @@ fn emit_mesh(
     };
 
     let mask = prim_ray_mask(prim);
-    let motion = prim_motion_translate(prim);
 
     // Non-invertible placements (a zero scale axis) cannot be instanced —
     // bake the degenerate transform into world-space triangles as before.
@@ -936,9 +1295,9 @@ fn emit_mesh(
             "Mesh at {} has a non-invertible transform — baking instead of instancing",
             prim.path()
         );
-        if motion.is_some() {
+        if world_xf_end.is_some() {
             warn!(
-                "Mesh at {}: crust:motion:translate is ignored on baked (non-invertible) geometry",
+                "Mesh at {}: motion blur is ignored on baked (non-invertible) geometry",
                 prim.path()
             );
         }
@@ -953,6 +1312,10 @@ fn emit_mesh(
         check_face_count(prim, &counts, material.as_ref());
         match triangulate(&counts, &indices, verts.len(), want_faces) {
             Some((tris, faces)) => {
+                let light = emits(material.as_ref()).then(|| {
+                    let triangles = world_triangles(&verts, &tris, &Affine3A::IDENTITY);
+                    (triangles, Arc::clone(&material))
+                });
                 let geom_id = world.attach_masked(
                     Geometry::TriangleMesh {
                         vertices: verts,
@@ -962,6 +1325,9 @@ fn emit_mesh(
                     material,
                     mask,
                 );
+                if let Some((triangles, material)) = light {
+                    add_mesh_light(lights, triangles, material, geom_id);
+                }
                 // This path bakes world-space vertices directly without going
                 // through `bake_indices`, so the winding — and with it the
                 // barycentric order — is whatever the transform produced.
@@ -983,13 +1349,19 @@ fn emit_mesh(
         return;
     };
     meshes.slots[slot as usize].n_place += 1;
+    if world_xf_end.is_some() && emits(material.as_ref()) {
+        warn!(
+            "Mesh at {} emits but moves over the shutter — found by bounce rays only, not sampled as a light",
+            prim.path()
+        );
+    }
 
     let geom_id = world.reserve_slot(material, mask);
     pending.push(MeshPlacement {
         geom_id,
         slot,
         l2w: Affine3A::from_mat4(world_xf),
-        motion,
+        l2w_end: world_xf_end.map(Affine3A::from_mat4),
     });
 }
 
@@ -1022,7 +1394,12 @@ fn emit_mesh(
 /// behaviour. That is the A/B switch: with it set the output must be
 /// bit-identical, which is what separates "the deferral is wrong" from "the
 /// baking changed something".
-fn flush_meshes(world: &mut WorldBuilder, meshes: &mut MeshArena, pending: Vec<MeshPlacement>) {
+fn flush_meshes(
+    world: &mut WorldBuilder,
+    lights: &mut LightList,
+    meshes: &mut MeshArena,
+    pending: Vec<MeshPlacement>,
+) {
     let bake_enabled = std::env::var("CRUST_MESH_BAKE").as_deref() != Ok("0");
     let mut baked = 0usize;
     let mut instanced = 0usize;
@@ -1033,9 +1410,19 @@ fn flush_meshes(world: &mut WorldBuilder, meshes: &mut MeshArena, pending: Vec<M
         // already made it resident as a kernel scene, and it does not move —
         // a baked mesh has no transform left to interpolate over the shutter.
         let bake = bake_enabled
+            && !meshes.resident
             && slot.n_place == 1
             && slot.committed.is_none()
-            && p.motion.is_none();
+            && p.l2w_end.is_none();
+
+        // A light's triangles are fixed in world space, so only a placement
+        // that holds still can be one.
+        if let Some(e) = &slot.emitter
+            && p.l2w_end.is_none()
+        {
+            let triangles = world_triangles(&e.geom.verts, &e.geom.tris, &p.l2w);
+            add_mesh_light(lights, triangles, Arc::clone(&e.material), p.geom_id);
+        }
 
         let faces = slot.faces.clone();
         if bake {
@@ -1060,15 +1447,12 @@ fn flush_meshes(world: &mut WorldBuilder, meshes: &mut MeshArena, pending: Vec<M
             baked += 1;
         } else {
             let scene = meshes.committed_scene(p.slot);
-            let l2w = p.l2w;
             world.set_geometry(
                 p.geom_id,
                 Geometry::Instance {
                     scene,
-                    transform: l2w,
-                    transform_end: p
-                        .motion
-                        .map(|v| Box::new(Affine3A::from_translation(v) * l2w)),
+                    transform: p.l2w,
+                    transform_end: p.l2w_end.map(Box::new),
                 },
             );
             // An instance keeps the prototype's own winding: the transform is
@@ -1088,6 +1472,38 @@ fn flush_meshes(world: &mut WorldBuilder, meshes: &mut MeshArena, pending: Vec<M
     }
 }
 
+/// Whether surfaces of `material` emit light, and so need sampling as one.
+fn emits(material: &dyn Material) -> bool {
+    material.emitted().max_element() > 0.0
+}
+
+/// A mesh's triangles as world-space corners, in index order — the order of
+/// the `prim_id`s the kernel reports for hits on it.
+fn world_triangles(verts: &[Vec3A], tris: &[[u32; 3]], l2w: &Affine3A) -> Vec<[Vec3A; 3]> {
+    tris.iter()
+        .map(|t| t.map(|i| l2w.transform_point3a(verts[i as usize])))
+        .collect()
+}
+
+/// Registers emissive geometry `geom_id` as a [`MeshLight`] over
+/// `triangles`, so NEE samples it and bounce hits on it are MIS-weighted
+/// against that.
+fn add_mesh_light(
+    lights: &mut LightList,
+    triangles: Vec<[Vec3A; 3]>,
+    material: Arc<dyn Material>,
+    geom_id: u32,
+) {
+    let n = triangles.len();
+    match MeshLight::new(triangles, material, geom_id) {
+        Some(light) => {
+            debug!("Emissive mesh (geometry {geom_id}): {n} triangles sampled as a light");
+            lights.add(Arc::new(light));
+        }
+        None => debug!("Emissive mesh (geometry {geom_id}) has no area — not a light"),
+    }
+}
+
 /// Local-space vertices into world space.
 fn bake_verts(verts: &[Vec3A], l2w: &Affine3A) -> Vec<Vec3A> {
     verts.iter().map(|v| l2w.transform_point3a(*v)).collect()
@@ -1237,6 +1653,7 @@ fn emit_sphere(
     prim: &Prim,
     sphere: &UsdSphere,
     world_xf: GMat4,
+    world_xf_end: Option<GMat4>,
     material: Arc<dyn Material>,
 ) {
     let radius = sphere_radius(sphere);
@@ -1249,10 +1666,11 @@ fn emit_sphere(
         center
     );
     let mask = prim_ray_mask(prim);
-    match prim_motion_translate(prim) {
+    match world_xf_end {
         // A moving sphere rides an identity-placed instance whose end
-        // transform is the shutter translation.
-        Some(v) => {
+        // transform is the centre's travel over the shutter.
+        Some(end) => {
+            let v = end.transform_point3(Vec3::ZERO) - center_world;
             let mut b = RtSceneBuilder::new();
             b.attach(Geometry::Sphere { center, radius });
             world.attach_masked(
@@ -1316,12 +1734,37 @@ struct ImportCaches<'a> {
     /// Nothing here is freed before the import ends, so those addresses
     /// stay unique — and the mesh and material caches keep deduplicating
     /// across stages, which is what stops streaming costing extra memory.
+    /// The one exception is a sequence's material whose inputs changed,
+    /// which [`MeshArena::forget_material`] unindexes before it is dropped.
     epoch: u32,
     /// The host's decoder, for materials that carry a texture asset.
     assets: &'a dyn AssetLoader,
     /// Root layer, the fallback anchor for an asset path openusd handed back
     /// unresolved.
     stage_path: &'a Path,
+    /// The render's working space; materials are converted into it as they
+    /// are resolved, so the cached `Arc`s are already in it.
+    color_space: ColorSpace,
+    /// Resolved dome-light image path → the decoded map, or `None` if the
+    /// host could not decode it. Lets every frame of a sequence share one
+    /// decode.
+    environments: HashMap<std::path::PathBuf, Option<Arc<crate::EnvironmentMap>>>,
+    /// Resolved aperture-mask path → the decoded mask, likewise.
+    apertures: HashMap<std::path::PathBuf, Option<Arc<ApertureImage>>>,
+    /// Resolved lens-file path and the film, focus distance and f-stop (as
+    /// bits) it was built for → the lens, or `None` if it would not build.
+    /// Bounding a lens's exit pupil traces a million rays, which every
+    /// frame of a sequence without a focus pull can share.
+    lenses: HashMap<(std::path::PathBuf, [u32; 4]), Option<Arc<LensSystem>>>,
+    /// The time code attributes are read at: `None` for a still, the frame
+    /// for a sequence (see [`value_at`]). Transforms, cameras, lights and
+    /// materials follow it; geometry and prototypes are read at the default
+    /// time, so a sequence builds them once.
+    time: Option<f64>,
+    /// The camera's `shutter:open` and `shutter:close`, offsets in time
+    /// codes from the frame, when it is open for a while: geometry is then
+    /// placed at both and blurs between them (see [`prim_motion`]).
+    shutter: Option<(f64, f64)>,
     /// Time the host spent decoding assets — environment maps *and* Ptex files.
     ///
     /// One accumulator for both, deliberately: it is reported as the "Load
@@ -1333,7 +1776,7 @@ struct ImportCaches<'a> {
 }
 
 impl<'a> ImportCaches<'a> {
-    fn new(assets: &'a dyn AssetLoader, stage_path: &'a Path) -> Self {
+    fn new(assets: &'a dyn AssetLoader, stage_path: &'a Path, color_space: ColorSpace) -> Self {
         ImportCaches {
             materials: MaterialCache::default(),
             meshes: MeshArena::default(),
@@ -1341,9 +1784,23 @@ impl<'a> ImportCaches<'a> {
             epoch: 0,
             assets,
             stage_path,
+            color_space,
+            environments: HashMap::new(),
+            apertures: HashMap::new(),
+            lenses: HashMap::new(),
+            time: None,
+            shutter: None,
             asset_time: Duration::ZERO,
         }
     }
+
+    /// The time codes the shutter opens and closes at in `frame` — the
+    /// sequence's time when there is one.
+    fn shutter_times(&self, frame: isize) -> Option<(f64, f64)> {
+        let base = self.time.unwrap_or(frame as f64);
+        self.shutter
+            .map(|(open, close)| (base + open, base + close))
+    }
 }
 
 /// One leaf geometry of a prototype: a committed kernel scene in its own
@@ -1402,13 +1859,15 @@ fn collect_proto_parts(
 
         // The prototype root's own transform is deliberately excluded: a
         // `PointInstancer` prototype is placed entirely by its per-instance
-        // transform, and a native prototype root carries none.
+        // transform, and a native prototype root carries none. The rest is
+        // read at the default time: a prototype is built once and shared by
+        // every frame of a sequence, and its instances are what animate.
         let this_local = if prim.path() == root.path() {
             GMat4::IDENTITY
         } else if resets_xform_stack_at(stage, &prim) {
-            local_matrix_at(stage, &prim)
+            local_matrix_at(stage, &prim, None)
         } else {
-            parent_local * local_matrix_at(stage, &prim)
+            parent_local * local_matrix_at(stage, &prim, None)
         };
 
         let mask = prim_ray_mask(&prim);
@@ -2171,25 +2630,136 @@ fn emit_curves(
 // Camera
 // -----------------------------------------------------------------------
 
-fn build_camera(stage: &Stage, prim: &Prim, settings: &RenderSettings) -> Option<Camera> {
+fn build_camera(
+    stage: &Stage,
+    prim: &Prim,
+    settings: &RenderSettings,
+    caches: &mut ImportCaches<'_>,
+) -> Option<Camera> {
+    let time = caches.time;
     let cam = UsdCamera::get(stage, prim.path().clone()).ok().flatten()?;
-    let world = local_to_world(stage, prim);
+    let world = local_to_world(stage, prim, time);
+    // USD has no schema for how the shutter opens; `crust:shutter:opening`
+    // is when it is fully open, as fractions of `shutter:open..close`.
+    let shutter = Shutter::new(
+        custom_f32_pair(prim, "crust:shutter:opening").unwrap_or((0.0, 1.0)),
+        custom_f32(prim, "crust:shutter:rolling").unwrap_or(0.0),
+    );
+    let motion = camera_motion(stage, prim, world, caches.shutter_times(settings.frame()));
 
     // USD camera looks down -Z with +Y up in local space.
     let lookfrom_v = world.transform_point3(Vec3::ZERO);
     let forward_v = world.transform_vector3(Vec3::NEG_Z).normalize();
     let up_v = world.transform_vector3(Vec3::Y).normalize();
 
-    let focal_length = attr_f32(&cam.focal_length_attr()).unwrap_or(50.0);
-    let horiz_aperture = attr_f32(&cam.horizontal_aperture_attr()).unwrap_or(20.955);
-    let vert_aperture_authored = attr_f32(&cam.vertical_aperture_attr());
-    let f_stop = attr_f32(&cam.f_stop_attr()).unwrap_or(0.0);
-    let focus_distance = attr_f32(&cam.focus_distance_attr()).unwrap_or(10.0);
+    let focal_length = attr_f32(&cam.focal_length_attr(), time).unwrap_or(50.0);
+    let horiz_aperture = attr_f32(&cam.horizontal_aperture_attr(), time).unwrap_or(20.955);
+    let vert_aperture_authored = attr_f32(&cam.vertical_aperture_attr(), time);
+    let f_stop = attr_f32(&cam.f_stop_attr(), time).unwrap_or(0.0);
+    let focus_distance = attr_f32(&cam.focus_distance_attr(), time).unwrap_or(10.0);
 
     let (w, h) = settings.get_dimensions();
     let (w_f, h_f) = (w as f32, h as f32);
     let vert_aperture = vert_aperture_authored.unwrap_or(horiz_aperture * h_f / w_f);
 
+    let lookfrom = Vec3A::new(lookfrom_v.x, lookfrom_v.y, lookfrom_v.z);
+    let vup = Vec3A::new(up_v.x, up_v.y, up_v.z);
+    let forward = Vec3A::new(forward_v.x, forward_v.y, forward_v.z);
+
+    // The film back's shift is in the apertures' units, so as a fraction of
+    // them it shifts every model's frame alike.
+    let offset = |name: &str| attr_f32(&prim.attribute(name), time).unwrap_or(0.0);
+    let film_offset = (
+        offset("horizontalApertureOffset") / horiz_aperture,
+        offset("verticalApertureOffset") / vert_aperture,
+    );
+    let clipping = attr_f32_pair(&cam.clipping_range_attr(), time);
+    let exposure = camera_exposure(prim, time);
+    // Everything past the projection applies to every camera model.
+    let finish = |camera: Camera| {
+        let camera = camera
+            .with_shutter(shutter)
+            .with_motion(&motion)
+            .with_film_offset(film_offset.0, film_offset.1)
+            .with_exposure(exposure);
+        match clipping {
+            Some((near, far)) => camera.with_clipping(near, far),
+            None => camera,
+        }
+    };
+
+    // `crust:camera:projection` adds the panoramic models USD has no
+    // schema for, and wins over `projection` when both are authored.
+    let projection =
+        custom_token(prim, "crust:camera:projection").or_else(|| custom_token(prim, "projection"));
+    let fisheye_fov = || custom_f32(prim, "crust:camera:fov").unwrap_or(180.0);
+    let panoramic = match projection.as_deref() {
+        Some("spherical") => Some(Projection::Spherical),
+        Some("fisheye_equidistant") => Some(Projection::FisheyeEquidistant { fov: fisheye_fov() }),
+        Some("fisheye_equisolid") => Some(Projection::FisheyeEquisolid { fov: fisheye_fov() }),
+        Some("cubemap") => Some(Projection::Cubemap),
+        _ => None,
+    };
+    if let Some(projection) = panoramic {
+        debug!(
+            "USD camera: {:?} lookfrom={:?} forward={:?} vup={:?}",
+            projection, lookfrom, forward, vup
+        );
+        return Some(finish(Camera::panoramic(
+            lookfrom,
+            lookfrom + forward,
+            vup,
+            projection,
+            w_f / h_f,
+        )));
+    }
+
+    match projection.as_deref() {
+        None | Some("perspective") => {}
+        Some("orthographic") => {
+            // USD measures apertures in tenths of a scene unit, and an
+            // orthographic camera's apertures are its film: the view is
+            // exactly that wide and tall, at any distance.
+            let (width, height) = (0.1 * horiz_aperture, 0.1 * vert_aperture);
+            debug!(
+                "USD camera: orthographic lookfrom={:?} forward={:?} vup={:?} view={}x{}",
+                lookfrom, forward, vup, width, height
+            );
+            return Some(finish(Camera::orthographic(
+                lookfrom,
+                lookfrom + forward,
+                vup,
+                width,
+                height,
+            )));
+        }
+        Some(other) => warn!(
+            "Camera at {} has unknown projection \"{}\" (expected perspective | orthographic | spherical | fisheye_equidistant | fisheye_equisolid | cubemap) — using perspective",
+            prim.path(),
+            other
+        ),
+    }
+
+    // Apertures are the film's size, in tenths of a scene unit: the
+    // millimetres a lens prescription is in, for centimetre scenes.
+    let film = Vec2::new(horiz_aperture, vert_aperture);
+    if let Some(lens) = camera_lens(prim, film, 10.0 * focus_distance, f_stop, caches) {
+        debug!(
+            "USD camera: realistic lookfrom={:?} forward={:?} vup={:?} focal length={}",
+            lookfrom,
+            forward,
+            vup,
+            lens.focal_length()
+        );
+        return Some(finish(Camera::realistic(
+            lookfrom,
+            lookfrom + forward,
+            vup,
+            lens,
+            0.1,
+        )));
+    }
+
     let vfov_deg = 2.0 * (vert_aperture / (2.0 * focal_length)).atan().to_degrees();
     let aperture = if f_stop > 0.0 {
         focal_length / f_stop
@@ -2198,30 +2768,203 @@ fn build_camera(stage: &Stage, prim: &Prim, settings: &RenderSettings) -> Option
     };
 
     let aspect = w_f / h_f;
-    let lookfrom = Vec3A::new(lookfrom_v.x, lookfrom_v.y, lookfrom_v.z);
-    let lookat_v = lookfrom_v + forward_v * focus_distance;
-    let lookat = Vec3A::new(lookat_v.x, lookat_v.y, lookat_v.z);
-    let vup = Vec3A::new(up_v.x, up_v.y, up_v.z);
+    let lookat = lookfrom + forward * focus_distance;
 
     debug!(
         "USD camera: lookfrom={:?} lookat={:?} vup={:?} vfov={} aspect={} aperture={} focus={}",
         lookfrom, lookat, vup, vfov_deg, aspect, aperture, focus_distance
     );
 
-    Some(Camera::new(
-        lookfrom,
-        lookat,
-        vup,
-        vfov_deg,
-        aspect,
-        aperture,
-        focus_distance,
+    Some(finish(
+        Camera::new(
+            lookfrom,
+            lookat,
+            vup,
+            vfov_deg,
+            aspect,
+            aperture,
+            focus_distance,
+        )
+        .with_aperture(camera_aperture(prim, caches)),
     ))
 }
 
+/// How much the camera scales the light reaching its film, from USD's
+/// physical exposure: `exposure:time` seconds at `exposure:iso` (100 is
+/// unity) through `exposure:fStop` (1 is unity — the lens's `fStop` only
+/// sets its depth of field), times `exposure:responsivity`, then `exposure`
+/// stops on top, as an EV offset. Unauthored, each leaves the light as
+/// it is.
+fn camera_exposure(prim: &Prim, time: Option<f64>) -> f32 {
+    let read = |name: &str, default: f32| attr_f32(&prim.attribute(name), time).unwrap_or(default);
+    let f_stop = read("exposure:fStop", 1.0).max(1e-3);
+    read("exposure:time", 1.0) * read("exposure:iso", 100.0) / 100.0
+        * read("exposure:responsivity", 1.0)
+        / (f_stop * f_stop)
+        * read("exposure", 0.0).exp2()
+}
+
+/// The camera's keys over an open shutter (see [`Camera::with_motion`]):
+/// its placement relative to `world`, its pose at the frame, at
+/// `motion:nonlinearSampleCount` (default 3) evenly spaced time codes from
+/// the shutter's opening to its closing. Empty when it does not move.
+fn camera_motion(
+    stage: &Stage,
+    prim: &Prim,
+    world: GMat4,
+    shutter: Option<(f64, f64)>,
+) -> Vec<Affine3A> {
+    let Some((open, close)) = shutter else {
+        return Vec::new();
+    };
+    if world.determinant().abs() < 1e-12 {
+        return Vec::new();
+    }
+    let n = custom_i32(prim, "motion:nonlinearSampleCount")
+        .unwrap_or(3)
+        .clamp(2, 64);
+    let to_frame = world.inverse();
+    let keys: Vec<GMat4> = (0..n)
+        .map(|k| {
+            let time = open + (close - open) * k as f64 / (n - 1) as f64;
+            local_to_world(stage, prim, Some(time)) * to_frame
+        })
+        .collect();
+    if keys.iter().all(|k| k.abs_diff_eq(GMat4::IDENTITY, 1e-6)) {
+        return Vec::new();
+    }
+    debug!("Camera {} moves over the shutter ({n} keys)", prim.path());
+    keys.into_iter().map(Affine3A::from_mat4).collect()
+}
+
+/// The lens opening's shape: `crust:aperture:blades` (fewer than 3 is
+/// round) turned by `crust:aperture:rotation` degrees, narrowed by
+/// `crust:aperture:squeeze`, or the mask `crust:aperture:texture` decodes
+/// to.
+fn camera_aperture(prim: &Prim, caches: &mut ImportCaches<'_>) -> Aperture {
+    let mut aperture = Aperture::default()
+        .with_blades(
+            custom_i32(prim, "crust:aperture:blades")
+                .unwrap_or(0)
+                .max(0) as u32,
+            custom_f32(prim, "crust:aperture:rotation").unwrap_or(0.0),
+        )
+        .with_squeeze(custom_f32(prim, "crust:aperture:squeeze").unwrap_or(1.0));
+    let texture = prim
+        .attribute("crust:aperture:texture")
+        .get::<sdf::Value>()
+        .ok()
+        .flatten()
+        .and_then(|v| asset_value_path(&v, caches.stage_path));
+    if let Some(texture) = texture {
+        let image = match caches.apertures.get(&texture) {
+            Some(hit) => hit.clone(),
+            None => {
+                let started = Instant::now();
+                let loaded = caches.assets.load_aperture(&texture).map(Arc::new);
+                caches.asset_time += started.elapsed();
+                if loaded.is_none() {
+                    warn!(
+                        "Camera at {}: could not load aperture {} — keeping its blades",
+                        prim.path(),
+                        texture.display()
+                    );
+                }
+                caches.apertures.insert(texture, loaded.clone());
+                loaded
+            }
+        };
+        if let Some(image) = image {
+            aperture = aperture.with_image(image);
+        }
+    }
+    aperture
+}
+
+/// The lens `crust:camera:lensFile` prescribes (see [`LensSystem`]), built
+/// for `film` and focused at `focus` millimetres, stopped down to `f_stop`.
+/// `None`, with a warning, if it does not read or build — the camera then
+/// falls back to the thin-lens perspective model.
+fn camera_lens(
+    prim: &Prim,
+    film: Vec2,
+    focus: f32,
+    f_stop: f32,
+    caches: &mut ImportCaches<'_>,
+) -> Option<Arc<LensSystem>> {
+    let path = prim
+        .attribute("crust:camera:lensFile")
+        .get::<sdf::Value>()
+        .ok()
+        .flatten()
+        .and_then(|v| asset_value_path(&v, caches.stage_path))?;
+    let key = (path, [film.x, film.y, focus, f_stop].map(f32::to_bits));
+    if let Some(hit) = caches.lenses.get(&key) {
+        return hit.clone();
+    }
+    let started = Instant::now();
+    let built = std::fs::read_to_string(&key.0)
+        .map_err(|e| e.to_string())
+        .and_then(|text| LensSystem::parse(&text))
+        .and_then(|rows| LensSystem::new(&rows, film, focus, f_stop));
+    caches.asset_time += started.elapsed();
+    let lens = match built {
+        Ok(lens) => Some(Arc::new(lens)),
+        Err(e) => {
+            warn!(
+                "Camera at {}: lens {} unusable ({e}) — using a perspective camera",
+                prim.path(),
+                key.0.display()
+            );
+            None
+        }
+    };
+    caches.lenses.insert(key, lens.clone());
+    lens
+}
+
+/// The render camera's shutter interval, as [`ImportCaches::shutter`]
+/// holds it: `None` for an instantaneous shutter, USD's default
+/// (`shutter:open` and `shutter:close` both 0). Read at `time`, like the
+/// camera's other attributes, and before the walk, which needs it from the
+/// first prim on: from `camera`, or else the first camera — the one the
+/// walk imports.
+fn import_shutter(
+    stage: &Stage,
+    camera: Option<&sdf::Path>,
+    time: Option<f64>,
+) -> Option<(f64, f64)> {
+    let start = camera.cloned().unwrap_or_else(sdf::Path::abs_root);
+    let mut stack = vec![stage.prim(start)];
+    while let Some(prim) = stack.pop() {
+        if prim.is_abstract().unwrap_or(false) || !prim.is_active().unwrap_or(true) {
+            continue;
+        }
+        if let Ok(Some(cam)) = UsdCamera::get(stage, prim.path().clone()) {
+            let open = attr_f32(&cam.shutter_open_attr(), time).unwrap_or(0.0) as f64;
+            let close = attr_f32(&cam.shutter_close_attr(), time).unwrap_or(0.0) as f64;
+            if close > open {
+                info!(
+                    "Shutter of {} is open from {open} to {close} time codes around the frame",
+                    prim.path()
+                );
+                return Some((open, close));
+            }
+            return None;
+        }
+        if prim.is_instance().unwrap_or(false) {
+            continue;
+        }
+        if let Ok(children) = prim.children() {
+            stack.extend(children);
+        }
+    }
+    None
+}
+
 /// Composed local-to-world by walking the prim path upwards. Slower than
 /// tracking it during DFS, but exact and only used at build_camera time.
-fn local_to_world(stage: &Stage, prim: &Prim) -> GMat4 {
+fn local_to_world(stage: &Stage, prim: &Prim, time: Option<f64>) -> GMat4 {
     let mut ancestors: Vec<Prim> = Vec::new();
     let mut cur_path = prim.path().clone();
     ancestors.push(stage.prim(cur_path.clone()));
@@ -2235,7 +2978,7 @@ fn local_to_world(stage: &Stage, prim: &Prim) -> GMat4 {
     ancestors.reverse();
     let mut acc = GMat4::IDENTITY;
     for p in &ancestors {
-        let local = local_matrix_at(stage, p);
+        let local = local_matrix_at(stage, p, time);
         let resets = resets_xform_stack_at(stage, p);
         acc = if resets { local } else { acc * local };
     }
@@ -2246,14 +2989,14 @@ fn local_to_world(stage: &Stage, prim: &Prim) -> GMat4 {
 // Lights
 // -----------------------------------------------------------------------
 
-/// Effective emitted radiance of a lux light: color scaled by intensity and
-/// exposure gain.
-fn lux_emission(light: &impl UsdLight) -> Vec3A {
-    let intensity = attr_f32(&light.intensity_attr()).unwrap_or(1.0);
-    let exposure = attr_f32(&light.exposure_attr()).unwrap_or(0.0);
-    let color = attr_color3f(&light.color_attr()).unwrap_or([1.0, 1.0, 1.0]);
+/// Effective emitted radiance of a lux light at `time`: color scaled by
+/// intensity and exposure gain, in the working space `space`.
+fn lux_emission(light: &impl UsdLight, space: ColorSpace, time: Option<f64>) -> Vec3A {
+    let intensity = attr_f32(&light.intensity_attr(), time).unwrap_or(1.0);
+    let exposure = attr_f32(&light.exposure_attr(), time).unwrap_or(0.0);
+    let color = attr_color3f(&light.color_attr(), time).unwrap_or([1.0, 1.0, 1.0]);
     let gain = intensity * 2f32.powf(exposure);
-    Vec3A::new(color[0] * gain, color[1] * gain, color[2] * gain)
+    space.convert_rec709(Vec3A::from(color) * gain)
 }
 
 fn emit_sphere_light(
@@ -2262,9 +3005,11 @@ fn emit_sphere_light(
     prim: &Prim,
     light: &SphereLight,
     world_xf: GMat4,
+    space: ColorSpace,
+    time: Option<f64>,
 ) {
-    let radius = attr_f32(&light.radius_attr()).unwrap_or(0.5);
-    let effective = lux_emission(light);
+    let radius = attr_f32(&light.radius_attr(), time).unwrap_or(0.5);
+    let effective = lux_emission(light, space, time);
     let pos_v = world_xf.transform_point3(Vec3::ZERO);
     let position = Vec3A::new(pos_v.x, pos_v.y, pos_v.z);
 
@@ -2301,10 +3046,12 @@ fn emit_rect_light(
     prim: &Prim,
     light: &RectLight,
     world_xf: GMat4,
+    space: ColorSpace,
+    time: Option<f64>,
 ) {
-    let width = attr_f32(&light.width_attr()).unwrap_or(1.0);
-    let height = attr_f32(&light.height_attr()).unwrap_or(1.0);
-    let effective = lux_emission(light);
+    let width = attr_f32(&light.width_attr(), time).unwrap_or(1.0);
+    let height = attr_f32(&light.height_attr(), time).unwrap_or(1.0);
+    let effective = lux_emission(light, space, time);
 
     // UsdLux RectLight: a rectangle in the local XY plane, centered at the
     // origin, emitting along local -Z.
@@ -2348,6 +3095,172 @@ fn emit_rect_light(
     );
 }
 
+/// Segments around the tessellated geometry of a disk or cylinder light.
+/// The light itself samples the true circle; the inscribed polygon falls
+/// short of its area by under 0.2%, far below the noise of a render.
+const LIGHT_SEGMENTS: u32 = 64;
+
+/// Point `i` of [`LIGHT_SEGMENTS`] around the unit circle, as `(cos, sin)`.
+fn light_ring(i: u32) -> (f32, f32) {
+    let phi = std::f32::consts::TAU * i as f32 / LIGHT_SEGMENTS as f32;
+    (phi.cos(), phi.sin())
+}
+
+/// A triangle fan over the ellipse `center + cos φ·axis_u + sin φ·axis_v`,
+/// its vertex indices offset by `base`.
+fn light_fan(
+    center: Vec3A,
+    axis_u: Vec3A,
+    axis_v: Vec3A,
+    base: u32,
+) -> (Vec<Vec3A>, Vec<[u32; 3]>) {
+    let mut vertices = vec![center];
+    vertices.extend((0..LIGHT_SEGMENTS).map(|i| {
+        let (c, s) = light_ring(i);
+        center + c * axis_u + s * axis_v
+    }));
+    let indices = (0..LIGHT_SEGMENTS)
+        .map(|i| [base, base + 1 + i, base + 1 + (i + 1) % LIGHT_SEGMENTS])
+        .collect();
+    (vertices, indices)
+}
+
+fn emit_disk_light(
+    world: &mut WorldBuilder,
+    lights: &mut LightList,
+    prim: &Prim,
+    light: &DiskLight,
+    world_xf: GMat4,
+    space: ColorSpace,
+    time: Option<f64>,
+) {
+    let radius = attr_f32(&light.radius_attr(), time).unwrap_or(0.5);
+    let effective = lux_emission(light, space, time);
+
+    // UsdLux DiskLight: a disk in the local XY plane, centered at the
+    // origin, emitting along local -Z — RectLight's convention.
+    let c = world_xf.transform_point3(Vec3::ZERO);
+    let au = world_xf.transform_vector3(Vec3::new(radius, 0.0, 0.0));
+    let av = world_xf.transform_vector3(Vec3::new(0.0, radius, 0.0));
+    let nz = world_xf.transform_vector3(Vec3::NEG_Z);
+    let shape = DiskShape::new(
+        Vec3A::new(c.x, c.y, c.z),
+        Vec3A::new(au.x, au.y, au.z),
+        Vec3A::new(av.x, av.y, av.z),
+        Vec3A::new(nz.x, nz.y, nz.z),
+    );
+
+    // The geometry (a triangle fan over the disk) and the AreaLight share
+    // one surface, as for a RectLight; the mask hides the surface from
+    // camera rays unless the prim opts in (see light_ray_mask).
+    let material = Arc::new(Emissive::new(effective));
+    let (vertices, indices) = light_fan(shape.center, shape.axis_u, shape.axis_v, 0);
+    let geom_id = world.attach_masked(
+        Geometry::TriangleMesh {
+            vertices,
+            indices,
+            normals: None,
+        },
+        material.clone(),
+        light_ray_mask(prim),
+    );
+    debug!(
+        "DiskLight: center={:?} axis_u={:?} axis_v={:?} effective_color={:?}",
+        shape.center, shape.axis_u, shape.axis_v, effective
+    );
+    lights.add(Arc::new(AreaLight::new(Box::new(shape), material, geom_id)));
+}
+
+/// Imports a `UsdLuxCylinderLight`: a tube along the local X axis,
+/// centered at the origin, emitting outward from its side. The prim's
+/// transform may stretch the tube, but its cross-section stays a circle,
+/// scaled by the mean of the transform's two radial scales.
+///
+/// The geometry is the emitting side plus a pair of black end caps in a
+/// second mesh. The caps emit nothing, as UsdLux has it, but they close the
+/// tube: a ray can never reach its inside, where the side would glow at a
+/// surface the light's sampling treats as back-facing.
+fn emit_cylinder_light(
+    world: &mut WorldBuilder,
+    lights: &mut LightList,
+    prim: &Prim,
+    light: &CylinderLight,
+    world_xf: GMat4,
+    space: ColorSpace,
+    time: Option<f64>,
+) {
+    let radius = attr_f32(&light.radius_attr(), time).unwrap_or(0.5);
+    let length = attr_f32(&light.length_attr(), time).unwrap_or(1.0);
+    let effective = lux_emission(light, space, time);
+
+    let c = world_xf.transform_point3(Vec3::ZERO);
+    let ax = world_xf.transform_vector3(Vec3::new(length, 0.0, 0.0));
+    let radial_scale = 0.5
+        * (world_xf.transform_vector3(Vec3::Y).length()
+            + world_xf.transform_vector3(Vec3::Z).length());
+    let axis = Vec3A::new(ax.x, ax.y, ax.z);
+    let (half_length, radius) = (0.5 * axis.length(), radius * radial_scale);
+    if half_length <= 0.0 || radius <= 0.0 {
+        warn!("CylinderLight at {} is degenerate — skipped", prim.path());
+        return;
+    }
+    let shape = CylinderShape::new(Vec3A::new(c.x, c.y, c.z), axis, half_length, radius);
+
+    // Two rings of the side, interleaved (bottom, top) per segment, with
+    // the true radial normals.
+    let reach = shape.axis * shape.half_length;
+    let radial_u = utils::align_to_normal(Vec3A::X, shape.axis) * shape.radius;
+    let radial_v = utils::align_to_normal(Vec3A::Y, shape.axis) * shape.radius;
+    let mut vertices = Vec::with_capacity(2 * LIGHT_SEGMENTS as usize);
+    let mut normals = Vec::with_capacity(2 * LIGHT_SEGMENTS as usize);
+    for i in 0..LIGHT_SEGMENTS {
+        let (cos, sin) = light_ring(i);
+        let out = cos * radial_u + sin * radial_v;
+        vertices.extend([shape.center + out - reach, shape.center + out + reach]);
+        normals.extend([out.normalize(); 2]);
+    }
+    let indices = (0..LIGHT_SEGMENTS)
+        .flat_map(|i| {
+            let j = (i + 1) % LIGHT_SEGMENTS;
+            [[2 * i, 2 * j, 2 * j + 1], [2 * i, 2 * j + 1, 2 * i + 1]]
+        })
+        .collect();
+
+    let material = Arc::new(Emissive::new(effective));
+    let mask = light_ray_mask(prim);
+    let geom_id = world.attach_masked(
+        Geometry::TriangleMesh {
+            vertices,
+            indices,
+            normals: Some(normals),
+        },
+        material.clone(),
+        mask,
+    );
+    let (mut caps, mut cap_indices) = light_fan(shape.center - reach, radial_u, radial_v, 0);
+    let (far, far_indices) = light_fan(shape.center + reach, radial_u, radial_v, caps.len() as u32);
+    caps.extend(far);
+    cap_indices.extend(far_indices);
+    world.attach_masked(
+        Geometry::TriangleMesh {
+            vertices: caps,
+            indices: cap_indices,
+            normals: None,
+        },
+        Arc::new(Emissive::new(Vec3A::ZERO)),
+        mask,
+    );
+    debug!(
+        "CylinderLight: center={:?} axis={:?} length={} radius={} effective_color={:?}",
+        shape.center,
+        shape.axis,
+        2.0 * shape.half_length,
+        shape.radius,
+        effective
+    );
+    lights.add(Arc::new(AreaLight::new(Box::new(shape), material, geom_id)));
+}
+
 /// Imports a `UsdLuxDistantLight`. The light points down its local -Z, so
 /// the world direction it travels toward is that axis under the prim's
 /// transform. `inputs:angle` is the source's angular *diameter* in degrees
@@ -2357,14 +3270,20 @@ fn emit_rect_light(
 /// facing the light; [`DistantLight`] derives the radiance over the cone.
 /// The light has no scene geometry, so it is light-list-only: bounce rays
 /// find it by escaping along a direction inside its cone.
-fn emit_distant_light(lights: &mut LightList, light: &UsdDistantLight, world_xf: GMat4) {
+fn emit_distant_light(
+    lights: &mut LightList,
+    light: &UsdDistantLight,
+    world_xf: GMat4,
+    space: ColorSpace,
+    time: Option<f64>,
+) {
     let direction = world_xf.transform_vector3(Vec3::NEG_Z);
     if direction.length_squared() < 1e-12 {
         warn!("DistantLight has a degenerate orientation — skipped");
         return;
     }
-    let angle = attr_f32(&light.angle_attr()).unwrap_or(0.53);
-    let irradiance = lux_emission(light);
+    let angle = attr_f32(&light.angle_attr(), time).unwrap_or(0.53);
+    let irradiance = lux_emission(light, space, time);
     debug!(
         "DistantLight: direction={:?} angle={}° irradiance={:?}",
         direction, angle, irradiance
@@ -2391,13 +3310,10 @@ fn emit_dome_light(
     prim: &Prim,
     light: &DomeLight,
     world_xf: GMat4,
-    stage_path: &Path,
-    assets: &dyn AssetLoader,
-    // Accumulates time spent in the host's decoder, so the report can
-    // separate "decoding a 14k HDRI" from the rest of the traversal.
-    asset_time: &mut Duration,
+    caches: &mut ImportCaches<'_>,
 ) {
-    let tint = lux_emission(light);
+    let space = caches.color_space;
+    let tint = lux_emission(light, space, caches.time);
 
     let format = light
         .texture_format_attr()
@@ -2408,23 +3324,32 @@ fn emit_dome_light(
             sdf::Value::Token(t) => Some(t.to_string()),
             _ => None,
         });
-    let map = match dome_texture_path(light, stage_path) {
+    let map = match dome_texture_path(light, caches.stage_path) {
         Some(texture) => match format.as_deref() {
             // `automatic` infers from the image; for the equirectangular
             // images a dome light normally carries that means latlong.
             None | Some("latlong") | Some("automatic") => {
-                let started = Instant::now();
-                let loaded = assets.load_environment(&texture);
-                *asset_time += started.elapsed();
-                if loaded.is_none() {
-                    warn!(
-                        "DomeLight at {}: could not load {} — falling back to \
-                         the uniform colour",
-                        prim.path(),
-                        texture.display()
-                    );
+                if let Some(hit) = caches.environments.get(&texture) {
+                    hit.clone()
+                } else {
+                    // Time spent in the host's decoder is reported on its
+                    // own, separating "decoding a 14k HDRI" from the rest
+                    // of the traversal.
+                    let started = Instant::now();
+                    let loaded = caches.assets.load_environment(&texture);
+                    caches.asset_time += started.elapsed();
+                    if loaded.is_none() {
+                        warn!(
+                            "DomeLight at {}: could not load {} — falling back to \
+                             the uniform colour",
+                            prim.path(),
+                            texture.display()
+                        );
+                    }
+                    let map = loaded.map(|m| Arc::new(m.in_color_space(space)));
+                    caches.environments.insert(texture, map.clone());
+                    map
                 }
-                loaded.map(Arc::new)
             }
             Some(other) => {
                 warn!(
@@ -2472,29 +3397,6 @@ fn dome_texture_path(light: &DomeLight, stage_path: &Path) -> Option<std::path::
     asset_value_path(&value, stage_path)
 }
 
-fn warn_unsupported_light(stage: &Stage, prim: &Prim) {
-    let warn_type = |name: &str| {
-        warn!(
-            "USD light type '{}' at {} is not yet supported — skipped",
-            name,
-            prim.path()
-        );
-    };
-    if DiskLight::get(stage, prim.path().clone())
-        .ok()
-        .flatten()
-        .is_some()
-    {
-        warn_type("DiskLight");
-    } else if CylinderLight::get(stage, prim.path().clone())
-        .ok()
-        .flatten()
-        .is_some()
-    {
-        warn_type("CylinderLight");
-    }
-}
-
 // -----------------------------------------------------------------------
 // Materials
 // -----------------------------------------------------------------------
@@ -2512,6 +3414,13 @@ struct MaterialCache {
     /// Which stage the prototype-scoped entries belong to; see
     /// [`MaterialCache::key`]. Kept in step with [`ImportCaches::epoch`].
     epoch: u32,
+    /// The description each `by_path` entry was built from, before the
+    /// colour-space conversion. A sequence re-reads its materials every
+    /// frame and keeps an entry — and so every mesh keyed on its `Arc` —
+    /// while the description comes back unchanged.
+    sources: HashMap<(u32, String), OpenPBR>,
+    /// Entries already re-read this frame; see [`MaterialCache::next_frame`].
+    fresh: HashSet<(u32, String)>,
 }
 
 impl MaterialCache {
@@ -2519,6 +3428,16 @@ impl MaterialCache {
         self.default.get_or_insert_with(default_material).clone()
     }
 
+    /// Bound material path per resolved material, keyed by `Arc` address
+    /// like the mesh cache keys them — the material ID matte's names. The
+    /// shared default (no binding at all) has no path and stays unnamed.
+    fn names(&self) -> HashMap<*const (), Arc<str>> {
+        self.by_path
+            .iter()
+            .map(|((_, path), m)| (Arc::as_ptr(m).cast::<()>(), Arc::from(path.as_str())))
+            .collect()
+    }
+
     /// Cache key for a bound material path.
     ///
     /// Authored scene paths are stable across stages, so they key on the
@@ -2542,6 +3461,12 @@ impl MaterialCache {
         };
         (epoch, path.to_string())
     }
+
+    /// Starts a sequence's next frame: every entry is read again, at the
+    /// new time, the first time a prim binds it.
+    fn next_frame(&mut self) {
+        self.fresh.clear();
+    }
 }
 
 fn resolve_material(stage: &Stage, prim: &Prim, caches: &mut ImportCaches<'_>) -> Arc<dyn Material> {
@@ -2554,12 +3479,33 @@ fn resolve_material(stage: &Stage, prim: &Prim, caches: &mut ImportCaches<'_>) -
         return caches.materials.default_material();
     };
 
+    // A still trusts every hit; a sequence re-reads each material once per
+    // frame, in case its inputs are animated.
     let key = caches.materials.key(mat_path.as_str());
-    if let Some(hit) = caches.materials.by_path.get(&key) {
+    let animated = caches.time.is_some();
+    if let Some(hit) = caches.materials.by_path.get(&key)
+        && (!animated || caches.materials.fresh.contains(&key))
+    {
+        return hit.clone();
+    }
+    let source = resolve_material_uncached(stage, &mat_path, caches);
+    let cache = &mut caches.materials;
+    if animated {
+        cache.fresh.insert(key.clone());
+    }
+    if let Some(hit) = cache.by_path.get(&key)
+        && cache.sources.get(&key) == Some(&source)
+    {
         return hit.clone();
     }
-    let resolved = resolve_material_uncached(stage, &mat_path, caches);
-    caches.materials.by_path.insert(key, resolved.clone());
+    let resolved: Arc<dyn Material> = Arc::new(source.clone().in_color_space(caches.color_space));
+    if let Some(replaced) = cache.by_path.insert(key.clone(), resolved.clone()) {
+        // Nothing binds the replaced material again, and once the frames
+        // that did are dropped its address is free for a later material to
+        // take — see [`ImportCaches::epoch`].
+        caches.meshes.forget_material(&replaced);
+    }
+    cache.sources.insert(key, source);
     resolved
 }
 
@@ -2567,7 +3513,7 @@ fn resolve_material_uncached(
     stage: &Stage,
     mat_path: &sdf::Path,
     caches: &mut ImportCaches<'_>,
-) -> Arc<dyn Material> {
+) -> OpenPBR {
 
     let mat = match UsdMaterial::get(stage, mat_path.clone()) {
         Ok(Some(m)) => m,
@@ -2576,7 +3522,7 @@ fn resolve_material_uncached(
                 "Material at {} not resolvable — using default grey OpenPBR",
                 mat_path
             );
-            return default_material();
+            return default_openpbr();
         }
     };
 
@@ -2590,7 +3536,7 @@ fn resolve_material_uncached(
     // Decoding that gives a material with every parameter at its default: the
     // island rendered uniformly pale and glossy instead of matte dark rock.
     if has_shader_id(stage, mat_path, "PxrDisneyBsdf") {
-        return Arc::new(disney_to_openpbr(stage, mat_path, caches));
+        return disney_to_openpbr(stage, mat_path, caches);
     }
 
     let shader = match mat.compute_surface_source() {
@@ -2600,42 +3546,51 @@ fn resolve_material_uncached(
                 "Material {} has no surface shader — using default grey OpenPBR",
                 mat_path
             );
-            return default_material();
+            return default_openpbr();
         }
     };
 
     let shader_id = shader_info_id(&shader);
     debug!("Material {mat_path}: surface shader id = {shader_id:?}");
     match shader_id.as_deref() {
-        Some("crust:openpbr") => decode_crust_openpbr(&shader),
+        Some("crust:openpbr") => decode_crust_openpbr(&shader, caches.time),
         Some("UsdPreviewSurface") => {
             // The preview surface may still be the Ptex-driven one — the Moana
             // island wires its `diffuseColor` to a Ptex node — so consult the
             // material's own interface input either way.
             let mut o = preview_surface_openpbr(stage, mat_path);
+            if let Some(time) = caches.time {
+                animate_preview_surface(&shader, time, &mut o);
+            }
             o.base_color_ptex = material_ptex(stage, mat_path, caches);
-            Arc::new(o)
+            o
         }
-        Some("PxrDisneyBsdf") => Arc::new(disney_to_openpbr(stage, mat_path, caches)),
+        Some("PxrDisneyBsdf") => disney_to_openpbr(stage, mat_path, caches),
         Some(other) => {
             warn!(
                 "Unrecognized shader id '{}' at {} — using default grey OpenPBR",
                 other, mat_path
             );
-            default_material()
+            default_openpbr()
         }
         None => {
             warn!(
                 "Shader at {} has no info:id — using default grey OpenPBR",
                 mat_path
             );
-            default_material()
+            default_openpbr()
         }
     }
 }
 
+/// The shared fallback for prims with no binding at all. Neutral grey is
+/// grey in every working space, so it skips the colour-space conversion.
 fn default_material() -> Arc<dyn Material> {
-    Arc::new(OpenPBR::diffuse(Vec3A::new(0.5, 0.5, 0.5)))
+    Arc::new(default_openpbr())
+}
+
+fn default_openpbr() -> OpenPBR {
+    OpenPBR::diffuse(Vec3A::new(0.5, 0.5, 0.5))
 }
 
 fn shader_info_id(shader: &Shader) -> Option<String> {
@@ -2702,6 +3657,40 @@ fn preview_surface_openpbr(stage: &Stage, mat_path: &sdf::Path) -> OpenPBR {
     o
 }
 
+/// Overrides `o` with the preview surface's own inputs evaluated at `time`.
+/// [`preview_surface_openpbr`] goes through openusd's reader, which sees
+/// default values only; an input that is connected rather than authored
+/// has no value of its own and keeps what the reader found.
+fn animate_preview_surface(shader: &Shader, time: f64, o: &mut OpenPBR) {
+    let time = Some(time);
+    let f = |n: &str| shader_input_f32(shader, n, time);
+    if let Some(rgb) = shader_input_vec3(shader, "diffuseColor", time) {
+        o.base_color = rgb;
+    }
+    if let Some(v) = f("metallic") {
+        o.base_metalness = v;
+    }
+    if let Some(v) = f("roughness") {
+        o.specular_roughness = v;
+    }
+    if let Some(v) = f("opacity") {
+        o.geometry_opacity = v;
+    }
+    if let Some(rgb) = shader_input_vec3(shader, "emissiveColor", time) {
+        o.emission_color = rgb;
+        o.emission_luminance = if rgb.max_element() > 0.0 { 1.0 } else { 0.0 };
+    }
+    if let Some(v) = f("ior") {
+        o.specular_ior = v;
+    }
+    if let Some(v) = f("clearcoat") {
+        o.coat_weight = v;
+    }
+    if let Some(v) = f("clearcoatRoughness") {
+        o.coat_roughness = v;
+    }
+}
+
 /// Whether the material has a child `Shader` prim with this `info:id`.
 ///
 /// Cheaper and more reliable than resolving a render-context output for the
@@ -2748,8 +3737,9 @@ fn disney_to_openpbr(
     caches: &mut ImportCaches<'_>,
 ) -> OpenPBR {
     let prim = stage.prim(mat_path.clone());
-    let f = |n: &str| custom_f32(&prim, &format!("inputs:{n}"));
-    let c = |n: &str| custom_vec3(&prim, &format!("inputs:{n}"));
+    let time = caches.time;
+    let f = |n: &str| attr_f32(&prim.attribute(&format!("inputs:{n}")), time);
+    let c = |n: &str| custom_vec3(&prim, &format!("inputs:{n}"), time);
 
     let mut o = OpenPBR::default();
 
@@ -2887,8 +3877,8 @@ fn srgb_to_linear(c: Vec3A) -> Vec3A {
     )
 }
 
-fn custom_vec3(prim: &Prim, name: &str) -> Option<Vec3A> {
-    let v = prim.attribute(name).get::<sdf::Value>().ok()??;
+fn custom_vec3(prim: &Prim, name: &str, time: Option<f64>) -> Option<Vec3A> {
+    let v = value_at(&prim.attribute(name), time)?;
     match v {
         sdf::Value::Vec3f(p) => Some(Vec3A::new(p.x, p.y, p.z)),
         sdf::Value::Vec3d(p) => Some(Vec3A::new(p.x as f32, p.y as f32, p.z as f32)),
@@ -2899,12 +3889,13 @@ fn custom_vec3(prim: &Prim, name: &str) -> Option<Vec3A> {
 /// Decode a `crust:openpbr` shader into the OpenPBR material. Every input
 /// name is camelCase mirror of the Rust snake_case, e.g. `base_color` →
 /// `inputs:baseColor`, `subsurface_radius_scale` → `inputs:subsurfaceRadiusScale`.
-fn decode_crust_openpbr(shader: &Shader) -> Arc<dyn Material> {
+/// Inputs are read at `time`.
+fn decode_crust_openpbr(shader: &Shader, time: Option<f64>) -> OpenPBR {
     let mut o = OpenPBR::default();
 
-    let f = |n: &str, d: f32| shader_input_f32(shader, n).unwrap_or(d);
-    let c = |n: &str, d: Vec3A| shader_input_vec3(shader, n).unwrap_or(d);
-    let b = |n: &str, d: bool| shader_input_bool(shader, n).unwrap_or(d);
+    let f = |n: &str, d: f32| shader_input_f32(shader, n, time).unwrap_or(d);
+    let c = |n: &str, d: Vec3A| shader_input_vec3(shader, n, time).unwrap_or(d);
+    let b = |n: &str, d: bool| shader_input_bool(shader, n, time).unwrap_or(d);
 
     // Base
     o.base_weight = f("baseWeight", o.base_weight);
@@ -2976,12 +3967,12 @@ fn decode_crust_openpbr(shader: &Shader) -> Arc<dyn Material> {
     o.geometry_opacity = f("geometryOpacity", o.geometry_opacity);
     o.geometry_thin_walled = b("geometryThinWalled", o.geometry_thin_walled);
 
-    Arc::new(o)
+    o
 }
 
-fn shader_input_f32(shader: &Shader, name: &str) -> Option<f32> {
+fn shader_input_f32(shader: &Shader, name: &str, time: Option<f64>) -> Option<f32> {
     let attr_name = format!("inputs:{}", name);
-    let v = shader.attribute(&attr_name).get::<sdf::Value>().ok()??;
+    let v = value_at(&shader.attribute(&attr_name), time)?;
     match v {
         sdf::Value::Float(f) => Some(f),
         sdf::Value::Double(d) => Some(d as f32),
@@ -2989,18 +3980,18 @@ fn shader_input_f32(shader: &Shader, name: &str) -> Option<f32> {
     }
 }
 
-fn shader_input_bool(shader: &Shader, name: &str) -> Option<bool> {
+fn shader_input_bool(shader: &Shader, name: &str, time: Option<f64>) -> Option<bool> {
     let attr_name = format!("inputs:{}", name);
-    let v = shader.attribute(&attr_name).get::<sdf::Value>().ok()??;
+    let v = value_at(&shader.attribute(&attr_name), time)?;
     match v {
         sdf::Value::Bool(b) => Some(b),
         _ => None,
     }
 }
 
-fn shader_input_vec3(shader: &Shader, name: &str) -> Option<Vec3A> {
+fn shader_input_vec3(shader: &Shader, name: &str, time: Option<f64>) -> Option<Vec3A> {
     let attr_name = format!("inputs:{}", name);
-    let v = shader.attribute(&attr_name).get::<sdf::Value>().ok()??;
+    let v = value_at(&shader.attribute(&attr_name), time)?;
     match v {
         sdf::Value::Vec3f(p) => Some(Vec3A::new(p.x, p.y, p.z)),
         // USD encodes color3f as an sdf::Value::Vec3f — no dedicated variant.
@@ -3012,16 +4003,104 @@ fn shader_input_vec3(shader: &Shader, name: &str) -> Option<Vec3A> {
 // Render settings
 // -----------------------------------------------------------------------
 
-fn import_render_settings(stage: &Stage) -> RenderSettings {
-    let path = match UsdRenderSettings::stage_settings_path(stage).ok().flatten() {
-        Some(p) => p,
-        None => {
-            // Fall back to the conventional /Render/settings location.
-            match sdf::path("/Render/settings").ok() {
-                Some(p) => p,
-                None => return default_settings(),
+/// The stage's RenderSettings prim path: its `renderSettingsPrimPath`
+/// metadata, or the conventional `/Render/settings` location.
+fn render_settings_path(stage: &Stage) -> Option<sdf::Path> {
+    match UsdRenderSettings::stage_settings_path(stage).ok().flatten() {
+        Some(p) => Some(p),
+        None => sdf::path("/Render/settings").ok(),
+    }
+}
+
+/// The render settings prim and camera a render uses — see
+/// [`ImportOptions`].
+struct Selection {
+    settings: Option<sdf::Path>,
+    camera: Option<sdf::Path>,
+    /// Whether the options named `camera`, rather than the settings.
+    camera_asked: bool,
+}
+
+impl Selection {
+    /// Reports that the stage has no camera at `camera`: an error when the
+    /// options asked for it, a warning when the settings' camera failed to
+    /// build — the render falls back as for a stage without a camera.
+    fn missing_camera(&self) -> Result<(), crate::Error> {
+        let Some(camera) = &self.camera else {
+            return Ok(());
+        };
+        if self.camera_asked {
+            return Err(crate::Error::Selection(format!("no camera at {camera}")));
+        }
+        warn!("Render settings name camera {camera}, which could not be imported");
+        Ok(())
+    }
+}
+
+/// Resolves `options` against the stage. Fails on a path that does not
+/// parse, or settings the stage does not have. A camera the options ask
+/// for is only known missing after the walk, which may load it from a
+/// payload; the settings' `camera` relationship is checked here, on the
+/// stage the settings themselves were read from, and a target that is no
+/// camera is dropped so the first camera renders — before the shutter is
+/// read from it.
+fn select_prims(stage: &Stage, options: &ImportOptions) -> Result<Selection, crate::Error> {
+    let parse = |text: &str| {
+        sdf::path(text).map_err(|_| crate::Error::Selection(format!("{text:?} is not a prim path")))
+    };
+    let settings = match options.settings() {
+        Some(text) => {
+            let path = parse(text)?;
+            if UsdRenderSettings::get(stage, path.clone())
+                .ok()
+                .flatten()
+                .is_none()
+            {
+                return Err(crate::Error::Selection(format!(
+                    "no RenderSettings at {path}"
+                )));
             }
+            Some(path)
         }
+        None => render_settings_path(stage),
+    };
+    let (camera, camera_asked) = match options.camera() {
+        Some(text) => (Some(parse(text)?), true),
+        None => {
+            let named = settings
+                .as_ref()
+                .and_then(|p| UsdRenderSettings::get(stage, p.clone()).ok().flatten())
+                .and_then(|s| s.camera_rel().targets().ok())
+                .and_then(|targets| targets.into_iter().next());
+            let named = named.filter(|camera| {
+                let found = UsdCamera::get(stage, camera.clone())
+                    .ok()
+                    .flatten()
+                    .is_some();
+                if !found {
+                    warn!(
+                        "Render settings name camera {camera}, which the stage does not have — \
+                         using its first camera instead"
+                    );
+                }
+                found
+            });
+            (named, false)
+        }
+    };
+    if let Some(camera) = &camera {
+        info!("Rendering through camera {camera}");
+    }
+    Ok(Selection {
+        settings,
+        camera,
+        camera_asked,
+    })
+}
+
+fn import_render_settings(stage: &Stage, path: Option<&sdf::Path>) -> RenderSettings {
+    let Some(path) = path.cloned() else {
+        return default_settings();
     };
 
     let s = match UsdRenderSettings::get(stage, path.clone()).ok().flatten() {
@@ -3093,10 +4172,96 @@ fn import_render_settings(stage: &Stage) -> RenderSettings {
         filter = filter.with_radius(radius);
     }
 
-    RenderSettings::new(spp, max_depth, w, h, min_spp, variance, frame)
+    // Extra output layers, as a comma-separated list (`"albedo,N,Z"`) or
+    // `"all"`. An unknown name drops the list rather than guessing at it.
+    let aovs = match custom_token(&prim, "crust:aovs") {
+        None => AovSet::empty(),
+        Some(list) => AovSet::parse(&list).unwrap_or_else(|bad| {
+            warn!(
+                "Unknown AOV \"{}\" in crust:aovs (expected albedo | N | P | Z | diffuse_direct | diffuse_indirect | specular | transmission | emission | volume | sample_count | all) — rendering no AOVs",
+                bad
+            );
+            AovSet::empty()
+        }),
+    };
+
+    // ID mattes: `"object"`, `"material"`, or both.
+    let cryptomatte = match custom_token(&prim, "crust:cryptomatte") {
+        None => CryptoSet::empty(),
+        Some(list) => CryptoSet::parse(&list).unwrap_or_else(|bad| {
+            warn!(
+                "Unknown Cryptomatte layer \"{}\" in crust:cryptomatte (expected object | material | all) — rendering no ID mattes",
+                bad
+            );
+            CryptoSet::empty()
+        }),
+    };
+
+    // Deep output, written next to the flat image.
+    let deep = custom_bool(&prim, "crust:deep").unwrap_or(false);
+
+    // Built-in denoiser, guided by the albedo / normal / depth AOVs.
+    let denoise = custom_bool(&prim, "crust:denoise").unwrap_or(false);
+
+    // Working colour space: `lin_rec709` (default) | `acescg` | `lin_rec2020`.
+    let color_space = match custom_token(&prim, "crust:colorSpace") {
+        None => ColorSpace::default(),
+        Some(name) => ColorSpace::from_name(&name).unwrap_or_else(|| {
+            warn!(
+                "Unknown crust:colorSpace \"{}\" (expected lin_rec709 | acescg | lin_rec2020) — using lin_rec709",
+                name
+            );
+            ColorSpace::default()
+        }),
+    };
+
+    let settings = RenderSettings::new(spp, max_depth, w, h, min_spp, variance, frame)
         .with_guiding(guiding, guiding_iters, guiding_prob)
         .with_sampling_strategy(strategy)
         .with_pixel_filter(filter)
+        .with_aovs(aovs)
+        .with_cryptomatte(cryptomatte)
+        .with_deep(deep)
+        .with_color_space(color_space)
+        .with_denoise(denoise)
+        .with_overscan(custom_i32(&prim, "crust:overscan").unwrap_or(0).max(0) as u32);
+
+    // Data window: `int4 crust:dataWindow = (xmin, ymin, xmax, ymax)` in
+    // pixels, top-left origin, max exclusive — only that rectangle is traced.
+    match custom_window(&prim, "crust:dataWindow") {
+        None => settings,
+        Some(window) if settings.with_crop(window).data_window().is_empty() => {
+            warn!(
+                "crust:dataWindow {:?} lies outside the {}x{} frame — rendering the full frame",
+                window, w, h
+            );
+            settings
+        }
+        Some(window) => settings.with_crop(window),
+    }
+}
+
+/// Light path expressions from `crust:lpes` on the RenderSettings prim: a
+/// `;`-separated list of `name=expression` entries, e.g.
+/// `"diffuse_direct=C<RD>L; caustics=C.*<TS>.*L"`. A malformed entry is
+/// skipped with a warning; the rest still render.
+fn import_lpes(stage: &Stage, path: Option<&sdf::Path>) -> Vec<LightPathExpr> {
+    let Some(path) = path.cloned() else {
+        return Vec::new();
+    };
+    let Some(list) = custom_token(&stage.prim(path), "crust:lpes") else {
+        return Vec::new();
+    };
+    list.split(';')
+        .filter(|spec| !spec.trim().is_empty())
+        .filter_map(|spec| match LightPathExpr::parse(spec) {
+            Ok(lpe) => Some(lpe),
+            Err(e) => {
+                warn!("Invalid light path expression in crust:lpes ({e}) — skipped");
+                None
+            }
+        })
+        .collect()
 }
 
 fn default_settings() -> RenderSettings {
@@ -3156,6 +4321,15 @@ fn custom_color3(prim: &Prim, name: &str) -> Option<Vec3A> {
     }
 }
 
+fn custom_f32_pair(prim: &Prim, name: &str) -> Option<(f32, f32)> {
+    let v = prim.attribute(name).get::<sdf::Value>().ok()??;
+    match v {
+        sdf::Value::Vec2f(p) => Some((p.x, p.y)),
+        sdf::Value::Vec2d(p) => Some((p.x as f32, p.y as f32)),
+        _ => None,
+    }
+}
+
 fn custom_f32_array(prim: &Prim, name: &str) -> Option<Vec<f32>> {
     let v = prim.attribute(name).get::<sdf::Value>().ok()??;
     match v {
@@ -3165,6 +4339,16 @@ fn custom_f32_array(prim: &Prim, name: &str) -> Option<Vec<f32>> {
     }
 }
 
+fn custom_window(prim: &Prim, name: &str) -> Option<PixelWindow> {
+    let v = prim.attribute(name).get::<sdf::Value>().ok()??;
+    match v {
+        sdf::Value::IntVec(b) if b.len() == 4 => Some(PixelWindow::new(b[0], b[1], b[2], b[3])),
+        other => other
+            .try_as_vec_4i()
+            .map(|b| PixelWindow::new(b.x, b.y, b.z, b.w)),
+    }
+}
+
 fn custom_i32_array(prim: &Prim, name: &str) -> Option<Vec<i32>> {
     let v = prim.attribute(name).get::<sdf::Value>().ok()??;
     match v {
@@ -3177,16 +4361,37 @@ fn custom_i32_array(prim: &Prim, name: &str) -> Option<Vec<i32>> {
 // Attribute helpers
 // -----------------------------------------------------------------------
 
-fn attr_f32(attr: &openusd::usd::Attribute) -> Option<f32> {
-    match attr.get::<sdf::Value>().ok()?? {
+/// `attr`'s value at time code `time`, by USD's value resolution: time
+/// samples evaluated there when the attribute has any, its default
+/// otherwise. `None` is USD's default time — what a still import reads
+/// everything at, so time samples are ignored.
+fn value_at(attr: &openusd::usd::Attribute, time: Option<f64>) -> Option<sdf::Value> {
+    match time {
+        Some(t) => attr.get_at::<sdf::Value>(t),
+        None => attr.get::<sdf::Value>(),
+    }
+    .ok()
+    .flatten()
+}
+
+fn attr_f32(attr: &openusd::usd::Attribute, time: Option<f64>) -> Option<f32> {
+    match value_at(attr, time)? {
         sdf::Value::Float(f) => Some(f),
         sdf::Value::Double(d) => Some(d as f32),
         _ => None,
     }
 }
 
-fn attr_color3f(attr: &openusd::usd::Attribute) -> Option<[f32; 3]> {
-    match attr.get::<sdf::Value>().ok()?? {
+fn attr_f32_pair(attr: &openusd::usd::Attribute, time: Option<f64>) -> Option<(f32, f32)> {
+    match value_at(attr, time)? {
+        sdf::Value::Vec2f(p) => Some((p.x, p.y)),
+        sdf::Value::Vec2d(p) => Some((p.x as f32, p.y as f32)),
+        _ => None,
+    }
+}
+
+fn attr_color3f(attr: &openusd::usd::Attribute, time: Option<f64>) -> Option<[f32; 3]> {
+    match value_at(attr, time)? {
         // color3f is stored as Vec3f in sdf::Value
         sdf::Value::Vec3f(v) => Some([v.x, v.y, v.z]),
         _ => None,
@@ -16,6 +16,8 @@
 
 use glam::Vec3A;
 
+use crate::color::ColorSpace;
+
 /// A per-face texture the host has opened and can sample.
 ///
 /// Implementations live in the host (`crust-render` wraps the `ptex` crate);
@@ -48,12 +50,45 @@ pub trait PtexTexture: Send + Sync {
 #[derive(Clone)]
 pub struct PtexRef(pub std::sync::Arc<dyn PtexTexture>);
 
+/// Two references are equal when they share one texture.
+impl PartialEq for PtexRef {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 impl PtexRef {
     /// Samples the texture — see [`PtexTexture::eval`].
     #[inline]
     pub fn eval(&self, face_id: u32, u: f32, v: f32) -> Vec3A {
         self.0.eval(face_id, u, v)
     }
+
+    /// The same texture with its (linear Rec.709) texels converted into
+    /// `space` on every lookup. Converting the host's file in place is not an
+    /// option — crust-core never sees its texels, only answers to `eval`.
+    pub fn in_color_space(self, space: ColorSpace) -> PtexRef {
+        if space == ColorSpace::LinRec709 {
+            return self;
+        }
+        PtexRef(std::sync::Arc::new(ConvertedPtex { inner: self, space }))
+    }
+}
+
+/// A [`PtexTexture`] whose lookups are converted into a working space.
+struct ConvertedPtex {
+    inner: PtexRef,
+    space: ColorSpace,
+}
+
+impl PtexTexture for ConvertedPtex {
+    fn eval(&self, face_id: u32, u: f32, v: f32) -> Vec3A {
+        self.space.convert_rec709(self.inner.eval(face_id, u, v))
+    }
+
+    fn num_faces(&self) -> usize {
+        self.inner.0.num_faces()
+    }
 }
 
 impl std::fmt::Debug for PtexRef {
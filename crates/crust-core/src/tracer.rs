@@ -1,17 +1,31 @@
+use crate::aabb::AABB;
+use crate::aov::{Aov, AovAccum, AovSample, AovSet};
 use crate::buffer::Buffer;
+use crate::checkpoint::{Checkpoint, GuidedState, PixelAccum, PriorPass, fingerprint};
+use crate::color::ColorSpace;
+use crate::cryptomatte::{CryptoAccum, CryptoSet, CryptoTable};
+use crate::deep::{DeepAccum, DeepSample};
+use crate::denoise::denoise;
+use crate::error::Error;
 use crate::filter::{FilterSampler, PixelFilter};
-use crate::guiding::{GuidingConfig, GuidingField, SampleData, luminance};
+use crate::guiding::{GuidingConfig, GuidingField, SampleData};
 use crate::hittable::HitRecord;
-use crate::material::{Material, ScatterSample};
+use crate::lpe::{Event, LightPathExpr, LobeEvents};
+use crate::material::{LobeSplit, Material, ScatterSample};
 use crate::medium::sample_henyey_greenstein;
+use crate::partial::{PartialRender, TileSplit};
 use crate::ray::Ray;
 use crate::rt_world::{World, WorldHit};
 use crate::stats::RayStats;
 use crate::volume::{PhaseMix, VolumeEvent, Volumes};
+use crate::window::PixelWindow;
 use crate::{LightList, PathSampler, camera::Camera};
 use glam::Vec3A;
 use rayon::prelude::*;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 use tracing::{info, warn};
 
 // OpenQMC domain-tree keys. The camera and the path subtree hang off the root
@@ -35,6 +49,60 @@ const K_VOLUME: i32 = 7; // off vertex: volume-region delta tracking
 /// logging) is the caller's concern — the engine has no UI dependencies.
 pub type ProgressCallback<'a> = &'a (dyn Fn(u64, u64) + Sync);
 
+/// Progressive-render callback: invoked on the calling thread with each
+/// whole-frame pass's image and the samples per pixel it reached — see
+/// [`Renderer::render_progressive`].
+pub type PassCallback<'a> = &'a mut dyn FnMut(&Buffer, u32);
+
+/// A final-pass slice's image, its variance and its spp — what a guided
+/// render needs to blend an intermediate image before handing it on.
+type SliceCallback<'a> = &'a mut dyn FnMut(&Buffer, f64, u32);
+
+/// Stops a render from another thread — see [`Renderer::with_cancel`].
+/// Clones share one flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the render to stop. It finishes the samples in flight and
+    /// returns the image it has.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// What may end a render before its full sample budget: the caller's
+/// cancel token or the time limit's deadline.
+#[derive(Default)]
+struct Stop {
+    cancel: Option<CancelToken>,
+    deadline: Option<Instant>,
+}
+
+impl Stop {
+    fn requested(&self) -> bool {
+        self.cancelled() || self.overdue()
+    }
+
+    /// An atomic load, cheap enough to check every sample.
+    fn cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    /// Reads the clock, so pixels check it once rather than per sample.
+    fn overdue(&self) -> bool {
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+}
+
 /// Training-only clamp on recorded radiance so a single firefly cannot
 /// dominate a directional distribution. Affects the guiding field, never the
 /// image estimator.
@@ -46,6 +114,14 @@ const TRAIN_RADIANCE_CLAMP: f32 = 1e3;
 const RR_START_BOUNCE: usize = 3;
 const RR_MIN_PROB: f32 = 0.05;
 
+/// With checkpoints on, the final pass runs in slices of this many samples
+/// per pixel and a checkpoint may be written between two slices.
+const CHECKPOINT_CHUNK_SPP: u32 = 16;
+
+/// Bucket edge length, in pixels, of tiled rendering and of the buckets a
+/// split render divides the frame into.
+pub(crate) const TILE_SIZE: usize = 16;
+
 /// How the integrator combines its two direct-lighting strategies — light
 /// sampling (NEE) and BSDF/phase sampling — into one estimate. The two MIS
 /// variants weight each strategy's samples with a Veach heuristic; the
@@ -113,11 +189,16 @@ struct GuidingContext<'a> {
 
 /// Parameters of one full-frame render pass.
 #[derive(Clone, Copy)]
-struct PassConfig {
+struct PassConfig<'a> {
     spp: u32,
     seed: u32,
     tiled: bool,
     adaptive: bool,
+    /// Checked before every sample but a pixel's first: once it fires,
+    /// pixels keep the samples they have.
+    stop: &'a Stop,
+    /// Render only this part's buckets (tiled passes only).
+    split: Option<TileSplit>,
 }
 
 /// Image-quality statistics of one render pass.
@@ -128,12 +209,36 @@ struct PassStats {
     variance: f64,
     /// Per-pixel variance of the pixel-mean luminance, row-major. Feeds the
     /// guiding efficiency estimate, which normalizes it against a reference
-    /// image shared by every pass being compared (`mean_relative_error`).
+    /// image shared by every pass being compared (`mean_relative_error`),
+    /// and the denoiser's colour weights.
     var_map: Vec<f64>,
     /// Integrator work this pass did.
     rays: RayStats,
 }
 
+/// Everything `render_pixel` produces for one pixel.
+struct PixelResult {
+    color: Vec3A,
+    /// Premultiplied coverage, filtered like `color`.
+    alpha: f32,
+    /// AOV values in [`AovSet::iter`] order — empty when none are requested.
+    aovs: Vec<Vec3A>,
+    /// LPE values in `Renderer::lpes` order.
+    lpes: Vec<Vec3A>,
+    /// Ranked `(id, coverage)` per Cryptomatte layer, in
+    /// [`CryptoSet::iter`] order.
+    crypto: Vec<Vec<(f32, f32)>>,
+    /// Deep samples, front to back — empty unless the render is deep.
+    deep: Vec<DeepSample>,
+    /// Guiding training samples (empty outside training passes).
+    samples: Vec<SampleData>,
+    /// Variance of the pixel-mean luminance (see `PassStats::var_map`).
+    variance: f64,
+    /// The running estimate after this pass, for the next slice or a
+    /// checkpoint.
+    accum: PixelAccum,
+}
+
 pub struct Renderer {
     pub camera: Camera,
     /// The committed world: the `crust-rt` kernel scene plus the material
@@ -145,6 +250,22 @@ pub struct Renderer {
     /// `volume.rs`). Empty for scenes without volumes — every volume code
     /// path short-circuits then.
     pub volumes: Volumes,
+    /// Light path expressions rendered as extra layers (see `lpe.rs`).
+    pub lpes: Vec<LightPathExpr>,
+    /// Cryptomatte IDs of every geometry, hashed by the first render that
+    /// asks for ID mattes.
+    crypto: OnceLock<CryptoTable>,
+    /// Where to save checkpoints, and how often (see `checkpoint.rs`).
+    checkpoint: Option<(PathBuf, Duration)>,
+    /// The checkpoint this render continues from.
+    resume: Option<Checkpoint>,
+    cancel: Option<CancelToken>,
+    time_limit: Option<Duration>,
+    /// Image-wide relative error a progressive render stops at.
+    noise_target: Option<f32>,
+    /// The scene's [`crate::Scene::identity`], for checkpoints and split
+    /// renders.
+    scene_identity: u64,
 }
 
 impl Renderer {
@@ -161,6 +282,14 @@ impl Renderer {
             lights,
             settings,
             volumes: Volumes::default(),
+            lpes: Vec::new(),
+            crypto: OnceLock::new(),
+            checkpoint: None,
+            resume: None,
+            cancel: None,
+            time_limit: None,
+            noise_target: None,
+            scene_identity: 0,
         }
     }
 
@@ -169,19 +298,158 @@ impl Renderer {
         self
     }
 
+    /// Tie checkpoints and split renders to the scene `identity` names —
+    /// pass on [`crate::Scene::identity`]. Without it, only the settings
+    /// and the geometry count tell one scene's checkpoint from another's.
+    pub fn with_scene_identity(mut self, identity: u64) -> Self {
+        self.scene_identity = identity;
+        self
+    }
+
+    /// Render each expression as its own layer alongside the beauty.
+    pub fn with_lpes(mut self, lpes: Vec<LightPathExpr>) -> Self {
+        self.lpes = lpes;
+        self
+    }
+
+    /// Save the running image to `path` every `interval` of the final pass,
+    /// and once more when it completes — see [`Checkpoint`]. Renders with
+    /// AOVs, LPEs, Cryptomatte or deep output run without checkpoints.
+    pub fn with_checkpoints(mut self, path: PathBuf, interval: Duration) -> Self {
+        self.checkpoint = Some((path, interval));
+        self
+    }
+
+    /// Continue from `checkpoint` rather than from zero samples. A
+    /// checkpoint of another scene, resolution or settings is ignored, with
+    /// a warning.
+    pub fn with_resume(mut self, checkpoint: Checkpoint) -> Self {
+        self.resume = Some(checkpoint);
+        self
+    }
+
+    /// Stop rendering when `token` is cancelled. Every pixel keeps at least
+    /// one sample, and the render returns the image it has by then.
+    pub fn with_cancel(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Stop rendering once `limit` of wall-clock time has passed, as a
+    /// cancelled render does. [`Renderer::render_progressive`]'s passes
+    /// spread the samples evenly over the frame; a single pass cut short
+    /// keeps every output layer but leaves the pixels it reached last with
+    /// fewer samples.
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Let [`Renderer::render_progressive`] stop once the image's mean
+    /// relative standard error — the adaptive-sampling measure, averaged
+    /// over the pixels — drops to `target`.
+    pub fn with_noise_target(mut self, target: f32) -> Self {
+        self.noise_target = Some(target);
+        self
+    }
+
+    /// The output layers this render writes beyond the beauty and alpha,
+    /// by name: AOVs (which denoising implies), LPEs, Cryptomatte and deep
+    /// samples. Checkpoints, progressive passes and split renders carry the
+    /// beauty and alpha only, so a caller offering those should refuse a
+    /// render that lists anything here rather than lose the layers.
+    pub fn extra_layers(&self) -> Vec<&'static str> {
+        let mut layers = Vec::new();
+        if !self.settings.aovs().is_empty() {
+            layers.push("AOVs");
+        }
+        if !self.lpes.is_empty() {
+            layers.push("LPEs");
+        }
+        if !self.settings.cryptomatte.is_empty() {
+            layers.push("Cryptomatte");
+        }
+        if self.settings.deep {
+            layers.push("deep output");
+        }
+        layers
+    }
+
+    /// Checkpoints cover the beauty and alpha only.
+    fn checkpointable(&self) -> bool {
+        self.extra_layers().is_empty()
+    }
+
+    /// The checkpoint to resume from, if there is one and it belongs to
+    /// this render.
+    fn resume_state(&self) -> Option<&Checkpoint> {
+        let ckpt = self.resume.as_ref()?;
+        if !self.checkpointable() {
+            warn!("checkpoint: extra output layers cannot resume; starting over");
+            return None;
+        }
+        let same_size = (ckpt.width, ckpt.height) == self.image_size();
+        if !same_size || ckpt.fingerprint != self.fingerprint() {
+            warn!("checkpoint: saved by a different scene or settings; starting over");
+            return None;
+        }
+        Some(ckpt)
+    }
+
+    /// What a checkpoint or a split part must carry to belong to this
+    /// render.
+    fn fingerprint(&self) -> u64 {
+        fingerprint(&self.settings, self.scene_identity, self.world.count())
+    }
+
+    fn crypto_table(&self) -> &CryptoTable {
+        self.crypto.get_or_init(|| CryptoTable::new(&self.world))
+    }
+
+    /// The size of the image this renderer produces: the data window's,
+    /// which is the display window's unless cropped or overscanned.
+    fn image_size(&self) -> (usize, usize) {
+        let window = self.settings.data_window();
+        (window.width(), window.height())
+    }
+
+    /// A black buffer with the layers this renderer fills.
+    fn output_buffer(&self) -> Buffer {
+        let crypto = self
+            .settings
+            .cryptomatte
+            .iter()
+            .map(|l| {
+                (
+                    l.name().to_owned(),
+                    self.crypto_table().manifest(l).to_owned(),
+                )
+            })
+            .collect();
+        let (width, height) = self.image_size();
+        let buffer = Buffer::with_aovs(width, height, self.settings.aovs())
+            .with_lpes(self.lpes.iter().map(|l| l.name().to_owned()).collect())
+            .with_cryptomatte(crypto);
+        if self.settings.deep {
+            buffer.with_deep()
+        } else {
+            buffer
+        }
+    }
+
     pub fn render(&self) -> Buffer {
-        self.render_impl(false, None).0
+        self.render_impl(false, None, None).0
     }
 
     pub fn render_with_tiles(&self) -> Buffer {
-        self.render_impl(true, None).0
+        self.render_impl(true, None, None).0
     }
 
     /// Renders with a progress callback — see [`ProgressCallback`]. With
     /// guiding enabled, only the final pass reports (training passes are
     /// silent, as before).
     pub fn render_with_progress(&self, tiled: bool, progress: ProgressCallback) -> Buffer {
-        self.render_impl(tiled, Some(progress)).0
+        self.render_impl(tiled, Some(progress), None).0
     }
 
     /// As [`Renderer::render_with_progress`], also returning what the
@@ -196,29 +464,120 @@ impl Renderer {
         tiled: bool,
         progress: ProgressCallback,
     ) -> (Buffer, RayStats) {
-        self.render_impl(tiled, Some(progress))
+        self.render_impl(tiled, Some(progress), None)
     }
 
-    fn render_impl(
+    /// Progressive rendering: the final pass runs as whole-frame passes of
+    /// doubling spp (2, 4, 8, … up to the budget), each continuing from the
+    /// last, and `on_pass` sees every pass's image. The render stops after
+    /// the full budget, at the noise target, or — mid-pass — at the time
+    /// limit or on cancellation, and returns the image so far. Guided
+    /// renders train first; their intermediate images are blended with the
+    /// training passes like the final one.
+    ///
+    /// Passes continue per-pixel sums that cover the beauty and alpha only,
+    /// so callers must refuse a render whose [`Renderer::extra_layers`] is
+    /// not empty. Given one anyway, this warns and renders a single pass,
+    /// which the time limit or the cancel token may still cut short.
+    pub fn render_progressive(
         &self,
         tiled: bool,
         progress: Option<ProgressCallback>,
+        on_pass: PassCallback,
     ) -> (Buffer, RayStats) {
+        self.render_impl(tiled, progress, Some(on_pass))
+    }
+
+    /// Renders one part of a frame split across processes — see
+    /// [`TileSplit`] — and returns its share of the pixels, for
+    /// [`PartialRender::merge`] to assemble. Bucket rendering of the beauty
+    /// and alpha only: a guided render, or one with
+    /// [`Renderer::extra_layers`], is refused. The time limit and the
+    /// cancel token stop it as they stop any render.
+    pub fn render_partial(
+        &self,
+        split: TileSplit,
+        progress: Option<ProgressCallback>,
+    ) -> Result<(PartialRender, RayStats), Error> {
         if self.settings.guiding {
-            return self.render_guided(tiled, progress);
+            return Err(Error::Split(
+                "path guiding trains on the whole frame; turn it off".to_owned(),
+            ));
+        }
+        let layers = self.extra_layers();
+        if !layers.is_empty() {
+            return Err(Error::Split(format!(
+                "parts carry the beauty and alpha only, but the render asks for {}",
+                layers.join(", ")
+            )));
+        }
+        let stop = self.stop();
+        let cfg = PassConfig {
+            split: Some(split),
+            ..self.final_pass_config(true, &stop)
+        };
+        let (width, height) = self.image_size();
+        let mut accums = vec![PixelAccum::default(); width * height];
+        let (_, _, stats) = self.render_pass(cfg, None, progress, Some(accums.as_mut_slice()));
+        let part = PartialRender::gather(
+            self.fingerprint(),
+            split,
+            self.settings.get_dimensions(),
+            self.settings.data_window(),
+            self.settings.color_space,
+            &accums,
+        );
+        Ok((part, stats.rays))
+    }
+
+    fn render_impl(
+        &self,
+        tiled: bool,
+        progress: Option<ProgressCallback>,
+        mut on_pass: Option<PassCallback>,
+    ) -> (Buffer, RayStats) {
+        let stop = self.stop();
+        let (mut buffer, var_map, rays) = if self.settings.guiding {
+            self.render_guided(tiled, &stop, progress, on_pass)
+        } else {
+            let progressive = on_pass.is_some();
+            let mut forward = |buffer: &Buffer, _: f64, spp: u32| {
+                if let Some(cb) = on_pass.as_mut() {
+                    cb(buffer, spp);
+                }
+            };
+            let hook = progressive.then_some(&mut forward as SliceCallback);
+            let cfg = self.final_pass_config(tiled, &stop);
+            let (buf, pass) = self.render_final(cfg, None, progress, None, hook);
+            (buf, pass.var_map, pass.rays)
+        };
+        if self.settings.denoise {
+            let start = Instant::now();
+            buffer.set_denoised(denoise(&buffer, &var_map));
+            info!("denoised in {:.2}s", start.elapsed().as_secs_f64());
+        }
+        (buffer, rays)
+    }
+
+    /// What may end this render early, with the time limit counted from
+    /// now.
+    fn stop(&self) -> Stop {
+        Stop {
+            cancel: self.cancel.clone(),
+            deadline: self.time_limit.map(|limit| Instant::now() + limit),
         }
-        let (buf, _, pass) = self.render_pass(self.final_pass_config(tiled), None, progress);
-        (buf, pass.rays)
     }
 
     /// Config of a final (image-quality) pass: full budget, adaptive
     /// sampling.
-    fn final_pass_config(&self, tiled: bool) -> PassConfig {
+    fn final_pass_config<'a>(&self, tiled: bool, stop: &'a Stop) -> PassConfig<'a> {
         PassConfig {
             spp: self.settings.samples_per_pixel,
             seed: self.settings.frame as u32,
             tiled,
             adaptive: true,
+            stop,
+            split: None,
         }
     }
 
@@ -245,8 +604,10 @@ impl Renderer {
     fn render_guided(
         &self,
         tiled: bool,
+        stop: &Stop,
         progress: Option<ProgressCallback>,
-    ) -> (Buffer, RayStats) {
+        mut on_pass: Option<PassCallback>,
+    ) -> (Buffer, Vec<f64>, RayStats) {
         // Every pass costs time, training included, so the counters cover
         // all of them rather than the final pass alone.
         let mut rays = RayStats::default();
@@ -254,11 +615,88 @@ impl Renderer {
             Some(b) => b,
             None => {
                 warn!("path guiding enabled but the scene has no bounding box; rendering unguided");
-                let (buf, _, pass) =
-                    self.render_pass(self.final_pass_config(tiled), None, progress);
-                return (buf, pass.rays);
+                let cfg = self.final_pass_config(tiled, stop);
+                let progressive = on_pass.is_some();
+                let mut forward = |buffer: &Buffer, _: f64, spp: u32| {
+                    if let Some(cb) = on_pass.as_mut() {
+                        cb(buffer, spp);
+                    }
+                };
+                let hook = progressive.then_some(&mut forward as SliceCallback);
+                let (buf, pass) = self.render_final(cfg, None, progress, None, hook);
+                return (buf, pass.var_map, pass.rays);
+            }
+        };
+        // A resumed render picks up after training: the checkpoint holds
+        // the field, the guided/unguided verdict and the training passes.
+        let resumed = self.resume_state().and_then(|c| c.guided.as_ref());
+        let (field, guide_final, mut passes) = match resumed {
+            Some(state) => {
+                info!("path guiding: resuming from a checkpoint, training skipped");
+                let passes = state
+                    .passes
+                    .iter()
+                    .map(|p| (self.prior_buffer(&p.pixels), p.variance))
+                    .collect();
+                (state.field.clone(), state.guide_final, passes)
             }
+            None => self.train_guiding(bounds, tiled, stop, &mut rays),
         };
+
+        info!(
+            "path guiding: final pass at {} spp ({})",
+            self.settings.samples_per_pixel,
+            if guide_final { "guided" } else { "unguided" }
+        );
+        let gctx = GuidingContext {
+            field: &field,
+            training: false,
+        };
+        let final_gctx = if guide_final { Some(&gctx) } else { None };
+        let guided = (self.checkpoint.is_some() && self.checkpointable()).then(|| GuidedState {
+            field: field.clone(),
+            guide_final,
+            passes: passes
+                .iter()
+                .map(|(buffer, variance)| PriorPass {
+                    pixels: self.beauty_pixels(buffer),
+                    variance: *variance,
+                })
+                .collect(),
+        });
+        // Intermediate images blend with the training passes, as the final
+        // one does.
+        let progressive = on_pass.is_some();
+        let mut blend = |buffer: &Buffer, variance: f64, spp: u32| {
+            if let Some(cb) = on_pass.as_mut() {
+                let mut all: Vec<(Buffer, f64)> =
+                    passes.iter().map(|(b, v)| (b.clone(), *v)).collect();
+                all.push((buffer.clone(), variance));
+                cb(&self.blend_passes(all), spp);
+            }
+        };
+        let hook = progressive.then_some(&mut blend as SliceCallback);
+        let cfg = self.final_pass_config(tiled, stop);
+        let (final_buffer, final_stats) =
+            self.render_final(cfg, final_gctx, progress, guided, hook);
+        rays.merge(&final_stats.rays);
+        passes.push((final_buffer, final_stats.variance));
+
+        // The final pass's variance map bounds the blend's from above —
+        // near enough for the denoiser, which is all that reads it.
+        (self.blend_passes(passes), final_stats.var_map, rays)
+    }
+
+    /// The training half of [`Renderer::render_guided`]: returns the trained
+    /// field, whether the final pass should be guided, and the training
+    /// passes with their variances.
+    fn train_guiding(
+        &self,
+        bounds: AABB,
+        tiled: bool,
+        stop: &Stop,
+        rays: &mut RayStats,
+    ) -> (GuidingField, bool, Vec<(Buffer, f64)>) {
         let cfg = GuidingConfig {
             train_iterations: self.settings.guiding_train_iterations,
             guide_prob: self.settings.guiding_prob,
@@ -289,9 +727,11 @@ impl Renderer {
                 seed,
                 tiled,
                 adaptive: false,
+                stop,
+                split: None,
             };
             let start = std::time::Instant::now();
-            let (buffer, samples, stats) = self.render_pass(train_cfg, Some(&gctx), None);
+            let (buffer, samples, stats) = self.render_pass(train_cfg, Some(&gctx), None, None);
             rays.merge(&stats.rays);
             let secs = start.elapsed().as_secs_f64();
             drop(gctx);
@@ -311,6 +751,10 @@ impl Renderer {
             }
             field.update(&samples, k + 1);
             passes.push((buffer, stats.variance));
+            if stop.requested() {
+                info!("path guiding: render stopped during training");
+                break;
+            }
         }
 
         let guide_final = match (&eff_unguided, &eff_guided) {
@@ -319,7 +763,8 @@ impl Renderer {
                 // training passes — our stand-in for the paper's denoised
                 // accumulated image, and crucially the *same* image for both
                 // sides of the ratio.
-                let ref_lum = blend_luminance(&passes, self.settings.width, self.settings.height);
+                let (width, height) = self.image_size();
+                let ref_lum = blend_luminance(&passes, width, height, self.settings.color_space);
                 let mrse_pt = mean_relative_error(var_pt, &ref_lum);
                 let mrse_pg = mean_relative_error(var_pg, &ref_lum);
                 if mrse_pt.is_finite() && mrse_pg.is_finite() && mrse_pt > 0.0 && mrse_pg > 0.0 {
@@ -344,22 +789,150 @@ impl Renderer {
             _ => true,
         };
 
-        info!(
-            "path guiding: final pass at {} spp ({})",
-            self.settings.samples_per_pixel,
-            if guide_final { "guided" } else { "unguided" }
-        );
-        let gctx = GuidingContext {
-            field: &field,
-            training: false,
-        };
-        let final_gctx = if guide_final { Some(&gctx) } else { None };
-        let (final_buffer, _, final_stats) =
-            self.render_pass(self.final_pass_config(tiled), final_gctx, progress);
-        rays.merge(&final_stats.rays);
-        passes.push((final_buffer, final_stats.variance));
+        (field, guide_final, passes)
+    }
+
+    /// A buffer holding saved beauty and alpha, row-major.
+    fn prior_buffer(&self, pixels: &[(Vec3A, f32)]) -> Buffer {
+        let mut buffer = self.output_buffer();
+        let width = self.image_size().0;
+        for (k, &(color, alpha)) in pixels.iter().enumerate() {
+            let (x, y) = (k % width, k / width);
+            buffer.set_pixel(x, y, color);
+            buffer.set_alpha(x, y, alpha);
+        }
+        buffer
+    }
 
-        (self.blend_passes(passes), rays)
+    /// The inverse of [`Renderer::prior_buffer`].
+    fn beauty_pixels(&self, buffer: &Buffer) -> Vec<(Vec3A, f32)> {
+        let (width, height) = self.image_size();
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (buffer.get_pixel(x, y), buffer.get_alpha(x, y))))
+            .collect()
+    }
+
+    /// The final pass: checkpointed, resumable and progressive as asked.
+    /// Without any of that it is one plain [`Renderer::render_pass`]. With
+    /// them it runs in slices — [`CHECKPOINT_CHUNK_SPP`] samples each, or
+    /// doubling spp when progressive — every pixel carrying its running
+    /// estimate from one slice to the next, with checkpoints saved between
+    /// slices. Sample `k` of a pixel does not depend on where the slices
+    /// fall, and the adaptive check runs at the same sample counts, so the
+    /// image is the one a single uninterrupted pass renders. `guided` is
+    /// what a guided render stores alongside the pixels; `on_pass` sees
+    /// every slice's image.
+    fn render_final(
+        &self,
+        cfg: PassConfig,
+        gctx: Option<&GuidingContext>,
+        progress: Option<ProgressCallback>,
+        guided: Option<GuidedState>,
+        mut on_pass: Option<SliceCallback>,
+    ) -> (Buffer, PassStats) {
+        let save_to = self.checkpoint.as_ref();
+        if save_to.is_some() && !self.checkpointable() {
+            warn!("checkpoint: extra output layers are not checkpointed; rendering without");
+        }
+        if on_pass.is_some() && !self.checkpointable() {
+            warn!("progressive: extra output layers cannot render in passes; rendering one");
+        }
+        let save_to = save_to.filter(|_| self.checkpointable());
+        let progressive = on_pass.is_some() && self.checkpointable();
+        let resume = self.resume_state();
+        if save_to.is_none() && resume.is_none() && !progressive {
+            let (buffer, _, stats) = self.render_pass(cfg, gctx, progress, None);
+            if let Some(cb) = on_pass.as_mut() {
+                cb(&buffer, stats.variance, cfg.spp);
+            }
+            return (buffer, stats);
+        }
+
+        let (width, height) = self.image_size();
+        let mut ckpt = Checkpoint {
+            fingerprint: self.fingerprint(),
+            width,
+            height,
+            pixels: resume.map_or_else(
+                || vec![PixelAccum::default(); width * height],
+                |c| c.pixels.clone(),
+            ),
+            guided: guided.or_else(|| resume.and_then(|c| c.guided.clone())),
+        };
+        if let Some(c) = resume {
+            info!(
+                "checkpoint: resuming with {} samples already taken",
+                c.samples_taken()
+            );
+        }
+        // Every pixel still sampling has taken the same number of samples
+        // (a slice boundary, or the previous target spp), so the slowest of
+        // them sets the next slice.
+        let behind = |pixels: &[PixelAccum]| {
+            pixels
+                .iter()
+                .filter(|p| !p.converged)
+                .map(|p| p.taken)
+                .min()
+                .filter(|&t| t < cfg.spp)
+        };
+        let next = |t: u32| {
+            let until = if progressive {
+                (2 * t).max(2)
+            } else {
+                (t / CHECKPOINT_CHUNK_SPP + 1) * CHECKPOINT_CHUNK_SPP
+            };
+            until.min(cfg.spp)
+        };
+        let chunks = std::iter::successors(behind(&ckpt.pixels), |&t| {
+            Some(next(t)).filter(|&n| n < cfg.spp)
+        })
+        .count()
+        .max(1) as u64;
+        let mut rays = RayStats::default();
+        let mut last_save = Instant::now();
+        let mut chunk = 0u64;
+        loop {
+            let until = behind(&ckpt.pixels).map_or(cfg.spp, next);
+            let slice = PassConfig { spp: until, ..cfg };
+            let report = |done: u64, total: u64| {
+                if let Some(cb) = progress {
+                    cb(chunk * total + done, chunks * total);
+                }
+            };
+            let (buffer, _, stats) =
+                self.render_pass(slice, gctx, Some(&report), Some(ckpt.pixels.as_mut_slice()));
+            rays.merge(&stats.rays);
+            let finished = until >= cfg.spp;
+            let stopped = cfg.stop.requested();
+            if let Some((path, interval)) = save_to
+                && (finished || stopped || last_save.elapsed() >= *interval)
+            {
+                match ckpt.save(path) {
+                    Ok(()) => info!("checkpoint: saved {} at {} spp", path.display(), until),
+                    Err(e) => warn!("checkpoint: cannot write {}: {}", path.display(), e),
+                }
+                last_save = Instant::now();
+            }
+            if let Some(cb) = on_pass.as_mut() {
+                cb(&buffer, stats.variance, until);
+            }
+            let mut quiet = false;
+            if progressive {
+                let noise = image_noise(&ckpt.pixels);
+                info!("progressive: pass at {} spp, noise {:.4}", until, noise);
+                quiet = self.noise_target.is_some_and(|t| noise <= t as f64);
+            }
+            if stopped {
+                info!("render stopped at up to {} spp", until);
+            } else if quiet && !finished {
+                info!("progressive: noise target reached at {} spp", until);
+            }
+            if finished || stopped || quiet {
+                return (buffer, PassStats { rays, ..stats });
+            }
+            chunk += 1;
+        }
     }
 
     /// Inverse-variance blend of independent unbiased passes. Passes whose
@@ -388,15 +961,53 @@ impl Renderer {
                 .map(|w| (w / total * 100.0).round() as i32)
                 .collect::<Vec<_>>()
         );
-        let (width, height) = (self.settings.width, self.settings.height);
-        let mut out = Buffer::new(width, height);
+        let (width, height) = self.image_size();
+        let aovs = self.settings.aovs();
+        let mut out = self.output_buffer();
+        let last = &passes.last().expect("at least the final pass exists").0;
         for y in 0..height {
             for x in 0..width {
                 let mut c = Vec3A::ZERO;
+                let mut a = 0.0;
                 for (pass, w) in passes.iter().zip(&weights) {
                     c += pass.0.get_pixel(x, y) * (*w / total) as f32;
+                    a += pass.0.get_alpha(x, y) * (*w / total) as f32;
                 }
                 out.set_pixel(x, y, c);
+                out.set_alpha(x, y, a);
+                // The lighting layers blend exactly like the beauty, so they
+                // still sum to it; depth keeps the nearest surface any pass
+                // saw, and sample counts add up across passes.
+                for aov in aovs.iter() {
+                    let value = match aov {
+                        Aov::Depth => passes.iter().fold(Vec3A::INFINITY, |d, (p, _)| {
+                            d.min(p.get_aov(x, y, aov))
+                        }),
+                        Aov::SampleCount => passes.iter().map(|(p, _)| p.get_aov(x, y, aov)).sum(),
+                        _ => passes
+                            .iter()
+                            .zip(&weights)
+                            .map(|((p, _), w)| p.get_aov(x, y, aov) * (*w / total) as f32)
+                            .sum(),
+                    };
+                    out.set_aov(x, y, aov, value);
+                }
+                for i in 0..self.lpes.len() {
+                    let value = passes
+                        .iter()
+                        .zip(&weights)
+                        .map(|((p, _), w)| p.get_lpe(x, y, i) * (*w / total) as f32)
+                        .sum();
+                    out.set_lpe(x, y, i, value);
+                }
+                // Coverage ranks do not blend — merging ranked lists would
+                // need every pass's full ID set — so the mattes come from
+                // the final pass, which holds most of the samples anyway.
+                // Deep samples likewise.
+                for i in 0..self.settings.cryptomatte.len() {
+                    out.set_crypto(x, y, i, last.get_crypto(x, y, i));
+                }
+                out.set_deep(x, y, last.get_deep(x, y).to_vec());
             }
         }
         out
@@ -405,31 +1016,42 @@ impl Renderer {
     /// One full-frame pass at `spp` samples per pixel. Returns the image,
     /// whatever training samples the pass recorded (empty unless a training
     /// `GuidingContext` is supplied), and the pass's [`PassStats`].
+    ///
+    /// With `accums`, every pixel continues from its running estimate there
+    /// (taking only the samples it is short of `spp`), and the estimate is
+    /// written back.
     fn render_pass(
         &self,
         cfg: PassConfig,
         gctx: Option<&GuidingContext>,
         progress: Option<ProgressCallback>,
+        mut accums: Option<&mut [PixelAccum]>,
     ) -> (Buffer, Vec<SampleData>, PassStats) {
-        let mut buffer = Buffer::new(self.settings.width, self.settings.height);
+        let aovs = self.settings.aovs();
+        let mut buffer = self.output_buffer();
         let mut all_samples = Vec::new();
         let mut variance_sum = 0.0f64;
         let mut rays = RayStats::default();
-        let mut var_map = vec![0.0f64; self.settings.width * self.settings.height];
-        let pixel_count = (self.settings.width * self.settings.height) as f64;
+        let (width, height) = self.image_size();
+        let mut var_map = vec![0.0f64; width * height];
+        let pixel_count = (width * height) as f64;
         // One tabulation per pass, shared read-only by every worker.
         let filter = FilterSampler::new(self.settings.pixel_filter);
 
         if cfg.tiled {
-            let tiles = generate_tiles(self.settings.width, self.settings.height, 16); // tile size: 16x16
+            let tiles: Vec<Tile> = generate_tiles(width, height, TILE_SIZE)
+                .into_iter()
+                .enumerate()
+                .filter(|(t, _)| cfg.split.is_none_or(|s| s.owns(*t)))
+                .map(|(_, tile)| tile)
+                .collect();
             let total = tiles.len() as u64;
             let done = AtomicU64::new(0);
-            type TileOut = (Vec<(usize, usize, Vec3A, f64)>, Vec<SampleData>, RayStats);
+            type TileOut = (Vec<(usize, usize, PixelResult)>, RayStats);
             let results: Vec<TileOut> = tiles
                 .into_par_iter()
                 .map(|tile| {
                     let mut pixels = Vec::with_capacity(tile.width * tile.height);
-                    let mut samples = Vec::new();
                     // Private to this tile, so no two threads share a
                     // counter and there is nothing to synchronise. The path
                     // scratch has the same ownership story: one buffer serves
@@ -438,61 +1060,103 @@ impl Renderer {
                     let mut scratch = PathScratch::new(self.settings.max_depth as usize);
                     for j in tile.y..tile.y + tile.height {
                         for i in tile.x..tile.x + tile.width {
-                            let (color, mut s, v) = self.render_pixel(
+                            let start = accums
+                                .as_deref()
+                                .map_or_else(Default::default, |a| a[j * width + i]);
+                            let px = self.render_pixel(
                                 i,
                                 j,
                                 &cfg,
                                 &filter,
                                 gctx,
+                                start,
                                 &mut scratch,
                                 &mut tile_rays,
                             );
-                            pixels.push((i, j, color, v));
-                            samples.append(&mut s);
+                            pixels.push((i, j, px));
                         }
                     }
                     if let Some(cb) = progress {
                         cb(done.fetch_add(1, Ordering::Relaxed) + 1, total);
                     }
-                    (pixels, samples, tile_rays)
+                    (pixels, tile_rays)
                 })
                 .collect();
-            for (pixels, samples, tile_rays) in results {
+            for (pixels, tile_rays) in results {
                 rays.merge(&tile_rays);
-                for (i, j, color, var) in pixels {
-                    buffer.set_pixel(i, j, color);
-                    var_map[j * self.settings.width + i] = var;
-                    variance_sum += var;
+                for (i, j, px) in pixels {
+                    buffer.set_pixel(i, j, px.color);
+                    buffer.set_alpha(i, j, px.alpha);
+                    for (aov, value) in aovs.iter().zip(&px.aovs) {
+                        buffer.set_aov(i, j, aov, *value);
+                    }
+                    for (k, value) in px.lpes.iter().enumerate() {
+                        buffer.set_lpe(i, j, k, *value);
+                    }
+                    for (k, ranks) in px.crypto.iter().enumerate() {
+                        buffer.set_crypto(i, j, k, ranks);
+                    }
+                    buffer.set_deep(i, j, px.deep);
+                    var_map[j * width + i] = px.variance;
+                    variance_sum += px.variance;
+                    all_samples.extend(px.samples);
+                    if let Some(a) = accums.as_deref_mut() {
+                        a[j * width + i] = px.accum;
+                    }
                 }
-                all_samples.extend(samples);
             }
         } else {
-            let total = self.settings.height as u64;
+            let total = height as u64;
             let mut done = 0u64;
-            for j in (0..self.settings.height).rev() {
+            for j in (0..height).rev() {
                 // `map_init` rather than `map`: the path scratch is reused
                 // across every pixel rayon hands one worker, instead of being
                 // rebuilt per pixel. (This path parallelises over pixels, so
                 // unlike the tiled path there is no per-work-unit closure to
                 // hang the buffer on.)
-                let row: Vec<(Vec3A, Vec<SampleData>, f64, RayStats)> = (0..self.settings.width)
+                let row: Vec<(PixelResult, RayStats)> = (0..width)
                     .into_par_iter()
                     .map_init(
                         || PathScratch::new(self.settings.max_depth as usize),
                         |scratch, i| {
-                            let mut px = RayStats::default();
-                            let (c, s, v) =
-                                self.render_pixel(i, j, &cfg, &filter, gctx, scratch, &mut px);
-                            (c, s, v, px)
+                            let mut px_rays = RayStats::default();
+                            let start = accums
+                                .as_deref()
+                                .map_or_else(Default::default, |a| a[j * width + i]);
+                            let px = self.render_pixel(
+                                i,
+                                j,
+                                &cfg,
+                                &filter,
+                                gctx,
+                                start,
+                                scratch,
+                                &mut px_rays,
+                            );
+                            (px, px_rays)
                         },
                     )
                     .collect();
-                for (i, (color, samples, var, px_rays)) in row.into_iter().enumerate() {
+                for (i, (px, px_rays)) in row.into_iter().enumerate() {
                     rays.merge(&px_rays);
-                    buffer.set_pixel(i, j, color);
-                    all_samples.extend(samples);
-                    var_map[j * self.settings.width + i] = var;
-                    variance_sum += var;
+                    buffer.set_pixel(i, j, px.color);
+                    buffer.set_alpha(i, j, px.alpha);
+                    for (aov, value) in aovs.iter().zip(&px.aovs) {
+                        buffer.set_aov(i, j, aov, *value);
+                    }
+                    for (k, value) in px.lpes.iter().enumerate() {
+                        buffer.set_lpe(i, j, k, *value);
+                    }
+                    for (k, ranks) in px.crypto.iter().enumerate() {
+                        buffer.set_crypto(i, j, k, ranks);
+                    }
+                    buffer.set_deep(i, j, px.deep);
+                    all_samples.extend(px.samples);
+                    var_map[j * width + i] = px.variance;
+                    variance_sum += px.variance;
+                    if let Some(a) = accums.as_deref_mut() {
+                        a[j * width + i] = px.accum;
+                    }
                 }
                 done += 1;
                 if let Some(cb) = progress {
@@ -519,45 +1183,80 @@ impl Renderer {
         cfg: &PassConfig,
         filter: &FilterSampler,
         gctx: Option<&GuidingContext>,
+        start: PixelAccum,
         scratch: &mut PathScratch,
         stats: &mut RayStats,
-    ) -> (Vec3A, Vec<SampleData>, f64) {
-        let mut sum = Vec3A::ZERO;
+    ) -> PixelResult {
+        // A resumed pixel picks its sums up where a checkpoint left them;
+        // a fresh one starts from zero.
+        let mut sum = start.sum;
         // FIS weight sum (see `filter.rs`): the pixel estimate is the
         // weighted average Σwᵢ·Lᵢ / Σwᵢ. For box and triangle every wᵢ is
         // exactly 1.0, so the sum is exactly `taken as f32` and the estimate
         // is the plain mean — box at radius 0.5 stays bit-identical to the
         // historical unweighted, unfiltered estimator.
-        let mut weight_sum = 0.0f32;
+        let mut weight_sum = start.weight_sum;
+        // Alpha is filtered exactly like the beauty, so the pair stays
+        // premultiplied through soft, filtered edges.
+        let mut alpha_sum = start.alpha_sum;
         let mut samples = Vec::new();
-        let mut lum_sum = 0.0f64;
-        let mut lum_sq = 0.0f64;
+        let mut lum_sum = start.lum_sum;
+        let mut lum_sq = start.lum_sq;
+        // Filtered alongside the beauty with the same weights; skipped
+        // outright when no AOVs or LPEs were asked for.
+        let aovs = self.settings.aovs();
+        let n_lpes = self.lpes.len();
+        let mut aov_acc =
+            (!aovs.is_empty() || n_lpes > 0).then(|| AovAccum::new(aovs, n_lpes));
+        let crypto_layers = self.settings.cryptomatte;
+        let mut crypto_acc = (!crypto_layers.is_empty()).then(CryptoAccum::new);
+        let mut deep_acc = self.settings.deep.then(DeepAccum::new);
+        let mut aov_sample = AovSample::new(n_lpes);
 
         let threshold = self.settings.variance_threshold as f64;
         let min_spp = self.settings.min_samples_per_pixel.max(2);
-        let mut taken = 0u32;
+        let mut taken = start.taken;
+        let mut converged = start.converged;
+        let first = if converged { cfg.spp } else { taken };
+
+        // `(i, j)` index the buffer, which holds the data window; `(x, y)`
+        // is the same pixel on the full frame's raster (bottom-up, like the
+        // buffer). Rays and sample patterns follow the raster position, so
+        // a cropped render reproduces the full render's pixels exactly.
+        // Overscan pixels sit at negative or past-the-edge positions.
+        let window = self.settings.data_window();
+        let x = window.x_min + i as i32;
+        let y = self.settings.height as i32 - window.y_max + j as i32;
 
         // OpenQMC decorrelates pixels within a 256×256 tile; distinguish tiles
         // with an extra domain so images wider/taller than 256 stay fully
         // decorrelated (the frame seed alone is constant within one render).
-        let tile = (i >> 8) as i32 + ((j >> 8) as i32) * 4096;
+        let tile = (x >> 8) + (y >> 8) * 4096;
 
         // Is the shutter coordinate worth sampling at all? `ray.time` is read
-        // by exactly one thing — a moving instance interpolating its
-        // transform — so on a scene where nothing moves, every value of it
-        // produces the same image and drawing one is pure waste. It is not
-        // cheap waste: `draw_sample_f32::<N>` computes a whole 4-dimensional
+        // by exactly two things — a moving instance interpolating its
+        // transform, and a moving camera placing the ray — so on a scene
+        // where nothing moves, every value of it produces the same image
+        // and drawing one is pure waste. It is not cheap waste:
+        // `draw_sample_f32::<N>` computes a whole 4-dimensional
         // Owen-scrambled Sobol block whatever `N` is, which measured 4.2% of
         // the render on cornellbox, one block per camera ray for one float.
         //
         // Skipping the draw cannot perturb the other dimensions: `new_domain`
         // is a pure function of the parent state and takes `&self`, so a
         // domain that is never derived leaves `root` untouched.
-        let motion = self.world.has_motion();
-
-        for sample in 0..cfg.spp {
-            let root =
-                PathSampler::new(i as i32, j as i32, cfg.seed as i32, sample as i32).new_domain(tile);
+        let motion = self.world.has_motion() || self.camera.has_motion();
+
+        // The deadline is read once per pixel: a pixel's samples take far
+        // less time than a time limit is measured in.
+        let overdue = cfg.stop.overdue();
+        for sample in first..cfg.spp {
+            // Two samples keep the pixel's variance estimable, which is
+            // what blending a stopped pass with others weighs it by.
+            if taken >= 2 && (overdue || cfg.stop.cancelled()) {
+                break;
+            }
+            let root = PathSampler::new(x, y, cfg.seed as i32, sample as i32).new_domain(tile);
             let cam = root.new_domain(K_CAMERA).draw_sample_f32::<4>();
             // Warp the in-pixel jitter through the reconstruction filter's
             // distribution (filter importance sampling): the offset places
@@ -571,8 +1270,10 @@ impl Renderer {
             // exactly. The historical `/ (w-1)` divisor stretched the pixel
             // grid over a plane 1 pixel too wide — a sub-pixel zoom of ~1/w
             // that also let the last row and column sample past v = 1.
-            let u = ((i as f32) + fx) / self.settings.width as f32;
-            let v = ((j as f32) + fy) / self.settings.height as f32;
+            // Overscan pixels land outside [0, 1), which the camera's plane
+            // extends to.
+            let u = ((x as f32) + fx) / self.settings.width as f32;
+            let v = ((y as f32) + fy) / self.settings.height as f32;
             // `Ray::new` defaults `time` to 0.0, and `transforms_at` takes the
             // start transform at time 0, so this is the value a static scene
             // was already effectively using.
@@ -581,26 +1282,54 @@ impl Renderer {
             } else {
                 0.0
             };
-            let r = self.camera.get_ray(u, v, [cam[2], cam[3]], time);
-            stats.camera_rays += 1;
-            let color = trace_path(
-                &r,
-                &self.world,
-                &self.lights,
-                &self.volumes,
-                self.settings.max_depth as i32,
-                self.settings.sampling_strategy,
-                root,
-                gctx,
-                &mut samples,
-                scratch,
-                stats,
-            ) * (wx * wy);
-            sum += color;
-            weight_sum += wx * wy;
-            let lum = luminance(color) as f64;
-            lum_sum += lum;
-            lum_sq += lum * lum;
+            let ray = self.camera.sample_ray(u, v, [cam[2], cam[3]], time);
+            if let Some((r, film_weight)) = ray {
+                stats.camera_rays += 1;
+                aov_sample.reset();
+                let (color, alpha) = trace_path(
+                    &r,
+                    &self.world,
+                    &self.lights,
+                    &self.volumes,
+                    self.settings.max_depth as i32,
+                    self.settings.sampling_strategy,
+                    self.settings.color_space,
+                    root,
+                    gctx,
+                    &mut samples,
+                    (aov_acc.is_some() || crypto_acc.is_some() || deep_acc.is_some())
+                        .then_some(&mut aov_sample),
+                    &self.lpes,
+                    scratch,
+                    stats,
+                );
+                // The camera's exposure and a realistic lens's vignetting scale
+                // the light reaching the film; they do not thin out the
+                // coverage.
+                let color = color * film_weight;
+                if let Some(acc) = &mut deep_acc {
+                    acc.add(&aov_sample, color, alpha, wx * wy);
+                }
+                let color = color * (wx * wy);
+                sum += color;
+                alpha_sum += alpha * (wx * wy);
+                weight_sum += wx * wy;
+                if let Some(acc) = &mut aov_acc {
+                    acc.add(&aov_sample, wx * wy);
+                }
+                if let Some(acc) = &mut crypto_acc {
+                    acc.add(aov_sample.geom_id, wx * wy);
+                }
+                let lum = self.settings.color_space.luminance(color) as f64;
+                lum_sum += lum;
+                lum_sq += lum * lum;
+            } else {
+                // Outside a panoramic camera's image, or blocked inside a
+                // realistic camera's lens: a black, transparent sample,
+                // weighed like any other. Its luminance is zero, so the
+                // moments adaptive sampling checks are already right.
+                weight_sum += wx * wy;
+            }
             taken = sample + 1;
 
             // Adaptive early stop: once past the minimum budget, quit as soon
@@ -612,6 +1341,7 @@ impl Renderer {
                     ((lum_sq - lum_sum * lum_sum / n) / (n - 1.0) / n).max(0.0);
                 let mean = (lum_sum / n).max(1e-4);
                 if var_of_mean.sqrt() / mean < threshold {
+                    converged = true;
                     break;
                 }
             }
@@ -627,12 +1357,36 @@ impl Renderer {
         // Weighted-average film estimator. A Mitchell pixel whose few
         // samples all landed on negative lobes could zero the denominator;
         // the plain mean is the sane fallback there.
-        let mean = if weight_sum > 0.0 {
-            sum / weight_sum
+        let norm = if weight_sum > 0.0 {
+            weight_sum
         } else {
-            sum / taken as f32
+            taken as f32
         };
-        (mean, samples, variance)
+        let mean = sum / norm;
+        let alpha = alpha_sum / norm;
+        let (aovs, lpes) = aov_acc.map_or_else(Default::default, |acc| acc.finish(weight_sum, taken));
+        let crypto = crypto_acc
+            .map_or_else(Vec::new, |acc| acc.finish(self.crypto_table(), crypto_layers, norm));
+        let deep = deep_acc.map_or_else(Vec::new, |acc| acc.finish(norm));
+        PixelResult {
+            color: mean,
+            alpha,
+            aovs,
+            lpes,
+            crypto,
+            deep,
+            samples,
+            variance,
+            accum: PixelAccum {
+                sum,
+                weight_sum,
+                alpha_sum,
+                lum_sum,
+                lum_sq,
+                taken,
+                converged,
+            },
+        }
     }
 }
 
@@ -658,6 +1412,24 @@ pub struct RenderSettings {
     // Pixel reconstruction filter (see `PixelFilter`; `crust:pixelFilter` /
     // `--filter`). Applied by filter importance sampling in `render_pixel`.
     pixel_filter: PixelFilter,
+    // Extra output layers (see `aov.rs`; `crust:aovs` / `--aov`).
+    aovs: AovSet,
+    // ID matte layers (see `cryptomatte.rs`; `crust:cryptomatte` /
+    // `--cryptomatte`).
+    cryptomatte: CryptoSet,
+    // Deep output (see `deep.rs`; `crust:deep` / `--deep`).
+    deep: bool,
+    // Working colour space (see `color.rs`; `crust:colorSpace`). The
+    // importer converts scene colours into it; the tracer only needs it
+    // for luminance and the built-in sky.
+    color_space: ColorSpace,
+    // Built-in denoiser (see `denoise.rs`; `crust:denoise` / `--denoise`).
+    denoise: bool,
+    // Data window (see `window.rs`; `crust:dataWindow` / `--crop` and
+    // `crust:overscan` / `--overscan`). `width`/`height` stay the display
+    // window, so the camera frames the full shot either way.
+    crop: Option<PixelWindow>,
+    overscan: u32,
 }
 impl RenderSettings {
     pub fn new(
@@ -682,6 +1454,13 @@ impl RenderSettings {
             guiding_prob: 0.5,
             sampling_strategy: SamplingStrategy::default(),
             pixel_filter: PixelFilter::default(),
+            aovs: AovSet::empty(),
+            cryptomatte: CryptoSet::empty(),
+            deep: false,
+            color_space: ColorSpace::default(),
+            denoise: false,
+            crop: None,
+            overscan: 0,
         }
     }
 
@@ -721,6 +1500,114 @@ impl RenderSettings {
         self.pixel_filter
     }
 
+    /// Select the AOV layers rendered alongside the beauty — see
+    /// [`crate::Aov`].
+    pub fn with_aovs(mut self, aovs: AovSet) -> Self {
+        self.aovs = aovs;
+        self
+    }
+
+    /// The AOV layers rendered: those asked for, plus the albedo, normal
+    /// and depth the denoiser is guided by when it is on.
+    pub fn aovs(&self) -> AovSet {
+        if self.denoise {
+            self.aovs
+                .with(Aov::Albedo)
+                .with(Aov::Normal)
+                .with(Aov::Depth)
+        } else {
+            self.aovs
+        }
+    }
+
+    /// Select the Cryptomatte layers rendered alongside the beauty — see
+    /// [`crate::CryptoLayer`].
+    pub fn with_cryptomatte(mut self, layers: CryptoSet) -> Self {
+        self.cryptomatte = layers;
+        self
+    }
+
+    pub fn cryptomatte(&self) -> CryptoSet {
+        self.cryptomatte
+    }
+
+    /// Also keep each pixel's depth-sorted deep samples — see
+    /// [`crate::DeepSample`].
+    pub fn with_deep(mut self, enabled: bool) -> Self {
+        self.deep = enabled;
+        self
+    }
+
+    pub fn deep(&self) -> bool {
+        self.deep
+    }
+
+    /// Select the working colour space — see [`ColorSpace`].
+    pub fn with_color_space(mut self, space: ColorSpace) -> Self {
+        self.color_space = space;
+        self
+    }
+
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Denoise the beauty once the render finishes, keeping the noisy one
+    /// as its own layer. Renders the feature AOVs the denoiser needs.
+    pub fn with_denoise(mut self, enabled: bool) -> Self {
+        self.denoise = enabled;
+        self
+    }
+
+    pub fn denoise(&self) -> bool {
+        self.denoise
+    }
+
+    /// Trace only `window` of the frame — see [`PixelWindow`]. It may
+    /// reach into the overscan margins, but not beyond them.
+    pub fn with_crop(mut self, window: PixelWindow) -> Self {
+        self.crop = Some(window);
+        self
+    }
+
+    pub fn crop(&self) -> Option<PixelWindow> {
+        self.crop
+    }
+
+    /// Extend the frame by `margin` pixels past each edge of the display
+    /// window, rendered with the same camera.
+    pub fn with_overscan(mut self, margin: u32) -> Self {
+        self.overscan = margin;
+        self
+    }
+
+    pub fn overscan(&self) -> u32 {
+        self.overscan
+    }
+
+    /// The frame number, which seeds the sampler: each frame of a sequence
+    /// draws its own samples, so the noise does not stand still.
+    pub fn with_frame(mut self, frame: isize) -> Self {
+        self.frame = frame;
+        self
+    }
+
+    pub fn frame(&self) -> isize {
+        self.frame
+    }
+
+    /// The pixels actually traced: the display window grown by the
+    /// overscan, clipped to the crop. Empty if the crop misses the frame.
+    pub fn data_window(&self) -> PixelWindow {
+        let frame = PixelWindow::full(self.width, self.height).grow(self.overscan);
+        match self.crop {
+            Some(crop) => crop.intersect(&frame),
+            None => frame,
+        }
+    }
+
+    /// The display window's resolution — the full frame, whatever the
+    /// data window.
     pub fn get_dimensions(&self) -> (usize, usize) {
         (self.width, self.height)
     }
@@ -755,12 +1642,16 @@ pub fn ray_color(
         volumes,
         depth,
         strategy,
+        ColorSpace::default(),
         sampler,
         None,
         &mut no_training,
+        None,
+        &[],
         &mut scratch,
         &mut stats,
     )
+    .0
 }
 
 /// Choose the bounce direction and the pdf its contribution is divided by.
@@ -882,6 +1773,11 @@ struct TrainRec {
 /// and there is nothing to synchronise.
 pub(crate) struct PathScratch {
     records: Vec<VertexRec>,
+    /// Parallel to `records`, filled only when LPEs are evaluated.
+    events: Vec<VertexEvents>,
+    /// The weighted automaton states `gather_lpes` steps between vertices.
+    lpe_states: Vec<(u16, Vec3A)>,
+    lpe_next: Vec<(u16, Vec3A)>,
 }
 
 impl PathScratch {
@@ -889,10 +1785,133 @@ impl PathScratch {
     pub(crate) fn new(max_depth: usize) -> Self {
         Self {
             records: Vec::with_capacity(max_depth),
+            events: Vec::new(),
+            lpe_states: Vec::new(),
+            lpe_next: Vec::new(),
         }
     }
 }
 
+/// The light path events one [`VertexRec`] stands for — what the LPE
+/// automaton reads where the gather reads the record's values.
+#[derive(Clone, Copy)]
+struct VertexEvents {
+    /// Scattering events of the NEE sample, with their shares of `nee`.
+    nee: LobeEvents,
+    /// Scattering events of the bounce, with their shares of everything
+    /// the continuation brings back.
+    bounce: LobeEvents,
+    /// Emitter label of `emit_here`.
+    emit_here: Event,
+    /// Emitter label of `next_emit`.
+    next_emit: Event,
+}
+
+impl VertexEvents {
+    fn volume() -> Self {
+        VertexEvents {
+            nee: LobeEvents::single(Event::VOLUME),
+            bounce: LobeEvents::single(Event::VOLUME),
+            emit_here: Event::OBJECT,
+            next_emit: Event::OBJECT,
+        }
+    }
+}
+
+/// The emitter label of a surface hit: a light when the geometry belongs
+/// to one, an emissive object otherwise.
+fn emitter_event(lights: &LightList, hit: &WorldHit) -> Event {
+    if lights.find_by_geom(hit.geom_id).is_some() {
+        Event::LIGHT
+    } else {
+        Event::OBJECT
+    }
+}
+
+/// What reached the path from beyond its last vertex, split for the LPEs:
+/// volume emission along the final segment and the background, with the
+/// background's label (`L` when a light at infinity covered the
+/// direction, `B` for the built-in sky).
+struct LpeTerminal {
+    emit: Vec3A,
+    background: Vec3A,
+    event: Event,
+}
+
+/// Splits the path's estimate between the light path expressions.
+///
+/// The backward gather's nested sum is expanded forward, term by term. Each
+/// term is one light path — the segment emission arriving at a vertex, the
+/// vertex's own emission, its NEE sample, its bounce-hit emission, and at
+/// the end the terminal radiance — whose event string is `C`, the
+/// scattering events of the vertices before it, and the emitter. A vertex
+/// whose BSDF mixes lobes stands for several events at once, and its
+/// continuation is divided between them per channel by the same lobe shares
+/// the lighting AOVs use, so the automaton runs over a weighted set of
+/// states instead of one. An expression matching every path (`C.*`)
+/// therefore reproduces the beauty exactly.
+fn gather_lpes(
+    lpes: &[LightPathExpr],
+    records: &[VertexRec],
+    scratch_events: &[VertexEvents],
+    terminal: &LpeTerminal,
+    states: &mut Vec<(u16, Vec3A)>,
+    next: &mut Vec<(u16, Vec3A)>,
+    out: &mut [Vec3A],
+) {
+    for (lpe, out) in lpes.iter().zip(out.iter_mut()) {
+        states.clear();
+        states.push((lpe.camera_state(), Vec3A::ONE));
+        let accepts = |s: u16, e: Event| lpe.accepts(lpe.step(s, e));
+        // Throughput up to (not including) the current vertex's arriving
+        // segment.
+        let mut prefix = Vec3A::ONE;
+        let mut sum = Vec3A::ZERO;
+        for (rec, ev) in records.iter().zip(scratch_events) {
+            let at = prefix * rec.atten;
+            next.clear();
+            for &(s, w) in states.iter() {
+                if accepts(s, Event::OBJECT) {
+                    sum += prefix * w * rec.segment_emit;
+                }
+                if accepts(s, ev.emit_here) {
+                    sum += at * w * rec.emit_here;
+                }
+                for (e, share) in ev.nee.iter() {
+                    if accepts(lpe.step(s, e), Event::LIGHT) {
+                        sum += at * w * share * rec.nee;
+                    }
+                }
+                for (e, share) in ev.bounce.iter() {
+                    let s1 = lpe.step(s, e);
+                    if !lpe.is_live(s1) {
+                        continue;
+                    }
+                    let w1 = w * share;
+                    if accepts(s1, ev.next_emit) {
+                        sum += at * w1 * rec.factor * rec.next_emit * rec.next_emit_weight;
+                    }
+                    match next.iter_mut().find(|(t, _)| *t == s1) {
+                        Some((_, acc)) => *acc += w1,
+                        None => next.push((s1, w1)),
+                    }
+                }
+            }
+            prefix = at * rec.factor;
+            std::mem::swap(states, next);
+        }
+        for &(s, w) in states.iter() {
+            if accepts(s, Event::OBJECT) {
+                sum += prefix * w * terminal.emit;
+            }
+            if accepts(s, terminal.event) {
+                sum += prefix * w * terminal.background;
+            }
+        }
+        *out = sum;
+    }
+}
+
 /// The state of the previous surface bounce that the next vertex needs to
 /// MIS-weight its emission: the sampling context (`ray`/`rec`/`mat`/`dir`
 /// for the lazy NEE-capability check) and the bounce density.
@@ -925,8 +1944,9 @@ enum PrevVertex<'a> {
 /// from eval), so the bounce carries the emission whole — likewise at
 /// vertices where NEE is inactive, and for emissive geometry with no
 /// light-list entry, which NEE can never sample. Otherwise the competing
-/// density is the same strategy the NEE side uses: uniform 1-of-N pick
-/// times the hit light's area-sampling pdf.
+/// density is the same strategy the NEE side uses: the light tree's
+/// probability of choosing the hit light from `from`, times that light's
+/// own pdf for the point hit.
 fn bounce_emission_weight(
     prev: &PrevVertex,
     lights: &LightList,
@@ -944,8 +1964,8 @@ fn bounce_emission_weight(
     };
     match lights.find_by_geom(hit.geom_id) {
         Some(light) => {
-            let light_pdf =
-                (light.pdf_at_point(from, hit.rec.p) / lights.count() as f32).max(1e-6);
+            let pdf = light.pdf_at_hit(from, hit.rec.p, hit.prim_id);
+            let light_pdf = (pdf * lights.pmf(from, light)).max(1e-6);
             strategy.bounce_weight(bounce_pdf, light_pdf)
         }
         None => 1.0,
@@ -956,7 +1976,7 @@ fn bounce_emission_weight(
 /// scene along `direction` picks up.
 ///
 /// This is [`bounce_emission_weight`]'s mirror. A light with geometry is
-/// found by a bounce ray *hitting* it and weighted by `pdf_at_point`; a
+/// found by a bounce ray *hitting* it and weighted by `pdf_at_hit`; a
 /// light at infinity is found by a bounce ray *escaping* along a direction
 /// it covers, and weighted by the pdf reported from `Light::escaped`. Both
 /// must use the same density NEE used, or emission is double-counted.
@@ -984,11 +2004,9 @@ fn escaped_emission(
         // NEE — full weight, exactly as `prev = None` means elsewhere.
         None => None,
     };
-    let n_lights = lights.count() as f32;
-
     let mut radiance = Vec3A::ZERO;
     let mut covered = false;
-    for light in &lights.lights {
+    for light in lights.infinite() {
         let from = competing.map_or(Vec3A::ZERO, |(p, _)| p);
         let Some((emitted, pdf)) = light.escaped(from, direction) else {
             continue;
@@ -996,7 +2014,7 @@ fn escaped_emission(
         covered = true;
         let weight = match competing {
             Some((_, bounce_pdf)) if strategy.samples_lights() => {
-                let light_pdf = (pdf / n_lights).max(1e-6);
+                let light_pdf = (pdf * lights.pmf(from, light)).max(1e-6);
                 strategy.bounce_weight(bounce_pdf, light_pdf)
             }
             // No NEE ran for this vertex (or the strategy does not sample
@@ -1035,7 +2053,7 @@ fn shadow_transmittance(
 }
 
 /// Direct lighting at a volume-region scatter point. The exact mirror of
-/// the surface NEE block: same uniform 1-of-N light strategy, with the
+/// the surface NEE block: same light-tree choice of light, with the
 /// phase function (value == pdf for the HG mixture) in place of
 /// `brdf·cos`, and the same phase pdf as the competing bounce density that
 /// `bounce_emission_weight`'s `Phase` arm uses.
@@ -1055,10 +2073,9 @@ fn volume_nee(
         return Vec3A::ZERO;
     }
     let nee = vertex.new_domain(K_NEE).draw_sample_f32::<4>();
-    let Some(light) = lights.pick(nee[0]) else {
+    let Some((light, pick_pdf)) = lights.sample(p, nee[0]) else {
         return Vec3A::ZERO;
     };
-    let n_lights = lights.count() as f32;
     let Some(s) = light.sample_li(p, nee[1], nee[2]) else {
         return Vec3A::ZERO;
     };
@@ -1069,12 +2086,101 @@ fn volume_nee(
     if tr == Vec3A::ZERO {
         return Vec3A::ZERO;
     }
-    let light_pdf = (s.pdf / n_lights).max(1e-6);
+    let light_pdf = (s.pdf * pick_pdf).max(1e-6);
     let phase_val = phase.pdf(wi.dot(s.direction));
     let weight = strategy.light_weight(light_pdf, phase_val);
     s.radiance * phase_val * tr * weight / light_pdf
 }
 
+/// One light sample at a shadow-catcher hit, estimating the irradiance
+/// there twice: through the scene's occluders and media, and as if nothing
+/// stood in the way. Their ratio is the catcher's shadow. The BSDF plays no
+/// part — the plate already shows the real surface's response — so this is
+/// plain light sampling, `L·cos/pdf`, with no MIS against a bounce that
+/// never happens.
+fn catcher_light(
+    rec: &HitRecord,
+    world: &World,
+    volumes: &Volumes,
+    lights: &LightList,
+    strategy: SamplingStrategy,
+    vertex: PathSampler,
+    time: f32,
+    stats: &mut RayStats,
+) -> (Vec3A, Vec3A) {
+    if !strategy.samples_lights() {
+        return (Vec3A::ZERO, Vec3A::ZERO);
+    }
+    let nee = vertex.new_domain(K_NEE).draw_sample_f32::<4>();
+    let Some((light, pick_pdf)) = lights.sample(rec.p, nee[0]) else {
+        return (Vec3A::ZERO, Vec3A::ZERO);
+    };
+    let Some(s) = light.sample_li(rec.p, nee[1], nee[2]) else {
+        return (Vec3A::ZERO, Vec3A::ZERO);
+    };
+    // `rec.normal` faces the camera, so light from under the catcher does
+    // not count — the plate's ground is opaque.
+    let cosine = rec.normal.dot(s.direction).max(0.0);
+    if cosine == 0.0 {
+        return (Vec3A::ZERO, Vec3A::ZERO);
+    }
+    let light_pdf = (s.pdf * pick_pdf).max(1e-6);
+    let free = s.radiance * cosine / light_pdf;
+    let shadow_ray = Ray::new(rec.p, s.direction)
+        .with_time(time)
+        .with_mask(crate::ray::MASK_SHADOW);
+    let tr = shadow_transmittance(world, volumes, &shadow_ray, s.distance, vertex, stats);
+    (free * tr, free)
+}
+
+/// How the primary vertex divides the path's lighting between the AOV
+/// layers (see `aov.rs`): the per-channel lobe shares of its NEE sample and
+/// of its bounce, or the whole path to `volume` when the first event was a
+/// medium scatter. `Escaped` also covers a path that never got a vertex.
+enum PrimaryShares {
+    Escaped,
+    Surface { nee: LobeSplit, bounce: LobeSplit },
+    Volume,
+}
+
+/// Normalizes a lobe split into per-channel shares that sum to one. A
+/// channel the BSDF does not reflect at all files under diffuse — its
+/// contribution is zero anyway, so only the sum matters.
+fn lobe_shares(split: Option<LobeSplit>) -> LobeSplit {
+    let split = split.unwrap_or_default();
+    let total = split.total();
+    let share = |c: Vec3A| {
+        Vec3A::new(
+            if total.x > 0.0 { c.x / total.x } else { 0.0 },
+            if total.y > 0.0 { c.y / total.y } else { 0.0 },
+            if total.z > 0.0 { c.z / total.z } else { 0.0 },
+        )
+    };
+    let specular = share(split.specular);
+    let transmission = share(split.transmission);
+    LobeSplit {
+        diffuse: Vec3A::ONE - specular - transmission,
+        specular,
+        transmission,
+    }
+}
+
+/// Shares for a delta sample, which `eval` cannot see: the whole
+/// throughput goes to specular when the sample reflected and to
+/// transmission when it crossed the surface.
+fn delta_shares(reflected: bool) -> LobeSplit {
+    let (specular, transmission) = if reflected {
+        (Vec3A::ONE, Vec3A::ZERO)
+    } else {
+        (Vec3A::ZERO, Vec3A::ONE)
+    };
+    LobeSplit {
+        diffuse: Vec3A::ZERO,
+        specular,
+        transmission,
+    }
+}
+
 /// The integrator: an iterative path tracer in two passes. The forward walk
 /// traces one segment per bounce (each hit serves both as the previous
 /// vertex's potential light hit and as the next vertex — the old recursion
@@ -1083,6 +2189,18 @@ fn volume_nee(
 /// then folds the records into the radiance estimate and emits guiding
 /// training samples, which need the radiance arriving from the rest of the
 /// path and therefore cannot be computed forward.
+///
+/// With `aov` set, the walk also records the primary hit's features and
+/// splits the estimate into the lighting layers and the `lpes`; without it,
+/// none of that work is done.
+///
+/// `space` is the working colour space: the built-in sky is converted into
+/// it, and its luminance weights collapse radiance for guiding training and
+/// shadow-catcher alpha.
+///
+/// Returns the radiance and the camera ray's alpha: 1 when it ended on a
+/// surface or scattered in a volume, 0 when it escaped or met a holdout,
+/// and the shadow's density when it met a shadow catcher.
 fn trace_path(
     r: &Ray,
     world: &World,
@@ -1090,19 +2208,31 @@ fn trace_path(
     volumes: &Volumes,
     depth: i32,
     strategy: SamplingStrategy,
+    space: ColorSpace,
     sampler: PathSampler,
     guiding: Option<&GuidingContext>,
     train_out: &mut Vec<SampleData>,
+    mut aov: Option<&mut AovSample>,
+    lpes: &[LightPathExpr],
     scratch: &mut PathScratch,
     stats: &mut RayStats,
-) -> Vec3A {
+) -> (Vec3A, f32) {
     let training = guiding.is_some_and(|g| g.training);
+    let mut shares = PrimaryShares::Escaped;
+    let track_lpe = aov.is_some() && !lpes.is_empty();
+    let mut lpe_terminal = LpeTerminal {
+        emit: Vec3A::ZERO,
+        background: Vec3A::ZERO,
+        event: Event::BACKGROUND,
+    };
     // The bounce subtree; each vertex derives its own domain off this by depth.
     let path = sampler.new_domain(K_PATH);
     // Borrowed, not allocated — see `PathScratch`. Capacity carries over from
     // the previous sample, so after the first walk this is free.
     let records = &mut scratch.records;
     records.clear();
+    let events = &mut scratch.events;
+    events.clear();
     let mut ray = r.clone();
     let mut remaining = depth;
     // Set after surface bounces and volume-region phase scatters; `None`
@@ -1114,6 +2244,7 @@ fn trace_path(
     let mut beta = Vec3A::ONE;
     // Radiance entering the path from beyond the last vertex.
     let mut terminal = Vec3A::ZERO;
+    let mut alpha = 0.0;
 
     loop {
         // This vertex's domain: `records.len()` is the vertex index (nothing
@@ -1142,6 +2273,9 @@ fn trace_path(
                         let last = records.last_mut().expect("prev implies a record");
                         last.next_emit = emitted;
                         last.next_emit_weight = bounce_emission_weight(p, lights, &hit, strategy);
+                        if let Some(e) = events.last_mut() {
+                            e.next_emit = emitter_event(lights, &hit);
+                        }
                     }
                 }
             }
@@ -1149,7 +2283,10 @@ fn trace_path(
         }
 
         stats.closest_hit += 1;
-        let hit_opt = world.intersect(&ray, 0.001, f32::INFINITY);
+        let (t_min, t_max) = ray
+            .clip()
+            .map_or((0.001, f32::INFINITY), |(near, far)| (near.max(0.001), far));
+        let hit_opt = world.intersect(&ray, t_min, t_max);
         let t_surf = hit_opt.as_ref().map_or(f32::INFINITY, |h| h.rec.t);
 
         // Free-flight candidate in the carried homogeneous medium
@@ -1202,6 +2339,13 @@ fn trace_path(
                 // because the HG value and pdf cancel exactly. Volume
                 // vertices are not trained on — the field guides surface
                 // bounces only.
+                if records.is_empty() {
+                    shares = PrimaryShares::Volume;
+                    alpha = 1.0;
+                    if let Some(a) = aov.as_deref_mut() {
+                        a.scatter_depth = Some((p - ray.origin()).length());
+                    }
+                }
                 let mut vrec = VertexRec {
                     atten: weight,
                     segment_emit: emitted,
@@ -1212,6 +2356,9 @@ fn trace_path(
                     next_emit_weight: 1.0,
                     train: None,
                 };
+                if track_lpe {
+                    events.push(VertexEvents::volume());
+                }
                 beta *= weight;
                 let mut survived = true;
                 if records.len() >= RR_START_BOUNCE {
@@ -1273,6 +2420,13 @@ fn trace_path(
             // top double-counted extinction.
             let e = (Vec3A::splat(sigma_bar) - (medium.sigma_a + medium.sigma_s)) * t_med;
             let factor = medium.sigma_s / sigma_bar * Vec3A::new(e.x.exp(), e.y.exp(), e.z.exp());
+            if records.is_empty() {
+                shares = PrimaryShares::Volume;
+                alpha = 1.0;
+                if let Some(a) = aov.as_deref_mut() {
+                    a.scatter_depth = Some((pos - ray.origin()).length());
+                }
+            }
             // Subsurface vertices run no NEE (their shadow rays are
             // blocked by the enclosing surface), so `prev = None` keeps
             // the next hit's emission at full weight — the pairing that
@@ -1287,6 +2441,9 @@ fn trace_path(
                 next_emit_weight: 1.0,
                 train: None,
             };
+            if track_lpe {
+                events.push(VertexEvents::volume());
+            }
             beta *= vol_tr * factor;
             let mut survived = true;
             if records.len() >= RR_START_BOUNCE {
@@ -1332,16 +2489,86 @@ fn trace_path(
                 // built-in sky gradient so scenes without an environment
                 // light look as they always have.
                 let t = 0.5 * (unit_direction.y + 1.0);
-                background +=
-                    (1.0 - t) * Vec3A::new(1.0, 1.0, 1.0) + t * Vec3A::new(0.5, 0.7, 1.0);
+                background += space.convert_rec709(
+                    (1.0 - t) * Vec3A::new(1.0, 1.0, 1.0) + t * Vec3A::new(0.5, 0.7, 1.0),
+                );
             }
             // Segment emission is already weighted; the background pays the
             // volume transmittance of the final segment.
             terminal = vol_emit + vol_tr * background;
+            lpe_terminal = LpeTerminal {
+                emit: vol_emit,
+                background: vol_tr * background,
+                event: if covered {
+                    Event::LIGHT
+                } else {
+                    Event::BACKGROUND
+                },
+            };
+            if records.is_empty()
+                && let Some(a) = aov.as_deref_mut()
+            {
+                a.emission = vol_tr * background;
+                a.volume = vol_emit;
+            }
             break;
         };
+        if records.is_empty() {
+            // A holdout is a hole in the image: the camera sees neither it
+            // nor anything behind it. Only the camera — every later segment
+            // treats it as the ordinary surface it is, which is how it keeps
+            // its shadows and reflections. Media in front of it still count.
+            if world.is_holdout(hit.geom_id) {
+                terminal = vol_emit;
+                lpe_terminal.emit = vol_emit;
+                if let Some(a) = aov.as_deref_mut() {
+                    a.volume = vol_emit;
+                }
+                break;
+            }
+            // A shadow catcher is a holdout that keeps its shadows: the
+            // camera sees through it, and alpha carries how much of the
+            // light reaching it the scene blocks, so black at that alpha
+            // composited over the plate darkens it by exactly the shadow.
+            if world.is_shadow_catcher(hit.geom_id) {
+                let (lit, free) = catcher_light(
+                    &hit.rec,
+                    world,
+                    volumes,
+                    lights,
+                    strategy,
+                    v,
+                    ray.time(),
+                    stats,
+                );
+                let free_lum = space.luminance(free);
+                if free_lum > 0.0 {
+                    alpha = (1.0 - space.luminance(lit) / free_lum).clamp(0.0, 1.0);
+                }
+                terminal = vol_emit;
+                lpe_terminal.emit = vol_emit;
+                if let Some(a) = aov.as_deref_mut() {
+                    a.volume = vol_emit;
+                    a.catcher = Some((lit, free));
+                }
+                break;
+            }
+            alpha = 1.0;
+        }
         let rec: HitRecord = hit.rec;
         let mat = hit.mat;
+        // Lobe shares are needed at the primary vertex for the lighting
+        // layers, and at every vertex for the LPEs.
+        let split_lobes = records.is_empty() && aov.is_some();
+        let want_shares = split_lobes || track_lpe;
+        let mut nee_shares = LobeSplit::default();
+        if split_lobes && let Some(a) = aov.as_deref_mut() {
+            a.geom_id = Some(hit.geom_id);
+            a.albedo = mat.albedo(&rec);
+            a.normal = rec.normal;
+            a.position = rec.p;
+            a.depth = Some((rec.p - ray.origin()).length());
+        }
 
         // Attenuation across the arriving segment: volume-region
         // transmittance times the carried medium's. For a *scattering*
@@ -1375,6 +2602,9 @@ fn trace_path(
                     let last = records.last_mut().expect("prev implies a record");
                     last.next_emit = atten * emitted;
                     last.next_emit_weight = bounce_emission_weight(p, lights, &hit, strategy);
+                    if let Some(e) = events.last_mut() {
+                        e.next_emit = emitter_event(lights, &hit);
+                    }
                 }
             }
             None => emit_here = emitted,
@@ -1386,9 +2616,9 @@ fn trace_path(
         let guiding_here = if prev.is_some() { guiding } else { None };
 
         // === 1. Direct Lighting via Light Sampling ===
-        // The light strategy is "pick one light uniformly, then sample a
-        // point on it by area", so its solid-angle density is
-        // `light.pdf / n_lights`. `bounce_emission_weight` evaluates the
+        // The light strategy is "choose a light through the light tree,
+        // then sample a point on it by area", so its solid-angle density is
+        // `light.pdf · pmf`. `bounce_emission_weight` evaluates the
         // same expression for a bounce-hit light — both MIS weights must
         // describe the same strategy or emission is double-counted.
         let mut nee = Vec3A::ZERO;
@@ -1396,13 +2626,12 @@ fn trace_path(
         // `sample_li` returns `None` when the light cannot be reached from
         // this point at all — below a dome's horizon, or a degenerate
         // coincident point.
-        if let Some(light) = strategy
+        if let Some((light, pick_pdf)) = strategy
             .samples_lights()
-            .then(|| lights.pick(nee_s[0]))
+            .then(|| lights.sample(rec.p, nee_s[0]))
             .flatten()
             && let Some(ls) = light.sample_li(rec.p, nee_s[1], nee_s[2])
         {
-            let n_lights = lights.count() as f32;
             let light_dir_unit = ls.direction;
 
             let shadow_ray = Ray::new(rec.p, light_dir_unit)
@@ -1416,7 +2645,7 @@ fn trace_path(
                 // through a continuous transmission lobe (opaque materials
                 // evaluate to zero there anyway).
                 let cosine = rec.normal.dot(light_dir_unit).abs();
-                let light_pdf = (ls.pdf / n_lights).max(1e-6);
+                let light_pdf = (ls.pdf * pick_pdf).max(1e-6);
 
                 // Evaluate the BSDF toward the light direction. Delta and
                 // transmissive materials return None — they cannot see a
@@ -1440,6 +2669,9 @@ fn trace_path(
                     let weight = strategy.light_weight(light_pdf, bounce_pdf);
                     nee += ls.radiance * brdf_value * cosine * shadow_tr * weight
                         / light_pdf;
+                    if want_shares {
+                        nee_shares = lobe_shares(mat.eval_lobes(&ray, &rec, light_dir_unit));
+                    }
                 }
             }
         }
@@ -1454,6 +2686,16 @@ fn trace_path(
             next_emit_weight: 1.0,
             train: None,
         };
+        let mut vertex_events = VertexEvents {
+            nee: LobeEvents::surface(nee_shares),
+            bounce: LobeEvents::NONE,
+            emit_here: if track_lpe {
+                emitter_event(lights, &hit)
+            } else {
+                Event::OBJECT
+            },
+            next_emit: Event::OBJECT,
+        };
 
         // === 2. Indirect Lighting via BSDF (or guided) Sampling ===
         if let Some(sample) = sample_bounce_direction(&ray, &rec, mat, guiding_here, v) {
@@ -1489,6 +2731,26 @@ fn trace_path(
                 }
             }
 
+            if want_shares {
+                let reflected = dir.dot(rec.normal) * ray.direction().dot(rec.normal) < 0.0;
+                let bounce = if sample.delta {
+                    delta_shares(reflected)
+                } else {
+                    lobe_shares(mat.eval_lobes(&ray, &rec, dir))
+                };
+                if split_lobes {
+                    shares = PrimaryShares::Surface {
+                        nee: nee_shares,
+                        bounce,
+                    };
+                }
+                vertex_events.bounce = if sample.delta {
+                    LobeEvents::singular(reflected)
+                } else {
+                    LobeEvents::surface(bounce)
+                };
+            }
+
             if survived {
                 // Training samples cover continuous surface bounces only —
                 // the guide can never produce a delta direction. The
@@ -1511,6 +2773,9 @@ fn trace_path(
                 }));
                 stats.vertices += 1;
                 records.push(vrec);
+                if track_lpe {
+                    events.push(vertex_events);
+                }
                 // Materials build the scattered ray without path context;
                 // stamp the path's shutter time and the indirect category.
                 ray = sample
@@ -1524,8 +2789,17 @@ fn trace_path(
 
         // Absorbed or roulette-killed: this vertex's own gathers stand
         // (factor stays zero), the path ends here.
+        if split_lobes && matches!(shares, PrimaryShares::Escaped) {
+            shares = PrimaryShares::Surface {
+                nee: nee_shares,
+                bounce: LobeSplit::default(),
+            };
+        }
         stats.vertices += 1;
         records.push(vrec);
+        if track_lpe {
+            events.push(vertex_events);
+        }
         break;
     }
 
@@ -1535,7 +2809,13 @@ fn trace_path(
     // (next vertex's emission suppressed — its MIS-weighted share enters
     // separately through `next_emit`).
     let mut radiance = terminal;
-    for vrec in records.iter().rev() {
+    // What arrives at the primary vertex from beyond its first bounce — the
+    // indirect share of the lighting AOVs.
+    let mut beyond_primary = terminal;
+    for (k, vrec) in records.iter().enumerate().rev() {
+        if k == 0 {
+            beyond_primary = radiance;
+        }
         if let Some(t) = &vrec.train {
             // The full incident radiance (reflected + the raw hit emission),
             // weighted by cos² to match this tracer's estimator, which
@@ -1544,7 +2824,7 @@ fn trace_path(
             train_out.push(SampleData {
                 pos: t.pos,
                 dir: t.dir,
-                radiance: (luminance(radiance + vrec.next_emit) * t.cos * t.cos)
+                radiance: (space.luminance(radiance + vrec.next_emit) * t.cos * t.cos)
                     .min(TRAIN_RADIANCE_CLAMP),
             });
         }
@@ -1554,7 +2834,36 @@ fn trace_path(
                     + vrec.nee
                     + vrec.factor * (vrec.next_emit * vrec.next_emit_weight + radiance));
     }
-    radiance
+    if track_lpe && let Some(a) = aov.as_deref_mut() {
+        gather_lpes(
+            lpes,
+            records,
+            events,
+            &lpe_terminal,
+            &mut scratch.lpe_states,
+            &mut scratch.lpe_next,
+            &mut a.lpe,
+        );
+    }
+    if let (Some(a), Some(v0)) = (aov, records.first()) {
+        match shares {
+            PrimaryShares::Surface { nee, bounce } => {
+                let nee_direct = v0.atten * v0.nee;
+                let bounce_direct = v0.atten * v0.factor * v0.next_emit * v0.next_emit_weight;
+                let indirect = v0.atten * v0.factor * beyond_primary;
+                a.emission = v0.atten * v0.emit_here;
+                a.volume = v0.segment_emit;
+                a.diffuse_direct = nee_direct * nee.diffuse + bounce_direct * bounce.diffuse;
+                a.diffuse_indirect = indirect * bounce.diffuse;
+                a.specular = nee_direct * nee.specular + (bounce_direct + indirect) * bounce.specular;
+                a.transmission =
+                    nee_direct * nee.transmission + (bounce_direct + indirect) * bounce.transmission;
+            }
+            PrimaryShares::Volume => a.volume = radiance,
+            PrimaryShares::Escaped => {}
+        }
+    }
+    (radiance, alpha)
 }
 
 /// Per-pixel luminance of the inverse-variance blend of `passes` — the
@@ -1562,7 +2871,12 @@ fn trace_path(
 /// Un-weightable passes (non-finite or zero variance) contribute nothing;
 /// if no pass is weightable the result is black and the floor in
 /// `mean_relative_error` takes over.
-fn blend_luminance(passes: &[(Buffer, f64)], width: usize, height: usize) -> Vec<f64> {
+fn blend_luminance(
+    passes: &[(Buffer, f64)],
+    width: usize,
+    height: usize,
+    space: ColorSpace,
+) -> Vec<f64> {
     let weights: Vec<f64> = passes
         .iter()
         .map(|(_, var)| {
@@ -1581,12 +2895,19 @@ fn blend_luminance(passes: &[(Buffer, f64)], width: usize, height: usize) -> Vec
             for ((pass, _), w) in passes.iter().zip(&weights) {
                 c += pass.get_pixel(x, y) * (*w / total) as f32;
             }
-            out[y * width + x] = luminance(c) as f64;
+            out[y * width + x] = space.luminance(c) as f64;
         }
     }
     out
 }
 
+/// Mean relative standard error of the pixel means (see
+/// [`PixelAccum::relative_error`]) — the image-wide noise a progressive
+/// render's noise target is compared with.
+fn image_noise(pixels: &[PixelAccum]) -> f64 {
+    pixels.iter().map(PixelAccum::relative_error).sum::<f64>() / pixels.len().max(1) as f64
+}
+
 /// Mean relative squared error of a pass (Rousselle et al. 2011): per-pixel
 /// variance over the squared luminance of a reference image, floored at
 /// 1e-4 so directly visible light sources don't dominate. The reference
@@ -1603,7 +2924,7 @@ fn mean_relative_error(var_map: &[f64], ref_lum: &[f64]) -> f64 {
         / n
 }
 
-struct Tile {
+pub(crate) struct Tile {
     pub x: usize,
     pub y: usize,
     pub width: usize,
@@ -1612,7 +2933,14 @@ struct Tile {
 
 #[cfg(test)]
 mod tests {
-    use super::SamplingStrategy;
+    use super::{
+        Aov, AovSet, CancelToken, Duration, LpeTerminal, RenderSettings, Renderer,
+        SamplingStrategy, VertexEvents, VertexRec, gather_lpes,
+    };
+    use crate::lpe::{Event, LightPathExpr, LobeEvents};
+    use crate::material::LobeSplit;
+    use crate::world::ball_on_floor;
+    use glam::Vec3A;
 
     /// The invariant every strategy must keep: for a light both strategies
     /// can reach, the NEE weight and the bounce-emission weight are a
@@ -1666,9 +2994,152 @@ mod tests {
         let power = SamplingStrategy::PowerMis.light_weight(a, b);
         assert!(power > balance, "power {power} <= balance {balance}");
     }
+
+    /// `C.*` matches every light path, so the LPE expansion must reproduce
+    /// the backward gather's estimate exactly; a narrower expression picks
+    /// out only its own terms, at its lobe's share.
+    #[test]
+    fn lpe_expansion_reproduces_the_gather() {
+        let rec = |atten, emit_here, nee, factor, next_emit| VertexRec {
+            atten,
+            segment_emit: Vec3A::splat(0.01),
+            emit_here,
+            nee,
+            factor,
+            next_emit,
+            next_emit_weight: 0.5,
+            train: None,
+        };
+        let records = [
+            rec(
+                Vec3A::splat(0.9),
+                Vec3A::splat(0.2),
+                Vec3A::new(0.3, 0.4, 0.5),
+                Vec3A::splat(0.7),
+                Vec3A::splat(1.5),
+            ),
+            rec(
+                Vec3A::ONE,
+                Vec3A::ZERO,
+                Vec3A::splat(0.25),
+                Vec3A::splat(0.6),
+                Vec3A::ZERO,
+            ),
+        ];
+        let split = LobeSplit {
+            diffuse: Vec3A::splat(0.75),
+            specular: Vec3A::splat(0.25),
+            transmission: Vec3A::ZERO,
+        };
+        let ev = VertexEvents {
+            nee: LobeEvents::surface(split),
+            bounce: LobeEvents::surface(split),
+            emit_here: Event::OBJECT,
+            next_emit: Event::LIGHT,
+        };
+        let terminal = LpeTerminal {
+            emit: Vec3A::splat(0.05),
+            background: Vec3A::splat(2.0),
+            event: Event::BACKGROUND,
+        };
+
+        let mut expected = terminal.emit + terminal.background;
+        for r in records.iter().rev() {
+            expected = r.segment_emit
+                + r.atten
+                    * (r.emit_here
+                        + r.nee
+                        + r.factor * (r.next_emit * r.next_emit_weight + expected));
+        }
+
+        let lpes = [
+            LightPathExpr::new("all", "C.*").unwrap(),
+            LightPathExpr::new("dd", "C<RD>L").unwrap(),
+        ];
+        let mut out = [Vec3A::ZERO; 2];
+        gather_lpes(
+            &lpes,
+            &records,
+            &[ev, ev],
+            &terminal,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut out,
+        );
+        assert!((out[0] - expected).abs().max_element() < 1e-5, "{} vs {expected}", out[0]);
+
+        let r0 = &records[0];
+        let direct_diffuse =
+            r0.atten * (r0.nee * 0.75 + r0.factor * r0.next_emit * r0.next_emit_weight * 0.75);
+        assert!((out[1] - direct_diffuse).abs().max_element() < 1e-5);
+    }
+
+    fn renderer(settings: impl FnOnce(RenderSettings) -> RenderSettings) -> Renderer {
+        let (camera, world, lights, base) = ball_on_floor();
+        Renderer::new(camera, world, lights, settings(base))
+    }
+
+    /// Progressive passes grow their spp and end on the straight render's
+    /// image; a noise target stops them early, and so does a cancelled token —
+    /// after the first pass, whose two samples per pixel always complete.
+    #[test]
+    fn progressive_render_passes_and_stops() {
+        let spp = |s: RenderSettings| s.with_samples_per_pixel(24);
+        let passes = |renderer: Renderer| {
+            let mut seen = Vec::new();
+            let (buffer, _) =
+                renderer.render_progressive(false, None, &mut |_, spp| seen.push(spp));
+            (buffer, seen)
+        };
+
+        // Pixels adaptive sampling stops early drop out of later passes, so
+        // only the ends of the schedule are fixed.
+        let (progressive, seen) = passes(renderer(spp));
+        assert_eq!(seen.first(), Some(&2));
+        assert_eq!(seen.last(), Some(&24));
+        assert!(seen.windows(2).all(|w| w[1] > w[0]));
+        let straight = renderer(spp).render();
+        for y in 0..10 {
+            for x in 0..12 {
+                assert_eq!(progressive.get_pixel(x, y), straight.get_pixel(x, y));
+            }
+        }
+
+        let (_, seen) = passes(renderer(spp).with_noise_target(1e6));
+        assert_eq!(seen, [2]);
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let (stopped, seen) = passes(renderer(spp).with_cancel(cancel));
+        assert_eq!(seen, [2]);
+        assert!(stopped.get_pixel(6, 5).is_finite());
+    }
+
+    /// A time limit cuts a single pass short without losing its layers:
+    /// every pixel still gets its two samples, and the denoiser its AOVs.
+    #[test]
+    fn time_limited_pass_keeps_every_layer() {
+        let denoised = |s: RenderSettings| {
+            s.with_samples_per_pixel(64)
+                .with_aovs(AovSet::empty().with(Aov::SampleCount))
+                .with_denoise(true)
+        };
+        let buffer = renderer(denoised).with_time_limit(Duration::ZERO).render();
+        assert!(buffer.is_denoised());
+        for y in 0..10 {
+            for x in 0..12 {
+                assert!(buffer.get_pixel(x, y).is_finite(), "pixel ({x}, {y})");
+                assert_eq!(buffer.get_aov(x, y, Aov::SampleCount).x, 2.0);
+            }
+        }
+    }
 }
 
-fn generate_tiles(image_width: usize, image_height: usize, tile_size: usize) -> Vec<Tile> {
+pub(crate) fn generate_tiles(
+    image_width: usize,
+    image_height: usize,
+    tile_size: usize,
+) -> Vec<Tile> {
     let mut tiles = Vec::new();
     for y in (0..image_height).step_by(tile_size) {
         for x in (0..image_width).step_by(tile_size) {
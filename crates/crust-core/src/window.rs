@@ -0,0 +1,89 @@
+//! Pixel windows: which part of the frame gets traced.
+//!
+//! The *display window* is the full frame the camera frames — `width ×
+//! height` pixels. The *data window* is the rectangle actually rendered: all
+//! of it by default, a sub-rectangle when re-rendering a region (`--crop`,
+//! `crust:dataWindow`), or more than all of it when overscan margins extend
+//! the frame past its edges. Only the data window's pixels are traced and
+//! stored; the camera, and therefore every pixel's ray, stays that of the
+//! full frame, so a cropped pixel is the same pixel a full render makes.
+
+/// A pixel rectangle in display-window coordinates, the OpenEXR way: origin
+/// at the frame's top-left pixel, y pointing down. Unlike EXR's inclusive
+/// `box2i`, `max` is exclusive. Corners may lie outside the display window
+/// (negative, or past the resolution) — that is what overscan is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelWindow {
+    pub x_min: i32,
+    pub y_min: i32,
+    pub x_max: i32,
+    pub y_max: i32,
+}
+
+impl PixelWindow {
+    pub fn new(x_min: i32, y_min: i32, x_max: i32, y_max: i32) -> Self {
+        PixelWindow {
+            x_min,
+            y_min,
+            x_max,
+            y_max,
+        }
+    }
+
+    /// The whole `width × height` display window.
+    pub fn full(width: usize, height: usize) -> Self {
+        PixelWindow::new(0, 0, width as i32, height as i32)
+    }
+
+    pub fn width(&self) -> usize {
+        (self.x_max - self.x_min).max(0) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.y_max - self.y_min).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width() == 0 || self.height() == 0
+    }
+
+    /// This window with `margin` pixels added on every side.
+    pub fn grow(&self, margin: u32) -> Self {
+        let m = margin as i32;
+        PixelWindow::new(
+            self.x_min - m,
+            self.y_min - m,
+            self.x_max + m,
+            self.y_max + m,
+        )
+    }
+
+    /// The overlap of two windows; empty if they do not meet.
+    pub fn intersect(&self, other: &PixelWindow) -> Self {
+        PixelWindow::new(
+            self.x_min.max(other.x_min),
+            self.y_min.max(other.y_min),
+            self.x_max.min(other.x_max),
+            self.y_max.min(other.y_max),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_grow_and_clip() {
+        let frame = PixelWindow::full(64, 32);
+        assert_eq!((frame.width(), frame.height()), (64, 32));
+        let overscanned = frame.grow(8);
+        assert_eq!(overscanned, PixelWindow::new(-8, -8, 72, 40));
+        let crop = PixelWindow::new(-20, 10, 20, 50);
+        assert_eq!(
+            crop.intersect(&overscanned),
+            PixelWindow::new(-8, 10, 20, 40)
+        );
+        assert!(crop.intersect(&PixelWindow::new(30, 0, 40, 5)).is_empty());
+    }
+}
@@ -194,3 +194,26 @@ pub fn get_settings() -> (Camera, RenderSettings) {
 
     (cam, render_settings)
 }
+
+/// A diffuse ball on a floor under the built-in sky, 12x10 pixels at 8 spp
+/// with adaptive sampling on: small enough for a unit test to render a
+/// handful of times.
+#[cfg(test)]
+pub(crate) fn ball_on_floor() -> (Camera, World, LightList, RenderSettings) {
+    let mut world = WorldBuilder::new();
+    let floor = Arc::new(OpenPBR::diffuse(Vec3A::splat(0.5)));
+    add_sphere(&mut world, Vec3A::new(0.0, -1001.0, 0.0), 1000.0, floor);
+    let ball = Arc::new(OpenPBR::diffuse(Vec3A::new(0.8, 0.3, 0.3)));
+    add_sphere(&mut world, Vec3A::ZERO, 1.0, ball);
+    let camera = Camera::new(
+        Vec3A::new(0.0, 0.0, 5.0),
+        Vec3A::ZERO,
+        Vec3A::Y,
+        40.0,
+        1.2,
+        0.0,
+        5.0,
+    );
+    let settings = RenderSettings::new(8, 4, 12, 10, 8, 0.05, 0);
+    (camera, world.commit(), LightList::new(), settings)
+}
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use crust_core::Scene;
+use crust_core::{Light, Scene};
 use openusd::schemas::shade::{Material as UsdMaterial, MaterialBindingAPI};
 use openusd::sdf;
 use openusd::usd::{PrimPredicate, Stage};
@@ -11,6 +11,45 @@ fn sample(name: &str) -> PathBuf {
     root.parent().unwrap().parent().unwrap().join("samples").join(name)
 }
 
+/// A test's scratch directory: named for the test and this process, so
+/// concurrent runs never share files, and removed when dropped — even when
+/// an assert fails before the end of the test.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("crust_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        Self(dir)
+    }
+
+    /// Where `file` lives inside the directory.
+    fn path(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+
+    /// Writes `contents` to `file` inside the directory and returns its path.
+    fn write(&self, file: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.path(file);
+        std::fs::write(&path, contents).expect("write probe file");
+        path
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+/// Writes `usda` to `<name>.usda` in a fresh [`Scratch`] directory. Keep
+/// the guard alive for as long as the stage is read.
+fn write_stage(name: &str, usda: &str) -> (Scratch, PathBuf) {
+    let scratch = Scratch::new(name);
+    let path = scratch.write(&format!("{name}.usda"), usda);
+    (scratch, path)
+}
+
 #[test]
 fn loads_cornellbox_usda() {
     let scene = Scene::from_usd(&sample("cornellbox.usda"))
@@ -406,6 +445,113 @@ fn light_geometry_camera_visibility() {
     }
 }
 
+/// Disk and cylinder lights import as emissive geometry paired with an
+/// area light. The pendant disk keeps the default camera visibility and
+/// the tube opts in; its black end caps close it without emitting.
+#[test]
+fn loads_practicals_usda() {
+    let scene = Scene::from_usd(&sample("practicals.usda"))
+        .expect("failed to open practicals.usda");
+
+    // Ball + floor + disk fan + tube side + tube caps; two lights.
+    assert_eq!(scene.world.count(), 5, "expected 5 geometries, got {}", scene.world.count());
+    assert_eq!(scene.lights.count(), 2, "expected 2 lights, got {}", scene.lights.count());
+
+    let hit = |origin: crust_core::Vec3A, direction: crust_core::Vec3A, mask: u32| {
+        scene
+            .world
+            .intersect(
+                &crust_core::Ray::new(origin, direction).with_mask(mask),
+                0.001,
+                f32::INFINITY,
+            )
+            .expect("the floor backstops every ray")
+    };
+    let down = |x: f32| crust_core::Vec3A::new(x, 5.0, 0.01);
+
+    // The disk hangs at y = 3: hidden from the camera, there for shadows.
+    let camera = hit(down(-2.0), -crust_core::Vec3A::Y, crust_core::MASK_CAMERA);
+    assert!((camera.rec.t - 5.0).abs() < 1e-3);
+    let shadow = hit(down(-2.0), -crust_core::Vec3A::Y, crust_core::MASK_SHADOW);
+    assert!((shadow.rec.t - 2.0).abs() < 1e-3);
+    assert!(scene.lights.find_by_geom(shadow.geom_id).is_some());
+
+    // The tube's top is at y = 0.75, and the camera sees it.
+    let side = hit(down(2.0), -crust_core::Vec3A::Y, crust_core::MASK_CAMERA);
+    assert!(
+        (side.rec.t - 4.25).abs() < 1e-3,
+        "tube side at t = {}",
+        side.rec.t
+    );
+    assert!(scene.lights.find_by_geom(side.geom_id).is_some());
+
+    // Down its axis, a ray meets the cap at x = 3: geometry, but no light.
+    let along = crust_core::Vec3A::new(5.0, 0.5, 0.01);
+    let cap = hit(along, -crust_core::Vec3A::X, crust_core::MASK_CAMERA);
+    assert!(
+        (cap.rec.t - 2.0).abs() < 1e-3,
+        "tube cap at t = {}",
+        cap.rec.t
+    );
+    assert!(scene.lights.find_by_geom(cap.geom_id).is_none());
+}
+
+/// Meshes whose material emits become lights of their own: the baked strip
+/// and both placements of the instanced screen, but not the floor.
+#[test]
+fn loads_neon_usda() {
+    let scene = Scene::from_usd(&sample("neon.usda")).expect("failed to open neon.usda");
+
+    // Ball + floor + strip + two screens; no UsdLux prims, three lights.
+    assert_eq!(scene.world.count(), 5, "expected 5 geometries, got {}", scene.world.count());
+    assert_eq!(scene.lights.count(), 3, "expected 3 lights, got {}", scene.lights.count());
+
+    let hit = |origin: crust_core::Vec3A, direction: crust_core::Vec3A| {
+        scene
+            .world
+            .intersect(
+                &crust_core::Ray::new(origin, direction),
+                0.001,
+                f32::INFINITY,
+            )
+            .expect("the floor backstops every ray")
+    };
+    let down = |x: f32| hit(crust_core::Vec3A::new(x, 5.0, 0.01), -crust_core::Vec3A::Y);
+
+    let strip = down(1.5);
+    assert!(
+        (strip.rec.t - 2.0).abs() < 1e-3,
+        "strip at t = {}",
+        strip.rec.t
+    );
+    assert!(scene.lights.find_by_geom(strip.geom_id).is_some());
+
+    let floor = down(5.0);
+    assert!(
+        (floor.rec.t - 5.0).abs() < 1e-3,
+        "floor at t = {}",
+        floor.rec.t
+    );
+    assert!(scene.lights.find_by_geom(floor.geom_id).is_none());
+
+    // Beside the ball, looking out at either screen.
+    for side in [-1.0f32, 1.0] {
+        let from = crust_core::Vec3A::new(2.0 * side, 1.0, 0.01);
+        let screen = hit(from, crust_core::Vec3A::new(side, 0.0, 0.0));
+        assert!(
+            (screen.rec.t - 1.0).abs() < 1e-3,
+            "screen at t = {}",
+            screen.rec.t
+        );
+        let light = scene
+            .lights
+            .find_by_geom(screen.geom_id)
+            .expect("each screen placement is a light");
+        // The bounce side of MIS finds the triangle it hit.
+        assert!(light.pdf_at_hit(from, screen.rec.p, screen.prim_id) > 0.0);
+    }
+}
+
 #[test]
 fn loads_motionblur_usda() {
     let scene =
@@ -1043,3 +1189,843 @@ def Xform "World"
 
     std::fs::remove_dir_all(&dir).ok();
 }
+
+/// A holdout sphere filling the middle of an 8x8 frame, in front of a
+/// backdrop that fills the rest; `camera` is spliced into the camera prim.
+fn holdout_stage(camera: &str) -> String {
+    format!(
+        r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {{
+    def Camera "Cam" {{
+        float focalLength = 50
+        float horizontalAperture = 20
+        float verticalAperture = 20
+        double3 xformOp:translate = (0, 0, 5)
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+        {camera}
+    }}
+    def Sphere "Matte" {{
+        double radius = 0.5
+        custom bool crust:holdout = 1
+    }}
+    def Mesh "Backdrop" {{
+        int[] faceVertexCounts = [4]
+        int[] faceVertexIndices = [0, 1, 2, 3]
+        point3f[] points = [(-50, -50, -3), (50, -50, -3), (50, 50, -3), (-50, 50, -3)]
+    }}
+    def RenderSettings "settings" {{
+        int2 resolution = (8, 8)
+        int crust:samplesPerPixel = 4
+        int crust:maxDepth = 2
+        token crust:pixelFilter = "box"
+    }}
+}}
+"#
+    )
+}
+
+/// `crust:holdout` cuts a hole in the image — black, alpha zero — where the
+/// camera sees the prim, while the backdrop around it stays opaque. The
+/// geometry keeps its prim path, which is what its ID mattes key on.
+#[test]
+fn holdout_punches_a_hole_in_alpha() {
+    let (_scratch, path) = write_stage("holdout", &holdout_stage(""));
+
+    let scene = Scene::from_usd(&path).expect("holdout stage loads");
+    let matte = (0..scene.world.count() as u32)
+        .find(|&g| scene.world.object_name(g) == Some("/W/Matte"))
+        .expect("the sphere is named after its prim");
+    assert!(scene.world.is_holdout(matte));
+
+    let settings = scene.settings;
+    let buffer = crust_core::Renderer::new(scene.camera, scene.world, scene.lights, settings)
+        .render();
+    // The sphere covers the middle of the frame; the corners see the
+    // backdrop.
+    assert_eq!(buffer.get_alpha(4, 4), 0.0);
+    assert_eq!(buffer.get_pixel(4, 4), crust_core::Vec3A::ZERO);
+    assert_eq!(buffer.get_alpha(0, 0), 1.0);
+}
+
+/// A hidden blocker shadows the left half of a catcher plane under a
+/// straight-down sun: the shadow side goes opaque black with `shadow` at 0,
+/// the lit side stays clear with `shadow` at 1.
+#[test]
+fn shadow_catcher_keeps_only_its_shadows() {
+    let (_scratch, path) = write_stage(
+        "shadow_catcher",
+        r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {
+    def Camera "Cam" {
+        float focalLength = 50
+        float horizontalAperture = 20.955
+        double3 xformOp:translate = (0, 0, 5)
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+    def DistantLight "Sun" {
+        float inputs:intensity = 1
+    }
+    def Mesh "Blocker" {
+        int[] faceVertexCounts = [4]
+        int[] faceVertexIndices = [0, 1, 2, 3]
+        point3f[] points = [(-50, -50, 0), (0, -50, 0), (0, 50, 0), (-50, 50, 0)]
+        custom int crust:rayMask = 6
+    }
+    def Mesh "Ground" {
+        int[] faceVertexCounts = [4]
+        int[] faceVertexIndices = [0, 1, 2, 3]
+        point3f[] points = [(-50, -50, -3), (50, -50, -3), (50, 50, -3), (-50, 50, -3)]
+        custom bool crust:shadowCatcher = 1
+    }
+    def RenderSettings "settings" {
+        int2 resolution = (8, 8)
+        int crust:samplesPerPixel = 4
+        int crust:maxDepth = 2
+        token crust:pixelFilter = "box"
+    }
+}
+"#,
+    );
+
+    let scene = Scene::from_usd(&path).expect("catcher stage loads");
+    assert!(scene.world.has_shadow_catchers());
+    // Not requested, but a catcher is pointless without it.
+    assert!(scene.settings.aovs().contains(crust_core::Aov::Shadow));
+
+    let settings = scene.settings;
+    let buffer = crust_core::Renderer::new(scene.camera, scene.world, scene.lights, settings)
+        .render();
+    let shadow = |x| buffer.get_aov(x, 4, crust_core::Aov::Shadow);
+    assert_eq!(buffer.get_pixel(1, 4), crust_core::Vec3A::ZERO);
+    assert_eq!(buffer.get_alpha(1, 4), 1.0);
+    assert_eq!(shadow(1), crust_core::Vec3A::ZERO);
+    assert_eq!(buffer.get_pixel(6, 4), crust_core::Vec3A::ZERO);
+    assert_eq!(buffer.get_alpha(6, 4), 0.0);
+    assert_eq!(shadow(6), crust_core::Vec3A::ONE);
+}
+
+/// `crust:colorSpace` round-trips into the settings, and the working space
+/// carries through the render: the built-in sky, the only thing this stage
+/// shows, comes out as the Rec.709 render converted into ACEScg.
+#[test]
+fn color_space_converts_what_the_camera_sees() {
+    use crust_core::ColorSpace;
+
+    let scratch = Scratch::new("color_space");
+    let stage = |space: &str| {
+        format!(
+            r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {{
+    def Camera "Cam" {{
+        float focalLength = 50
+        float horizontalAperture = 20.955
+    }}
+    def RenderSettings "settings" {{
+        int2 resolution = (4, 4)
+        int crust:samplesPerPixel = 4
+        int crust:minSamplesPerPixel = 4
+        token crust:pixelFilter = "box"
+        token crust:colorSpace = "{space}"
+    }}
+}}
+"#
+        )
+    };
+    let render = |space: &str| {
+        let path = scratch.write(&format!("{space}.usda"), stage(space));
+        let scene = Scene::from_usd(&path).expect("colour space stage loads");
+        let settings = scene.settings;
+        let buffer =
+            crust_core::Renderer::new(scene.camera, scene.world, scene.lights, settings).render();
+        (settings.color_space(), buffer)
+    };
+
+    let (rec709, reference) = render("lin_rec709");
+    let (acescg, converted) = render("ACEScg");
+    assert_eq!(rec709, ColorSpace::LinRec709);
+    assert_eq!(acescg, ColorSpace::AcesCg);
+    for (x, y) in [(0, 0), (2, 1), (3, 3)] {
+        let expected = ColorSpace::AcesCg.convert_rec709(reference.get_pixel(x, y));
+        let got = converted.get_pixel(x, y);
+        assert!(
+            (got - expected).abs().max_element() < 1e-4,
+            "pixel ({x}, {y}): {got:?} != {expected:?}"
+        );
+    }
+}
+
+/// A lit ball on a floor under the default sky, small enough to render in a
+/// test at a few dozen spp, with adaptive sampling on.
+const BALL_ON_FLOOR_STAGE: &str = r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {
+    def Camera "Cam" {
+        float focalLength = 35
+        float horizontalAperture = 20.955
+        double3 xformOp:translate = (0, 0, 5)
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+    def Sphere "Ball" {
+        double radius = 1
+    }
+    def Mesh "Floor" {
+        int[] faceVertexCounts = [4]
+        int[] faceVertexIndices = [0, 1, 2, 3]
+        point3f[] points = [(-50, -1, 50), (50, -1, 50), (50, -1, -50), (-50, -1, -50)]
+    }
+    def RenderSettings "settings" {
+        int2 resolution = (12, 10)
+        int crust:samplesPerPixel = 8
+        int crust:minSamplesPerPixel = 8
+        float crust:varianceThreshold = 0.05
+        int crust:maxDepth = 4
+    }
+}
+"#;
+
+/// A cropped render traces exactly the full render's pixels over its data
+/// window, and overscan extends the frame without moving the pixels inside
+/// it — so a region can be re-rendered and dropped back into the frame.
+#[test]
+fn cropped_and_overscanned_renders_match_the_full_frame() {
+    use crust_core::{PixelWindow, Renderer};
+
+    let scratch = Scratch::new("crop");
+    let full_path = scratch.write("full.usda", BALL_ON_FLOOR_STAGE);
+    let cropped = BALL_ON_FLOOR_STAGE.replace(
+        "int crust:maxDepth = 4",
+        "int crust:maxDepth = 4\n        int4 crust:dataWindow = (3, 2, 9, 7)",
+    );
+    let crop_path = scratch.write("crop.usda", cropped);
+    let render = |path: &PathBuf, overscan: u32| {
+        let scene = Scene::from_usd(path).expect("crop stage loads");
+        let settings = scene.settings.with_overscan(overscan);
+        let window = settings.data_window();
+        let buffer = Renderer::new(scene.camera, scene.world, scene.lights, settings).render();
+        (window, buffer)
+    };
+
+    let (_, full) = render(&full_path, 0);
+    let (window, crop) = render(&crop_path, 0);
+    assert_eq!(window, PixelWindow::new(3, 2, 9, 7));
+    // Buffers are bottom-up; the crop's bottom row is frame row 10 - 7.
+    for j in 0..window.height() {
+        for i in 0..window.width() {
+            assert_eq!(
+                crop.get_pixel(i, j),
+                full.get_pixel(3 + i, 3 + j),
+                "pixel ({i}, {j})"
+            );
+            assert_eq!(crop.get_alpha(i, j), full.get_alpha(3 + i, 3 + j));
+        }
+    }
+
+    let (window, overscanned) = render(&full_path, 2);
+    assert_eq!(window, PixelWindow::new(-2, -2, 14, 12));
+    for y in 0..10 {
+        for x in 0..12 {
+            assert_eq!(overscanned.get_pixel(x + 2, y + 2), full.get_pixel(x, y));
+        }
+    }
+}
+
+/// A sequence evaluates every frame at its own time code: the animated
+/// mesh and camera move between their samples, interpolated in between,
+/// and the frame number seeds the sampler.
+#[test]
+fn sequence_frames_follow_time_samples() {
+    let (_scratch, path) = write_stage(
+        "sequence",
+        r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {
+    def Camera "Cam" {
+        float focalLength = 35
+        float horizontalAperture = 20.955
+        double3 xformOp:translate.timeSamples = {
+            1: (0, 0, 5),
+            11: (0, 0, 15),
+        }
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+    def Mesh "Card" {
+        int[] faceVertexCounts = [4]
+        int[] faceVertexIndices = [0, 1, 2, 3]
+        point3f[] points = [(-1, -1, 0), (1, -1, 0), (1, 1, 0), (-1, 1, 0)]
+        double3 xformOp:translate.timeSamples = {
+            1: (0, 0, 0),
+            11: (10, 0, 0),
+        }
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+    def RenderSettings "settings" {
+        int2 resolution = (8, 8)
+    }
+}
+"#,
+    );
+
+    let mut sequence = Scene::open_sequence(&path, &crust_core::NoAssets).expect("shot opens");
+    for (frame, x, z) in [
+        (1, 0.0, 5.0),
+        (6, 5.0, 10.0),
+        (11, 10.0, 15.0),
+        (1, 0.0, 5.0),
+    ] {
+        let scene = sequence.frame(frame);
+        assert_eq!(scene.settings.frame(), frame);
+        let bounds = scene.world.bounds().expect("the card is placed");
+        let centre = 0.5 * (bounds.minimum.x + bounds.maximum.x);
+        assert!(
+            (centre - x).abs() < 1e-4,
+            "frame {frame}: card at x = {centre}"
+        );
+        let eye = scene
+            .camera
+            .get_ray(0.5, 0.5, [0.5, 0.5], 0.0)
+            .expect("a perspective camera sees everywhere")
+            .origin();
+        assert!(
+            (eye.z - z).abs() < 1e-4,
+            "frame {frame}: camera at z = {}",
+            eye.z
+        );
+    }
+}
+
+/// An orthographic camera's film is its view: `horizontalAperture = 40`
+/// (tenths of a unit) frames 4 units across, every ray runs down the
+/// camera's -Z, and the frame's corners are that far apart at any depth.
+#[test]
+fn orthographic_camera_frames_its_aperture() {
+    use crust_core::{Projection, Vec3A};
+
+    let (_scratch, path) = write_stage(
+        "ortho",
+        r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {
+    def Camera "Cam" {
+        token projection = "orthographic"
+        float horizontalAperture = 40
+        float verticalAperture = 20
+        double3 xformOp:translate = (1, 2, 10)
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+    def RenderSettings "settings" {
+        int2 resolution = (8, 4)
+    }
+}
+"#,
+    );
+    let scene = Scene::from_usd(&path).expect("ortho stage loads");
+    let camera = scene.camera;
+    assert_eq!(camera.projection(), Projection::Orthographic);
+
+    let corner = |s, t| camera.get_ray(s, t, [0.5, 0.5], 0.0).unwrap();
+    let (low, high) = (corner(0.0, 0.0), corner(1.0, 1.0));
+    for ray in [&low, &high] {
+        assert!((ray.direction().normalize() - Vec3A::NEG_Z).length() < 1e-5);
+    }
+    assert!((low.origin() - Vec3A::new(-1.0, 1.0, 10.0)).length() < 1e-4);
+    assert!((high.origin() - Vec3A::new(3.0, 3.0, 10.0)).length() < 1e-4);
+}
+
+/// `crust:camera:projection` selects a panoramic model. Inside a sphere
+/// every ray hits, so a fisheye's image circle renders opaque and the
+/// frame's corners, outside it, stay transparent.
+#[test]
+fn panoramic_cameras_are_selected_and_rendered() {
+    use crust_core::{Aov, AovSet, Projection};
+
+    let scratch = Scratch::new("panoramic");
+    let stage = |projection: &str| {
+        format!(
+            r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {{
+    def Camera "Cam" {{
+        token crust:camera:projection = "{projection}"
+        float crust:camera:fov = 200
+    }}
+    def Sphere "Inside" {{
+        double radius = 50
+    }}
+    def RenderSettings "settings" {{
+        int2 resolution = (16, 8)
+        int crust:samplesPerPixel = 4
+        int crust:minSamplesPerPixel = 4
+        int crust:maxDepth = 1
+        token crust:pixelFilter = "box"
+    }}
+}}
+"#
+        )
+    };
+    let load = |projection: &str| {
+        let path = scratch.write(&format!("{projection}.usda"), stage(projection));
+        Scene::from_usd(&path).expect("panoramic stage loads")
+    };
+
+    assert_eq!(load("spherical").camera.projection(), Projection::Spherical);
+    assert_eq!(load("cubemap").camera.projection(), Projection::Cubemap);
+    assert_eq!(
+        load("fisheye_equisolid").camera.projection(),
+        Projection::FisheyeEquisolid { fov: 200.0 }
+    );
+    let scene = load("fisheye_equidistant");
+    assert_eq!(
+        scene.camera.projection(),
+        Projection::FisheyeEquidistant { fov: 200.0 }
+    );
+    let buffer =
+        crust_core::Renderer::new(scene.camera, scene.world, scene.lights, scene.settings).render();
+    assert_eq!(buffer.get_alpha(8, 4), 1.0);
+    for (x, y) in [(0, 0), (15, 0), (0, 7), (15, 7)] {
+        assert_eq!(buffer.get_alpha(x, y), 0.0, "corner ({x}, {y})");
+    }
+
+    // A ray outside the image circle is a black sample like any other, so
+    // adaptive sampling stops the corners as soon as it may.
+    let scene = load("fisheye_equidistant");
+    let settings = scene
+        .settings
+        .with_samples_per_pixel(64)
+        .with_aovs(AovSet::empty().with(Aov::SampleCount));
+    let buffer =
+        crust_core::Renderer::new(scene.camera, scene.world, scene.lights, settings).render();
+    for (x, y) in [(0, 0), (15, 0), (0, 7), (15, 7)] {
+        assert_eq!(
+            buffer.get_aov(x, y, Aov::SampleCount).x,
+            4.0,
+            "corner ({x}, {y})"
+        );
+    }
+}
+
+/// The camera's shutter interval is read in time codes around the frame:
+/// a card moving one unit per code, seen through a shutter open from -0.25
+/// to +0.25 at `crust:frame = 4`, streaks from x = 3.75 to x = 4.25, and
+/// the camera carries the authored opening and rolling offset.
+#[test]
+fn shutter_interval_places_motion_at_its_time_codes() {
+    use crust_core::{Ray, Shutter, Vec3A};
+
+    let (scratch, path) = write_stage(
+        "shutter",
+        r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {
+    def Camera "Cam" {
+        double shutter:open = -0.25
+        double shutter:close = 0.25
+        float2 crust:shutter:opening = (0.2, 0.8)
+        float crust:shutter:rolling = 0.1
+        double3 xformOp:translate = (0, 0, 10)
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+    def Mesh "Card" {
+        int[] faceVertexCounts = [4]
+        int[] faceVertexIndices = [0, 1, 2, 3]
+        point3f[] points = [(-1, -1, 0), (1, -1, 0), (1, 1, 0), (-1, 1, 0)]
+        double3 xformOp:translate.timeSamples = {
+            0: (0, 0, 0),
+            8: (8, 0, 0),
+        }
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+    def RenderSettings "settings" {
+        int2 resolution = (8, 8)
+        int crust:frame = 4
+    }
+}
+"#,
+    );
+
+    let scene = Scene::from_usd(&path).expect("shutter stage loads");
+    assert_eq!(scene.camera.shutter(), Shutter::new((0.2, 0.8), 0.1));
+    let hits = |x: f32, time: f32| {
+        let ray = Ray::new(Vec3A::new(x, 0.0, 5.0), Vec3A::NEG_Z).with_time(time);
+        scene.world.intersect(&ray, 0.001, 10.0).is_some()
+    };
+    // Open: the card spans [2.75, 4.75]; closed: [3.25, 5.25].
+    assert!(hits(2.9, 0.0) && !hits(2.9, 1.0));
+    assert!(!hits(5.1, 0.0) && hits(5.1, 1.0));
+
+    // A sequence frame moves the interval with it.
+    let mut sequence = Scene::open_sequence(&path, &crust_core::NoAssets).expect("shot opens");
+    let scene = sequence.frame(6);
+    let ray = |x: f32, time: f32| Ray::new(Vec3A::new(x, 0.0, 5.0), Vec3A::NEG_Z).with_time(time);
+    assert!(scene.world.intersect(&ray(4.9, 0.0), 0.001, 10.0).is_some());
+    assert!(scene.world.intersect(&ray(4.9, 1.0), 0.001, 10.0).is_none());
+
+    // An animated shutter is read at the frame: closed by frame 6, it
+    // leaves the card standing still over [5, 7].
+    let animated = std::fs::read_to_string(&path).expect("read stage").replace(
+        "double shutter:close = 0.25",
+        "double shutter:close.timeSamples = { 0: 0.25, 6: -0.25 }",
+    );
+    let path = scratch.write("animated.usda", animated);
+    let mut sequence = Scene::open_sequence(&path, &crust_core::NoAssets).expect("shot opens");
+    let scene = sequence.frame(6);
+    assert!(scene.world.intersect(&ray(4.9, 0.0), 0.001, 10.0).is_none());
+    assert!(scene.world.intersect(&ray(5.1, 1.0), 0.001, 10.0).is_some());
+}
+
+/// `crust:aperture:*` shapes the lens: six blades and a 2× squeeze keep
+/// every lens sample inside a hexagon half as wide as it is tall.
+#[test]
+fn aperture_blades_and_squeeze_shape_the_lens() {
+    use crust_core::Vec3A;
+
+    let (_scratch, path) = write_stage(
+        "aperture",
+        r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {
+    def Camera "Cam" {
+        float focalLength = 50
+        float fStop = 2
+        float focusDistance = 10
+        int crust:aperture:blades = 6
+        float crust:aperture:squeeze = 2
+    }
+    def RenderSettings "settings" {
+        int2 resolution = (8, 8)
+    }
+}
+"#,
+    );
+    let scene = Scene::from_usd(&path).expect("bokeh stage loads");
+    let aperture = scene.camera.aperture();
+    assert_eq!((aperture.blades(), aperture.squeeze()), (6, 2.0));
+
+    // A 50 mm lens at f/2 is 25 mm across: lens radius 12.5.
+    let (mut reach_x, mut reach_y) = (0.0f32, 0.0f32);
+    for k in 0..256 {
+        let u = [(k % 16) as f32 / 16.0 + 0.03, (k / 16) as f32 / 16.0 + 0.03];
+        let origin = scene.camera.get_ray(0.5, 0.5, u, 0.0).unwrap().origin();
+        assert!(origin.z.abs() < 1e-4);
+        let p = Vec3A::new(2.0 * origin.x, origin.y, 0.0) / 12.5;
+        assert!(p.length() <= 1.0 + 1e-4, "{p:?} outside the lens");
+        reach_x = reach_x.max(origin.x);
+        reach_y = reach_y.max(origin.y);
+    }
+    assert!(reach_x < 0.5 * reach_y);
+}
+
+/// An animated camera moves over the shutter: at `crust:frame = 4`, with
+/// the shutter open from -0.25 to +0.25, a camera dollying one unit per
+/// time code sends its rays from x = 3.75 at the opening to 4.25 at the
+/// close.
+#[test]
+fn animated_camera_moves_over_the_shutter() {
+    let (_scratch, path) = write_stage(
+        "camera_motion",
+        r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {
+    def Camera "Cam" {
+        double shutter:open = -0.25
+        double shutter:close = 0.25
+        double3 xformOp:translate.timeSamples = {
+            0: (0, 0, 10),
+            8: (8, 0, 10),
+        }
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+    def RenderSettings "settings" {
+        int2 resolution = (8, 8)
+        int crust:frame = 4
+    }
+}
+"#,
+    );
+
+    let scene = Scene::from_usd(&path).expect("dolly stage loads");
+    assert!(scene.camera.has_motion());
+    let eye = |time: f32| {
+        scene
+            .camera
+            .get_ray(0.5, 0.5, [0.5, 0.5], time)
+            .expect("a perspective camera sees everywhere")
+            .origin()
+    };
+    for (time, x) in [(0.0, 3.75), (0.5, 4.0), (1.0, 4.25)] {
+        assert!(
+            (eye(time).x - x).abs() < 1e-4,
+            "time {time}: eye at {}",
+            eye(time).x
+        );
+    }
+}
+
+/// `crust:camera:lensFile` traces rays through a lens prescription (pbrt's
+/// 50 mm double Gauss), sized by the film apertures and focused at
+/// `focusDistance`: rays from the film's centre converge there, and a
+/// missing file falls back to the perspective camera.
+#[test]
+fn lens_file_makes_a_realistic_camera() {
+    use crust_core::Projection;
+
+    let scratch = Scratch::new("lens");
+    scratch.write(
+        "dgauss.50mm.dat",
+        "# radius thickness ior aperture
+29.475 3.76 1.67 25.2
+84.83 0.12 1 25.2
+19.275 4.025 1.67 23
+40.77 3.275 1.699 23
+12.75 5.705 1 18
+0 4.5 0 17.1
+-14.495 1.18 1.603 17
+40.77 6.065 1.658 20
+-20.385 0.19 1 20
+437.065 3.22 1.717 20
+-39.73 0 1 20
+",
+    );
+    let stage = |lens: &str| {
+        let path = scratch.write(
+            "lens.usda",
+            format!(
+                r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {{
+    def Camera "Cam" {{
+        float horizontalAperture = 36
+        float verticalAperture = 24
+        float focusDistance = 100
+        asset crust:camera:lensFile = @./{lens}@
+    }}
+    def RenderSettings "settings" {{
+        int2 resolution = (12, 8)
+    }}
+}}
+"#
+            ),
+        );
+        Scene::from_usd(&path).expect("lens stage loads")
+    };
+
+    let scene = stage("dgauss.50mm.dat");
+    assert_eq!(scene.camera.projection(), Projection::Realistic);
+    let lens = scene.camera.lens().expect("a realistic camera has a lens");
+    assert!((lens.focal_length() - 50.0).abs() < 1.5);
+    // 100 scene units ahead is 1000 mm from the film.
+    for u in [[0.3, 0.5], [0.6, 0.7], [0.5, 0.35]] {
+        let ray = scene
+            .camera
+            .get_ray(0.5, 0.5, u, 0.0)
+            .expect("the centre is clear");
+        let (o, d) = (ray.origin(), ray.direction());
+        let p = o + d * ((-100.0 - o.z) / d.z);
+        assert!(p.x.hypot(p.y) < 0.1, "{p:?} is out of focus");
+    }
+    // The lens inverts the film, which the camera turns back the right way
+    // up: the right of the image looks right.
+    let right = scene
+        .camera
+        .get_ray(0.9, 0.5, [0.5, 0.5], 0.0)
+        .expect("clear");
+    assert!(right.direction().x > 0.0);
+    let (_, weight) = scene.camera.sample_ray(0.5, 0.5, [0.5, 0.5], 0.0).unwrap();
+    assert!(weight > 0.5);
+
+    let fallback = stage("missing.dat");
+    assert_eq!(fallback.camera.projection(), Projection::Perspective);
+}
+
+/// The camera's film-back offsets, clipping range and physical exposure:
+/// a near plane behind a holdout sphere reveals the backdrop behind it, a
+/// far plane short of the backdrop leaves the corners empty, and an
+/// animated range moves with the frame.
+#[test]
+fn camera_clipping_offset_and_exposure_are_imported() {
+    let scratch = Scratch::new("clipping");
+    let stage = |camera: &str| {
+        let path = scratch.write("clipping.usda", holdout_stage(camera));
+        Scene::from_usd(&path).expect("clipping stage loads")
+    };
+    let alphas = |scene: Scene| {
+        let settings = scene.settings;
+        let buffer =
+            crust_core::Renderer::new(scene.camera, scene.world, scene.lights, settings).render();
+        (buffer.get_alpha(4, 4), buffer.get_alpha(0, 0))
+    };
+
+    assert_eq!(alphas(stage("")), (0.0, 1.0));
+    assert_eq!(alphas(stage("float2 clippingRange = (6, 100)")), (1.0, 1.0));
+    assert_eq!(alphas(stage("float2 clippingRange = (1, 6)")), (0.0, 0.0));
+
+    // An animated range is read at the frame's time code.
+    let path = scratch.write(
+        "animated.usda",
+        holdout_stage("float2 clippingRange.timeSamples = { 1: (1, 100), 2: (6, 100) }"),
+    );
+    let mut sequence = Scene::open_sequence(&path, &crust_core::NoAssets).expect("shot opens");
+    assert_eq!(alphas(sequence.frame(1)), (0.0, 1.0));
+    assert_eq!(alphas(sequence.frame(2)), (1.0, 1.0));
+
+    let scene = stage(
+        "float horizontalApertureOffset = 5
+        float verticalApertureOffset = -2.5
+        float exposure = -1
+        float exposure:time = 0.5
+        float exposure:iso = 400
+        float exposure:fStop = 2",
+    );
+    assert_eq!(scene.camera.film_offset(), (0.25, -0.125));
+    assert!((scene.camera.exposure() - 0.25).abs() < 1e-6);
+}
+
+/// With several cameras and settings prims, the render settings' `camera`
+/// relationship picks the camera, `renderSettingsPrimPath` the settings,
+/// and [`crust_core::ImportOptions`] overrides either; naming a prim the
+/// stage lacks is an error, while a dangling `camera` relationship falls
+/// back to the stage's first camera.
+#[test]
+fn render_camera_and_settings_are_selectable() {
+    use crust_core::{Error, ImportOptions, NoAssets};
+
+    let (_scratch, path) = write_stage(
+        "selection",
+        r#"#usda 1.0
+(
+    defaultPrim = "W"
+    renderSettingsPrimPath = "/Render/preview"
+)
+def Xform "W" {
+    def Camera "A" {
+        double3 xformOp:translate = (-5, 0, 0)
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+    def Camera "B" {
+        double3 xformOp:translate = (5, 0, 0)
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+    def Camera "C" {
+        double3 xformOp:translate = (0, 5, 0)
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+}
+def Scope "Render" {
+    def RenderSettings "preview" {
+        rel camera = </W/B>
+        int2 resolution = (16, 8)
+    }
+    def RenderSettings "final" {
+        rel camera = </W/A>
+        int2 resolution = (64, 32)
+    }
+    def RenderSettings "stale" {
+        rel camera = </W/Gone>
+        int2 resolution = (16, 8)
+    }
+}
+"#,
+    );
+    let load = |options: ImportOptions| Scene::from_usd_with_options(&path, &NoAssets, &options);
+    let eye = |scene: &Scene| {
+        scene
+            .camera
+            .get_ray(0.5, 0.5, [0.5, 0.5], 0.0)
+            .expect("a perspective camera sees everywhere")
+            .origin()
+    };
+
+    let preview = load(ImportOptions::default()).expect("layout loads");
+    assert_eq!(preview.settings.get_dimensions(), (16, 8));
+    assert_eq!(eye(&preview).x, 5.0);
+
+    let final_pass = load(ImportOptions::default().with_settings("/Render/final")).expect("final");
+    assert_eq!(final_pass.settings.get_dimensions(), (64, 32));
+    assert_eq!(eye(&final_pass).x, -5.0);
+
+    // A relationship the stage cannot satisfy is dropped, not fatal: the
+    // first camera stands in.
+    let stale = ImportOptions::default().with_settings("/Render/stale");
+    assert_eq!(eye(&load(stale.clone()).expect("stale")).x, -5.0);
+    let mut sequence =
+        Scene::open_sequence_with_options(&path, &NoAssets, &stale).expect("sequence opens");
+    assert_eq!(eye(&sequence.frame(1)).x, -5.0);
+
+    let c = load(ImportOptions::default().with_camera("/W/C")).expect("camera C");
+    assert_eq!(eye(&c).y, 5.0);
+    let mut sequence = Scene::open_sequence_with_options(
+        &path,
+        &NoAssets,
+        &ImportOptions::default().with_camera("/W/C"),
+    )
+    .expect("sequence opens");
+    assert_eq!(eye(&sequence.frame(1)).y, 5.0);
+
+    for options in [
+        ImportOptions::default().with_camera("/W/D"),
+        ImportOptions::default().with_settings("/Render/nope"),
+    ] {
+        assert!(matches!(load(options), Err(Error::Selection(_))));
+    }
+    assert!(matches!(
+        Scene::open_sequence_with_options(
+            &path,
+            &NoAssets,
+            &ImportOptions::default().with_camera("/W/D")
+        ),
+        Err(Error::Selection(_))
+    ));
+}
+
+/// A dangling settings `camera` falls back to the stage's first camera
+/// before the shutter is read, so motion is sampled over that camera's
+/// shutter: a card moving one unit per time code, under a shutter open
+/// from -0.25 to +0.25 at `crust:frame = 4`, is at x = 3.75 at the opening.
+#[test]
+fn dangling_settings_camera_keeps_the_fallback_shutter() {
+    use crust_core::{Ray, Vec3A};
+
+    let (_scratch, path) = write_stage(
+        "dangling_shutter",
+        r#"#usda 1.0
+(defaultPrim = "W")
+def Xform "W" {
+    def Camera "Cam" {
+        double shutter:open = -0.25
+        double shutter:close = 0.25
+        double3 xformOp:translate = (0, 0, 10)
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+    def Mesh "Card" {
+        int[] faceVertexCounts = [4]
+        int[] faceVertexIndices = [0, 1, 2, 3]
+        point3f[] points = [(-1, -1, 0), (1, -1, 0), (1, 1, 0), (-1, 1, 0)]
+        double3 xformOp:translate.timeSamples = {
+            0: (0, 0, 0),
+            8: (8, 0, 0),
+        }
+        uniform token[] xformOpOrder = ["xformOp:translate"]
+    }
+}
+def Scope "Render" {
+    def RenderSettings "settings" {
+        rel camera = </W/Gone>
+        int2 resolution = (8, 8)
+        int crust:frame = 4
+    }
+}
+"#,
+    );
+
+    let hits = |scene: &Scene, time: f32| {
+        let ray = Ray::new(Vec3A::new(2.9, 0.0, 5.0), Vec3A::NEG_Z).with_time(time);
+        scene.world.intersect(&ray, 0.001, 10.0).is_some()
+    };
+    let scene = Scene::from_usd(&path).expect("dangling camera stage loads");
+    assert!(hits(&scene, 0.0) && !hits(&scene, 1.0));
+    let mut sequence = Scene::open_sequence(&path, &crust_core::NoAssets).expect("shot opens");
+    let scene = sequence.frame(4);
+    assert!(hits(&scene, 0.0) && !hits(&scene, 1.0));
+}
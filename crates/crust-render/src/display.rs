@@ -0,0 +1,386 @@
+//! The display pipeline behind the preview PNG: scene-linear pixels in,
+//! 8-bit sRGB out. The EXR never passes through here — it stays
+//! scene-linear for compositing; this only decides how the PNG *looks*.
+//!
+//! Per pixel, in order:
+//!
+//! 0. Pixels rendered in a wider working space (ACEScg, Rec.2020) are
+//!    converted to linear Rec.709 first, and what falls outside its gamut
+//!    is clipped — the PNG is sRGB.
+//! 1. **Exposure** — a multiply by `2^stops`.
+//! 2. **Look** — a contrast (and for `punchy`, saturation) adjustment
+//!    pivoting on 18% grey, still in scene-linear.
+//! 3. **View** — the tone curve from unbounded scene-linear to display
+//!    [0, 1]: `standard` clamps (the old behaviour: anything above 1.0 is
+//!    flat white), `filmic` is Hable's filmic curve, `aces` Hill's fit of
+//!    the ACES RRT + sRGB ODT, `agx` Sobotka's AgX base in Wrensch's
+//!    minimal polynomial form.
+//! 4. The sRGB transfer curve.
+//! 5. **LUT** — an optional 3D `.cube` LUT on the display-encoded values,
+//!    the domain creative and calibration LUTs are authored in.
+
+use crust_core::{ColorSpace, Vec3A};
+use std::path::Path;
+
+/// Tone curve from scene-linear to display-linear (see the module docs).
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum View {
+    /// Clamp to [0, 1]
+    #[default]
+    Standard,
+    /// Hable's filmic curve, white at 11.2
+    Filmic,
+    /// ACES RRT + sRGB ODT (Hill's fit)
+    Aces,
+    /// AgX base
+    Agx,
+}
+
+/// Contrast grade applied before the view, pivoting on 18% grey.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Look {
+    /// No grade
+    #[default]
+    None,
+    /// Flatter: contrast 0.8
+    LowContrast,
+    /// Steeper: contrast 1.25
+    HighContrast,
+    /// Steeper and more saturated
+    Punchy,
+}
+
+impl Look {
+    /// (contrast exponent, saturation).
+    fn params(self) -> (f32, f32) {
+        match self {
+            Look::None => (1.0, 1.0),
+            Look::LowContrast => (0.8, 1.0),
+            Look::HighContrast => (1.25, 1.0),
+            Look::Punchy => (1.25, 1.2),
+        }
+    }
+}
+
+/// Mid grey, the pivot of the looks.
+const MID_GREY: f32 = 0.18;
+
+/// Rec.709 luminance weights.
+fn luminance(c: Vec3A) -> f32 {
+    c.dot(Vec3A::new(0.2126, 0.7152, 0.0722))
+}
+
+/// `m` is row-major: `out[i] = m[i] · c`.
+fn mul(m: &[[f32; 3]; 3], c: Vec3A) -> Vec3A {
+    Vec3A::new(
+        Vec3A::from(m[0]).dot(c),
+        Vec3A::from(m[1]).dot(c),
+        Vec3A::from(m[2]).dot(c),
+    )
+}
+
+fn hable(x: f32) -> f32 {
+    const A: f32 = 0.15;
+    const B: f32 = 0.50;
+    const C: f32 = 0.10;
+    const D: f32 = 0.20;
+    const E: f32 = 0.02;
+    const F: f32 = 0.30;
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+fn filmic(c: Vec3A) -> Vec3A {
+    const WHITE: f32 = 11.2;
+    // Hable's exposure bias: the curve is shaped for input around 2× the
+    // usual scene values.
+    const BIAS: f32 = 2.0;
+    let white = hable(WHITE);
+    Vec3A::new(hable(c.x * BIAS), hable(c.y * BIAS), hable(c.z * BIAS)) / white
+}
+
+fn aces(c: Vec3A) -> Vec3A {
+    // sRGB → ACES AP1 (with the RRT's saturation folded in), and back.
+    const INPUT: [[f32; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    const OUTPUT: [[f32; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+    let fit =
+        |v: f32| (v * (v + 0.0245786) - 0.000090537) / (v * (0.983729 * v + 0.4329510) + 0.238081);
+    let v = mul(&INPUT, c);
+    mul(&OUTPUT, Vec3A::new(fit(v.x), fit(v.y), fit(v.z)))
+}
+
+fn agx(c: Vec3A) -> Vec3A {
+    // Inset into the AgX working space, and out again.
+    const INSET: [[f32; 3]; 3] = [
+        [0.842479062253094, 0.0784335999999992, 0.0792237451477643],
+        [0.0423282422610123, 0.878468636469772, 0.0791661274605434],
+        [0.0423756549057051, 0.0784336, 0.879142973793104],
+    ];
+    const OUTSET: [[f32; 3]; 3] = [
+        [1.19687900512017, -0.0980208811401368, -0.0990297440797205],
+        [-0.0528968517574562, 1.15190312990417, -0.0989611768448433],
+        [-0.0529716355144438, -0.0980434501171241, 1.15107367264116],
+    ];
+    const MIN_EV: f32 = -12.47393;
+    const MAX_EV: f32 = 4.026069;
+    let curve = |v: f32| {
+        let x = (v.max(1e-10).log2().clamp(MIN_EV, MAX_EV) - MIN_EV) / (MAX_EV - MIN_EV);
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    };
+    let v = mul(&INSET, c);
+    let v = mul(&OUTSET, Vec3A::new(curve(v.x), curve(v.y), curve(v.z)));
+    // The curve's output is display-encoded with a 2.2 gamma; back to
+    // linear so the sRGB step below encodes it like every other view.
+    Vec3A::new(
+        v.x.max(0.0).powf(2.2),
+        v.y.max(0.0).powf(2.2),
+        v.z.max(0.0).powf(2.2),
+    )
+}
+
+fn srgb_encode(linear: f32) -> f32 {
+    let clamped = linear.clamp(0.0, 1.0);
+    if clamped <= 0.0031308 {
+        12.92 * clamped
+    } else {
+        1.055 * clamped.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// A 3D LUT read from a `.cube` file: `size³` RGB entries, red varying
+/// fastest, over `[domain_min, domain_max]`.
+#[derive(Clone)]
+pub struct Lut3D {
+    size: usize,
+    domain_min: Vec3A,
+    domain_max: Vec3A,
+    table: Vec<Vec3A>,
+}
+
+impl Lut3D {
+    /// Parses the text of a `.cube` file (the Adobe / Resolve format):
+    /// `LUT_3D_SIZE`, optional `DOMAIN_MIN` / `DOMAIN_MAX` and `TITLE`,
+    /// `#` comments, then the table. 1D LUTs are rejected.
+    pub fn parse(text: &str) -> Result<Lut3D, String> {
+        let mut size = None;
+        let mut domain_min = Vec3A::ZERO;
+        let mut domain_max = Vec3A::ONE;
+        let mut table = Vec::new();
+        let triple = |rest: &[&str], line: usize| -> Result<Vec3A, String> {
+            let v: Vec<f32> = rest
+                .iter()
+                .map(|s| s.parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("line {line}: {e}"))?;
+            match v[..] {
+                [r, g, b] => Ok(Vec3A::new(r, g, b)),
+                _ => Err(format!("line {line}: expected three values")),
+            }
+        };
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[0] {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => return Err("1D LUTs are not supported".to_owned()),
+                "LUT_3D_SIZE" => {
+                    let n = words
+                        .get(1)
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .filter(|n| (2..=256).contains(n))
+                        .ok_or_else(|| format!("line {number}: bad LUT_3D_SIZE"))?;
+                    size = Some(n);
+                }
+                "DOMAIN_MIN" => domain_min = triple(&words[1..], number)?,
+                "DOMAIN_MAX" => domain_max = triple(&words[1..], number)?,
+                _ => table.push(triple(&words, number)?),
+            }
+        }
+        let size = size.ok_or("missing LUT_3D_SIZE")?;
+        if table.len() != size * size * size {
+            return Err(format!(
+                "expected {} entries for LUT_3D_SIZE {size}, found {}",
+                size * size * size,
+                table.len()
+            ));
+        }
+        if (domain_max - domain_min).min_element() <= 0.0 {
+            return Err("DOMAIN_MAX must exceed DOMAIN_MIN".to_owned());
+        }
+        Ok(Lut3D {
+            size,
+            domain_min,
+            domain_max,
+            table,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Lut3D, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Lut3D::parse(&text)
+    }
+
+    fn at(&self, r: usize, g: usize, b: usize) -> Vec3A {
+        self.table[(b * self.size + g) * self.size + r]
+    }
+
+    /// Trilinear lookup; input outside the domain clamps to its edge.
+    pub fn apply(&self, c: Vec3A) -> Vec3A {
+        let n = (self.size - 1) as f32;
+        let p = ((c - self.domain_min) / (self.domain_max - self.domain_min))
+            .clamp(Vec3A::ZERO, Vec3A::ONE)
+            * n;
+        let i = p.floor().min(Vec3A::splat(n - 1.0));
+        let f = p - i;
+        let (r, g, b) = (i.x as usize, i.y as usize, i.z as usize);
+        let lerp = |a: Vec3A, b: Vec3A, t: f32| a + (b - a) * t;
+        let c00 = lerp(self.at(r, g, b), self.at(r + 1, g, b), f.x);
+        let c10 = lerp(self.at(r, g + 1, b), self.at(r + 1, g + 1, b), f.x);
+        let c01 = lerp(self.at(r, g, b + 1), self.at(r + 1, g, b + 1), f.x);
+        let c11 = lerp(self.at(r, g + 1, b + 1), self.at(r + 1, g + 1, b + 1), f.x);
+        lerp(lerp(c00, c10, f.y), lerp(c01, c11, f.y), f.z)
+    }
+}
+
+/// The whole pipeline, configured once from the CLI.
+#[derive(Clone, Default)]
+pub struct DisplayTransform {
+    exposure: f32,
+    view: View,
+    look: Look,
+    lut: Option<Lut3D>,
+    working_space: ColorSpace,
+}
+
+impl DisplayTransform {
+    pub fn new(view: View, look: Look, exposure: f32) -> Self {
+        Self {
+            exposure,
+            view,
+            look,
+            lut: None,
+            working_space: ColorSpace::default(),
+        }
+    }
+
+    pub fn with_lut(mut self, lut: Lut3D) -> Self {
+        self.lut = Some(lut);
+        self
+    }
+
+    /// The space the rendered pixels are in (see step 0).
+    pub fn with_working_space(mut self, space: ColorSpace) -> Self {
+        self.working_space = space;
+        self
+    }
+
+    /// One scene-linear pixel to display-encoded [0, 1].
+    pub fn apply(&self, linear: Vec3A) -> Vec3A {
+        let linear = self.working_space.to_rec709(linear);
+        let mut c = linear.max(Vec3A::ZERO) * self.exposure.exp2();
+        let (contrast, saturation) = self.look.params();
+        if contrast != 1.0 {
+            let grade = |v: f32| MID_GREY * (v / MID_GREY).powf(contrast);
+            c = Vec3A::new(grade(c.x), grade(c.y), grade(c.z));
+        }
+        if saturation != 1.0 {
+            let y = luminance(c);
+            c = (Vec3A::splat(y) + (c - y) * saturation).max(Vec3A::ZERO);
+        }
+        let display = match self.view {
+            View::Standard => c,
+            View::Filmic => filmic(c),
+            View::Aces => aces(c),
+            View::Agx => agx(c),
+        };
+        let encoded = Vec3A::new(
+            srgb_encode(display.x),
+            srgb_encode(display.y),
+            srgb_encode(display.z),
+        );
+        match &self.lut {
+            Some(lut) => lut.apply(encoded).clamp(Vec3A::ZERO, Vec3A::ONE),
+            None => encoded,
+        }
+    }
+
+    /// As [`DisplayTransform::apply`], quantized to 8 bits.
+    pub fn to_rgb8(&self, linear: Vec3A) -> [u8; 3] {
+        let c = self.apply(linear);
+        [c.x, c.y, c.z].map(|v| (v * 255.0 + 0.5).floor() as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `standard` with no exposure is the historical clamp + sRGB.
+    #[test]
+    fn standard_view_clamps_like_before() {
+        let t = DisplayTransform::default();
+        assert_eq!(t.to_rgb8(Vec3A::new(0.0, 1.0, 4.0)), [0, 255, 255]);
+        assert_eq!(t.to_rgb8(Vec3A::splat(0.5)), [188, 188, 188]);
+    }
+
+    #[test]
+    fn exposure_is_in_stops() {
+        let t = DisplayTransform::new(View::Standard, Look::None, 1.0);
+        let plain = DisplayTransform::default();
+        assert_eq!(t.apply(Vec3A::splat(0.25)), plain.apply(Vec3A::splat(0.5)));
+    }
+
+    /// The tone curves keep highlights apart that `standard` flattens, and
+    /// stay within the display range.
+    #[test]
+    fn tone_curves_roll_off_highlights() {
+        for view in [View::Filmic, View::Aces, View::Agx] {
+            let t = DisplayTransform::new(view, Look::None, 0.0);
+            let (lo, hi) = (t.apply(Vec3A::splat(2.0)), t.apply(Vec3A::splat(8.0)));
+            assert!(hi.x > lo.x, "{view:?} is not increasing");
+            assert!(hi.max_element() <= 1.0 && t.apply(Vec3A::ZERO).max_element() < 0.05);
+        }
+    }
+
+    #[test]
+    fn looks_pivot_on_mid_grey() {
+        let t = DisplayTransform::new(View::Standard, Look::HighContrast, 0.0);
+        let plain = DisplayTransform::default();
+        let grey = Vec3A::splat(MID_GREY);
+        assert!((t.apply(grey) - plain.apply(grey)).abs().max_element() < 1e-6);
+        assert!(t.apply(Vec3A::splat(0.05)).x < plain.apply(Vec3A::splat(0.05)).x);
+    }
+
+    #[test]
+    fn cube_luts_parse_and_interpolate() {
+        // An inverting 2³ LUT.
+        let mut text = String::from("TITLE \"invert\"\n# comment\nLUT_3D_SIZE 2\n");
+        for b in [1.0, 0.0] {
+            for g in [1.0, 0.0] {
+                for r in [1.0, 0.0] {
+                    text.push_str(&format!("{r} {g} {b}\n"));
+                }
+            }
+        }
+        let lut = Lut3D::parse(&text).expect("valid cube");
+        let out = lut.apply(Vec3A::new(0.25, 0.5, 1.0));
+        assert!((out - Vec3A::new(0.75, 0.5, 0.0)).abs().max_element() < 1e-6);
+
+        assert!(Lut3D::parse("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+        assert!(Lut3D::parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+    }
+}
@@ -1,15 +1,28 @@
+mod display;
+
 use clap::Parser;
+use crust_core::AovSet;
 use crust_core::Buffer;
+use crust_core::Checkpoint;
+use crust_core::ColorSpace;
+use crust_core::CryptoSet;
+use crust_core::ImportOptions;
+use crust_core::LightPathExpr;
+use crust_core::PartialRender;
 use crust_core::PixelFilter;
+use crust_core::PixelWindow;
 use crust_core::Renderer;
 use crust_core::SamplingStrategy;
-use crust_core::{AssetLoader, EnvironmentMap, PtexTexture, Scene, Vec3A};
-use crust_core::{get_settings, simple_scene};
+use crust_core::TileSplit;
+use crust_core::{ApertureImage, AssetLoader, EnvironmentMap, PtexTexture, Scene, Vec3A};
+use crust_core::{CRYPTO_RANKS, cryptomatte_header, get_settings, simple_scene};
+use display::{DisplayTransform, Look, Lut3D, View};
 use exr::prelude::*;
 use indicatif::ProgressBar;
-use std::path::Path;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use tracing::{Level, debug, error, info};
+use tracing::{Level, debug, error, info, warn};
 
 /// The host side of `crust_core::AssetLoader`: the engine asks for pixels,
 /// the CLI decodes them. That split is why `crust-core` carries no image
@@ -23,16 +36,8 @@ struct CliAssets;
 
 impl AssetLoader for CliAssets {
     fn load_environment(&self, path: &Path) -> Option<EnvironmentMap> {
-        let ext = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or_default()
-            .to_ascii_lowercase();
         let started = Instant::now();
-        let loaded = match ext.as_str() {
-            "exr" => load_exr_environment(path),
-            _ => load_image_environment(path),
-        };
+        let loaded = load_rgb(path).and_then(|(w, h, pixels)| EnvironmentMap::new(w, h, pixels));
         match &loaded {
             Some(map) => info!(
                 "Loaded environment {} ({}x{}) in {:?}",
@@ -73,9 +78,40 @@ impl AssetLoader for CliAssets {
             }
         }
     }
+
+    fn load_aperture(&self, path: &Path) -> Option<ApertureImage> {
+        // A mask is grayscale; a colour one is read as its channels' mean.
+        let loaded = load_rgb(path).and_then(|(w, h, pixels)| {
+            let values = pixels.iter().map(|p| p.element_sum() / 3.0).collect();
+            ApertureImage::new(w, h, values)
+        });
+        match &loaded {
+            Some(image) => info!(
+                "Loaded aperture {} ({}x{})",
+                path.display(),
+                image.width(),
+                image.height()
+            ),
+            None => error!("Could not load aperture {}", path.display()),
+        }
+        loaded
+    }
+}
+
+/// Decodes an image file to linear, row-major RGB, top row first.
+fn load_rgb(path: &Path) -> Option<(usize, usize, Vec<Vec3A>)> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "exr" => load_exr_rgb(path),
+        _ => load_image_rgb(path),
+    }
 }
 
-fn load_exr_environment(path: &Path) -> Option<EnvironmentMap> {
+fn load_exr_rgb(path: &Path) -> Option<(usize, usize, Vec<Vec3A>)> {
     let image = read_first_rgba_layer_from_file(
         path,
         |resolution, _| {
@@ -90,11 +126,10 @@ fn load_exr_environment(path: &Path) -> Option<EnvironmentMap> {
     )
     .map_err(|e| error!("EXR decode failed for {}: {e}", path.display()))
     .ok()?;
-    let (w, h, pixels) = image.layer_data.channel_data.pixels;
-    EnvironmentMap::new(w, h, pixels)
+    Some(image.layer_data.channel_data.pixels)
 }
 
-fn load_image_environment(path: &Path) -> Option<EnvironmentMap> {
+fn load_image_rgb(path: &Path) -> Option<(usize, usize, Vec<Vec3A>)> {
     // `image::open`'s default 512MiB decode-allocation limit is well below a
     // production-scale panorama (e.g. a 16k HDRI): lift it for this trusted,
     // locally-authored asset rather than have large dome lights fail to load.
@@ -131,7 +166,7 @@ fn load_image_environment(path: &Path) -> Option<EnvironmentMap> {
         .pixels()
         .map(|p| Vec3A::new(to_linear(p[0]), to_linear(p[1]), to_linear(p[2])))
         .collect();
-    EnvironmentMap::new(w, h, pixels)
+    Some((w, h, pixels))
 }
 
 // ---------------------------------------------------------------------------
@@ -358,12 +393,23 @@ enum LoggerLevel {
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// Input scene path — .usda / .usdc / .usdz.
     /// When absent, falls back to a hard-coded procedural scene.
     #[arg(short, long)]
     input: Option<String>,
+    /// Render through the camera at this prim path, e.g. `/shots/sh010/cam`,
+    /// over the one the render settings name.
+    #[arg(long, requires = "input")]
+    camera: Option<String>,
+    /// Read the render settings from this prim path, over the stage's
+    /// `renderSettingsPrimPath`.
+    #[arg(long, requires = "input")]
+    settings: Option<String>,
     /// Output image path. The linear EXR is written here and a tone-mapped
-    /// sRGB PNG next to it (same path with a .png extension).
+    /// sRGB PNG next to it (same path with a .png extension). The display
+    /// options below shape the PNG only.
     #[arg(short, long, default_value = "output.exr")]
     output: String,
     /// Verbose level
@@ -393,6 +439,127 @@ struct Cli {
     /// render, output) when the render finishes.
     #[arg(long, default_value_t = false)]
     stats: bool,
+    /// Extra EXR layers, comma-separated: albedo, N, P, Z, diffuse_direct,
+    /// diffuse_indirect, specular, transmission, emission, volume, shadow,
+    /// sample_count — or `all`. Overrides the scene's `crust:aovs`.
+    #[arg(long)]
+    aov: Option<String>,
+    /// Light path expression layer as `name=expression`, e.g.
+    /// `caustics='C.*<TS>.*L'`. Repeatable; replaces the scene's
+    /// `crust:lpes` when given.
+    #[arg(long)]
+    lpe: Vec<String>,
+    /// Cryptomatte ID matte layers, comma-separated: object, material — or
+    /// `all`. Overrides the scene's `crust:cryptomatte`.
+    #[arg(long)]
+    cryptomatte: Option<String>,
+    /// Also write a deep EXR next to the flat one (`<output>.deep.exr`),
+    /// as the scene's `crust:deep` does.
+    #[arg(long, default_value_t = false)]
+    deep: bool,
+    /// Denoise the beauty, guided by albedo, normal and depth (written as
+    /// AOVs too). The noisy beauty is kept as the `noisy` layer. Turns on
+    /// the scene's `crust:denoise`.
+    #[arg(long, default_value_t = false)]
+    denoise: bool,
+    /// PNG exposure in stops.
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    exposure: f32,
+    /// PNG view transform: the tone curve from scene-linear to display.
+    #[arg(long, value_enum, default_value_t = View::Standard)]
+    view: View,
+    /// PNG look: a contrast grade applied before the view.
+    #[arg(long, value_enum, default_value_t = Look::None)]
+    look: Look,
+    /// 3D `.cube` LUT applied to the PNG's display-encoded values, after
+    /// the view.
+    #[arg(long)]
+    lut: Option<String>,
+    /// Save the render's progress to this file every
+    /// `--checkpoint-interval` seconds and when it finishes, so `--resume`
+    /// can pick it up. Checkpoints hold the beauty and alpha only: not
+    /// available with AOVs, LPEs, Cryptomatte, deep output or denoising.
+    #[arg(long, conflicts_with_all = ["aov", "lpe", "cryptomatte", "deep", "denoise"])]
+    checkpoint: Option<String>,
+    /// Seconds between two checkpoints.
+    #[arg(long, default_value_t = 300)]
+    checkpoint_interval: u64,
+    /// Continue the render a checkpoint saved, up to `--samples` (which
+    /// may be raised). The scene and the other settings must match. Keeps
+    /// checkpointing to the same file unless `--checkpoint` names another.
+    /// Not available with AOVs, LPEs, Cryptomatte, deep output or
+    /// denoising.
+    #[arg(long, conflicts_with_all = ["aov", "lpe", "cryptomatte", "deep", "denoise"])]
+    resume: Option<String>,
+    /// Render the final pass progressively, as whole-frame passes of
+    /// doubling spp. Implied by `--noise-target`. Passes carry the beauty
+    /// and alpha only: not available with AOVs, LPEs, Cryptomatte, deep
+    /// output or denoising.
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["aov", "lpe", "cryptomatte", "deep", "denoise"]
+    )]
+    progressive: bool,
+    /// Stop after this much wall-clock time and write the image so far,
+    /// e.g. `90s`, `10m`, `1h30m` (a bare number is seconds). Without
+    /// `--progressive` it cuts the single pass short, with every layer
+    /// written; the pixels it reached last have fewer samples.
+    #[arg(long, value_parser = parse_duration)]
+    time_limit: Option<Duration>,
+    /// Stop once the image's mean relative standard error drops to this
+    /// value, e.g. `0.02`. Implies `--progressive`.
+    #[arg(long, conflicts_with_all = ["aov", "lpe", "cryptomatte", "deep", "denoise"])]
+    noise_target: Option<f32>,
+    /// Render only this pixel rectangle of the frame, `x0,y0,x1,y1`: top-left
+    /// origin, max exclusive. The camera still frames the whole shot, and the
+    /// EXR records the crop as its data window. Overrides the scene's
+    /// `crust:dataWindow`.
+    #[arg(long, value_parser = parse_window, allow_hyphen_values = true)]
+    crop: Option<PixelWindow>,
+    /// Render this many pixels past each edge of the frame. Overrides the
+    /// scene's `crust:overscan`.
+    #[arg(long)]
+    overscan: Option<u32>,
+    /// Render only part `k` of `n` of the frame's buckets, e.g. `3/16`, and
+    /// write its pixels' sample sums to `<output>.3of16.part` instead of an
+    /// image. `crust-render merge` assembles the parts into the EXR and PNG.
+    /// Parts carry the beauty and alpha only: not available with AOVs,
+    /// LPEs, Cryptomatte, deep output, denoising or path guiding.
+    #[arg(
+        long,
+        value_parser = parse_tiles,
+        conflicts_with_all = [
+            "checkpoint", "resume", "progressive", "noise_target",
+            "aov", "lpe", "cryptomatte", "deep", "denoise",
+        ]
+    )]
+    tiles: Option<TileSplit>,
+    /// Render a frame range of the `--input` stage, e.g. `1001-1100` (or a
+    /// single frame): the stage is opened once and each frame evaluated at
+    /// its time code, written as `<output>.1001.exr` and so on.
+    #[arg(
+        long,
+        value_parser = parse_frames,
+        allow_hyphen_values = true,
+        requires = "input",
+        conflicts_with_all = ["checkpoint", "resume"]
+    )]
+    frames: Option<RangeInclusive<isize>>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Assemble the parts of a `--tiles` render into its EXR and PNG (the
+    /// PNG through the display flags given before `merge`).
+    Merge {
+        /// Every part of the render, each once, in any order.
+        #[arg(required = true)]
+        parts: Vec<String>,
+        /// Output EXR path.
+        #[arg(short, long, default_value = "output.exr")]
+        output: String,
+    },
 }
 
 #[derive(clap::ValueEnum, Clone, Debug, Copy)]
@@ -456,33 +623,318 @@ fn get_logger_level(level: LoggerLevel) -> Level {
     }
 }
 
-/// Compress a linear f32 into [0,1] and encode it as an sRGB byte.
-fn tone_map(linear: f32) -> u8 {
-    let clamped = linear.clamp(0.0, 1.0);
-    let srgb = if clamped <= 0.0031308 {
-        12.92 * clamped
-    } else {
-        1.055 * clamped.powf(1.0 / 2.4) - 0.055
+/// Parses `--time-limit`: one or more `<number><unit>` terms with units
+/// `h`, `m` and `s` (`1h30m`, `2.5m`), or a bare number of seconds.
+fn parse_duration(text: &str) -> std::result::Result<Duration, String> {
+    let text = text.trim();
+    if let Ok(secs) = text.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).map_err(|e| e.to_string());
+    }
+    let mut secs = 0.0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(|| format!("\"{text}\" is missing a unit (h, m or s)"))?;
+        let (number, tail) = rest.split_at(split);
+        let value: f64 = number
+            .parse()
+            .map_err(|_| format!("\"{text}\" is not a duration"))?;
+        let unit = tail.chars().next().expect("split stops at a unit");
+        secs += value
+            * match unit {
+                'h' => 3600.0,
+                'm' => 60.0,
+                's' => 1.0,
+                _ => return Err(format!("unknown unit '{unit}' in \"{text}\"")),
+            };
+        rest = &tail[unit.len_utf8()..];
+    }
+    Duration::try_from_secs_f64(secs).map_err(|e| e.to_string())
+}
+
+/// Parses `--crop`: four comma-separated pixel coordinates.
+fn parse_window(text: &str) -> std::result::Result<PixelWindow, String> {
+    let bounds = text
+        .split(',')
+        .map(|v| v.trim().parse::<i32>())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| format!("\"{text}\" is not x0,y0,x1,y1"))?;
+    match bounds[..] {
+        [x0, y0, x1, y1] if x0 < x1 && y0 < y1 => Ok(PixelWindow::new(x0, y0, x1, y1)),
+        [_, _, _, _] => Err(format!("\"{text}\" is an empty rectangle")),
+        _ => Err(format!("\"{text}\" is not x0,y0,x1,y1")),
+    }
+}
+
+/// Parses `--tiles k/n`.
+fn parse_tiles(text: &str) -> std::result::Result<TileSplit, String> {
+    TileSplit::parse(text).ok_or_else(|| format!("\"{text}\" is not k/n with 1 <= k <= n"))
+}
+
+/// Parses `--frames`: `first-last`, both inclusive, or one frame number.
+fn parse_frames(text: &str) -> std::result::Result<RangeInclusive<isize>, String> {
+    let invalid = || format!("\"{text}\" is not a frame or a first-last range");
+    let text = text.trim();
+    // Skip a leading sign, so the separator is the first '-' after it.
+    let (first, last) = match text.get(1..).and_then(|rest| rest.find('-')) {
+        Some(i) => (&text[..=i], &text[i + 2..]),
+        None => (text, text),
     };
-    (srgb * 255.0 + 0.5).floor() as u8
+    let first: isize = first.trim().parse().map_err(|_| invalid())?;
+    let last: isize = last.trim().parse().map_err(|_| invalid())?;
+    if first > last {
+        return Err(format!("\"{text}\" ends before it starts"));
+    }
+    Ok(first..=last)
 }
 
-/// Tone-map the render buffer to an sRGB PNG at `path`.
+/// Write the linear EXR: the beauty as `R`, `G`, `B` (and, if it was
+/// denoised, the noisy original as `noisy.R/G/B`), plus every AOV the buffer
+/// carries under its conventional channel names (`albedo.R`, `N.X`, `Z`, …)
+/// and every LPE layer as `<name>.R/G/B`, all in one part — the layout Nuke
+/// reads as named layers. The `chromaticities` attribute names
+/// the working space the pixels are in. The buffer holds `window`, which
+/// becomes the data window; the display window is the full `display` frame.
+fn write_exr(
+    buffer: &Buffer,
+    window: PixelWindow,
+    display: (usize, usize),
+    space: ColorSpace,
+    path: &Path,
+) -> std::result::Result<(), exr::error::Error> {
+    let (width, height) = (window.width(), window.height());
+    let plane = |value: &dyn Fn(usize, usize) -> f32| {
+        let mut samples = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                samples.push(value(x, y));
+            }
+        }
+        FlatSamples::F32(samples)
+    };
+    let mut channels = Vec::new();
+    for (k, name) in ["R", "G", "B"].into_iter().enumerate() {
+        channels.push(AnyChannel::new(
+            name,
+            plane(&|x, y| {
+                let (r, g, b) = buffer.get_rgb(x, y);
+                [r, g, b][k]
+            }),
+        ));
+    }
+    channels.push(AnyChannel::new("A", plane(&|x, y| buffer.get_alpha_flipped(x, y))));
+    if buffer.is_denoised() {
+        for (k, name) in ["noisy.R", "noisy.G", "noisy.B"].into_iter().enumerate() {
+            channels.push(AnyChannel::new(
+                name,
+                plane(&|x, y| buffer.get_noisy_flipped(x, y)[k]),
+            ));
+        }
+    }
+    for aov in buffer.aovs().iter() {
+        for (k, name) in aov.channels().iter().enumerate() {
+            channels.push(AnyChannel::new(
+                *name,
+                plane(&|x, y| buffer.get_aov_flipped(x, y, aov)[k]),
+            ));
+        }
+    }
+    for (i, name) in buffer.lpe_names().iter().enumerate() {
+        for (k, suffix) in ["R", "G", "B"].into_iter().enumerate() {
+            channels.push(AnyChannel::new(
+                format!("{name}.{suffix}").as_str(),
+                plane(&|x, y| buffer.get_lpe_flipped(x, y, i)[k]),
+            ));
+        }
+    }
+    // Cryptomatte: ranks pair up into RGBA groups (id, coverage, id,
+    // coverage), and the manifest goes in the header.
+    let mut attributes = LayerAttributes {
+        layer_position: Vec2(window.x_min, window.y_min),
+        ..LayerAttributes::default()
+    };
+    for (index, (name, manifest)) in buffer.cryptomatte_layers().enumerate() {
+        for group in 0..CRYPTO_RANKS.div_ceil(2) {
+            for (c, suffix) in ["R", "G", "B", "A"].into_iter().enumerate() {
+                let rank = 2 * group + c / 2;
+                channels.push(AnyChannel::new(
+                    format!("{name}{group:02}.{suffix}").as_str(),
+                    plane(&|x, y| {
+                        let (id, coverage) = buffer
+                            .get_crypto_flipped(x, y, index)
+                            .get(rank)
+                            .copied()
+                            .unwrap_or_default();
+                        if c % 2 == 0 { id } else { coverage }
+                    }),
+                ));
+            }
+        }
+        for (key, value) in cryptomatte_header(name, manifest) {
+            match (Text::new_or_none(&key), Text::new_or_none(&value)) {
+                (Some(k), Some(v)) => {
+                    attributes.other.insert(k, AttributeValue::Text(v));
+                }
+                _ => warn!("Cryptomatte attribute {key} is not valid EXR text — skipped"),
+            }
+        }
+    }
+    let layer = Layer::new(
+        (width, height),
+        attributes,
+        Encoding::FAST_LOSSLESS,
+        AnyChannels::sort(channels.into()),
+    );
+    let c = space.chromaticities();
+    let mut image = Image::from_layer(layer);
+    image.attributes.display_window = IntegerBounds::new((0, 0), display);
+    image.attributes.chromaticities = Some(exr::meta::attribute::Chromaticities {
+        red: Vec2(c.red[0], c.red[1]),
+        green: Vec2(c.green[0], c.green[1]),
+        blue: Vec2(c.blue[0], c.blue[1]),
+        white: Vec2(c.white[0], c.white[1]),
+    });
+    image.write().to_file(path)
+}
+
+/// Write the buffer's deep samples as a single-part, uncompressed
+/// deep-scanline EXR with `R`, `G`, `B`, `A`, `Z` and `ZBack` channels.
+/// Encoded by hand: the `exr` crate reads and writes flat images only, and
+/// the deep layout is simple enough not to need a library — per scanline, a
+/// running sample count per pixel, then each channel's samples in turn.
+/// Windows as for [`write_exr`].
+fn write_deep_exr(
+    buffer: &Buffer,
+    window: PixelWindow,
+    display: (usize, usize),
+    space: ColorSpace,
+    path: &Path,
+) -> std::io::Result<()> {
+    let (width, height) = (window.width(), window.height());
+    fn attribute(out: &mut Vec<u8>, name: &str, ty: &str, value: &[u8]) {
+        out.extend_from_slice(name.as_bytes());
+        out.push(0);
+        out.extend_from_slice(ty.as_bytes());
+        out.push(0);
+        out.extend_from_slice(&(value.len() as i32).to_le_bytes());
+        out.extend_from_slice(value);
+    }
+    let ints = |v: &[i32]| v.iter().flat_map(|i| i.to_le_bytes()).collect::<Vec<u8>>();
+
+    // Channel list in the sorted order the samples are stored in.
+    const CHANNELS: [&str; 6] = ["A", "B", "G", "R", "Z", "ZBack"];
+    let mut chlist = Vec::new();
+    for name in CHANNELS {
+        chlist.extend_from_slice(name.as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&2i32.to_le_bytes()); // FLOAT
+        chlist.extend_from_slice(&[0, 0, 0, 0]); // pLinear, reserved
+        chlist.extend_from_slice(&ints(&[1, 1])); // x/y sampling
+    }
+    chlist.push(0);
+
+    let max_samples = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| buffer.get_deep(x, y).len())
+        .max()
+        .unwrap_or(0);
+    // `box2i` bounds are inclusive.
+    let data_window = ints(&[
+        window.x_min,
+        window.y_min,
+        window.x_max - 1,
+        window.y_max - 1,
+    ]);
+    let display_window = ints(&[0, 0, display.0 as i32 - 1, display.1 as i32 - 1]);
+    let mut header = Vec::new();
+    header.extend_from_slice(&20000630i32.to_le_bytes());
+    // Version 2 with the "non-image" (deep) flag.
+    header.extend_from_slice(&(2i32 | 0x800).to_le_bytes());
+    let c = space.chromaticities();
+    let chromaticities = [c.red, c.green, c.blue, c.white]
+        .concat()
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect::<Vec<u8>>();
+    attribute(&mut header, "channels", "chlist", &chlist);
+    attribute(&mut header, "chromaticities", "chromaticities", &chromaticities);
+    attribute(&mut header, "compression", "compression", &[0]);
+    attribute(&mut header, "dataWindow", "box2i", &data_window);
+    attribute(&mut header, "displayWindow", "box2i", &display_window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(&mut header, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(&mut header, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    attribute(&mut header, "type", "string", b"deepscanline");
+    attribute(&mut header, "version", "int", &1i32.to_le_bytes());
+    attribute(&mut header, "maxSamplesPerPixel", "int", &ints(&[max_samples as i32]));
+    attribute(&mut header, "chunkCount", "int", &ints(&[height as i32]));
+    header.push(0);
+
+    // One chunk per scanline, top row first.
+    let mut chunks = Vec::with_capacity(height);
+    for y in 0..height {
+        let pixels: Vec<_> = (0..width).map(|x| buffer.get_deep_flipped(x, y)).collect();
+        let mut counts = Vec::with_capacity(width);
+        let mut running = 0i32;
+        for samples in &pixels {
+            running += samples.len() as i32;
+            counts.push(running);
+        }
+        let mut data = Vec::new();
+        for channel in CHANNELS {
+            for s in pixels.iter().flat_map(|p| p.iter()) {
+                let value = match channel {
+                    "A" => s.alpha,
+                    "B" => s.color.z,
+                    "G" => s.color.y,
+                    "R" => s.color.x,
+                    "Z" => s.z,
+                    _ => s.z_back,
+                };
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        let table = ints(&counts);
+        let mut chunk = Vec::with_capacity(28 + table.len() + data.len());
+        chunk.extend_from_slice(&(window.y_min + y as i32).to_le_bytes());
+        chunk.extend_from_slice(&(table.len() as u64).to_le_bytes());
+        chunk.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        // Uncompressed, so the unpacked size is the packed size.
+        chunk.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        chunk.extend_from_slice(&table);
+        chunk.extend_from_slice(&data);
+        chunks.push(chunk);
+    }
+
+    let mut file = header;
+    let mut offset = (file.len() + 8 * height) as u64;
+    for chunk in &chunks {
+        file.extend_from_slice(&offset.to_le_bytes());
+        offset += chunk.len() as u64;
+    }
+    for chunk in chunks {
+        file.extend_from_slice(&chunk);
+    }
+    std::fs::write(path, file)
+}
+
+/// Tone-map the render buffer to an sRGB PNG at `path` through the
+/// display pipeline — see `display.rs`.
 fn write_png(
     buffer: &Buffer,
     width: usize,
     height: usize,
+    display: &DisplayTransform,
     path: &Path,
 ) -> std::result::Result<(), image::ImageError> {
     let mut img = image::RgbaImage::new(width as u32, height as u32);
     for y in 0..height {
         for x in 0..width {
             let (r, g, b) = buffer.get_rgb(x, y);
-            img.put_pixel(
-                x as u32,
-                y as u32,
-                image::Rgba([tone_map(r), tone_map(g), tone_map(b), 255]),
-            );
+            let [r, g, b] = display.to_rgb8(Vec3A::new(r, g, b));
+            img.put_pixel(x as u32, y as u32, image::Rgba([r, g, b, 255]));
         }
     }
     img.save(path)
@@ -495,12 +947,66 @@ fn main() {
     tracing_subscriber::fmt()
         .with_max_level(get_logger_level(cli.level))
         .init();
-    let input = cli.input;
-    let output = cli.output;
-    let scene: Scene = if let Some(t) = input {
-        let input_path = std::path::Path::new(&t);
+    // The display pipeline is settled before rendering, so a bad LUT path
+    // fails in a second rather than after the render.
+    let mut display = DisplayTransform::new(cli.view, cli.look, cli.exposure);
+    if let Some(path) = &cli.lut {
+        match Lut3D::load(Path::new(path)) {
+            Ok(lut) => display = display.with_lut(lut),
+            Err(e) => {
+                error!("Cannot read LUT {path}: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(Command::Merge { parts, output }) = &cli.command {
+        merge(parts, &display, output);
+        return;
+    }
+    let resume = match &cli.resume {
+        Some(path) => match Checkpoint::load(Path::new(path)) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                error!("{e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let mut options = ImportOptions::default();
+    if let Some(camera) = &cli.camera {
+        options = options.with_camera(camera);
+    }
+    if let Some(settings) = &cli.settings {
+        options = options.with_settings(settings);
+    }
+    // A sequence shares one open stage between its frames, each written
+    // to its own numbered file.
+    if let Some(frames) = cli.frames.clone() {
+        let input = cli
+            .input
+            .as_deref()
+            .expect("clap requires --input with --frames");
+        let mut sequence =
+            match Scene::open_sequence_with_options(Path::new(input), &CliAssets, &options) {
+                Ok(sequence) => sequence,
+                Err(e) => {
+                    error!("Failed to load USD scene: {}", e);
+                    std::process::exit(1);
+                }
+            };
+        for frame in frames {
+            info!("Rendering frame {frame}");
+            let output = Path::new(&cli.output).with_extension(format!("{frame:04}.exr"));
+            let scene = sequence.frame(frame);
+            render_scene(&cli, scene, &display, None, &output.display().to_string());
+        }
+        return;
+    }
+    let scene: Scene = if let Some(t) = &cli.input {
+        let input_path = std::path::Path::new(t);
         debug!("Scene loaded at path: {:?}", input_path);
-        match Scene::from_usd_with_assets(input_path, &CliAssets) {
+        match Scene::from_usd_with_options(input_path, &CliAssets, &options) {
             Ok(scene) => scene,
             Err(e) => {
                 error!("Failed to load USD scene: {}", e);
@@ -512,10 +1018,24 @@ fn main() {
         let (camera, settings) = get_settings();
         Scene::new(camera, world, lights, settings)
     };
+    render_scene(&cli, scene, &display, resume, &cli.output);
+}
+
+/// Applies the CLI's overrides to `scene`, renders it and writes the
+/// images (or, with `--tiles`, the part) to `output`.
+fn render_scene(
+    cli: &Cli,
+    scene: Scene,
+    display: &DisplayTransform,
+    resume: Option<Checkpoint>,
+    output: &str,
+) {
     let camera = scene.camera;
     let world = scene.world;
     let lights = scene.lights;
     let volumes = scene.volumes;
+    let mut lpes = scene.lpes;
+    let identity = scene.identity;
     // Import phases and scene counts come from the loader; render and
     // output are timed here.
     let mut stats = scene.stats;
@@ -535,6 +1055,56 @@ fn main() {
     if let Some(radius) = cli.filter_radius {
         settings = settings.with_pixel_filter(settings.pixel_filter().with_radius(radius));
     }
+    if let Some(list) = &cli.aov {
+        match AovSet::parse(list) {
+            Ok(aovs) => settings = settings.with_aovs(aovs),
+            Err(bad) => {
+                error!("Unknown AOV \"{bad}\" in --aov");
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(list) = &cli.cryptomatte {
+        match CryptoSet::parse(list) {
+            Ok(layers) => settings = settings.with_cryptomatte(layers),
+            Err(bad) => {
+                error!("Unknown Cryptomatte layer \"{bad}\" in --cryptomatte");
+                std::process::exit(1);
+            }
+        }
+    }
+    if cli.deep {
+        settings = settings.with_deep(true);
+    }
+    if cli.denoise {
+        settings = settings.with_denoise(true);
+    }
+    if let Some(margin) = cli.overscan {
+        settings = settings.with_overscan(margin);
+    }
+    if let Some(window) = cli.crop {
+        settings = settings.with_crop(window);
+    }
+    if settings.data_window().is_empty() {
+        let (w, h) = settings.get_dimensions();
+        let crop = settings.crop().expect("only a crop can empty the window");
+        error!("Crop window {crop:?} lies outside the {w}x{h} frame");
+        std::process::exit(1);
+    }
+    if !cli.lpe.is_empty() {
+        lpes = match cli
+            .lpe
+            .iter()
+            .map(|spec| LightPathExpr::parse(spec))
+            .collect::<std::result::Result<Vec<_>, _>>()
+        {
+            Ok(lpes) => lpes,
+            Err(e) => {
+                error!("Invalid --lpe ({e})");
+                std::process::exit(1);
+            }
+        };
+    }
     // A BVH can only cull primitives whose bounds are small against the
     // whole scene. Report the ratio so a scene whose instance boxes all
     // span everything -- where no split can help -- is visible.
@@ -561,7 +1131,34 @@ fn main() {
     debug!("World loaded with {} objects", world.count());
     debug!("Lights loaded with {} objects", lights.count());
     // Camera
-    let renderer = Renderer::new(camera, world, lights, settings).with_volumes(volumes);
+    let mut renderer = Renderer::new(camera, world, lights, settings)
+        .with_volumes(volumes)
+        .with_lpes(lpes)
+        .with_scene_identity(identity);
+    if let Some(path) = cli.checkpoint.as_ref().or(cli.resume.as_ref()) {
+        let interval = Duration::from_secs(cli.checkpoint_interval);
+        renderer = renderer.with_checkpoints(PathBuf::from(path), interval);
+    }
+    if let Some(checkpoint) = resume {
+        renderer = renderer.with_resume(checkpoint);
+    }
+    if let Some(limit) = cli.time_limit {
+        renderer = renderer.with_time_limit(limit);
+    }
+    if let Some(target) = cli.noise_target {
+        renderer = renderer.with_noise_target(target);
+    }
+    let progressive = cli.progressive || cli.noise_target.is_some();
+    // Clap refuses the CLI's own layer flags beside these modes; what is
+    // left to catch is the layers the scene authors itself.
+    if cli.resume.is_some() {
+        require_beauty_only(&renderer, "--resume");
+    } else if cli.checkpoint.is_some() {
+        require_beauty_only(&renderer, "--checkpoint");
+    }
+    if progressive {
+        require_beauty_only(&renderer, "A progressive render");
+    }
     info!("Let's start rendering...");
     if cli.bucket {
         info!("Bucket rendering is enabled");
@@ -574,7 +1171,7 @@ fn main() {
     bar.set_style(
         indicatif::ProgressStyle::default_bar()
             .template(
-                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})",
+                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta}) {msg}",
             )
             .unwrap(),
     );
@@ -585,7 +1182,43 @@ fn main() {
         }
         progress_bar.set_position(done);
     };
-    let (buffer, ray_stats) = renderer.render_with_stats(cli.bucket, &progress);
+    // A split render writes its part and stops there: the image only
+    // exists once `merge` has every part.
+    if let Some(split) = cli.tiles {
+        let (part, ray_stats) = match renderer.render_partial(split, Some(&progress)) {
+            Ok(rendered) => rendered,
+            Err(e) => {
+                error!("--tiles: {}", e);
+                std::process::exit(1);
+            }
+        };
+        bar.finish();
+        stats.record("Render", 0, start.elapsed());
+        stats.rays = ray_stats;
+        let output_start = Instant::now();
+        let part_path =
+            Path::new(output).with_extension(format!("{}of{}.part", split.index(), split.count()));
+        match part.save(&part_path) {
+            Ok(_) => info!("Part written to: {:?}", part_path),
+            Err(e) => {
+                error!("Error writing part: {}", e);
+                std::process::exit(1);
+            }
+        }
+        stats.record("Write output", 0, output_start.elapsed());
+        if cli.stats {
+            println!("{stats}");
+        }
+        return;
+    }
+    // A render returns the image it has when the time limit or the noise
+    // target stops it; it is written like any other.
+    let (buffer, ray_stats) = if progressive {
+        let mut on_pass = |_: &Buffer, spp: u32| bar.set_message(format!("{spp} spp"));
+        renderer.render_progressive(cli.bucket, Some(&progress), &mut on_pass)
+    } else {
+        renderer.render_with_stats(cli.bucket, &progress)
+    };
     bar.finish();
     // Close Timer
     let duration: Duration = start.elapsed();
@@ -594,16 +1227,35 @@ fn main() {
     info!("Time elapsed in rendering() is: {:?}", duration);
     // Write the linear EXR, then the tone-mapped sRGB PNG next to it.
     let output_start = Instant::now();
-    let (img_width, img_height) = settings.get_dimensions();
-    match write_rgb_file(&output, img_width, img_height, |x, y| buffer.get_rgb(x, y)) {
+    let window = settings.data_window();
+    let frame = settings.get_dimensions();
+    let space = settings.color_space();
+    match write_exr(&buffer, window, frame, space, Path::new(output)) {
         Ok(_) => info!("Image written to: {:?}", output),
         Err(e) => {
             error!("Error writing image: {}", e);
             std::process::exit(1);
         }
     }
-    let png_path = Path::new(&output).with_extension("png");
-    match write_png(&buffer, img_width, img_height, &png_path) {
+    if buffer.is_deep() {
+        let deep_path = Path::new(output).with_extension("deep.exr");
+        match write_deep_exr(&buffer, window, frame, space, &deep_path) {
+            Ok(_) => info!("Deep image written to: {:?}", deep_path),
+            Err(e) => {
+                error!("Error writing deep image: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    let png_path = Path::new(output).with_extension("png");
+    let display = display.clone().with_working_space(space);
+    match write_png(
+        &buffer,
+        window.width(),
+        window.height(),
+        &display,
+        &png_path,
+    ) {
         Ok(_) => info!("Image written to: {:?}", png_path),
         Err(e) => {
             error!("Error writing PNG: {}", e);
@@ -647,10 +1299,190 @@ fn main() {
     }
 }
 
+/// Exits with an error naming the scene's output layers `mode` cannot
+/// carry — see [`Renderer::extra_layers`] — rather than render without them.
+fn require_beauty_only(renderer: &Renderer, mode: &str) {
+    let layers = renderer.extra_layers();
+    if !layers.is_empty() {
+        error!(
+            "{mode} carries the beauty and alpha only, but the scene asks for {}",
+            layers.join(", ")
+        );
+        std::process::exit(1);
+    }
+}
+
+/// `crust-render merge`: reads the parts and writes the frame they make,
+/// EXR and PNG as a single-process render writes them.
+fn merge(paths: &[String], display: &DisplayTransform, output: &str) {
+    let parts = match paths
+        .iter()
+        .map(|path| PartialRender::load(Path::new(path)))
+        .collect::<std::result::Result<Vec<_>, _>>()
+    {
+        Ok(parts) => parts,
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let buffer = match PartialRender::merge(&parts) {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let part = &parts[0];
+    let (window, frame, space) = (part.window(), part.display_size(), part.color_space());
+    match write_exr(&buffer, window, frame, space, Path::new(output)) {
+        Ok(_) => info!("Image written to: {:?}", output),
+        Err(e) => {
+            error!("Error writing image: {}", e);
+            std::process::exit(1);
+        }
+    }
+    let png_path = Path::new(output).with_extension("png");
+    let display = display.clone().with_working_space(space);
+    match write_png(
+        &buffer,
+        window.width(),
+        window.height(),
+        &display,
+        &png_path,
+    ) {
+        Ok(_) => info!("Image written to: {:?}", png_path),
+        Err(e) => {
+            error!("Error writing PNG: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn time_limits_parse() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("2.5s"), Ok(Duration::from_millis(2500)));
+        assert!(parse_duration("10 minutes").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+
+    #[test]
+    fn crop_windows_parse() {
+        assert_eq!(
+            parse_window("0,8,64,40"),
+            Ok(PixelWindow::new(0, 8, 64, 40))
+        );
+        assert_eq!(
+            parse_window("-16, -16, 32, 32"),
+            Ok(PixelWindow::new(-16, -16, 32, 32))
+        );
+        assert!(parse_window("0,0,64").is_err());
+        assert!(parse_window("10,0,10,5").is_err());
+    }
+
+    #[test]
+    fn tile_splits_and_merge_parse() {
+        assert_eq!(parse_tiles("3/16"), Ok(TileSplit::new(3, 16).unwrap()));
+        assert!(parse_tiles("17/16").is_err());
+        let cli = Cli::try_parse_from(["crust-render", "merge", "a.part", "b.part", "-o", "f.exr"])
+            .expect("merge parses");
+        assert!(matches!(
+            cli.command,
+            Some(Command::Merge { parts, output }) if parts.len() == 2 && output == "f.exr"
+        ));
+        assert!(Cli::try_parse_from(["crust-render", "--tiles", "1/2", "--progressive"]).is_err());
+        for layers in [
+            &["--aov", "albedo"][..],
+            &["--lpe", "caustics=C.*<TS>.*L"],
+            &["--cryptomatte", "object"],
+            &["--deep"],
+            &["--denoise"],
+        ] {
+            let args = [&["crust-render", "--tiles", "1/2"][..], layers].concat();
+            assert!(
+                Cli::try_parse_from(args).is_err(),
+                "--tiles with {layers:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn checkpoints_refuse_extra_layers() {
+        assert!(Cli::try_parse_from(["crust-render", "--checkpoint", "a.ckpt"]).is_ok());
+        for flag in ["--checkpoint", "--resume"] {
+            for layers in [&["--aov", "N"][..], &["--deep"], &["--denoise"]] {
+                let args = [&["crust-render", flag, "a.ckpt"][..], layers].concat();
+                assert!(Cli::try_parse_from(args).is_err(), "{flag} with {layers:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn progressive_renders_refuse_extra_layers() {
+        for flag in [&["--progressive"][..], &["--noise-target", "0.02"]] {
+            assert!(Cli::try_parse_from([&["crust-render"][..], flag].concat()).is_ok());
+            for layers in [&["--cryptomatte", "all"][..], &["--denoise"]] {
+                let args = [&["crust-render"][..], flag, layers].concat();
+                assert!(
+                    Cli::try_parse_from(args).is_err(),
+                    "{flag:?} with {layers:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn time_limits_keep_every_layer() {
+        for layers in [
+            &["--cryptomatte", "all"][..],
+            &["--denoise"],
+            &["--aov", "N"],
+        ] {
+            let args = [&["crust-render", "--time-limit", "10m"][..], layers].concat();
+            assert!(
+                Cli::try_parse_from(args).is_ok(),
+                "--time-limit with {layers:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn frame_ranges_parse() {
+        assert_eq!(parse_frames("1001-1100"), Ok(1001..=1100));
+        assert_eq!(parse_frames("12"), Ok(12..=12));
+        assert_eq!(parse_frames("-5-3"), Ok(-5..=3));
+        assert!(parse_frames("10-1").is_err());
+        assert!(parse_frames("1001-").is_err());
+        assert!(Cli::try_parse_from(["crust-render", "--frames", "1-4"]).is_err());
+        let cli = Cli::try_parse_from(["crust-render", "-i", "shot.usda", "--frames", "1-4"])
+            .expect("frames parse");
+        assert_eq!(cli.frames, Some(1..=4));
+    }
+
+    #[test]
+    fn camera_and_settings_need_an_input() {
+        assert!(Cli::try_parse_from(["crust-render", "--camera", "/cam"]).is_err());
+        let cli = Cli::try_parse_from([
+            "crust-render",
+            "-i",
+            "layout.usda",
+            "--camera",
+            "/shots/sh010/cam",
+            "--settings",
+            "/Render/final",
+        ])
+        .expect("selection parses");
+        assert_eq!(cli.camera.as_deref(), Some("/shots/sh010/cam"));
+        assert_eq!(cli.settings.as_deref(), Some("/Render/final"));
+    }
+
     /// The host side of the asset seam: an EXR written to disk must come
     /// back as pixels the engine can build a map from, with the geometry
     /// and values intact. `crust-core` cannot test this — it has no
@@ -685,6 +1517,211 @@ mod tests {
         let _ = std::fs::remove_file(&path);
     }
 
+    /// The AOV layers land in the same file as the beauty, under the channel
+    /// names compositing packages look for and holding the values rendered
+    /// into them, beside the beauty's own R, G, B and A.
+    #[test]
+    fn aov_layers_are_written_as_named_channels() {
+        let dir = std::env::temp_dir().join("crust_aov_exr");
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let path = dir.join("aovs.exr");
+
+        let (w, h) = (4usize, 3usize);
+        let aovs = AovSet::empty()
+            .with(crust_core::Aov::Albedo)
+            .with(crust_core::Aov::Depth);
+        let mut buffer = Buffer::with_aovs(w, h, aovs);
+        for y in 0..h {
+            for x in 0..w {
+                buffer.set_pixel(x, y, Vec3A::new(x as f32, y as f32, 1.0));
+                buffer.set_aov(x, y, crust_core::Aov::Albedo, Vec3A::splat(0.25));
+                buffer.set_aov(x, y, crust_core::Aov::Depth, Vec3A::splat(7.0));
+            }
+        }
+        write_exr(
+            &buffer,
+            PixelWindow::full(w, h),
+            (w, h),
+            ColorSpace::default(),
+            &path,
+        )
+        .expect("write exr");
+
+        let image = read_all_flat_layers_from_file(&path).expect("read back");
+        let channels = &image.layer_data[0].channel_data.list;
+        let names: Vec<String> = channels.iter().map(|c| c.name.to_string()).collect();
+        for expected in ["R", "G", "B", "A", "albedo.R", "albedo.G", "albedo.B", "Z"] {
+            assert!(
+                names.iter().any(|n| n == expected),
+                "channel {expected} missing from {names:?}"
+            );
+        }
+        let values = |name: &str| -> Vec<f32> {
+            let channel = channels.iter().find(|c| c.name.to_string() == name);
+            channel.unwrap().sample_data.values_as_f32().collect()
+        };
+        for name in ["albedo.R", "albedo.G", "albedo.B"] {
+            assert_eq!(values(name), [0.25; 12], "channel {name}");
+        }
+        assert_eq!(values("Z"), [7.0; 12]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// The header's chromaticities name the working space, so a
+    /// compositor reading an ACEScg render does not take it for sRGB.
+    #[test]
+    fn exr_chromaticities_follow_the_working_space() {
+        let dir = std::env::temp_dir().join("crust_chromaticities_exr");
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let path = dir.join("acescg.exr");
+
+        let buffer = Buffer::new(2, 2);
+        write_exr(
+            &buffer,
+            PixelWindow::full(2, 2),
+            (2, 2),
+            ColorSpace::AcesCg,
+            &path,
+        )
+        .expect("write exr");
+
+        let image = read_all_flat_layers_from_file(&path).expect("read back");
+        let c = image.attributes.chromaticities.expect("chromaticities written");
+        assert_eq!((c.red.0, c.red.1), (0.713, 0.293));
+        assert_eq!((c.white.0, c.white.1), (0.32168, 0.33767));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// A crop is written where it sits on the frame: its rectangle as the
+    /// data window, the full frame as the display window.
+    #[test]
+    fn exr_windows_place_a_crop_on_the_frame() {
+        let dir = std::env::temp_dir().join("crust_window_exr");
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let path = dir.join("crop.exr");
+
+        let window = PixelWindow::new(-2, 3, 4, 5);
+        let buffer = Buffer::new(window.width(), window.height());
+        write_exr(&buffer, window, (16, 9), ColorSpace::default(), &path).expect("write exr");
+
+        let image = read_all_flat_layers_from_file(&path).expect("read back");
+        let display = image.attributes.display_window;
+        assert_eq!((display.position.0, display.position.1), (0, 0));
+        assert_eq!((display.size.0, display.size.1), (16, 9));
+        let layer = &image.layer_data[0];
+        assert_eq!(
+            (layer.attributes.layer_position.0, layer.attributes.layer_position.1),
+            (-2, 3)
+        );
+        assert_eq!((layer.size.0, layer.size.1), (6, 2));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// Cryptomatte layers follow the spec's layout: RGBA rank groups under
+    /// the layer name, and the manifest under a hashed key in the header.
+    #[test]
+    fn cryptomatte_layers_carry_their_manifest() {
+        let dir = std::env::temp_dir().join("crust_cryptomatte_exr");
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let path = dir.join("crypto.exr");
+
+        let (w, h) = (2usize, 2usize);
+        let manifest = r#"{"/World/bunny":"3f800000"}"#;
+        let mut buffer = Buffer::new(w, h)
+            .with_cryptomatte(vec![("CryptoObject".to_owned(), manifest.to_owned())]);
+        buffer.set_crypto(0, 0, 0, &[(1.0, 0.75), (2.0, 0.25)]);
+        write_exr(
+            &buffer,
+            PixelWindow::full(w, h),
+            (w, h),
+            ColorSpace::default(),
+            &path,
+        )
+        .expect("write exr");
+
+        let image = read_all_flat_layers_from_file(&path).expect("read back");
+        let layer = &image.layer_data[0];
+        let names: Vec<String> = layer
+            .channel_data
+            .list
+            .iter()
+            .map(|c| c.name.to_string())
+            .collect();
+        for expected in ["CryptoObject00.R", "CryptoObject00.A", "CryptoObject02.B"] {
+            assert!(
+                names.iter().any(|n| n == expected),
+                "channel {expected} missing from {names:?}"
+            );
+        }
+        let stored = layer
+            .attributes
+            .other
+            .iter()
+            .find(|(k, _)| k.to_string().ends_with("/manifest"))
+            .map(|(_, v)| v.clone());
+        assert!(
+            matches!(stored, Some(AttributeValue::Text(t)) if t.to_string() == manifest),
+            "manifest missing from the header"
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// The deep file's structure, walked by hand: a deep-flagged version,
+    /// a header ending in a null, one offset per scanline, and each
+    /// scanline's running sample counts followed by the samples.
+    #[test]
+    fn deep_exr_lists_each_pixels_samples() {
+        let dir = std::env::temp_dir().join("crust_deep_exr");
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let path = dir.join("deep.exr");
+
+        let (w, h) = (2usize, 1usize);
+        let mut buffer = Buffer::new(w, h).with_deep();
+        let sample = |z: f32, alpha: f32| crust_core::DeepSample {
+            z,
+            z_back: z,
+            color: Vec3A::splat(alpha),
+            alpha,
+        };
+        buffer.set_deep(1, 0, vec![sample(2.0, 0.5), sample(3.0, 1.0)]);
+        write_deep_exr(
+            &buffer,
+            PixelWindow::full(w, h),
+            (w, h),
+            ColorSpace::default(),
+            &path,
+        )
+        .expect("write deep exr");
+
+        let bytes = std::fs::read(&path).expect("read back");
+        let i32_at = |at: usize| i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        assert_eq!(i32_at(0), 20000630);
+        assert_ne!(i32_at(4) & 0x800, 0, "deep files set the non-image flag");
+        // Skip the attributes: name, type, size, value — until an empty name.
+        let mut at = 8;
+        while bytes[at] != 0 {
+            for _ in 0..2 {
+                at += bytes[at..].iter().position(|&b| b == 0).unwrap() + 1;
+            }
+            at += 4 + i32_at(at) as usize;
+        }
+        let chunk = u64_at(at + 1) as usize;
+        assert_eq!(i32_at(chunk), 0, "first chunk is scanline 0");
+        assert_eq!(u64_at(chunk + 4), 8, "two running counts");
+        assert_eq!(u64_at(chunk + 12), 6 * 2 * 4, "six channels of two samples");
+        assert_eq!((i32_at(chunk + 28), i32_at(chunk + 32)), (0, 2));
+        // Channel `A` comes first, front sample first.
+        let first = f32::from_le_bytes(bytes[chunk + 36..chunk + 40].try_into().unwrap());
+        assert_eq!(first, 0.5);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
     /// LDR images are sRGB-encoded; the renderer works in linear light, so
     /// the loader must undo the transfer curve or an image-based sky is
     /// noticeably wrong.